@@ -0,0 +1,496 @@
+//! `harbor daemon`: the watcher as a background service for machines without
+//! the desktop app, and the systemd user unit that runs it.
+//!
+//! The daemon shares the desktop app's activity log, history, organize lock
+//! and control socket, so `harbor ctl` and the app's Activity page work the
+//! same against either. Only one of them can serve the control socket at a
+//! time, which also keeps a second daemon from starting.
+
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
+use harbor_core::control::{
+    pause_end, ControlHandler, ControlServer, EventHub, OrganizeReport, ServiceReport,
+};
+use harbor_core::downloads::{
+    harbor_app_dir, harbor_control_path, harbor_history_path, harbor_lock_path, harbor_log_path,
+    load_downloads_config, load_or_initialize_config, DownloadsConfig,
+};
+use harbor_core::events::{OrganizeEvent, OrganizeEventDto};
+use harbor_core::history::JsonlHistory;
+use harbor_core::organizer::{FileLog, Organizer};
+use harbor_core::webhook::Webhook;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+/// How often the daemon checks for signals, pauses and reloads.
+const TICK: Duration = Duration::from_millis(200);
+
+/// Files the daemon reads and writes.
+#[derive(Debug, Clone)]
+pub struct DaemonPaths {
+    pub config: PathBuf,
+    pub pid_file: PathBuf,
+    pub socket: PathBuf,
+    pub log: PathBuf,
+    pub history: PathBuf,
+    pub lock: PathBuf,
+}
+
+impl DaemonPaths {
+    /// The locations the desktop app uses, with `config` as the
+    /// configuration file and the PID file in the app data folder.
+    pub fn shared(config: PathBuf) -> Self {
+        Self {
+            config,
+            pid_file: harbor_app_dir().join("daemon.pid"),
+            socket: harbor_control_path(),
+            log: harbor_log_path(),
+            history: harbor_history_path(),
+            lock: harbor_lock_path(),
+        }
+    }
+}
+
+static TERMINATE: AtomicBool = AtomicBool::new(false);
+static HANGUP: AtomicBool = AtomicBool::new(false);
+
+/// Routes SIGTERM and SIGINT to the first flag and SIGHUP to the second.
+/// Elsewhere, Ctrl+C ends the process as usual.
+pub fn signal_flags() -> (&'static AtomicBool, &'static AtomicBool) {
+    #[cfg(unix)]
+    {
+        extern "C" fn on_signal(signal: libc::c_int) {
+            let flag = if signal == libc::SIGHUP {
+                &HANGUP
+            } else {
+                &TERMINATE
+            };
+            flag.store(true, Ordering::SeqCst);
+        }
+        for signal in [libc::SIGTERM, libc::SIGINT, libc::SIGHUP] {
+            // SAFETY: the handler only stores to an atomic, which is
+            // async-signal-safe.
+            unsafe {
+                libc::signal(
+                    signal,
+                    on_signal as extern "C" fn(libc::c_int) as libc::sighandler_t,
+                );
+            }
+        }
+    }
+    (&TERMINATE, &HANGUP)
+}
+
+/// Runs the watcher until `terminate` is set. Setting `hangup` reloads the
+/// configuration and restarts the watcher with it; a configuration that
+/// fails to load is reported and the current one kept.
+pub fn run(
+    paths: DaemonPaths,
+    interval_secs: u64,
+    terminate: &AtomicBool,
+    hangup: &AtomicBool,
+) -> Result<()> {
+    let config = load_or_initialize_config(&paths.config)?;
+    let daemon = Arc::new(Daemon::new(paths, config));
+    let _server = ControlServer::start(&daemon.paths.socket, daemon.clone())?;
+    let _pid_file = PidFile::create(&daemon.paths.pid_file)?;
+    eprintln!(
+        "[Harbor] Daemon started (pid {}), config {}",
+        std::process::id(),
+        daemon.paths.config.display()
+    );
+
+    let mut watcher: Option<Watcher> = None;
+    while !terminate.load(Ordering::SeqCst) {
+        if hangup.swap(false, Ordering::SeqCst) {
+            match daemon.reload() {
+                Ok(()) => eprintln!("[Harbor] Reloaded {}", daemon.paths.config.display()),
+                Err(e) => eprintln!("[Harbor] Keeping the current configuration: {e:#}"),
+            }
+        }
+        let paused = daemon.paused_until().is_some();
+        let restart = daemon.restart.swap(false, Ordering::SeqCst);
+        if paused || restart {
+            if let Some(watcher) = watcher.take() {
+                watcher.stop();
+            }
+        }
+        if watcher.is_none() && !paused {
+            watcher = Some(daemon.start_watcher(interval_secs));
+        }
+        thread::sleep(TICK);
+    }
+
+    if let Some(watcher) = watcher.take() {
+        watcher.stop();
+    }
+    let webhook = daemon
+        .webhook
+        .read()
+        .ok()
+        .and_then(|webhook| webhook.clone());
+    crate::finish_webhook(webhook.as_deref());
+    eprintln!("[Harbor] Daemon stopped");
+    Ok(())
+}
+
+struct Daemon {
+    paths: DaemonPaths,
+    config: RwLock<DownloadsConfig>,
+    webhook: RwLock<Option<Arc<Webhook>>>,
+    events: EventHub,
+    paused_until: Mutex<Option<DateTime<Local>>>,
+    started: Instant,
+    /// Set when the watcher has to start over with a new configuration.
+    restart: AtomicBool,
+}
+
+impl Daemon {
+    fn new(paths: DaemonPaths, config: DownloadsConfig) -> Self {
+        let webhook = start_webhook(&config).unwrap_or_else(|e| {
+            eprintln!("[Harbor] Webhook disabled: {e:#}");
+            None
+        });
+        Self {
+            paths,
+            config: RwLock::new(config),
+            webhook: RwLock::new(webhook),
+            events: EventHub::new(),
+            paused_until: Mutex::new(None),
+            started: Instant::now(),
+            restart: AtomicBool::new(false),
+        }
+    }
+
+    fn config(&self) -> DownloadsConfig {
+        self.config
+            .read()
+            .map(|config| config.clone())
+            .unwrap_or_else(|poisoned| poisoned.into_inner().clone())
+    }
+
+    /// Organizer writing the shared activity log and history, printing like
+    /// `downloads-watch` and publishing to `harbor ctl tail-events`.
+    fn organizer(&self) -> Organizer {
+        let config = self.config();
+        let events = self.events.clone();
+        let mut builder = Organizer::builder(config.clone())
+            .log_sink(Arc::new(FileLog::from_config(&self.paths.log, &config)))
+            .history(Arc::new(JsonlHistory::new(&self.paths.history)))
+            .pass_lock(&self.paths.lock)
+            .on_event(move |event| {
+                if !matches!(event, OrganizeEvent::ScanFinished { .. }) {
+                    crate::print_event(event);
+                }
+                events.publish(event);
+            });
+        if let Some(webhook) = self.webhook.read().ok().and_then(|w| w.clone()) {
+            builder = builder.webhook(webhook);
+        }
+        builder.build()
+    }
+
+    fn start_watcher(&self, interval_secs: u64) -> Watcher {
+        let organizer = self.organizer();
+        let running = Arc::new(AtomicBool::new(true));
+        let flag = running.clone();
+        let handle = thread::spawn(move || {
+            if let Err(e) = organizer.watch(interval_secs, &flag) {
+                eprintln!("[Harbor] Watcher stopped: {e:#}");
+            }
+        });
+        Watcher { running, handle }
+    }
+
+    fn reload(&self) -> Result<()> {
+        let config = load_downloads_config(&self.paths.config)?;
+        let webhook = start_webhook(&config)?;
+        if let Ok(mut current) = self.webhook.write() {
+            *current = webhook;
+        }
+        if let Ok(mut current) = self.config.write() {
+            *current = config;
+        }
+        self.restart.store(true, Ordering::SeqCst);
+        Ok(())
+    }
+
+    /// When the current pause ends, if the daemon is paused.
+    fn paused_until(&self) -> Option<DateTime<Local>> {
+        let mut paused_until = self.paused_until.lock().ok()?;
+        if paused_until.is_some_and(|until| until <= Local::now()) {
+            *paused_until = None;
+        }
+        *paused_until
+    }
+
+    fn report(&self) -> ServiceReport {
+        let config = self.config();
+        let now = Local::now();
+        let paused_until = self.paused_until();
+        ServiceReport {
+            state: if paused_until.is_some() {
+                "paused"
+            } else {
+                "running"
+            }
+            .to_string(),
+            program: env!("CARGO_BIN_NAME").to_string(),
+            pid: std::process::id(),
+            download_dir: config.download_dir.clone(),
+            config_path: self.paths.config.to_string_lossy().into_owned(),
+            uptime_secs: Some(self.started.elapsed().as_secs()),
+            paused_until: paused_until.map(|t| t.to_rfc3339()),
+            quiet_until: config.quiet_until(now).map(|t| t.to_rfc3339()),
+            next_scheduled_run: config.next_scheduled_run(now).map(|t| t.to_rfc3339()),
+        }
+    }
+}
+
+impl ControlHandler for Daemon {
+    fn status(&self) -> Result<ServiceReport> {
+        Ok(self.report())
+    }
+
+    fn pause(&self, minutes: Option<u32>) -> Result<ServiceReport> {
+        if minutes == Some(0) {
+            bail!("Pause for at least one minute.");
+        }
+        if let Ok(mut paused_until) = self.paused_until.lock() {
+            *paused_until = Some(pause_end(minutes, Local::now()));
+        }
+        Ok(self.report())
+    }
+
+    fn resume(&self) -> Result<ServiceReport> {
+        if let Ok(mut paused_until) = self.paused_until.lock() {
+            *paused_until = None;
+        }
+        Ok(self.report())
+    }
+
+    fn organize_now(&self) -> Result<OrganizeReport> {
+        Ok(OrganizeReport::from(&self.organizer().run_once()?))
+    }
+
+    fn reload_config(&self) -> Result<ServiceReport> {
+        self.reload()?;
+        Ok(self.report())
+    }
+
+    fn subscribe(&self) -> Receiver<OrganizeEventDto> {
+        self.events.subscribe()
+    }
+}
+
+fn start_webhook(config: &DownloadsConfig) -> Result<Option<Arc<Webhook>>> {
+    Ok(crate::start_webhook(config)?.map(Arc::new))
+}
+
+struct Watcher {
+    running: Arc<AtomicBool>,
+    handle: JoinHandle<()>,
+}
+
+impl Watcher {
+    /// Stops the watcher after the file it is working on.
+    fn stop(self) {
+        self.running.store(false, Ordering::SeqCst);
+        let _ = self.handle.join();
+    }
+}
+
+/// Holds the daemon's PID file until dropped. A file left by a daemon that
+/// crashed is overwritten.
+struct PidFile(PathBuf);
+
+impl PidFile {
+    fn create(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        }
+        std::fs::write(path, format!("{}\n", std::process::id()))
+            .with_context(|| format!("write {}", path.display()))?;
+        Ok(Self(path.to_path_buf()))
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.0);
+    }
+}
+
+/// File name of the systemd user unit.
+pub const UNIT_NAME: &str = "harbor.service";
+
+/// A systemd user unit that runs `exe daemon --config config`, restarts it
+/// when it fails and reloads it with SIGHUP.
+pub fn systemd_unit(exe: &Path, config: &Path) -> String {
+    format!(
+        "[Unit]\n\
+         Description=Harbor download organizer\n\
+         \n\
+         [Service]\n\
+         Type=simple\n\
+         ExecStart={} daemon --config {}\n\
+         ExecReload=/bin/kill -HUP $MAINPID\n\
+         Restart=on-failure\n\
+         RestartSec=10\n\
+         \n\
+         [Install]\n\
+         WantedBy=default.target\n",
+        systemd_arg(&exe.to_string_lossy()),
+        systemd_arg(&config.to_string_lossy()),
+    )
+}
+
+/// Quotes `arg` for an `ExecStart=` line: specifiers and variables are
+/// escaped, and arguments with blanks, quotes or backslashes double-quoted.
+fn systemd_arg(arg: &str) -> String {
+    let arg = arg.replace('%', "%%").replace('$', "$$");
+    if arg.is_empty() || arg.contains([' ', '\t', '"', '\'', '\\']) {
+        format!("\"{}\"", arg.replace('\\', "\\\\").replace('"', "\\\""))
+    } else {
+        arg
+    }
+}
+
+/// Where systemd looks for user units: `$XDG_CONFIG_HOME/systemd/user`.
+pub fn systemd_user_dir() -> PathBuf {
+    let config_home = harbor_core::platform::config_dir();
+    config_home
+        .parent()
+        .unwrap_or(&config_home)
+        .join("systemd")
+        .join("user")
+}
+
+/// Writes `unit` as [`UNIT_NAME`] into `dir` and returns its path.
+pub fn install_unit(unit: &str, dir: &Path) -> Result<PathBuf> {
+    std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    let path = dir.join(UNIT_NAME);
+    std::fs::write(&path, unit).with_context(|| format!("write {}", path.display()))?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harbor_core::control::ControlClient;
+
+    fn paths(dir: &Path) -> DaemonPaths {
+        DaemonPaths {
+            config: dir.join("harbor.downloads.yaml"),
+            pid_file: dir.join("daemon.pid"),
+            socket: if cfg!(windows) {
+                PathBuf::from(format!(r"\\.\pipe\harbor-test-{}", std::process::id()))
+            } else {
+                dir.join("control.sock")
+            },
+            log: dir.join("recent_moves.log"),
+            history: dir.join("history.jsonl"),
+            lock: dir.join("organize.lock"),
+        }
+    }
+
+    fn write_config(path: &Path, download_dir: &Path, target_dir: &Path) {
+        let yaml = format!(
+            "download_dir: \"{}\"\nmin_age_secs: 0\nrules:\n  - name: Text\n    extensions: [\"txt\"]\n    target_dir: \"{}\"\n",
+            download_dir.display().to_string().replace('\\', "\\\\"),
+            target_dir.display().to_string().replace('\\', "\\\\"),
+        );
+        std::fs::write(path, yaml).unwrap();
+    }
+
+    struct StopOnDrop<'a>(&'a AtomicBool);
+
+    impl Drop for StopOnDrop<'_> {
+        fn drop(&mut self) {
+            self.0.store(true, Ordering::SeqCst);
+        }
+    }
+
+    fn wait_for(mut ready: impl FnMut() -> bool) {
+        let deadline = Instant::now() + Duration::from_secs(10);
+        while !ready() {
+            assert!(Instant::now() < deadline, "timed out");
+            thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_daemon_serves_ctl_reloads_on_hangup_and_stops_on_terminate() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dl = temp.path().join("DL");
+        let other = temp.path().join("Other");
+        std::fs::create_dir(&dl).unwrap();
+        std::fs::create_dir(&other).unwrap();
+        let paths = paths(temp.path());
+        write_config(&paths.config, &dl, &temp.path().join("Text"));
+        let terminate = AtomicBool::new(false);
+        let hangup = AtomicBool::new(false);
+        let client = ControlClient::new(&paths.socket);
+
+        thread::scope(|scope| {
+            let daemon = scope.spawn(|| run(paths.clone(), 1, &terminate, &hangup));
+            // Stops the daemon when an assertion fails, so the scope can end.
+            let _stop = StopOnDrop(&terminate);
+            // The socket is served before the PID file is written.
+            wait_for(|| {
+                client.status().is_ok()
+                    && std::fs::read_to_string(&paths.pid_file).is_ok_and(|pid| pid.ends_with('\n'))
+            });
+            assert_eq!(
+                std::fs::read_to_string(&paths.pid_file).unwrap().trim(),
+                std::process::id().to_string()
+            );
+
+            // A paused daemon leaves new files to "organize now".
+            assert_eq!(client.pause(Some(5)).unwrap().state, "paused");
+            thread::sleep(Duration::from_secs(1));
+            std::fs::write(dl.join("a.txt"), "a").unwrap();
+            thread::sleep(Duration::from_millis(1500));
+            assert!(dl.join("a.txt").exists());
+            assert_eq!(client.organize_now().unwrap().moved, 1);
+            assert!(temp.path().join("Text").join("a.txt").exists());
+            assert!(std::fs::read_to_string(&paths.log)
+                .unwrap()
+                .contains("a.txt"));
+            assert_eq!(client.resume().unwrap().state, "running");
+
+            write_config(&paths.config, &other, &temp.path().join("Text"));
+            hangup.store(true, Ordering::SeqCst);
+            wait_for(|| client.status().unwrap().download_dir == other.to_string_lossy());
+
+            terminate.store(true, Ordering::SeqCst);
+            daemon.join().unwrap().unwrap();
+        });
+
+        assert!(!paths.pid_file.exists());
+        assert!(client.status().is_err());
+    }
+
+    #[test]
+    fn test_systemd_unit_runs_the_daemon_with_quoted_paths() {
+        let unit = systemd_unit(
+            Path::new("/usr/local/bin/harbor"),
+            Path::new("/home/u/My Config/100% harbor.yaml"),
+        );
+        assert!(unit.contains(
+            "ExecStart=/usr/local/bin/harbor daemon --config \"/home/u/My Config/100%% harbor.yaml\"\n"
+        ));
+        assert!(unit.contains("ExecReload=/bin/kill -HUP $MAINPID\n"));
+        assert!(unit.contains("WantedBy=default.target\n"));
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path().join("systemd").join("user");
+        let path = install_unit(&unit, &dir).unwrap();
+        assert_eq!(path, dir.join("harbor.service"));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), unit);
+    }
+}
@@ -1,6 +1,19 @@
-use anyhow::Result;
+mod daemon;
+mod report;
+
+use anyhow::{bail, Result};
 use clap::{Parser, Subcommand};
+use harbor_core::cancel::CancellationToken;
+use harbor_core::clock::SystemClock;
+use harbor_core::control::{ControlClient, ServiceReport};
+use harbor_core::downloads::{harbor_config_path, harbor_control_path, harbor_lock_path};
+use harbor_core::events::OrganizeEvent;
+use harbor_core::organizer::Organizer;
+use harbor_core::webhook::Webhook;
+use report::OutputFormat;
+use std::io::{stdout, Write};
 use std::path::PathBuf;
+use std::time::Duration;
 #[cfg(windows)]
 use winreg::enums::HKEY_CURRENT_USER;
 #[cfg(windows)]
@@ -23,80 +36,431 @@ enum Commands {
     DownloadsOrganize {
         #[arg(default_value = "harbor.downloads.yaml")]
         path: String,
+        /// Prints the planned moves and skips without touching any file
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        /// Lock shared with the app and the daemon so only one of them
+        /// organizes at a time; defaults to the app data folder
+        #[arg(long)]
+        lock: Option<PathBuf>,
     },
     DownloadsWatch {
         #[arg(default_value = "harbor.downloads.yaml")]
         path: String,
         #[arg(default_value_t = 5)]
         interval_secs: u64,
+        /// Prints the plan whenever it changes instead of moving files
+        #[arg(long)]
+        dry_run: bool,
+        #[arg(long, value_enum, default_value_t = OutputFormat::Table)]
+        format: OutputFormat,
+        /// Lock shared with the app and the daemon so only one of them
+        /// organizes at a time; defaults to the app data folder
+        #[arg(long)]
+        lock: Option<PathBuf>,
     },
     TrayInstall {
         #[arg(long)]
         source: Option<String>,
     },
     TrayUninstall,
+    /// Runs the watcher as a background service until SIGTERM; SIGHUP
+    /// reloads the configuration
+    Daemon {
+        /// Configuration file; defaults to the one the desktop app uses
+        #[arg(long)]
+        config: Option<PathBuf>,
+        #[arg(long, default_value_t = 5)]
+        interval_secs: u64,
+        /// Where to write the process id; defaults to the app data folder
+        #[arg(long)]
+        pid_file: Option<PathBuf>,
+    },
+    /// Prints a systemd user unit that runs `harbor daemon`
+    DaemonUnit {
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Installs that unit into the systemd user unit folder (Linux)
+    DaemonInstall {
+        #[arg(long)]
+        config: Option<PathBuf>,
+    },
+    /// Controls the running Harbor service
+    Ctl {
+        /// Control socket to connect to; defaults to the service's own
+        #[arg(long)]
+        socket: Option<PathBuf>,
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
+}
+
+#[derive(Subcommand)]
+enum CtlCommand {
+    Status,
+    /// Stops the watcher for a while
+    Pause {
+        /// Minutes to pause for; until midnight when unset
+        #[arg(long)]
+        minutes: Option<u32>,
+    },
+    Resume,
+    OrganizeNow,
+    ReloadConfig,
+    /// Prints every organizer event as a JSON line until interrupted
+    TailEvents,
 }
 
 fn main() -> Result<()> {
     let cli = Cli::parse();
-    execute_command(cli.command, None)
+    execute_command(cli.command, None, &mut stdout())
 }
 
+/// Runs `command`; dry-run plans are written to `out`.
 fn execute_command(
     command: Commands,
     shutdown_signal: Option<std::sync::Arc<std::sync::atomic::AtomicBool>>,
+    out: &mut dyn Write,
 ) -> Result<()> {
     match command {
         Commands::DownloadsInit { path } => {
             init_downloads_config(&path)?;
             Ok(())
         }
-        Commands::DownloadsOrganize { path } => {
+        Commands::DownloadsOrganize {
+            path,
+            dry_run,
+            format,
+            lock,
+        } => {
             let cfg = harbor_core::downloads::load_downloads_config(&path)?;
-            let summary = harbor_core::downloads::organize_once(&cfg)?;
-            for err in &summary.errors {
-                eprintln!("[Harbor] {err}");
-            }
-            for result in summary.moved {
-                let sym = result.symlink_info.unwrap_or_default();
-                println!(
-                    "{} -> {} ({}) {}",
-                    result.source.display(),
-                    result.destination.display(),
-                    result.rule_name,
-                    sym
-                );
+            if dry_run {
+                let plan = Organizer::builder(cfg).build().plan()?;
+                match format {
+                    OutputFormat::Table => write!(out, "{}", report::plan_table(&plan))?,
+                    OutputFormat::Json => writeln!(
+                        out,
+                        "{}",
+                        serde_json::to_string_pretty(&report::plan_json(&plan))?
+                    )?,
+                }
+                return Ok(());
             }
+            let lock = lock.unwrap_or_else(harbor_lock_path);
+            let webhook = start_webhook(&cfg)?;
+            let (tx, rx) = std::sync::mpsc::channel();
+            let result = std::thread::scope(|scope| {
+                let worker = scope.spawn(move || {
+                    harbor_core::downloads::organize_with_events(
+                        &cfg,
+                        Some(&lock),
+                        &tx,
+                        &CancellationToken::new(),
+                    )
+                });
+                let mut pass = Vec::new();
+                for event in rx {
+                    match format {
+                        OutputFormat::Table => print_event(&event),
+                        OutputFormat::Json => report::print_event_json(&event),
+                    }
+                    publish_pass(webhook.as_ref(), &mut pass, event);
+                }
+                worker.join().expect("organize thread panicked")
+            });
+            finish_webhook(webhook.as_ref());
+            result?;
             Ok(())
         }
         Commands::DownloadsWatch {
             path,
             interval_secs,
+            dry_run,
+            format,
+            lock,
         } => {
             let cfg = harbor_core::downloads::load_downloads_config(&path)?;
             let should_continue = shutdown_signal
                 .unwrap_or_else(|| std::sync::Arc::new(std::sync::atomic::AtomicBool::new(true)));
-            harbor_core::downloads::watch_polling(
-                &cfg,
-                interval_secs,
-                &should_continue,
-                |actions| {
-                    for result in actions {
-                        let sym = result.symlink_info.as_deref().unwrap_or_default();
-                        println!(
-                            "{} -> {} ({}) {}",
-                            result.source.display(),
-                            result.destination.display(),
-                            result.rule_name,
-                            sym
-                        );
+            if dry_run {
+                watch_plan(
+                    &Organizer::builder(cfg).build(),
+                    interval_secs,
+                    &should_continue,
+                    format,
+                    out,
+                );
+                return Ok(());
+            }
+            let lock = lock.unwrap_or_else(harbor_lock_path);
+            let webhook = start_webhook(&cfg)?;
+            let (tx, rx) = std::sync::mpsc::channel();
+            let result = std::thread::scope(|scope| {
+                let worker = scope.spawn(move || {
+                    harbor_core::downloads::watch_with_events(
+                        &cfg,
+                        Some(&lock),
+                        interval_secs,
+                        &should_continue,
+                        &tx,
+                    )
+                });
+                let mut pass = Vec::new();
+                for event in rx {
+                    match format {
+                        OutputFormat::Table
+                            if !matches!(event, OrganizeEvent::ScanFinished { .. }) =>
+                        {
+                            print_event(&event)
+                        }
+                        OutputFormat::Table => {}
+                        OutputFormat::Json => report::print_event_json(&event),
                     }
-                },
-            )?;
+                    publish_pass(webhook.as_ref(), &mut pass, event);
+                }
+                worker.join().expect("watch thread panicked")
+            });
+            finish_webhook(webhook.as_ref());
+            result?;
             Ok(())
         }
         Commands::TrayInstall { source } => tray_install(source, None, None),
         Commands::TrayUninstall => tray_uninstall(None),
+        Commands::Daemon {
+            config,
+            interval_secs,
+            pid_file,
+        } => {
+            migrate_legacy_files();
+            let config = match config {
+                Some(config) => std::path::absolute(config)?,
+                None => harbor_config_path(),
+            };
+            let mut paths = daemon::DaemonPaths::shared(config);
+            if let Some(pid_file) = pid_file {
+                paths.pid_file = pid_file;
+            }
+            let (terminate, hangup) = daemon::signal_flags();
+            daemon::run(paths, interval_secs, terminate, hangup)
+        }
+        Commands::DaemonUnit { config } => {
+            print!("{}", daemon_unit(config)?);
+            Ok(())
+        }
+        Commands::DaemonInstall { config } => {
+            if !cfg!(target_os = "linux") {
+                bail!("systemd user units are only available on Linux");
+            }
+            let path = daemon::install_unit(&daemon_unit(config)?, &daemon::systemd_user_dir())?;
+            println!("Installed {}", path.display());
+            println!(
+                "Start it with: systemctl --user daemon-reload && systemctl --user enable --now {}",
+                daemon::UNIT_NAME
+            );
+            Ok(())
+        }
+        Commands::Ctl { socket, command } => {
+            let client = ControlClient::new(socket.unwrap_or_else(harbor_control_path));
+            match command {
+                CtlCommand::Status => print_report(&client.status()?),
+                CtlCommand::Pause { minutes } => print_report(&client.pause(minutes)?),
+                CtlCommand::Resume => print_report(&client.resume()?),
+                CtlCommand::ReloadConfig => print_report(&client.reload_config()?),
+                CtlCommand::OrganizeNow => {
+                    let report = client.organize_now()?;
+                    for error in &report.errors {
+                        eprintln!("[Harbor] {error}");
+                    }
+                    println!(
+                        "{} moved, {} skipped, {} left for later passes",
+                        report.moved, report.skipped, report.deferred
+                    );
+                }
+                CtlCommand::TailEvents => client.tail_events(|event| {
+                    serde_json::to_string(&event)
+                        .is_ok_and(|line| writeln!(stdout(), "{line}").is_ok())
+                })?,
+            }
+            Ok(())
+        }
+    }
+}
+
+/// Writes the plan to `out` every `interval_secs`, but only when it differs
+/// from the last one written, until `should_continue` is cleared. JSON plans
+/// are one line each.
+fn watch_plan(
+    organizer: &Organizer,
+    interval_secs: u64,
+    should_continue: &std::sync::atomic::AtomicBool,
+    format: OutputFormat,
+    out: &mut dyn Write,
+) {
+    use std::sync::atomic::Ordering;
+
+    let mut last = None;
+    while should_continue.load(Ordering::Relaxed) {
+        match organizer.plan() {
+            Ok(plan) => {
+                let text = match format {
+                    OutputFormat::Table => report::plan_table(&plan),
+                    OutputFormat::Json => format!("{}\n", report::plan_json(&plan)),
+                };
+                if last.as_ref() != Some(&text) {
+                    let _ = out.write_all(text.as_bytes()).and_then(|()| out.flush());
+                    last = Some(text);
+                }
+            }
+            Err(e) => eprintln!("[Harbor] Could not plan: {e:#}"),
+        }
+        // Sleep in small chunks so a stop request is noticed quickly.
+        let mut remaining = Duration::from_secs(interval_secs);
+        while !remaining.is_zero() && should_continue.load(Ordering::Relaxed) {
+            let chunk = remaining.min(Duration::from_millis(500));
+            std::thread::sleep(chunk);
+            remaining -= chunk;
+        }
+    }
+}
+
+/// Moves the shared files out of their pre-XDG location on Linux.
+fn migrate_legacy_files() {
+    match harbor_core::platform::migrate_legacy_files() {
+        Ok(migrated) => {
+            for path in migrated {
+                eprintln!("[Harbor] Moved {} to its new location", path.display());
+            }
+        }
+        Err(e) => eprintln!("[Harbor] Failed to move files from the old location: {e}"),
+    }
+}
+
+/// The daemon's systemd unit for this executable and `config`, by default
+/// the shared configuration file.
+fn daemon_unit(config: Option<PathBuf>) -> Result<String> {
+    let exe = std::env::current_exe()?;
+    let config = match config {
+        Some(config) => std::path::absolute(config)?,
+        None => harbor_config_path(),
+    };
+    Ok(daemon::systemd_unit(&exe, &config))
+}
+
+fn print_report(report: &ServiceReport) {
+    println!("{} (pid {}): {}", report.program, report.pid, report.state);
+    println!("  download folder: {}", report.download_dir);
+    println!("  config: {}", report.config_path);
+    let times = [
+        ("up for", report.uptime_secs.map(|secs| format!("{secs}s"))),
+        ("paused until", report.paused_until.clone()),
+        ("quiet until", report.quiet_until.clone()),
+        ("next scheduled run", report.next_scheduled_run.clone()),
+    ];
+    for (label, value) in times {
+        if let Some(value) = value {
+            println!("  {label}: {value}");
+        }
+    }
+}
+
+/// How long to wait for webhook deliveries before exiting.
+const WEBHOOK_GRACE: Duration = Duration::from_secs(30);
+
+fn start_webhook(cfg: &harbor_core::downloads::DownloadsConfig) -> Result<Option<Webhook>> {
+    cfg.webhook.clone().map(Webhook::start).transpose()
+}
+
+/// Collects the events of the current pass and hands them to `webhook` once
+/// the pass has finished.
+fn publish_pass(webhook: Option<&Webhook>, pass: &mut Vec<OrganizeEvent>, event: OrganizeEvent) {
+    let Some(webhook) = webhook else {
+        return;
+    };
+    let finished = matches!(event, OrganizeEvent::ScanFinished { .. });
+    pass.push(event);
+    if finished {
+        webhook.publish(pass, &SystemClock);
+        pass.clear();
+    }
+}
+
+fn finish_webhook(webhook: Option<&Webhook>) {
+    if webhook.is_some_and(|webhook| !webhook.flush(WEBHOOK_GRACE)) {
+        eprintln!(
+            "[Harbor] Gave up waiting for webhook deliveries after {}s",
+            WEBHOOK_GRACE.as_secs()
+        );
+    }
+}
+
+/// Prints moves and purges to stdout, and failures, noteworthy skips, failed
+/// hooks and the end-of-pass summary to stderr.
+fn print_event(event: &OrganizeEvent) {
+    match event {
+        OrganizeEvent::FileMoved(result) => println!(
+            "{} -> {} ({}) {}",
+            result.source.display(),
+            result.destination.display(),
+            result.rule_name,
+            result.symlink_info.as_deref().unwrap_or_default()
+        ),
+        OrganizeEvent::FilePurged(result) => println!(
+            "purged {} ({}) {}",
+            result.path.display(),
+            result.rule_name,
+            result.describe()
+        ),
+        OrganizeEvent::FileSkipped {
+            path,
+            rule_name,
+            reason,
+        } if reason.is_noteworthy() => eprintln!(
+            "[Harbor] Skipped '{}' ({}): {reason}",
+            path.display(),
+            rule_name.as_deref().unwrap_or("no rule")
+        ),
+        OrganizeEvent::FileFailed {
+            path,
+            destination,
+            error,
+            ..
+        } => match destination {
+            Some(dest) => eprintln!(
+                "[Harbor] Failed to move '{}' to '{}': {error}",
+                path.display(),
+                dest.display()
+            ),
+            None => eprintln!("[Harbor] Failed to move '{}': {error}", path.display()),
+        },
+        OrganizeEvent::HookFinished(outcome) if !outcome.status.is_success() => eprintln!(
+            "[Harbor] Hook for '{}': {}",
+            outcome.destination.display(),
+            outcome.describe()
+        ),
+        OrganizeEvent::ScanFinished {
+            duration,
+            counts,
+            cancelled,
+        } => {
+            eprintln!(
+                "[Harbor] {} moved, {} skipped, {} failed in {:.1}s{}",
+                counts.moved,
+                counts.skipped,
+                counts.failed,
+                duration.as_secs_f64(),
+                if *cancelled { " (cancelled)" } else { "" }
+            );
+            if counts.deferred > 0 {
+                eprintln!(
+                    "[Harbor] {} file(s) left to organize in later passes",
+                    counts.deferred
+                );
+            }
+        }
+        _ => {}
     }
 }
 
@@ -254,6 +618,7 @@ mod tests {
                 path: path.to_string(),
             },
             None,
+            &mut Vec::new(),
         )
         .unwrap();
         let content = std::fs::read_to_string(path).unwrap();
@@ -292,13 +657,128 @@ rules:
 
         assert!(execute_command(
             Commands::DownloadsOrganize {
-                path: cfg_path.to_str().unwrap().to_string()
+                path: cfg_path.to_str().unwrap().to_string(),
+                dry_run: false,
+                format: OutputFormat::Table,
+                lock: Some(temp.path().join("organize.lock")),
             },
-            None
+            None,
+            &mut Vec::new()
         )
         .is_ok());
 
         assert!(temp.path().join("Target").join("test.txt").exists());
+        assert!(temp.path().join("organize.lock").exists());
+    }
+
+    #[test]
+    fn test_downloads_dry_run_moves_nothing() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dl_dir = temp.path().join("DL");
+        std::fs::create_dir(&dl_dir).unwrap();
+        std::fs::write(dl_dir.join("test.txt"), "content").unwrap();
+        std::fs::write(dl_dir.join("notes.xyz"), "content").unwrap();
+        let cfg_path = temp.path().join("config.yaml");
+        std::fs::write(
+            &cfg_path,
+            format!(
+                "download_dir: \"{}\"\nmin_age_secs: 0\nrules:\n  - name: test\n    extensions: [\"txt\"]\n    target_dir: \"{}\"\n",
+                dl_dir.display().to_string().replace("\\", "\\\\"),
+                temp.path()
+                    .join("Target")
+                    .display()
+                    .to_string()
+                    .replace("\\", "\\\\")
+            ),
+        )
+        .unwrap();
+        let path = cfg_path.to_str().unwrap().to_string();
+
+        let dry_run = |format| {
+            let mut out = Vec::new();
+            execute_command(
+                Commands::DownloadsOrganize {
+                    path: path.clone(),
+                    dry_run: true,
+                    format,
+                    lock: Some(temp.path().join("organize.lock")),
+                },
+                None,
+                &mut out,
+            )
+            .unwrap();
+            String::from_utf8(out).unwrap()
+        };
+
+        let table = dry_run(OutputFormat::Table);
+        let lines: Vec<&str> = table.lines().collect();
+        assert_eq!(lines.len(), 4, "{table}");
+        assert!(lines[0].starts_with("ACTION"));
+        let cells = |line: &str| {
+            line.split_whitespace()
+                .map(String::from)
+                .collect::<Vec<_>>()
+        };
+        let file = |dir: &str, name: &str| temp.path().join(dir).join(name).display().to_string();
+        assert_eq!(
+            cells(lines[1]),
+            [
+                "move",
+                &file("DL", "test.txt"),
+                "test",
+                &file("Target", "test.txt")
+            ]
+        );
+        assert_eq!(
+            cells(lines[2]),
+            [
+                "skip",
+                &file("DL", "notes.xyz"),
+                "-",
+                "no",
+                "matching",
+                "rule"
+            ]
+        );
+        assert_eq!(lines[3], "1 to move, 1 held back");
+
+        let json: serde_json::Value = serde_json::from_str(&dry_run(OutputFormat::Json)).unwrap();
+        assert_eq!(json["moves"][0]["rule_name"], "test");
+        assert_eq!(json["skipped"][0]["code"], "no_matching_rule");
+
+        // The watch loop plans once, then the writer stops it.
+        struct StopOnFlush(Vec<u8>, Arc<AtomicBool>);
+        impl Write for StopOnFlush {
+            fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+                self.0.write(buf)
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                self.1.store(false, std::sync::atomic::Ordering::Relaxed);
+                Ok(())
+            }
+        }
+        let running = Arc::new(AtomicBool::new(true));
+        let mut out = StopOnFlush(Vec::new(), running.clone());
+        execute_command(
+            Commands::DownloadsWatch {
+                path,
+                interval_secs: 1,
+                dry_run: true,
+                format: OutputFormat::Json,
+                lock: Some(temp.path().join("organize.lock")),
+            },
+            Some(running),
+            &mut out,
+        )
+        .unwrap();
+        let watched = String::from_utf8(out.0).unwrap();
+        assert_eq!(watched.lines().count(), 1);
+        let plan: serde_json::Value = serde_json::from_str(&watched).unwrap();
+        assert_eq!(plan["moves"].as_array().unwrap().len(), 1);
+        assert_eq!(plan["skipped"][0]["rule_name"], serde_json::Value::Null);
+
+        assert!(dl_dir.join("test.txt").exists());
+        assert!(!temp.path().join("Target").exists());
     }
 
     #[test]
@@ -320,13 +800,34 @@ rules:
         assert!(execute_command(
             Commands::DownloadsWatch {
                 path: cfg_path.to_str().unwrap().to_string(),
-                interval_secs: 1
+                interval_secs: 1,
+                dry_run: false,
+                format: OutputFormat::Table,
+                lock: Some(temp.path().join("organize.lock")),
             },
-            Some(signal)
+            Some(signal),
+            &mut Vec::new()
         )
         .is_ok());
     }
 
+    #[test]
+    fn test_ctl_without_a_running_service() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let err = execute_command(
+            Commands::Ctl {
+                socket: Some(temp.path().join("control.sock")),
+                command: CtlCommand::Status,
+            },
+            None,
+            &mut Vec::new(),
+        )
+        .unwrap_err();
+        assert!(err
+            .to_string()
+            .starts_with("no Harbor service is listening"));
+    }
+
     #[cfg(windows)]
     #[test]
     fn test_tray_install_uninstall() {
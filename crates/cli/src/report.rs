@@ -0,0 +1,183 @@
+//! `--format` output of `downloads-organize` and `downloads-watch`: dry-run
+//! plans, and events as JSON lines.
+
+use clap::ValueEnum;
+use harbor_core::events::{OrganizeEvent, OrganizeEventDto};
+use harbor_core::organizer::Plan;
+use serde_json::{json, Value};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// Aligned columns for people
+    Table,
+    /// JSON for scripts and CI checks
+    Json,
+}
+
+/// One row per planned move and file left in place, then a summary line.
+pub fn plan_table(plan: &Plan) -> String {
+    let mut rows = vec![[
+        "ACTION".to_string(),
+        "FILE".to_string(),
+        "RULE".to_string(),
+        "DESTINATION / REASON".to_string(),
+    ]];
+    for planned in &plan.moves {
+        rows.push([
+            "move".to_string(),
+            planned.source.display().to_string(),
+            planned.rule_name.clone(),
+            planned.destination.display().to_string(),
+        ]);
+    }
+    for skipped in &plan.skipped {
+        rows.push([
+            "skip".to_string(),
+            skipped.path.display().to_string(),
+            skipped.rule_name.clone().unwrap_or_else(|| "-".to_string()),
+            skipped.reason.to_string(),
+        ]);
+    }
+
+    let mut out = String::new();
+    if rows.len() > 1 {
+        let widths: Vec<usize> = (0..3)
+            .map(|col| {
+                rows.iter()
+                    .map(|row| row[col].chars().count())
+                    .max()
+                    .unwrap_or(0)
+            })
+            .collect();
+        for row in &rows {
+            for (cell, width) in row.iter().zip(&widths) {
+                out.push_str(&format!("{cell:<width$}  "));
+            }
+            out.push_str(&row[3]);
+            out.push('\n');
+        }
+    }
+    out.push_str(&format!(
+        "{} to move, {} held back",
+        plan.moves.len(),
+        plan.skipped.len() - plan.deferred
+    ));
+    if plan.deferred > 0 {
+        out.push_str(&format!(", {} left for later passes", plan.deferred));
+    }
+    out.push('\n');
+    out
+}
+
+/// The plan as JSON, with the field names of [`OrganizeEventDto`] and the
+/// skip reasons as both a stable `code` and a `message`.
+pub fn plan_json(plan: &Plan) -> Value {
+    let moves: Vec<Value> = plan
+        .moves
+        .iter()
+        .map(|planned| {
+            json!({
+                "source_path": planned.source.to_string_lossy(),
+                "dest_path": planned.destination.to_string_lossy(),
+                "rule_name": planned.rule_name,
+            })
+        })
+        .collect();
+    let skipped: Vec<Value> = plan
+        .skipped
+        .iter()
+        .map(|skipped| {
+            json!({
+                "source_path": skipped.path.to_string_lossy(),
+                "dest_path": skipped.destination.as_ref().map(|d| d.to_string_lossy()),
+                "rule_name": skipped.rule_name,
+                "code": skipped.reason.code(),
+                "message": skipped.reason.to_string(),
+            })
+        })
+        .collect();
+    json!({ "moves": moves, "skipped": skipped, "deferred": plan.deferred })
+}
+
+/// Prints `event` as one line of the JSON the control socket's `tail-events`
+/// sends.
+pub fn print_event_json(event: &OrganizeEvent) {
+    if let Ok(line) = serde_json::to_string(&OrganizeEventDto::from(event)) {
+        println!("{line}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harbor_core::downloads::{OrganizeResult, SkipReason};
+    use harbor_core::organizer::PlannedSkip;
+    use std::path::PathBuf;
+
+    fn plan() -> Plan {
+        Plan {
+            moves: vec![OrganizeResult {
+                source: PathBuf::from("/dl/report.pdf"),
+                destination: PathBuf::from("/docs/report (1).pdf"),
+                rule_name: "Documents".into(),
+                symlink_info: None,
+            }],
+            skipped: vec![
+                PlannedSkip {
+                    path: PathBuf::from("/dl/disk.iso"),
+                    rule_name: Some("Images".into()),
+                    destination: Some(PathBuf::from("/isos/disk.iso")),
+                    reason: SkipReason::InsufficientSpace {
+                        required: 10,
+                        available: 4,
+                    },
+                },
+                PlannedSkip {
+                    path: PathBuf::from("/dl/notes.xyz"),
+                    rule_name: None,
+                    destination: None,
+                    reason: SkipReason::NoMatchingRule,
+                },
+                PlannedSkip {
+                    path: PathBuf::from("/dl/movie.mkv"),
+                    rule_name: Some("Videos".into()),
+                    destination: None,
+                    reason: SkipReason::PassLimitReached,
+                },
+            ],
+            deferred: 1,
+        }
+    }
+
+    #[test]
+    fn test_plan_table_aligns_moves_and_skips() {
+        assert_eq!(
+            plan_table(&plan()),
+            "ACTION  FILE            RULE       DESTINATION / REASON\n\
+             move    /dl/report.pdf  Documents  /docs/report (1).pdf\n\
+             skip    /dl/disk.iso    Images     insufficient space: 10 bytes required, 4 available\n\
+             skip    /dl/notes.xyz   -          no matching rule\n\
+             skip    /dl/movie.mkv   Videos     left for a later pass by the per-pass limits\n\
+             1 to move, 2 held back, 1 left for later passes\n"
+        );
+        assert_eq!(plan_table(&Plan::default()), "0 to move, 0 held back\n");
+    }
+
+    #[test]
+    fn test_plan_json_lists_moves_skips_and_reasons() {
+        let value = plan_json(&plan());
+
+        assert_eq!(value["moves"][0]["dest_path"], "/docs/report (1).pdf");
+        assert_eq!(value["moves"][0]["rule_name"], "Documents");
+        assert_eq!(value["skipped"][0]["source_path"], "/dl/disk.iso");
+        assert_eq!(value["skipped"][0]["code"], "insufficient_space");
+        assert_eq!(
+            value["skipped"][0]["message"],
+            "insufficient space: 10 bytes required, 4 available"
+        );
+        assert_eq!(value["skipped"][1]["rule_name"], Value::Null);
+        assert_eq!(value["skipped"][1]["dest_path"], Value::Null);
+        assert_eq!(value["skipped"][1]["code"], "no_matching_rule");
+        assert_eq!(value["deferred"], 1);
+    }
+}
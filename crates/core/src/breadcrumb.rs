@@ -0,0 +1,440 @@
+//! Breadcrumbs: pointers left at a file's original location after a move.
+//!
+//! Each [`BreadcrumbKind`] is created here and recognised again later, so
+//! symlink cleanup, undo and the organize pass (which must not move a
+//! breadcrumb into a target folder a second time) all agree on what counts
+//! as one of ours.
+
+use crate::types::BreadcrumbKind;
+use crate::vfs::{normalize_lexically, FileMeta, FileSystem};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+/// Extension of shortcut breadcrumbs on this platform.
+#[cfg(windows)]
+pub const SHORTCUT_EXTENSION: &str = "url";
+#[cfg(not(windows))]
+pub const SHORTCUT_EXTENSION: &str = "desktop";
+
+/// Shortcut files larger than this are never ours and are not read.
+const MAX_SHORTCUT_BYTES: u64 = 64 * 1024;
+
+/// A breadcrumb found in the download folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Breadcrumb {
+    pub kind: BreadcrumbKind,
+    /// The moved file it points to.
+    pub target: PathBuf,
+}
+
+/// Why a breadcrumb was removed by a cleanup pass.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CleanupReason {
+    /// The file it pointed to no longer exists.
+    Dangling,
+    /// It was older than the configured breadcrumb lifetime.
+    Expired,
+}
+
+impl std::fmt::Display for CleanupReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            Self::Dangling => "dangling",
+            Self::Expired => "expired",
+        })
+    }
+}
+
+/// A breadcrumb deleted from the download folder.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RemovedBreadcrumb {
+    pub path: PathBuf,
+    pub kind: BreadcrumbKind,
+    /// The moved file it pointed to.
+    pub target: PathBuf,
+    /// The rule whose target folder holds `target`, if any still does.
+    pub rule_name: Option<String>,
+    pub reason: CleanupReason,
+}
+
+impl RemovedBreadcrumb {
+    /// Short human-readable description, e.g. `expired symlink to /docs/a.pdf`.
+    pub fn describe(&self) -> String {
+        format!(
+            "{} {} to {}",
+            self.reason,
+            label(self.kind).to_lowercase(),
+            self.target.display()
+        )
+    }
+}
+
+/// Summary returned by [`cleanup_breadcrumbs`](crate::downloads::cleanup_breadcrumbs).
+#[derive(Debug, Default)]
+pub struct BreadcrumbCleanup {
+    pub removed: Vec<RemovedBreadcrumb>,
+    pub errors: Vec<String>,
+}
+
+/// Human-readable name used in `symlink_info` and logs.
+pub fn label(kind: BreadcrumbKind) -> &'static str {
+    match kind {
+        BreadcrumbKind::Symlink => "Symlink",
+        BreadcrumbKind::RelativeSymlink => "Relative symlink",
+        BreadcrumbKind::Hardlink => "Hardlink",
+        BreadcrumbKind::Shortcut => "Shortcut",
+    }
+}
+
+/// Where a shortcut breadcrumb for `original` is written: the original name
+/// plus [`SHORTCUT_EXTENSION`], e.g. `report.pdf.desktop`.
+pub fn shortcut_path(original: &Path) -> PathBuf {
+    let name = original
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    original.with_file_name(format!("{name}.{SHORTCUT_EXTENSION}"))
+}
+
+/// Leaves a breadcrumb of `kind` at `original` pointing to `moved_to`.
+///
+/// A relative symlink falls back to an absolute one when no relative path
+/// exists, e.g. between two Windows drives.
+pub(crate) fn create(
+    fs: &dyn FileSystem,
+    kind: BreadcrumbKind,
+    original: &Path,
+    moved_to: &Path,
+) -> io::Result<()> {
+    match kind {
+        BreadcrumbKind::Symlink => {
+            fs.symlink_file(moved_to, original)?;
+            let _ = fs.hide(original);
+        }
+        BreadcrumbKind::RelativeSymlink => {
+            let target = original
+                .parent()
+                .and_then(|dir| relative_path(dir, moved_to))
+                .unwrap_or_else(|| moved_to.to_path_buf());
+            fs.symlink_file(&target, original)?;
+            let _ = fs.hide(original);
+        }
+        BreadcrumbKind::Hardlink => fs.hard_link(moved_to, original)?,
+        BreadcrumbKind::Shortcut => {
+            let name = original
+                .file_name()
+                .map(|n| n.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            fs.write_file(
+                &shortcut_path(original),
+                shortcut_contents(&name, moved_to).as_bytes(),
+            )?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns the breadcrumb at `path` if it points into one of `target_dirs`.
+///
+/// Hard links are only looked for when `meta` reports more than one link,
+/// and shortcut files only when they carry [`SHORTCUT_EXTENSION`], so plain
+/// downloads cost nothing extra.
+pub fn identify(
+    fs: &dyn FileSystem,
+    path: &Path,
+    meta: &FileMeta,
+    target_dirs: &[PathBuf],
+) -> Option<Breadcrumb> {
+    let inside = |target: &Path| target_dirs.iter().any(|d| target.starts_with(d));
+    if meta.is_symlink() {
+        let raw = fs.read_link(path).ok()?;
+        let kind = if raw.is_relative() {
+            BreadcrumbKind::RelativeSymlink
+        } else {
+            BreadcrumbKind::Symlink
+        };
+        let target = resolve_link(path, &raw);
+        return inside(&target).then_some(Breadcrumb { kind, target });
+    }
+    if !meta.is_file() {
+        return None;
+    }
+    if meta.links > 1 {
+        if let Some(target) = find_hard_link(fs, path, meta, target_dirs) {
+            return Some(Breadcrumb {
+                kind: BreadcrumbKind::Hardlink,
+                target,
+            });
+        }
+    }
+    let target = shortcut_target(fs, path, meta)?;
+    inside(&target).then_some(Breadcrumb {
+        kind: BreadcrumbKind::Shortcut,
+        target,
+    })
+}
+
+/// The breadcrumbs that were left for a move from `original` to `moved_to`:
+/// a symlink or hard link at `original`, or a shortcut next to it.
+pub(crate) fn find_for(fs: &dyn FileSystem, original: &Path, moved_to: &Path) -> Vec<PathBuf> {
+    let mut found = Vec::new();
+    if let Ok(meta) = fs.metadata(original) {
+        let ours = if meta.is_symlink() {
+            fs.read_link(original)
+                .is_ok_and(|raw| resolve_link(original, &raw) == moved_to)
+        } else {
+            meta.is_file() && meta.links > 1 && fs.same_file(original, moved_to)
+        };
+        if ours {
+            found.push(original.to_path_buf());
+        }
+    }
+    let shortcut = shortcut_path(original);
+    if let Ok(meta) = fs.metadata(&shortcut) {
+        if shortcut_target(fs, &shortcut, &meta).as_deref() == Some(moved_to) {
+            found.push(shortcut);
+        }
+    }
+    found
+}
+
+/// Absolute path a symlink at `link` with target `raw` points to.
+fn resolve_link(link: &Path, raw: &Path) -> PathBuf {
+    if raw.is_relative() {
+        normalize_lexically(&link.parent().unwrap_or(Path::new("")).join(raw))
+    } else {
+        raw.to_path_buf()
+    }
+}
+
+/// Another name of `path` anywhere below `target_dirs`. Subfolders count
+/// because a templated target files into folders under its static prefix.
+fn find_hard_link(
+    fs: &dyn FileSystem,
+    path: &Path,
+    meta: &FileMeta,
+    target_dirs: &[PathBuf],
+) -> Option<PathBuf> {
+    let mut pending = target_dirs.to_vec();
+    while let Some(dir) = pending.pop() {
+        for candidate in fs.read_dir(&dir).unwrap_or_default() {
+            let Ok(found) = fs.metadata(&candidate) else {
+                continue;
+            };
+            if found.is_dir() {
+                pending.push(candidate);
+            } else if found.is_file()
+                && found.len == meta.len
+                && candidate != path
+                && fs.same_file(path, &candidate)
+            {
+                return Some(candidate);
+            }
+        }
+    }
+    None
+}
+
+/// The file a shortcut at `path` points to, if it is a small
+/// [`SHORTCUT_EXTENSION`] file with a `file://` URL.
+fn shortcut_target(fs: &dyn FileSystem, path: &Path, meta: &FileMeta) -> Option<PathBuf> {
+    let is_shortcut = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case(SHORTCUT_EXTENSION));
+    if !is_shortcut || !meta.is_file() || meta.len > MAX_SHORTCUT_BYTES {
+        return None;
+    }
+    let data = fs.read_file(path).ok()?;
+    let text = String::from_utf8(data).ok()?;
+    text.lines()
+        .find_map(|line| line.trim().strip_prefix("URL="))
+        .and_then(path_from_file_url)
+}
+
+#[cfg(windows)]
+fn shortcut_contents(_name: &str, target: &Path) -> String {
+    format!("[InternetShortcut]\r\nURL={}\r\n", file_url(target))
+}
+
+#[cfg(not(windows))]
+fn shortcut_contents(name: &str, target: &Path) -> String {
+    let name = name.replace(['\r', '\n'], " ");
+    format!(
+        "[Desktop Entry]\nVersion=1.0\nType=Link\nName={name}\nURL={}\n",
+        file_url(target)
+    )
+}
+
+/// Path from `from_dir` to `to` using `..` components, or `None` when they
+/// share no root (different drives) or either is relative.
+fn relative_path(from_dir: &Path, to: &Path) -> Option<PathBuf> {
+    if !from_dir.is_absolute() || !to.is_absolute() {
+        return None;
+    }
+    let (from_dir, to) = (normalize_lexically(from_dir), normalize_lexically(to));
+    let from: Vec<Component> = from_dir.components().collect();
+    let to: Vec<Component> = to.components().collect();
+    if from.first() != to.first() {
+        return None;
+    }
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    for component in &to[common..] {
+        relative.push(component);
+    }
+    Some(relative)
+}
+
+/// `file://` URL for an absolute path, percent-encoding everything except
+/// unreserved characters, `/` and `:`.
+fn file_url(path: &Path) -> String {
+    let mut text = path.to_string_lossy().replace('\\', "/");
+    if !text.starts_with('/') {
+        text.insert(0, '/');
+    }
+    let mut url = String::from("file://");
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                url.push(byte as char)
+            }
+            _ => url.push_str(&format!("%{byte:02X}")),
+        }
+    }
+    url
+}
+
+fn path_from_file_url(url: &str) -> Option<PathBuf> {
+    let encoded = url.strip_prefix("file://")?;
+    let mut bytes = Vec::with_capacity(encoded.len());
+    let mut iter = encoded.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let hex = [iter.next()?, iter.next()?];
+            let hex = std::str::from_utf8(&hex).ok()?;
+            bytes.push(u8::from_str_radix(hex, 16).ok()?);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    let text = String::from_utf8(bytes).ok()?;
+    #[cfg(windows)]
+    let text = {
+        // `/C:/Docs/a.pdf` → `C:\Docs\a.pdf`
+        let trimmed = match text.as_bytes() {
+            [b'/', _, b':', ..] => &text[1..],
+            _ => text.as_str(),
+        };
+        trimmed.replace('/', "\\")
+    };
+    Some(PathBuf::from(text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+
+    const ORIGINAL: &str = "/home/u/Downloads/my report.pdf";
+    const MOVED: &str = "/home/u/Docs/my report.pdf";
+
+    fn identify_at(fs: &MemoryFs, path: &Path) -> Option<Breadcrumb> {
+        let meta = fs.metadata(path).unwrap();
+        identify(fs, path, &meta, &[PathBuf::from("/home/u/Docs")])
+    }
+
+    #[test]
+    fn test_relative_path_climbs_to_common_ancestor() {
+        assert_eq!(
+            relative_path(
+                Path::new("/home/u/Downloads"),
+                Path::new("/home/u/Docs/a.pdf")
+            ),
+            Some(PathBuf::from("../Docs/a.pdf"))
+        );
+        assert_eq!(relative_path(Path::new("rel"), Path::new("/a")), None);
+    }
+
+    #[test]
+    fn test_file_url_round_trip() {
+        let url = file_url(Path::new(MOVED));
+        assert_eq!(url, "file:///home/u/Docs/my%20report.pdf");
+        assert_eq!(path_from_file_url(&url), Some(PathBuf::from(MOVED)));
+        assert_eq!(path_from_file_url("https://example.com"), None);
+    }
+
+    #[test]
+    fn test_each_kind_is_created_and_recognised() {
+        for kind in [
+            BreadcrumbKind::Symlink,
+            BreadcrumbKind::RelativeSymlink,
+            BreadcrumbKind::Hardlink,
+            BreadcrumbKind::Shortcut,
+        ] {
+            let fs = MemoryFs::new();
+            fs.add_file(MOVED, b"pdf".to_vec());
+            fs.add_dir("/home/u/Downloads");
+            let original = Path::new(ORIGINAL);
+
+            create(&fs, kind, original, Path::new(MOVED)).unwrap();
+
+            let at = if kind == BreadcrumbKind::Shortcut {
+                shortcut_path(original)
+            } else {
+                original.to_path_buf()
+            };
+            assert_eq!(
+                identify_at(&fs, &at),
+                Some(Breadcrumb {
+                    kind,
+                    target: PathBuf::from(MOVED)
+                }),
+                "{kind:?}"
+            );
+
+            assert_eq!(find_for(&fs, original, Path::new(MOVED)), vec![at.clone()]);
+            fs.remove_file(&at).unwrap();
+            assert!(fs.metadata(&at).is_err(), "{kind:?} not removed");
+            assert_eq!(fs.read(MOVED), Some(b"pdf".to_vec()));
+        }
+    }
+
+    #[test]
+    fn test_relative_symlink_target_is_relative() {
+        let fs = MemoryFs::new();
+        fs.add_file(MOVED, b"pdf".to_vec());
+        fs.add_dir("/home/u/Downloads");
+
+        create(
+            &fs,
+            BreadcrumbKind::RelativeSymlink,
+            Path::new(ORIGINAL),
+            Path::new(MOVED),
+        )
+        .unwrap();
+
+        assert_eq!(
+            fs.read_link(Path::new(ORIGINAL)).unwrap(),
+            PathBuf::from("../Docs/my report.pdf")
+        );
+    }
+
+    #[test]
+    fn test_unrelated_files_are_not_breadcrumbs() {
+        let fs = MemoryFs::new();
+        fs.add_file(
+            "/home/u/Downloads/app.desktop",
+            b"[Desktop Entry]\nExec=app\n".to_vec(),
+        );
+        fs.add_file("/home/u/Downloads/plain.pdf", b"pdf".to_vec());
+        fs.add_symlink("/home/u/Downloads/elsewhere", "/tmp/x");
+
+        for name in ["app.desktop", "plain.pdf", "elsewhere"] {
+            let path = Path::new("/home/u/Downloads").join(name);
+            assert_eq!(identify_at(&fs, &path), None, "{name}");
+        }
+    }
+}
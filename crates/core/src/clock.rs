@@ -0,0 +1,90 @@
+//! Source of the current time.
+//!
+//! Age gating, log timestamps and activity statistics read the time through
+//! [`Clock`] so tests can pin or advance it with [`ManualClock`] instead of
+//! depending on the wall clock.
+
+use chrono::{DateTime, Local};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+pub trait Clock: Send + Sync {
+    fn now(&self) -> SystemTime;
+
+    /// [`Clock::now`] in the local time zone, for log timestamps and
+    /// calendar-based windows such as "today".
+    fn local_now(&self) -> DateTime<Local> {
+        DateTime::from(self.now())
+    }
+}
+
+/// The real wall clock.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> SystemTime {
+        SystemTime::now()
+    }
+}
+
+/// A clock that only moves when told to.
+#[derive(Debug)]
+pub struct ManualClock(Mutex<SystemTime>);
+
+impl ManualClock {
+    pub fn new(now: SystemTime) -> Self {
+        Self(Mutex::new(now))
+    }
+
+    /// Starts at the given local date and time, e.g. `"2024-01-15 10:30:00"`.
+    ///
+    /// Panics if `local` does not parse or does not exist in the local time zone.
+    pub fn at_local(local: &str) -> Self {
+        let naive = chrono::NaiveDateTime::parse_from_str(local, "%Y-%m-%d %H:%M:%S")
+            .expect("ManualClock::at_local expects %Y-%m-%d %H:%M:%S");
+        let time = naive
+            .and_local_timezone(Local)
+            .earliest()
+            .expect("local time exists");
+        Self::new(time.into())
+    }
+
+    pub fn set(&self, now: SystemTime) {
+        *self.lock() = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.lock() += by;
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, SystemTime> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> SystemTime {
+        *self.lock()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_manual_clock_set_and_advance() {
+        let clock = ManualClock::at_local("2024-01-15 10:30:00");
+        assert_eq!(
+            clock.local_now().format("%Y-%m-%d %H:%M:%S").to_string(),
+            "2024-01-15 10:30:00"
+        );
+
+        clock.advance(Duration::from_secs(90));
+        assert_eq!(clock.local_now().format("%H:%M:%S").to_string(), "10:31:30");
+
+        clock.set(SystemTime::UNIX_EPOCH);
+        assert_eq!(clock.now(), SystemTime::UNIX_EPOCH);
+    }
+}
@@ -0,0 +1,694 @@
+//! Local control API of a running Harbor service.
+//!
+//! The desktop app and `harbor daemon` listen on a Unix domain socket, or a
+//! named pipe on Windows, at
+//! [`harbor_control_path`](crate::downloads::harbor_control_path).
+//! A client such as `harbor ctl` connects, writes one [`ControlRequest`] as a
+//! JSON line and reads one [`ControlReply`] line back. After `tail-events`
+//! the connection stays open and carries one [`OrganizeEventDto`] per line
+//! until either side closes it.
+//!
+//! Only the current user can connect: the socket file is made `0600`, and
+//! the pipe refuses remote clients.
+
+use crate::downloads::OrganizeSummary;
+use crate::events::{OrganizeEvent, OrganizeEventDto};
+use anyhow::{anyhow, bail, Context, Result};
+use chrono::{DateTime, Local, NaiveTime, TimeDelta, TimeZone};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+/// Longest request line the server reads.
+const MAX_REQUEST_BYTES: u64 = 64 * 1024;
+
+/// One command sent over the control socket, tagged by `command`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "kebab-case")]
+pub enum ControlRequest {
+    Status,
+    /// Stops the watcher for `minutes`, or until local midnight when unset.
+    Pause {
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        minutes: Option<u32>,
+    },
+    Resume,
+    OrganizeNow,
+    ReloadConfig,
+    TailEvents,
+}
+
+/// When a pause started at `now` ends: after `minutes`, or at the next local
+/// midnight when unset.
+pub fn pause_end(minutes: Option<u32>, now: DateTime<Local>) -> DateTime<Local> {
+    match minutes {
+        Some(minutes) => now + TimeDelta::minutes(minutes.into()),
+        None => now
+            .date_naive()
+            .succ_opt()
+            .and_then(|tomorrow| {
+                Local
+                    .from_local_datetime(&tomorrow.and_time(NaiveTime::MIN))
+                    .earliest()
+            })
+            .unwrap_or(now + TimeDelta::days(1)),
+    }
+}
+
+/// The answer to a [`ControlRequest`]: `{"ok": ...}` or `{"error": "..."}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ControlReply {
+    Ok(serde_json::Value),
+    Error(String),
+}
+
+/// What `status`, `pause`, `resume` and `reload-config` answer.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServiceReport {
+    /// `running`, `paused`, `stopped`, `restarting` or `degraded`.
+    pub state: String,
+    /// File name of the service's executable, e.g. `harbor-tauri-app`.
+    pub program: String,
+    pub pid: u32,
+    pub download_dir: String,
+    pub config_path: String,
+    #[serde(default)]
+    pub uptime_secs: Option<u64>,
+    /// RFC 3339.
+    #[serde(default)]
+    pub paused_until: Option<String>,
+    /// End of the current quiet hours, RFC 3339.
+    #[serde(default)]
+    pub quiet_until: Option<String>,
+    /// RFC 3339.
+    #[serde(default)]
+    pub next_scheduled_run: Option<String>,
+}
+
+/// What `organize-now` answers.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrganizeReport {
+    pub moved: usize,
+    pub skipped: usize,
+    pub deferred: usize,
+    pub errors: Vec<String>,
+}
+
+impl From<&OrganizeSummary> for OrganizeReport {
+    fn from(summary: &OrganizeSummary) -> Self {
+        Self {
+            moved: summary.moved.len(),
+            skipped: summary.skipped.len(),
+            deferred: summary.deferred,
+            errors: summary.errors.clone(),
+        }
+    }
+}
+
+/// The service side of the control socket. Errors are sent to the client as
+/// [`ControlReply::Error`].
+pub trait ControlHandler: Send + Sync {
+    fn status(&self) -> Result<ServiceReport>;
+    /// Pauses the watcher; see [`ControlRequest::Pause`].
+    fn pause(&self, minutes: Option<u32>) -> Result<ServiceReport>;
+    fn resume(&self) -> Result<ServiceReport>;
+    /// Runs one pass right away.
+    fn organize_now(&self) -> Result<OrganizeReport>;
+    /// Reads the configuration file again and applies it.
+    fn reload_config(&self) -> Result<ServiceReport>;
+    /// Events of every later pass, until the receiver is dropped.
+    fn subscribe(&self) -> Receiver<OrganizeEventDto>;
+}
+
+/// Fans organizer events out to `tail-events` subscribers. Clones share
+/// their subscribers.
+#[derive(Debug, Clone, Default)]
+pub struct EventHub {
+    subscribers: Arc<Mutex<Vec<Sender<OrganizeEventDto>>>>,
+}
+
+impl EventHub {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn subscribe(&self) -> Receiver<OrganizeEventDto> {
+        let (tx, rx) = mpsc::channel();
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.push(tx);
+        }
+        rx
+    }
+
+    /// Sends `event` to every subscriber and forgets those that went away.
+    pub fn publish(&self, event: &OrganizeEvent) {
+        let Ok(mut subscribers) = self.subscribers.lock() else {
+            return;
+        };
+        if subscribers.is_empty() {
+            return;
+        }
+        let dto = OrganizeEventDto::from(event);
+        subscribers.retain(|tx| tx.send(dto.clone()).is_ok());
+    }
+}
+
+/// Serves the control socket until dropped. Each connection is handled on
+/// its own thread.
+#[derive(Debug)]
+pub struct ControlServer {
+    path: PathBuf,
+    stop: Arc<AtomicBool>,
+}
+
+impl ControlServer {
+    /// Listens at `path`. Fails when another service already does; a socket
+    /// file left by a service that crashed is replaced.
+    pub fn start(path: &Path, handler: Arc<dyn ControlHandler>) -> Result<Self> {
+        let mut listener = transport::Listener::bind(path)?;
+        let stop = Arc::new(AtomicBool::new(false));
+        let stopped = stop.clone();
+        thread::Builder::new()
+            .name("harbor-control".into())
+            .spawn(move || loop {
+                let stream = listener.accept();
+                if stopped.load(Ordering::SeqCst) {
+                    return;
+                }
+                match stream {
+                    Ok(stream) => {
+                        let handler = handler.clone();
+                        thread::spawn(move || {
+                            let _ = serve(&stream, &*handler);
+                        });
+                    }
+                    Err(e) => {
+                        eprintln!("[Harbor] Control socket error: {e}");
+                        thread::sleep(std::time::Duration::from_millis(200));
+                    }
+                }
+            })
+            .context("start control socket thread")?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            stop,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        self.stop.store(true, Ordering::SeqCst);
+        // Wake the accept loop so it sees the flag.
+        let _ = transport::connect(&self.path);
+        transport::remove(&self.path);
+    }
+}
+
+fn serve(stream: &transport::Stream, handler: &dyn ControlHandler) -> io::Result<()> {
+    let mut line = String::new();
+    BufReader::new(stream.take(MAX_REQUEST_BYTES)).read_line(&mut line)?;
+    let reply = match serde_json::from_str::<ControlRequest>(&line) {
+        Ok(ControlRequest::TailEvents) => {
+            let events = handler.subscribe();
+            write_line(stream, &ControlReply::Ok(serde_json::Value::Null))?;
+            for event in events {
+                write_line(stream, &event)?;
+            }
+            return Ok(());
+        }
+        Ok(request) => match handle(handler, request) {
+            Ok(value) => ControlReply::Ok(value),
+            Err(e) => ControlReply::Error(format!("{e:#}")),
+        },
+        Err(e) => ControlReply::Error(format!("invalid request: {e}")),
+    };
+    write_line(stream, &reply)
+}
+
+fn handle(handler: &dyn ControlHandler, request: ControlRequest) -> Result<serde_json::Value> {
+    let value = match request {
+        ControlRequest::Status => serde_json::to_value(handler.status()?)?,
+        ControlRequest::Pause { minutes } => serde_json::to_value(handler.pause(minutes)?)?,
+        ControlRequest::Resume => serde_json::to_value(handler.resume()?)?,
+        ControlRequest::OrganizeNow => serde_json::to_value(handler.organize_now()?)?,
+        ControlRequest::ReloadConfig => serde_json::to_value(handler.reload_config()?)?,
+        ControlRequest::TailEvents => unreachable!("tail-events streams instead"),
+    };
+    Ok(value)
+}
+
+fn write_line(mut stream: &transport::Stream, value: &impl Serialize) -> io::Result<()> {
+    let mut text = serde_json::to_string(value)?;
+    text.push('\n');
+    stream.write_all(text.as_bytes())?;
+    stream.flush()
+}
+
+/// Talks to the service listening at a control socket. Every call opens its
+/// own connection.
+#[derive(Debug, Clone)]
+pub struct ControlClient {
+    path: PathBuf,
+}
+
+impl ControlClient {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+
+    pub fn status(&self) -> Result<ServiceReport> {
+        self.call(&ControlRequest::Status)
+    }
+
+    pub fn pause(&self, minutes: Option<u32>) -> Result<ServiceReport> {
+        self.call(&ControlRequest::Pause { minutes })
+    }
+
+    pub fn resume(&self) -> Result<ServiceReport> {
+        self.call(&ControlRequest::Resume)
+    }
+
+    pub fn organize_now(&self) -> Result<OrganizeReport> {
+        self.call(&ControlRequest::OrganizeNow)
+    }
+
+    pub fn reload_config(&self) -> Result<ServiceReport> {
+        self.call(&ControlRequest::ReloadConfig)
+    }
+
+    /// Calls `on_event` for every event the service publishes until it
+    /// returns `false` or the service closes the connection.
+    pub fn tail_events(&self, mut on_event: impl FnMut(OrganizeEventDto) -> bool) -> Result<()> {
+        let (reader, _) = self.request(&ControlRequest::TailEvents)?;
+        for line in reader.lines() {
+            let event = serde_json::from_str(&line.context("read event")?)
+                .context("invalid event from the service")?;
+            if !on_event(event) {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    fn call<T: DeserializeOwned>(&self, request: &ControlRequest) -> Result<T> {
+        let (_, value) = self.request(request)?;
+        serde_json::from_value(value).context("invalid reply from the service")
+    }
+
+    /// Sends `request` and returns the connection with the `ok` value.
+    fn request(
+        &self,
+        request: &ControlRequest,
+    ) -> Result<(BufReader<transport::Stream>, serde_json::Value)> {
+        let stream = transport::connect(&self.path).with_context(|| {
+            format!("no Harbor service is listening on {}", self.path.display())
+        })?;
+        write_line(&stream, request).context("send request")?;
+        let mut reader = BufReader::new(stream);
+        let mut line = String::new();
+        reader.read_line(&mut line).context("read reply")?;
+        if line.is_empty() {
+            bail!("the service closed the connection without replying");
+        }
+        match serde_json::from_str(&line).context("invalid reply from the service")? {
+            ControlReply::Ok(value) => Ok((reader, value)),
+            ControlReply::Error(message) => Err(anyhow!(message)),
+        }
+    }
+}
+
+#[cfg(unix)]
+mod transport {
+    use anyhow::{bail, Context, Result};
+    use std::fs::Permissions;
+    use std::io;
+    use std::os::unix::fs::{DirBuilderExt, PermissionsExt};
+    use std::os::unix::net::{UnixListener, UnixStream};
+    use std::path::Path;
+
+    pub type Stream = UnixStream;
+
+    pub struct Listener(UnixListener);
+
+    impl Listener {
+        pub fn bind(path: &Path) -> Result<Self> {
+            if let Some(dir) = path.parent() {
+                std::fs::DirBuilder::new()
+                    .recursive(true)
+                    .mode(0o700)
+                    .create(dir)
+                    .with_context(|| format!("create {}", dir.display()))?;
+            }
+            let listener = match bind_private(path) {
+                Ok(listener) => listener,
+                Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+                    if UnixStream::connect(path).is_ok() {
+                        bail!("another Harbor service is listening on {}", path.display());
+                    }
+                    // Left behind by a service that crashed.
+                    std::fs::remove_file(path)
+                        .with_context(|| format!("remove {}", path.display()))?;
+                    bind_private(path).with_context(|| format!("listen on {}", path.display()))?
+                }
+                Err(e) => return Err(e).with_context(|| format!("listen on {}", path.display())),
+            };
+            Ok(Self(listener))
+        }
+
+        pub fn accept(&mut self) -> io::Result<Stream> {
+            self.0.accept().map(|(stream, _)| stream)
+        }
+    }
+
+    /// Binds in a fresh `0700` folder next to `path`, makes the socket `0600`
+    /// there and only then links it to `path`, so other users can never
+    /// reach it. Unlike a umask, this leaves the rest of the process alone.
+    fn bind_private(path: &Path) -> io::Result<UnixListener> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        let staging = path.with_file_name(format!(".{name}.{}", std::process::id()));
+        // Left behind by a crashed process with the same pid.
+        let _ = std::fs::remove_dir_all(&staging);
+        std::fs::DirBuilder::new().mode(0o700).create(&staging)?;
+        let staged = staging.join("sock");
+        let bound = UnixListener::bind(&staged).and_then(|listener| {
+            std::fs::set_permissions(&staged, Permissions::from_mode(0o600))?;
+            match std::fs::hard_link(&staged, path) {
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    Err(io::Error::new(io::ErrorKind::AddrInUse, e))
+                }
+                linked => linked.map(|()| listener),
+            }
+        });
+        let _ = std::fs::remove_dir_all(&staging);
+        bound
+    }
+
+    pub fn connect(path: &Path) -> io::Result<Stream> {
+        UnixStream::connect(path)
+    }
+
+    pub fn remove(path: &Path) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+#[cfg(windows)]
+mod transport {
+    use anyhow::{bail, Context, Result};
+    use std::fs::File;
+    use std::io;
+    use std::os::windows::ffi::OsStrExt;
+    use std::os::windows::io::{AsRawHandle, FromRawHandle};
+    use std::path::Path;
+    use std::time::Duration;
+    use windows_sys::Win32::Foundation::{
+        ERROR_ACCESS_DENIED, ERROR_PIPE_BUSY, ERROR_PIPE_CONNECTED, INVALID_HANDLE_VALUE,
+    };
+    use windows_sys::Win32::Storage::FileSystem::{
+        FILE_FLAG_FIRST_PIPE_INSTANCE, PIPE_ACCESS_DUPLEX,
+    };
+    use windows_sys::Win32::System::Pipes::{
+        ConnectNamedPipe, CreateNamedPipeW, PIPE_READMODE_BYTE, PIPE_REJECT_REMOTE_CLIENTS,
+        PIPE_TYPE_BYTE, PIPE_UNLIMITED_INSTANCES, PIPE_WAIT,
+    };
+
+    /// A connected pipe instance; reads and writes go through `&File`.
+    pub type Stream = File;
+
+    pub struct Listener {
+        name: Vec<u16>,
+        /// The instance created by `bind`, handed out by the first `accept`.
+        next: Option<File>,
+    }
+
+    impl Listener {
+        pub fn bind(path: &Path) -> Result<Self> {
+            let name: Vec<u16> = path
+                .as_os_str()
+                .encode_wide()
+                .chain(std::iter::once(0))
+                .collect();
+            let first = match create_instance(&name, FILE_FLAG_FIRST_PIPE_INSTANCE) {
+                Ok(first) => first,
+                Err(e) if e.raw_os_error() == Some(ERROR_ACCESS_DENIED as i32) => {
+                    bail!("another Harbor service is listening on {}", path.display())
+                }
+                Err(e) => return Err(e).with_context(|| format!("listen on {}", path.display())),
+            };
+            Ok(Self {
+                name,
+                next: Some(first),
+            })
+        }
+
+        pub fn accept(&mut self) -> io::Result<Stream> {
+            let pipe = match self.next.take() {
+                Some(pipe) => pipe,
+                None => create_instance(&self.name, 0)?,
+            };
+            // SAFETY: `pipe` owns a valid pipe handle for the duration of the
+            // call; a null OVERLAPPED makes the call blocking.
+            let ok = unsafe { ConnectNamedPipe(pipe.as_raw_handle(), std::ptr::null_mut()) };
+            if ok == 0 {
+                let e = io::Error::last_os_error();
+                // The client connected before we started waiting.
+                if e.raw_os_error() != Some(ERROR_PIPE_CONNECTED as i32) {
+                    return Err(e);
+                }
+            }
+            Ok(pipe)
+        }
+    }
+
+    fn create_instance(name: &[u16], flags: u32) -> io::Result<File> {
+        // SAFETY: `name` is NUL-terminated; null security attributes select
+        // the default, which only grants the current user write access.
+        let handle = unsafe {
+            CreateNamedPipeW(
+                name.as_ptr(),
+                PIPE_ACCESS_DUPLEX | flags,
+                PIPE_TYPE_BYTE | PIPE_READMODE_BYTE | PIPE_WAIT | PIPE_REJECT_REMOTE_CLIENTS,
+                PIPE_UNLIMITED_INSTANCES,
+                4096,
+                4096,
+                0,
+                std::ptr::null(),
+            )
+        };
+        if handle == INVALID_HANDLE_VALUE {
+            return Err(io::Error::last_os_error());
+        }
+        // SAFETY: `handle` is a valid pipe handle that nothing else owns.
+        Ok(unsafe { File::from_raw_handle(handle) })
+    }
+
+    pub fn connect(path: &Path) -> io::Result<Stream> {
+        // Every instance can be busy for a moment between two accepts.
+        let mut attempts = 0;
+        loop {
+            match File::options().read(true).write(true).open(path) {
+                Err(e) if e.raw_os_error() == Some(ERROR_PIPE_BUSY as i32) && attempts < 50 => {
+                    attempts += 1;
+                    std::thread::sleep(Duration::from_millis(100));
+                }
+                result => return result,
+            }
+        }
+    }
+
+    pub fn remove(_path: &Path) {
+        // A pipe disappears with its last handle.
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[derive(Default)]
+    struct FakeService {
+        paused_for: Mutex<Option<Option<u32>>>,
+        events: EventHub,
+    }
+
+    impl FakeService {
+        fn report(&self) -> ServiceReport {
+            let paused = self.paused_for.lock().unwrap().is_some();
+            ServiceReport {
+                state: if paused { "paused" } else { "running" }.into(),
+                program: "harbor-test".into(),
+                pid: 7,
+                download_dir: "/home/u/Downloads".into(),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl ControlHandler for FakeService {
+        fn status(&self) -> Result<ServiceReport> {
+            Ok(self.report())
+        }
+
+        fn pause(&self, minutes: Option<u32>) -> Result<ServiceReport> {
+            if minutes == Some(0) {
+                bail!("Pause for at least one minute.");
+            }
+            *self.paused_for.lock().unwrap() = Some(minutes);
+            Ok(self.report())
+        }
+
+        fn resume(&self) -> Result<ServiceReport> {
+            *self.paused_for.lock().unwrap() = None;
+            Ok(self.report())
+        }
+
+        fn organize_now(&self) -> Result<OrganizeReport> {
+            Ok(OrganizeReport {
+                moved: 2,
+                ..Default::default()
+            })
+        }
+
+        fn reload_config(&self) -> Result<ServiceReport> {
+            Err(crate::lock::OrganizerBusy { holder: None }.into())
+        }
+
+        fn subscribe(&self) -> Receiver<OrganizeEventDto> {
+            self.events.subscribe()
+        }
+    }
+
+    fn socket_path(dir: &Path) -> PathBuf {
+        if cfg!(windows) {
+            PathBuf::from(format!(r"\\.\pipe\harbor-test-{}", uuid::Uuid::new_v4()))
+        } else {
+            dir.join("control.sock")
+        }
+    }
+
+    #[test]
+    fn test_client_drives_the_service_through_the_socket() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = socket_path(dir.path());
+        let service = Arc::new(FakeService::default());
+        let _server = ControlServer::start(&path, service.clone()).unwrap();
+        let client = ControlClient::new(&path);
+
+        assert_eq!(client.status().unwrap().state, "running");
+        assert_eq!(client.pause(Some(15)).unwrap().state, "paused");
+        assert_eq!(*service.paused_for.lock().unwrap(), Some(Some(15)));
+        assert_eq!(
+            client.pause(Some(0)).unwrap_err().to_string(),
+            "Pause for at least one minute."
+        );
+        assert_eq!(client.resume().unwrap().state, "running");
+        assert_eq!(client.organize_now().unwrap().moved, 2);
+        assert_eq!(
+            client.reload_config().unwrap_err().to_string(),
+            "another Harbor instance is organizing"
+        );
+
+        let mut raw = transport::connect(&path).unwrap();
+        raw.write_all(b"{\"command\":\"shutdown\"}\n").unwrap();
+        let mut reply = String::new();
+        BufReader::new(raw).read_line(&mut reply).unwrap();
+        assert!(matches!(
+            serde_json::from_str(&reply).unwrap(),
+            ControlReply::Error(message) if message.starts_with("invalid request")
+        ));
+    }
+
+    #[test]
+    fn test_tail_events_streams_later_events() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = socket_path(dir.path());
+        let service = Arc::new(FakeService::default());
+        let _server = ControlServer::start(&path, service.clone()).unwrap();
+        let client = ControlClient::new(&path);
+
+        let mut seen = Vec::new();
+        thread::scope(|scope| {
+            scope.spawn(|| {
+                client
+                    .tail_events(|event| {
+                        seen.push(event);
+                        seen.len() < 2
+                    })
+                    .unwrap()
+            });
+            while service.events.subscribers.lock().unwrap().is_empty() {
+                thread::sleep(std::time::Duration::from_millis(10));
+            }
+            for dir in ["a", "b"] {
+                service.events.publish(&OrganizeEvent::ScanStarted {
+                    download_dir: PathBuf::from(dir),
+                });
+            }
+        });
+
+        assert_eq!(
+            seen,
+            vec![
+                OrganizeEventDto::ScanStarted {
+                    download_dir: "a".into()
+                },
+                OrganizeEventDto::ScanStarted {
+                    download_dir: "b".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_only_one_service_listens_at_a_time() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = socket_path(dir.path());
+        let service = Arc::new(FakeService::default());
+
+        let server = ControlServer::start(&path, service.clone()).unwrap();
+        let err = ControlServer::start(&path, service.clone()).unwrap_err();
+        assert!(err.to_string().starts_with("another Harbor service"));
+        drop(server);
+
+        assert!(ControlClient::new(&path).status().is_err());
+        if cfg!(unix) {
+            // A socket file left by a crashed service does not block the next.
+            std::fs::write(&path, b"").unwrap();
+        }
+        let _server = ControlServer::start(&path, service).unwrap();
+        assert!(ControlClient::new(&path).status().is_ok());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_socket_and_its_new_folder_are_private() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("harbor").join("control.sock");
+        let _server = ControlServer::start(&path, Arc::new(FakeService::default())).unwrap();
+
+        let mode = |path: &Path| std::fs::metadata(path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode(path.parent().unwrap()), 0o700);
+        assert_eq!(mode(&path), 0o600);
+        // The folder the socket was bound in is gone.
+        let entries: Vec<_> = std::fs::read_dir(path.parent().unwrap())
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name())
+            .collect();
+        assert_eq!(entries, vec![std::ffi::OsString::from("control.sock")]);
+        assert!(ControlClient::new(&path).status().is_ok());
+    }
+}
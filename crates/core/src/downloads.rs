@@ -1,29 +1,74 @@
-use crate::types::{new_rule_id, Rule};
-use anyhow::{Context, Result};
+use crate::breadcrumb::{self, BreadcrumbCleanup, CleanupReason, RemovedBreadcrumb};
+use crate::cancel::CancellationToken;
+use crate::clock::{Clock, SystemClock};
+use crate::events::{EventSender, OrganizeEvent, ScanCounts};
+use crate::hooks;
+use crate::lock::{OrganizerBusy, PassLock};
+use crate::multipart::{self, SplitSet};
+use crate::normalize::normalize_file_name;
+use crate::retention::{has_retention, PurgeResult};
+use crate::schedule::{self, QuietWindow, Schedule};
+use crate::sidecar::{self, SidecarMatcher};
+use crate::template;
+use crate::types::{HookCommand, QuietHours, Rule, SidecarConfig, WebhookConfig};
+use crate::vfs::{FileMeta, FileSystem, RealFs};
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Local};
 
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use std::fs;
 use std::path::{Path, PathBuf};
 use std::thread;
 
-#[cfg(windows)]
-use std::os::windows::process::CommandExt;
-use std::time::{Duration, SystemTime};
+use std::time::{Duration, Instant, SystemTime};
 
 /// Returns the Harbor application data directory (cross-platform).
 /// - macOS: `$HOME/Library/Application Support/Harbor`
 /// - Windows: `%LOCALAPPDATA%\Harbor`
+/// - Linux: `$XDG_DATA_HOME/harbor`
 pub fn harbor_app_dir() -> PathBuf {
     crate::platform::app_data_dir()
 }
 
+/// Returns the path to the configuration file:
+/// `<config_dir>/harbor.downloads.yaml`, where `config_dir` is
+/// `$XDG_CONFIG_HOME/harbor` on Linux and the app data directory elsewhere.
+pub fn harbor_config_path() -> PathBuf {
+    crate::platform::config_dir().join("harbor.downloads.yaml")
+}
+
 /// Returns the path to the recent moves log file:
 /// `<app_data_dir>/recent_moves.log`
 pub fn harbor_log_path() -> PathBuf {
     harbor_app_dir().join("recent_moves.log")
 }
 
+/// Returns the path to the move history used for undo:
+/// `<app_data_dir>/history.jsonl`
+pub fn harbor_history_path() -> PathBuf {
+    harbor_app_dir().join("history.jsonl")
+}
+
+/// Returns the path to the lock file that keeps passes from overlapping:
+/// `<app_data_dir>/organize.lock`; see [`crate::lock`].
+pub fn harbor_lock_path() -> PathBuf {
+    harbor_app_dir().join("organize.lock")
+}
+
+/// Returns where a running service listens for `harbor ctl`:
+/// `<app_data_dir>/control.sock`, or the named pipe
+/// `\\.\pipe\harbor-control-<user>` on Windows; see [`crate::control`].
+pub fn harbor_control_path() -> PathBuf {
+    if cfg!(windows) {
+        let user = std::env::var("USERNAME").unwrap_or_default();
+        PathBuf::from(format!(r"\\.\pipe\harbor-control-{user}"))
+    } else {
+        harbor_app_dir().join("control.sock")
+    }
+}
+
 /// A `Rule` with its regex pattern pre-compiled once for efficient reuse.
 struct CompiledRule<'a> {
     rule: &'a Rule,
@@ -31,27 +76,138 @@ struct CompiledRule<'a> {
     /// `Some(Ok(re))` → valid compiled regex.
     /// `Some(Err(_))` → pattern exists but is invalid; this rule must be skipped.
     compiled_pattern: Option<Result<Regex, regex::Error>>,
+    /// `Some(None)` → the schedule is invalid; the rule never moves anything.
+    schedule: Option<Option<Schedule>>,
 }
 
 impl<'a> CompiledRule<'a> {
     fn new(rule: &'a Rule) -> Self {
         let compiled_pattern = rule.pattern.as_deref().map(Regex::new);
+        let schedule = rule
+            .schedule
+            .as_deref()
+            .map(|expr| Schedule::parse(expr).ok());
         Self {
             rule,
             compiled_pattern,
+            schedule,
+        }
+    }
+
+    /// Whether the rule's schedule lets it move files in a pass at `now`
+    /// whose predecessor ran at `since`.
+    fn is_due(&self, since: Option<SystemTime>, now: SystemTime) -> bool {
+        match &self.schedule {
+            None => true,
+            Some(schedule) => schedule.as_ref().is_some_and(|s| s.is_due(since, now)),
         }
     }
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct DownloadsConfig {
     pub download_dir: String,
     pub rules: Vec<Rule>,
     pub min_age_secs: Option<u64>,
     pub tutorial_completed: Option<bool>,
     pub service_enabled: Option<bool>,
+    /// When a pause of the service ends, in RFC 3339. Kept here so that
+    /// restarting Harbor does not end the pause early.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub paused_until: Option<String>,
     pub check_updates: Option<bool>,
     pub last_notified_version: Option<String>,
+    /// Number of compressed activity-log archives to keep (defaults to [`LOG_MAX_ARCHIVES`]).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_archive_max_files: Option<usize>,
+    /// Archives older than this many days are deleted during rotation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub log_archive_max_age_days: Option<u64>,
+    /// How often the watcher applies rule retention policies
+    /// (defaults to [`DEFAULT_RETENTION_INTERVAL_SECS`](crate::retention::DEFAULT_RETENTION_INTERVAL_SECS)).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention_interval_secs: Option<u64>,
+    /// Free space to keep on a destination filesystem when a move has to copy
+    /// across devices; rules can override it with `min_free_bytes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_free_space_bytes: Option<u64>,
+    /// Breadcrumbs older than this many days are removed by the watcher and
+    /// at startup. Unset keeps them until the next startup.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breadcrumb_max_age_days: Option<u64>,
+    /// Moves subtitles, signatures and other companions together with their
+    /// primary file; unset leaves every file to its own rule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sidecars: Option<SidecarConfig>,
+    /// Moves multi-part archives (`movie.part1.rar`, `backup.7z.001`,
+    /// `data.z01`) as one set once every part is there; unset means on.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub split_archives: Option<bool>,
+    /// Commands run after every move, after the rule's own `on_moved` hooks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_moved: Option<Vec<HookCommand>>,
+    /// Receiver notified of finished passes and failed moves.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub webhook: Option<WebhookConfig>,
+    /// Cron expression limiting when the watcher organizes, e.g.
+    /// `* 9-17 * * mon-fri`; see [`crate::schedule`]. Unset organizes on every
+    /// poll. "Organize now" ignores it, but not the rules' own schedules.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+    /// Recurring windows during which the watcher neither organizes nor runs
+    /// retention, e.g. `{start: "22:00", end: "07:00"}`. "Organize now"
+    /// ignores them.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub quiet_hours: Option<Vec<QuietHours>>,
+    /// Most files a pass moves; the rest wait for later passes. The watcher
+    /// works through a large backlog a batch per poll instead of all at once.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_moves_per_pass: Option<usize>,
+    /// Most bytes a pass moves. The first move of a pass goes ahead even if
+    /// it is larger on its own.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_bytes_per_pass: Option<u64>,
+    /// Caps how fast moves across filesystems copy, e.g. onto a NAS. Unset
+    /// copies at full speed.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_copy_bytes_per_sec: Option<u64>,
+}
+
+impl DownloadsConfig {
+    /// `breadcrumb_max_age_days` as a duration.
+    pub fn breadcrumb_max_age(&self) -> Option<Duration> {
+        self.breadcrumb_max_age_days
+            .map(|days| Duration::from_secs(days.saturating_mul(24 * 60 * 60)))
+    }
+
+    /// When the global `schedule` next lets the watcher organize after
+    /// `after`; `None` without a (valid) schedule.
+    pub fn next_scheduled_run(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        Schedule::parse(self.schedule.as_deref()?)
+            .ok()?
+            .next_after(after)
+    }
+
+    /// The saved end of a pause, if there is one that is still running at
+    /// `now`.
+    pub fn pause_running_at(&self, now: DateTime<Local>) -> Option<DateTime<Local>> {
+        let until = DateTime::parse_from_rfc3339(self.paused_until.as_deref()?).ok()?;
+        Some(until.with_timezone(&Local)).filter(|until| *until > now)
+    }
+
+    /// When the quiet hours covering `at` end; `None` outside them. Invalid
+    /// windows are ignored here and rejected when the config is loaded.
+    pub fn quiet_until(&self, at: DateTime<Local>) -> Option<DateTime<Local>> {
+        schedule::quiet_until(&self.quiet_windows(), at)
+    }
+
+    fn quiet_windows(&self) -> Vec<QuietWindow> {
+        self.quiet_hours
+            .iter()
+            .flatten()
+            .filter_map(|hours| QuietWindow::parse(hours).ok())
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -62,19 +218,194 @@ pub struct OrganizeResult {
     pub symlink_info: Option<String>,
 }
 
-/// Summary returned by [`organize_once`], containing both successfully moved files
-/// and any per-file errors that occurred during the pass.
+/// Why a file was left in the download folder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SkipReason {
+    /// The file is a browser temp file (`.part`, `.crdownload`, ...).
+    PartialDownload,
+    /// A temp file for this name exists, so the download is still running.
+    DownloadInProgress,
+    /// Zero-byte placeholder created before a download starts.
+    EmptyFile,
+    /// Modified more recently than `min_age_secs`.
+    TooRecent,
+    /// No enabled rule matches the file.
+    NoMatchingRule,
+    /// The file is a breadcrumb left behind for a file Harbor moved.
+    Breadcrumb,
+    /// The file is a sidecar of a file that is not ready to move yet.
+    WaitingForPrimary,
+    /// The file is part of a multi-part archive that is not complete yet.
+    IncompleteSet,
+    /// The matching rule has a `schedule` that is not due.
+    OutsideSchedule,
+    /// The file is ready to move, but the pass already reached
+    /// `max_moves_per_pass` or `max_bytes_per_pass`.
+    PassLimitReached,
+    /// The destination filesystem cannot take the file while keeping the
+    /// configured minimum free space. `required` includes that minimum.
+    InsufficientSpace { required: u64, available: u64 },
+}
+
+impl SkipReason {
+    /// Stable snake_case identifier for logs and front-ends.
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::PartialDownload => "partial_download",
+            Self::DownloadInProgress => "download_in_progress",
+            Self::EmptyFile => "empty_file",
+            Self::TooRecent => "too_recent",
+            Self::NoMatchingRule => "no_matching_rule",
+            Self::Breadcrumb => "breadcrumb",
+            Self::WaitingForPrimary => "waiting_for_primary",
+            Self::IncompleteSet => "incomplete_set",
+            Self::OutsideSchedule => "outside_schedule",
+            Self::PassLimitReached => "pass_limit_reached",
+            Self::InsufficientSpace { .. } => "insufficient_space",
+        }
+    }
+
+    /// Returns `true` for skips the user should hear about. The rest are the
+    /// normal state of a download folder and would only add noise to the log.
+    pub fn is_noteworthy(&self) -> bool {
+        matches!(self, Self::InsufficientSpace { .. })
+    }
+}
+
+impl std::fmt::Display for SkipReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::PartialDownload => write!(f, "partial download"),
+            Self::DownloadInProgress => write!(f, "download in progress"),
+            Self::EmptyFile => write!(f, "empty file"),
+            Self::TooRecent => write!(f, "modified too recently"),
+            Self::NoMatchingRule => write!(f, "no matching rule"),
+            Self::Breadcrumb => write!(f, "breadcrumb for a moved file"),
+            Self::WaitingForPrimary => write!(f, "waiting for its primary file"),
+            Self::IncompleteSet => write!(f, "waiting for the rest of its multi-part set"),
+            Self::OutsideSchedule => write!(f, "waiting for the rule's schedule"),
+            Self::PassLimitReached => write!(f, "left for a later pass by the per-pass limits"),
+            Self::InsufficientSpace {
+                required,
+                available,
+            } => write!(
+                f,
+                "insufficient space: {required} bytes required, {available} available"
+            ),
+        }
+    }
+}
+
+/// A file that matched a rule but was not moved; see [`SkipReason`].
+#[derive(Debug, Clone)]
+pub struct SkippedFile {
+    pub path: PathBuf,
+    pub destination: PathBuf,
+    pub rule_name: String,
+    pub reason: SkipReason,
+}
+
+/// Summary returned by [`organize_once`], containing successfully moved files,
+/// files that matched a rule but were skipped on purpose (e.g. not enough disk
+/// space) and any per-file errors that occurred during the pass.
 #[derive(Debug, Default)]
 pub struct OrganizeSummary {
     pub moved: Vec<OrganizeResult>,
+    pub skipped: Vec<SkippedFile>,
     pub errors: Vec<String>,
+    /// The pass was cancelled before every file was considered; the other
+    /// fields cover only the work finished until then.
+    pub cancelled: bool,
+    /// Files that were ready to move but were left for later passes by
+    /// `max_moves_per_pass` or `max_bytes_per_pass`. They are not listed in
+    /// `skipped`.
+    pub deferred: usize,
+    /// Total size of the `deferred` files.
+    pub deferred_bytes: u64,
+}
+
+/// The built-in configuration: the platform Downloads folder with one rule
+/// per common file category.
+pub fn default_config() -> DownloadsConfig {
+    let dl = crate::platform::downloads_dir();
+    let dl_str = dl.to_string_lossy().to_string();
+    let preset = |name: &str, extensions: &[&str], folder: &str| Rule {
+        name: name.to_string(),
+        extensions: Some(extensions.iter().map(|s| s.to_string()).collect()),
+        target_dir: dl.join(folder).to_string_lossy().to_string(),
+        ..Default::default()
+    };
+
+    DownloadsConfig {
+        download_dir: dl_str,
+        min_age_secs: Some(5),
+        tutorial_completed: Some(false),
+        service_enabled: Some(false),
+        paused_until: None,
+        check_updates: Some(true),
+        last_notified_version: None,
+        log_archive_max_files: None,
+        log_archive_max_age_days: None,
+        retention_interval_secs: None,
+        min_free_space_bytes: None,
+        breadcrumb_max_age_days: None,
+        sidecars: None,
+        split_archives: None,
+        on_moved: None,
+        webhook: None,
+        schedule: None,
+        quiet_hours: None,
+        max_moves_per_pass: None,
+        max_bytes_per_pass: None,
+        max_copy_bytes_per_sec: None,
+        rules: vec![
+            preset(
+                "Images",
+                &[
+                    "jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "heic", "svg", "avif",
+                ],
+                "Images",
+            ),
+            preset(
+                "Videos",
+                &["mp4", "mkv", "avi", "mov", "wmv", "webm"],
+                "Videos",
+            ),
+            preset("Music", &["mp3", "flac", "wav", "aac", "ogg"], "Music"),
+            preset(
+                "Archives",
+                &["zip", "rar", "7z", "tar", "gz", "xz"],
+                "Archives",
+            ),
+            preset(
+                "Documents",
+                &[
+                    "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "txt", "rtf",
+                ],
+                "Documents",
+            ),
+            preset(
+                "Installers",
+                &["exe", "msi", "msix", "dmg", "pkg", "apk"],
+                "Installers",
+            ),
+            preset("ISOs", &["iso"], "ISOs"),
+            preset("Torrents", &["torrent"], "Torrents"),
+            preset("Dev", &["json", "env", "xml", "plist"], "Dev"),
+            preset("Web Pages", &["html", "htm"], "Webpages"),
+            preset("Subtitles", &["srt", "vtt"], "Subtitles"),
+        ],
+    }
 }
 
 /// Loads and parses the downloads configuration file.
 ///
 /// This function reads a YAML file from the specified path, parses it into a
 /// `DownloadsConfig` struct, and expands environment variables (like `%USERPROFILE%`)
-/// in the paths.
+/// in the paths. A variable that is not defined and has no `${VAR:-default}`
+/// fallback is an error naming `download_dir`, the rule or the webhook
+/// setting it appears in. The webhook `url` and `secret` are expanded too, so
+/// the secret can be kept out of the file as `${HARBOR_WEBHOOK_SECRET}`.
 ///
 /// # Arguments
 ///
@@ -89,201 +420,62 @@ pub struct OrganizeSummary {
 ///     println!("Monitoring {}", cfg.download_dir);
 /// }
 /// ```
-pub fn default_config() -> DownloadsConfig {
-    let dl = crate::platform::downloads_dir();
-    let dl_str = dl.to_string_lossy().to_string();
-    let pictures = dl.join("Images");
-    let videos = dl.join("Videos");
-    let music = dl.join("Music");
-    let docs = dl.join("Documents");
-    let archives = dl.join("Archives");
-    let installers = dl.join("Installers");
-    let torrents = dl.join("Torrents");
-    let isos = dl.join("ISOs");
-    let dev = dl.join("Dev");
-    let subtitles = dl.join("Subtitles");
-    let webpages = dl.join("Webpages");
-
-    DownloadsConfig {
-        download_dir: dl_str,
-        min_age_secs: Some(5),
-        tutorial_completed: Some(false),
-        service_enabled: Some(false),
-        check_updates: Some(true),
-        last_notified_version: None,
-        rules: vec![
-            Rule {
-                id: new_rule_id(),
-                name: "Images".to_string(),
-                extensions: Some(
-                    [
-                        "jpg", "jpeg", "png", "gif", "bmp", "webp", "tiff", "heic", "svg", "avif",
-                    ]
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect(),
-                ),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: pictures.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "Videos".to_string(),
-                extensions: Some(
-                    ["mp4", "mkv", "avi", "mov", "wmv", "webm"]
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect(),
-                ),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: videos.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "Music".to_string(),
-                extensions: Some(
-                    ["mp3", "flac", "wav", "aac", "ogg"]
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect(),
-                ),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: music.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "Archives".to_string(),
-                extensions: Some(
-                    ["zip", "rar", "7z", "tar", "gz", "xz"]
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect(),
-                ),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: archives.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "Documents".to_string(),
-                extensions: Some(
-                    [
-                        "pdf", "doc", "docx", "xls", "xlsx", "ppt", "pptx", "txt", "rtf",
-                    ]
-                    .iter()
-                    .map(|s| s.to_string())
-                    .collect(),
-                ),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: docs.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "Installers".to_string(),
-                extensions: Some(
-                    ["exe", "msi", "msix", "dmg", "pkg", "apk"]
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect(),
-                ),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: installers.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "ISOs".to_string(),
-                extensions: Some(["iso"].iter().map(|s| s.to_string()).collect()),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: isos.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "Torrents".to_string(),
-                extensions: Some(["torrent"].iter().map(|s| s.to_string()).collect()),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: torrents.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "Dev".to_string(),
-                extensions: Some(
-                    ["json", "env", "xml", "plist"]
-                        .iter()
-                        .map(|s| s.to_string())
-                        .collect(),
-                ),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: dev.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "Web Pages".to_string(),
-                extensions: Some(["html", "htm"].iter().map(|s| s.to_string()).collect()),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: webpages.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-            Rule {
-                id: new_rule_id(),
-                name: "Subtitles".to_string(),
-                extensions: Some(["srt", "vtt"].iter().map(|s| s.to_string()).collect()),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: subtitles.to_string_lossy().to_string(),
-                create_symlink: false,
-                enabled: true,
-            },
-        ],
-    }
-}
-
 pub fn load_downloads_config(path: impl AsRef<Path>) -> Result<DownloadsConfig> {
     let p = path.as_ref();
     let content = fs::read_to_string(p).with_context(|| format!("read {}", p.display()))?;
     let mut cfg: DownloadsConfig =
         serde_yaml::from_str(&content).context("parse downloads yaml")?;
-    cfg.download_dir = expand_env(&cfg.download_dir);
+    let mut problems = Vec::new();
+    match expand_env_strict(&cfg.download_dir) {
+        Ok(dir) => cfg.download_dir = dir,
+        Err(e) => problems.push(format!("download_dir: {e}")),
+    }
     for r in cfg.rules.iter_mut() {
-        r.target_dir = expand_env(&r.target_dir);
+        match expand_env_strict(&r.target_dir) {
+            Ok(dir) => r.target_dir = dir,
+            Err(e) => problems.push(format!("rule \"{}\" target_dir: {e}", r.name)),
+        }
+    }
+    if let Some(webhook) = cfg.webhook.as_mut() {
+        match expand_env_strict(&webhook.url) {
+            Ok(url) => webhook.url = url,
+            Err(e) => problems.push(format!("webhook url: {e}")),
+        }
+        if let Some(secret) = webhook.secret.as_mut() {
+            match expand_env_strict(secret) {
+                Ok(value) => *secret = value,
+                Err(e) => problems.push(format!("webhook secret: {e}")),
+            }
+        }
+    }
+    if !problems.is_empty() {
+        bail!(
+            "cannot expand environment variables in {}:\n  {}",
+            p.display(),
+            problems.join("\n  ")
+        );
+    }
+    let schedules = std::iter::once(("schedule".to_string(), &cfg.schedule)).chain(
+        cfg.rules
+            .iter()
+            .map(|r| (format!("rule \"{}\" schedule", r.name), &r.schedule)),
+    );
+    for (field, schedule) in schedules {
+        if let Some(Err(e)) = schedule.as_deref().map(Schedule::parse) {
+            problems.push(format!("{field}: {e:#}"));
+        }
+    }
+    for (i, hours) in cfg.quiet_hours.iter().flatten().enumerate() {
+        if let Err(e) = QuietWindow::parse(hours) {
+            problems.push(format!("quiet_hours[{i}]: {e:#}"));
+        }
+    }
+    if !problems.is_empty() {
+        bail!(
+            "invalid schedule in {}:\n  {}",
+            p.display(),
+            problems.join("\n  ")
+        );
     }
     Ok(cfg)
 }
@@ -297,7 +489,7 @@ fn is_partial(name: &str) -> bool {
         || lower.ends_with(".opdownload")
 }
 
-fn matches_rule(path: &Path, meta: &fs::Metadata, compiled: &CompiledRule<'_>) -> bool {
+fn matches_rule(path: &Path, meta: &FileMeta, compiled: &CompiledRule<'_>) -> bool {
     let rule = compiled.rule;
     if let Some(exts) = &rule.extensions {
         let ext = path
@@ -323,7 +515,7 @@ fn matches_rule(path: &Path, meta: &fs::Metadata, compiled: &CompiledRule<'_>) -
             }
         }
     }
-    let size: u64 = meta.len();
+    let size: u64 = meta.len;
     if let Some(min) = rule.min_size_bytes {
         if size < min {
             return false;
@@ -337,13 +529,76 @@ fn matches_rule(path: &Path, meta: &fs::Metadata, compiled: &CompiledRule<'_>) -
     true
 }
 
-fn ensure_dir(dir: &Path) -> Result<()> {
-    fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+/// Matcher for the files `rule` would pick up by extension, pattern and
+/// size, for retention to tell them apart from other files in its folder.
+pub(crate) fn rule_matcher(rule: &Rule) -> impl Fn(&Path, &FileMeta) -> bool + '_ {
+    let compiled = CompiledRule::new(rule);
+    move |path, meta| matches_rule(path, meta, &compiled)
+}
+
+/// Where `path` goes under `compiled`, before name conflicts are resolved.
+///
+/// The rule's `target_dir` and `rename_to` templates are filled from the
+/// pattern's captures on the file name, then the rule's
+/// [`NormalizeStep`](crate::types::NormalizeStep)s run on the new name.
+/// Names that are not valid UTF-8 are kept as they are.
+fn destination(compiled: &CompiledRule<'_>, path: &Path) -> PathBuf {
+    let rule = compiled.rule;
+    let name = path.file_name().unwrap_or_default();
+    let Some(text) = name.to_str() else {
+        return PathBuf::from(&rule.target_dir).join(name);
+    };
+
+    let mut values: HashMap<String, String> = HashMap::new();
+    let stem = Path::new(text).file_stem().and_then(|s| s.to_str());
+    values.insert(
+        template::NAME_PLACEHOLDER.to_string(),
+        stem.unwrap_or(text).to_string(),
+    );
+    if let Some(Ok(re)) = &compiled.compiled_pattern {
+        if let Some(caps) = re.captures(text) {
+            for group in re.capture_names().flatten() {
+                if let Some(m) = caps.name(group) {
+                    values.insert(group.to_string(), m.as_str().to_string());
+                }
+            }
+        }
+    }
+
+    let target_dir = PathBuf::from(template::render(&rule.target_dir, &values));
+    let mut new_name = text.to_string();
+    if let Some(rename_to) = &rule.rename_to {
+        let renamed = template::render(rename_to, &values);
+        if !renamed.trim().is_empty() {
+            new_name = match Path::new(text).extension().and_then(|e| e.to_str()) {
+                Some(ext) => format!("{renamed}.{ext}"),
+                None => renamed,
+            };
+        }
+    }
+    if let Some(steps) = rule.normalize.as_deref().filter(|s| !s.is_empty()) {
+        new_name = normalize_file_name(&new_name, steps);
+    }
+    target_dir.join(new_name)
+}
+
+/// `dir`, or its closest ancestor that exists. Target folders are created
+/// only right before a move, so free space is checked where they will be.
+fn existing_ancestor<'a>(fs: &dyn FileSystem, dir: &'a Path) -> &'a Path {
+    dir.ancestors().find(|p| fs.exists(p)).unwrap_or(dir)
+}
+
+fn ensure_dir(fs: &dyn FileSystem, dir: &Path) -> Result<()> {
+    fs.create_dir_all(dir)
+        .with_context(|| format!("create {}", dir.display()))?;
     Ok(())
 }
 
-fn unique_target(target: &Path) -> PathBuf {
-    if !target.exists() {
+/// Returns `target`, or `name (n).ext` if that is taken on disk or listed in
+/// `taken`. Dry runs use `taken` to keep planned destinations apart.
+fn unique_target(fs: &dyn FileSystem, target: &Path, taken: &HashSet<PathBuf>) -> PathBuf {
+    let free = |p: &Path| !fs.exists(p) && !taken.contains(p);
+    if free(target) {
         return target.to_path_buf();
     }
     let mut i = 1u32;
@@ -360,13 +615,151 @@ fn unique_target(target: &Path) -> PathBuf {
             format!("{} ({}).{}", stem, i, ext)
         };
         p.set_file_name(name);
-        if !p.exists() {
+        if free(&p) {
             return p;
         }
         i += 1;
     }
 }
 
+/// Decides whether a file of `size` bytes may be copied onto a filesystem with
+/// `available` free bytes while leaving `min_free` untouched.
+///
+/// Same-filesystem moves are plain renames that consume no space and are never
+/// skipped. If free space cannot be determined the move is attempted anyway.
+fn check_free_space(
+    size: u64,
+    min_free: u64,
+    cross_device: bool,
+    available: std::io::Result<u64>,
+) -> Option<SkipReason> {
+    if !cross_device {
+        return None;
+    }
+    let available = available.ok()?;
+    let required = size.saturating_add(min_free);
+    (available < required).then_some(SkipReason::InsufficientSpace {
+        required,
+        available,
+    })
+}
+
+/// Moves `src` to `dst`, falling back to copy + delete when they live on
+/// different filesystems.
+///
+/// The copy is written to a hidden `.harbor-partial` file next to `dst` and
+/// renamed into place only once complete, so a failed copy (e.g. disk full)
+/// never leaves a truncated file under the final name. With
+/// `max_bytes_per_sec` set, the copy is paced to that rate.
+pub(crate) fn move_file(
+    fs: &dyn FileSystem,
+    src: &Path,
+    dst: &Path,
+    max_bytes_per_sec: Option<u64>,
+    cancelled: &dyn Fn() -> bool,
+) -> std::io::Result<()> {
+    match fs.rename(src, dst) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            match max_bytes_per_sec.filter(|rate| *rate > 0) {
+                Some(rate) => copy_then_remove(fs, src, dst, &paced(rate, cancelled)),
+                None => copy_then_remove(fs, src, dst, cancelled),
+            }
+        }
+        other => other,
+    }
+}
+
+/// Wraps `cancelled` so that it holds each copy chunk back until the copy
+/// is no faster than `bytes_per_sec`. [`FileSystem::copy_file`] asks before
+/// every [`COPY_CHUNK_BYTES`](crate::vfs::COPY_CHUNK_BYTES) chunk, so the
+/// `n`th call waits until `n` chunks' worth of time has passed.
+fn paced(bytes_per_sec: u64, cancelled: &dyn Fn() -> bool) -> impl Fn() -> bool + '_ {
+    let started = Instant::now();
+    let chunks = std::cell::Cell::new(0u32);
+    move || {
+        let due = Duration::from_secs_f64(
+            f64::from(chunks.get()) * crate::vfs::COPY_CHUNK_BYTES as f64 / bytes_per_sec as f64,
+        );
+        chunks.set(chunks.get().saturating_add(1));
+        loop {
+            if cancelled() {
+                return true;
+            }
+            let wait = due.saturating_sub(started.elapsed());
+            if wait.is_zero() {
+                return false;
+            }
+            // Short naps keep cancellation responsive during long waits.
+            thread::sleep(wait.min(Duration::from_millis(100)));
+        }
+    }
+}
+
+/// Tracks `max_moves_per_pass` and `max_bytes_per_pass` over one pass.
+struct PassBudget {
+    max_moves: Option<usize>,
+    max_bytes: Option<u64>,
+    moves: usize,
+    bytes: u64,
+    /// Something did not fit. Everything after it waits too, so later passes
+    /// pick the backlog up in the same order.
+    exhausted: bool,
+}
+
+impl PassBudget {
+    fn new(cfg: &DownloadsConfig) -> Self {
+        Self {
+            max_moves: cfg.max_moves_per_pass,
+            max_bytes: cfg.max_bytes_per_pass,
+            moves: 0,
+            bytes: 0,
+            exhausted: false,
+        }
+    }
+
+    /// Whether `files` more files totalling `bytes` may move in this pass.
+    /// The first move of a pass is only held back by a `max_moves_per_pass`
+    /// of 0, so a file or set larger than the limits still gets through.
+    fn admits(&mut self, files: usize, bytes: u64) -> bool {
+        let first = self.moves == 0;
+        let fits = !self.exhausted
+            && self
+                .max_moves
+                .is_none_or(|max| max > 0 && (first || self.moves + files <= max))
+            && self
+                .max_bytes
+                .is_none_or(|max| first || self.bytes.saturating_add(bytes) <= max);
+        self.exhausted |= !fits;
+        fits
+    }
+
+    fn record(&mut self, files: usize, bytes: u64) {
+        self.moves += files;
+        self.bytes = self.bytes.saturating_add(bytes);
+    }
+}
+
+fn copy_then_remove(
+    fs: &dyn FileSystem,
+    src: &Path,
+    dst: &Path,
+    cancelled: &dyn Fn() -> bool,
+) -> std::io::Result<()> {
+    let name = dst
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let partial = dst.with_file_name(format!(".{name}.harbor-partial"));
+    if let Err(e) = fs
+        .copy_file(src, &partial, cancelled)
+        .and_then(|_| fs.rename(&partial, dst))
+    {
+        let _ = fs.remove_file(&partial);
+        return Err(e);
+    }
+    fs.remove_file(src)
+}
+
 /// Runs a single organization pass based on the provided configuration.
 ///
 /// Iterates through files in the `download_dir`, checks them against the defined `rules`,
@@ -392,126 +785,655 @@ fn rule_priority(index: usize, total: usize, rule: &Rule) -> usize {
 }
 
 pub fn organize_once(cfg: &DownloadsConfig) -> Result<OrganizeSummary> {
-    let base = PathBuf::from(&cfg.download_dir);
-    let min_age = Duration::from_secs(cfg.min_age_secs.unwrap_or(5));
-    let mut summary = OrganizeSummary::default();
+    organize_once_with(cfg, &RealFs, &SystemClock)
+}
 
-    // Pre-compile each rule's regex pattern once for this pass, then sort by priority.
-    let mut compiled_rules: Vec<(usize, CompiledRule<'_>)> = cfg
-        .rules
-        .iter()
-        .enumerate()
-        .map(|(i, r)| (i, CompiledRule::new(r)))
-        .collect();
-    let total = compiled_rules.len();
-    compiled_rules.sort_by_key(|(i, cr)| std::cmp::Reverse(rule_priority(*i, total, cr.rule)));
+/// Same as [`organize_once`], but performs all file operations through `fs`
+/// and judges file age (`min_age_secs`) by `clock`.
+pub fn organize_once_with(
+    cfg: &DownloadsConfig,
+    fs: &dyn FileSystem,
+    clock: &dyn Clock,
+) -> Result<OrganizeSummary> {
+    organize_with(fs, clock, cfg, &|| false, &mut |_| {})
+}
 
-    for entry in fs::read_dir(&base).with_context(|| format!("list {}", base.display()))? {
-        let entry = entry?;
-        let path = entry.path();
-        let meta = match fs::symlink_metadata(&path) {
-            Ok(m) => m,
-            Err(_) => continue,
+/// Same as [`organize_once`], but stops early once `cancel` is triggered.
+///
+/// Cancellation is checked between files and while copying across devices.
+/// The file being copied stays in the download folder, and the returned
+/// summary has `cancelled` set and lists only the work already done.
+pub fn organize_cancellable(
+    cfg: &DownloadsConfig,
+    cancel: &CancellationToken,
+) -> Result<OrganizeSummary> {
+    organize_with(
+        &RealFs,
+        &SystemClock,
+        cfg,
+        &|| cancel.is_cancelled(),
+        &mut |_| {},
+    )
+}
+
+/// Same as [`organize_cancellable`], but sends an [`OrganizeEvent`] for every
+/// file decision to `events`, bracketed by `ScanStarted` and `ScanFinished`.
+///
+/// `ScanFinished` is sent even when the pass fails early or is cancelled. A
+/// disconnected receiver is ignored. With `lock` set, the pass holds that
+/// [`PassLock`] and fails with [`OrganizerBusy`], sending nothing, when
+/// another pass does.
+pub fn organize_with_events(
+    cfg: &DownloadsConfig,
+    lock: Option<&Path>,
+    events: &EventSender,
+    cancel: &CancellationToken,
+) -> Result<OrganizeSummary> {
+    organize_since(
+        &RealFs,
+        &SystemClock,
+        cfg,
+        None,
+        lock,
+        &|| cancel.is_cancelled(),
+        &mut |event| {
+            let _ = events.send(event);
+        },
+    )
+}
+
+pub(crate) fn organize_with(
+    fs: &dyn FileSystem,
+    clock: &dyn Clock,
+    cfg: &DownloadsConfig,
+    cancelled: &dyn Fn() -> bool,
+    emit: &mut dyn FnMut(OrganizeEvent),
+) -> Result<OrganizeSummary> {
+    organize_since(fs, clock, cfg, None, None, cancelled, emit)
+}
+
+/// A pass of the watcher, which ran its previous pass at `since`; rule
+/// schedules that came due in between count as due now. With `lock` set,
+/// the pass holds that [`PassLock`] and fails with [`OrganizerBusy`],
+/// before any event, when another pass does.
+pub(crate) fn organize_since(
+    fs: &dyn FileSystem,
+    clock: &dyn Clock,
+    cfg: &DownloadsConfig,
+    since: Option<SystemTime>,
+    lock: Option<&Path>,
+    cancelled: &dyn Fn() -> bool,
+    emit: &mut dyn FnMut(OrganizeEvent),
+) -> Result<OrganizeSummary> {
+    let _lock = lock.map(PassLock::try_acquire).transpose()?;
+    let started = Instant::now();
+    emit(OrganizeEvent::ScanStarted {
+        download_dir: PathBuf::from(&cfg.download_dir),
+    });
+    let mut counts = ScanCounts::default();
+    let result = organize_pass(fs, clock, cfg, false, since, cancelled, &mut |event| {
+        counts.record(&event);
+        let moved = match &event {
+            OrganizeEvent::FileMoved(result) if hooks::has_hooks(cfg, &result.rule_name) => {
+                Some(result.clone())
+            }
+            _ => None,
         };
-        if meta.file_type().is_symlink() || !meta.is_file() {
-            continue;
-        }
-        if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
-            if is_partial(name) {
-                continue;
+        emit(event);
+        if let Some(result) = moved {
+            for hook in hooks::hooks_for(cfg, &result.rule_name) {
+                emit(OrganizeEvent::HookFinished(hooks::run(hook, &result)));
             }
+        }
+    });
+    emit(OrganizeEvent::ScanFinished {
+        duration: started.elapsed(),
+        counts,
+        cancelled: result.as_ref().is_ok_and(|summary| summary.cancelled),
+    });
+    result
+}
 
-            // Check for corresponding partial files. Browsers often create the target file
-            // as a placeholder while downloading into a temporary (.part, .crdownload, etc.) file.
-            let part_path = path.with_file_name(format!("{}.part", name));
-            let cr_path = path.with_file_name(format!("{}.crdownload", name));
-            let tmp_path = path.with_file_name(format!("{}.tmp", name));
-            let dl_path = path.with_file_name(format!("{}.download", name));
-            let op_path = path.with_file_name(format!("{}.opdownload", name));
-
-            if part_path.exists()
-                || cr_path.exists()
-                || tmp_path.exists()
-                || dl_path.exists()
-                || op_path.exists()
-            {
-                continue;
-            }
+/// Checks whether `path` is ready to be organized, returning why not otherwise.
+fn pre_move_skip(
+    fs: &dyn FileSystem,
+    path: &Path,
+    meta: &FileMeta,
+    min_age: Duration,
+    now: SystemTime,
+) -> Option<SkipReason> {
+    if let Some(name) = path.file_name().and_then(|n| n.to_str()) {
+        if is_partial(name) {
+            return Some(SkipReason::PartialDownload);
         }
 
-        // Ensure we don't move 0-byte placeholders created by browsers
-        if meta.len() == 0 {
-            continue;
+        // Check for corresponding partial files. Browsers often create the target file
+        // as a placeholder while downloading into a temporary (.part, .crdownload, etc.) file.
+        let in_progress = ["part", "crdownload", "tmp", "download", "opdownload"]
+            .iter()
+            .any(|suffix| fs.exists(&path.with_file_name(format!("{name}.{suffix}"))));
+        if in_progress {
+            return Some(SkipReason::DownloadInProgress);
         }
-        if let Ok(modified) = meta.modified() {
-            if SystemTime::now()
-                .duration_since(modified)
-                .unwrap_or(Duration::from_secs(0))
-                < min_age
-            {
-                continue;
-            }
+    }
+
+    // Ensure we don't move 0-byte placeholders created by browsers
+    if meta.len == 0 {
+        return Some(SkipReason::EmptyFile);
+    }
+    if let Some(modified) = meta.modified {
+        if now
+            .duration_since(modified)
+            .unwrap_or(Duration::from_secs(0))
+            < min_age
+        {
+            return Some(SkipReason::TooRecent);
+        }
+    }
+    None
+}
+
+/// What a pass hands to [`organize_set`].
+struct PassContext<'a> {
+    fs: &'a dyn FileSystem,
+    cfg: &'a DownloadsConfig,
+    dry_run: bool,
+    min_age: Duration,
+    now: SystemTime,
+    /// When the previous pass ran, for rule schedules.
+    since: Option<SystemTime>,
+    cancelled: &'a dyn Fn() -> bool,
+}
+
+/// Moves every volume of `set` into one folder, or none of them.
+///
+/// The set is matched against the rules under its combined name
+/// (`movie.rar`) and total size. It is held with
+/// [`SkipReason::IncompleteSet`] until it looks complete and every volume
+/// passes the usual checks. If a volume fails to move, the ones already moved
+/// are put back.
+fn organize_set(
+    ctx: &PassContext<'_>,
+    compiled_rules: &[(usize, CompiledRule<'_>)],
+    set: &SplitSet,
+    planned: &mut HashSet<PathBuf>,
+    budget: &mut PassBudget,
+    summary: &mut OrganizeSummary,
+    emit: &mut dyn FnMut(OrganizeEvent),
+) -> Result<()> {
+    let fs = ctx.fs;
+    let mut metas = Vec::with_capacity(set.volumes.len());
+    for volume in &set.volumes {
+        match fs.metadata(&volume.path) {
+            Ok(meta) => metas.push(meta),
+            // Gone since the listing; look again next pass.
+            Err(_) => return Ok(()),
+        }
+    }
+    let sizes: Vec<u64> = metas.iter().map(|m| m.len).collect();
+    let held: Vec<Option<SkipReason>> = set
+        .volumes
+        .iter()
+        .zip(&metas)
+        .map(|(v, meta)| pre_move_skip(fs, &v.path, meta, ctx.min_age, ctx.now))
+        .collect();
+    if !set.is_complete(&sizes) || held.iter().any(Option::is_some) {
+        for (volume, reason) in set.volumes.iter().zip(held) {
+            emit(OrganizeEvent::FileSkipped {
+                path: volume.path.clone(),
+                rule_name: None,
+                reason: reason.unwrap_or(SkipReason::IncompleteSet),
+            });
+        }
+        return Ok(());
+    }
+
+    let first = &set.volumes[0].path;
+    let base_dir = first.parent().unwrap_or(Path::new(""));
+    let logical = base_dir.join(&set.name);
+    let total: u64 = sizes.iter().sum();
+    let meta = FileMeta {
+        len: total,
+        ..metas[0]
+    };
+    let matched = compiled_rules
+        .iter()
+        .map(|(_, compiled)| compiled)
+        .find(|compiled| compiled.rule.enabled && matches_rule(&logical, &meta, compiled));
+    let Some(compiled) = matched else {
+        for volume in &set.volumes {
+            emit(OrganizeEvent::FileSkipped {
+                path: volume.path.clone(),
+                rule_name: None,
+                reason: SkipReason::NoMatchingRule,
+            });
+        }
+        return Ok(());
+    };
+    let rule = compiled.rule;
+    let dest = destination(compiled, &logical);
+    let target_dir = dest.parent().unwrap_or(base_dir);
+    if !compiled.is_due(ctx.since, ctx.now) {
+        for volume in &set.volumes {
+            let reason = SkipReason::OutsideSchedule;
+            emit(OrganizeEvent::FileSkipped {
+                path: volume.path.clone(),
+                rule_name: Some(rule.name.clone()),
+                reason,
+            });
+            summary.skipped.push(SkippedFile {
+                path: volume.path.clone(),
+                destination: target_dir.join(volume.path.file_name().unwrap_or_default()),
+                rule_name: rule.name.clone(),
+                reason,
+            });
+        }
+        return Ok(());
+    }
+    // `rename_to` and `normalize` apply to the base name every volume shares.
+    let renamed = (dest.file_name() != logical.file_name())
+        .then(|| dest.file_stem().and_then(|s| s.to_str()))
+        .flatten();
+    let targets = set.targets(target_dir, renamed, |p| fs.exists(p) || planned.contains(p));
+
+    let min_free = rule
+        .min_free_bytes
+        .or(ctx.cfg.min_free_space_bytes)
+        .unwrap_or(0);
+    let probe = existing_ancestor(fs, target_dir);
+    let cross_device = !fs.same_filesystem(first, probe);
+    if let Some(reason) = check_free_space(total, min_free, cross_device, fs.available_space(probe))
+    {
+        for (volume, target) in set.volumes.iter().zip(&targets) {
+            emit(OrganizeEvent::FileSkipped {
+                path: volume.path.clone(),
+                rule_name: Some(rule.name.clone()),
+                reason,
+            });
+            summary.skipped.push(SkippedFile {
+                path: volume.path.clone(),
+                destination: target.clone(),
+                rule_name: rule.name.clone(),
+                reason,
+            });
+        }
+        return Ok(());
+    }
+    if !budget.admits(set.volumes.len(), total) {
+        summary.deferred += set.volumes.len();
+        summary.deferred_bytes += total;
+        for volume in &set.volumes {
+            emit(OrganizeEvent::FileSkipped {
+                path: volume.path.clone(),
+                rule_name: Some(rule.name.clone()),
+                reason: SkipReason::PassLimitReached,
+            });
+        }
+        return Ok(());
+    }
+    budget.record(set.volumes.len(), total);
+
+    let rate = ctx.cfg.max_copy_bytes_per_sec;
+    if !ctx.dry_run {
+        ensure_dir(fs, target_dir)?;
+        let mut moved: Vec<(&Path, &Path)> = Vec::new();
+        for (volume, target) in set.volumes.iter().zip(&targets) {
+            let Err(e) = move_file(fs, &volume.path, target, rate, ctx.cancelled) else {
+                moved.push((&volume.path, target));
+                continue;
+            };
+            for (src, dst) in moved.iter().rev() {
+                if let Err(undo) = move_file(fs, dst, src, rate, &|| false) {
+                    summary.errors.push(format!(
+                        "Failed to put '{}' back to '{}': {undo}",
+                        dst.display(),
+                        src.display()
+                    ));
+                }
+            }
+            if (ctx.cancelled)() {
+                summary.cancelled = true;
+                return Ok(());
+            }
+            let failed = volume
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy();
+            for (other, other_target) in set.volumes.iter().zip(&targets) {
+                let error = if other.path == volume.path {
+                    e.to_string()
+                } else {
+                    format!("not moved because {failed} failed: {e}")
+                };
+                emit(OrganizeEvent::FileFailed {
+                    path: other.path.clone(),
+                    destination: Some(other_target.clone()),
+                    rule_name: Some(rule.name.clone()),
+                    error,
+                });
+            }
+            summary.errors.push(format!(
+                "Failed to move '{}' to '{}': {e}",
+                volume.path.display(),
+                target.display()
+            ));
+            return Ok(());
+        }
+    }
+
+    for (volume, target) in set.volumes.iter().zip(targets) {
+        planned.insert(target.clone());
+        let symlink_info = if ctx.dry_run {
+            None
+        } else {
+            leave_breadcrumb(fs, rule, &volume.path, &target)
+        };
+        let result = OrganizeResult {
+            source: volume.path.clone(),
+            destination: target,
+            rule_name: rule.name.clone(),
+            symlink_info,
+        };
+        if !ctx.dry_run {
+            emit(OrganizeEvent::FileMoved(result.clone()));
+        }
+        summary.moved.push(result);
+    }
+    Ok(())
+}
+
+/// Leaves `rule`'s breadcrumb at `original` for a file moved to `target`,
+/// and describes the outcome for `symlink_info`.
+fn leave_breadcrumb(
+    fs: &dyn FileSystem,
+    rule: &Rule,
+    original: &Path,
+    target: &Path,
+) -> Option<String> {
+    rule.breadcrumb_kind().map(|kind| {
+        let label = breadcrumb::label(kind);
+        match breadcrumb::create(fs, kind, original, target) {
+            Ok(()) => format!("{label} created"),
+            Err(e) => format!("{label} failed: {e}"),
+        }
+    })
+}
+
+/// Computes what a pass would do without touching the disk: `moved` lists
+/// the planned moves and `skipped` the files that would be held back.
+/// `emit` gets the pass's events, including a `FileSkipped` for every file
+/// left in place.
+pub(crate) fn plan_with(
+    fs: &dyn FileSystem,
+    clock: &dyn Clock,
+    cfg: &DownloadsConfig,
+    emit: &mut dyn FnMut(OrganizeEvent),
+) -> Result<OrganizeSummary> {
+    organize_pass(fs, clock, cfg, true, None, &|| false, emit)
+}
+
+/// One pass over the download folder. With `dry_run` set, no directories
+/// are created and nothing is moved; the summary describes what would be.
+/// `since` is when the previous pass ran, for rule schedules.
+fn organize_pass(
+    fs: &dyn FileSystem,
+    clock: &dyn Clock,
+    cfg: &DownloadsConfig,
+    dry_run: bool,
+    since: Option<SystemTime>,
+    cancelled: &dyn Fn() -> bool,
+    emit: &mut dyn FnMut(OrganizeEvent),
+) -> Result<OrganizeSummary> {
+    let base = PathBuf::from(&cfg.download_dir);
+    let min_age = Duration::from_secs(cfg.min_age_secs.unwrap_or(5));
+    let mut summary = OrganizeSummary::default();
+    let mut planned: HashSet<PathBuf> = HashSet::new();
+    let mut budget = PassBudget::new(cfg);
+
+    // Pre-compile each rule's regex pattern once for this pass, then sort by priority.
+    let mut compiled_rules: Vec<(usize, CompiledRule<'_>)> = cfg
+        .rules
+        .iter()
+        .enumerate()
+        .map(|(i, r)| (i, CompiledRule::new(r)))
+        .collect();
+    let total = compiled_rules.len();
+    compiled_rules.sort_by_key(|(i, cr)| std::cmp::Reverse(rule_priority(*i, total, cr.rule)));
+
+    let target_dirs = rule_target_dirs(cfg);
+
+    let mut entries = fs
+        .read_dir(&base)
+        .with_context(|| format!("list {}", base.display()))?;
+
+    if cfg.split_archives != Some(false) {
+        let ctx = PassContext {
+            fs,
+            cfg,
+            dry_run,
+            min_age,
+            now: clock.now(),
+            since,
+            cancelled,
+        };
+        let regular = |p: &PathBuf| fs.metadata(p).is_ok_and(|m| m.is_file() && !m.is_symlink());
+        let files: Vec<PathBuf> = entries.iter().filter(|p| regular(p)).cloned().collect();
+        let sets = multipart::find_sets(&files, is_partial);
+        let volumes: HashSet<&PathBuf> = sets
+            .iter()
+            .flat_map(|set| set.volumes.iter().map(|v| &v.path))
+            .collect();
+        entries.retain(|p| !volumes.contains(p));
+        for set in &sets {
+            if cancelled() {
+                summary.cancelled = true;
+                return Ok(summary);
+            }
+            organize_set(
+                &ctx,
+                &compiled_rules,
+                set,
+                &mut planned,
+                &mut budget,
+                &mut summary,
+                emit,
+            )?;
+            if summary.cancelled {
+                return Ok(summary);
+            }
+        }
+    }
+
+    let sidecars = match &cfg.sidecars {
+        Some(sc) => SidecarMatcher::new(sc).group(&entries),
+        None => HashMap::new(),
+    };
+    // Where each primary went, or `None` if it is held back, for its sidecars.
+    let mut primaries: HashMap<PathBuf, Option<(&Rule, PathBuf)>> = HashMap::new();
+
+    for path in sidecar::primaries_first(entries, &sidecars) {
+        if cancelled() {
+            summary.cancelled = true;
+            break;
+        }
+        let meta = match fs.metadata(&path) {
+            Ok(m) => m,
+            Err(_) => continue,
+        };
+        if meta.is_symlink() || !meta.is_file() {
+            continue;
+        }
+        if breadcrumb::identify(fs, &path, &meta, &target_dirs).is_some() {
+            emit(OrganizeEvent::FileSkipped {
+                path,
+                rule_name: None,
+                reason: SkipReason::Breadcrumb,
+            });
+            continue;
+        }
+        let primary = sidecars.get(&path);
+        let followed = primary.and_then(|p| primaries.get(p));
+        if matches!(followed, Some(None)) {
+            emit(OrganizeEvent::FileSkipped {
+                path,
+                rule_name: None,
+                reason: SkipReason::WaitingForPrimary,
+            });
+            continue;
+        }
+        // A group moves once every member is ready, so a primary never
+        // leaves without the sidecars that arrived with it.
+        let held = pre_move_skip(fs, &path, &meta, min_age, clock.now()).or_else(|| {
+            sidecars
+                .iter()
+                .filter(|(_, p)| **p == path)
+                .find_map(|(sc, _)| {
+                    let meta = fs.metadata(sc).ok()?;
+                    pre_move_skip(fs, sc, &meta, min_age, clock.now())
+                })
+        });
+        if let Some(reason) = held {
+            primaries.insert(path.clone(), None);
+            emit(OrganizeEvent::FileSkipped {
+                path,
+                rule_name: None,
+                reason,
+            });
+            continue;
         }
         let mut applied: Option<(&Rule, PathBuf)> = None;
-        for (_, compiled) in &compiled_rules {
+        if let (Some(primary), Some(Some((rule, primary_dest)))) = (primary, followed) {
+            let target = sidecar::sidecar_target(&path, primary, primary_dest);
+            applied = Some((*rule, unique_target(fs, &target, &planned)));
+        }
+        // Sidecars go wherever their primary went, limits or not.
+        let follows_primary = applied.is_some();
+        let mut unscheduled: Option<(&Rule, PathBuf)> = None;
+        for (_, compiled) in compiled_rules.iter().filter(|_| applied.is_none()) {
             // Skip disabled rules
             if !compiled.rule.enabled {
                 continue;
             }
             if matches_rule(&path, &meta, compiled) {
-                let target_dir = PathBuf::from(&compiled.rule.target_dir);
-                ensure_dir(&target_dir)?;
-                let target = target_dir.join(
-                    path.file_name()
-                        .map(|n| n.to_os_string())
-                        .unwrap_or_default(),
-                );
-                let target = unique_target(&target);
+                let target = destination(compiled, &path);
+                if !compiled.is_due(since, clock.now()) {
+                    unscheduled = Some((compiled.rule, target));
+                    break;
+                }
+                let target = unique_target(fs, &target, &planned);
                 applied = Some((compiled.rule, target));
                 break;
             }
         }
-        if let Some((rule, target)) = applied {
-            if let Err(e) = fs::rename(&path, &target) {
-                summary.errors.push(format!(
-                    "Failed to move '{}' to '{}': {e}",
-                    path.display(),
-                    target.display()
-                ));
-                continue;
-            }
+        if let Some((rule, target)) = unscheduled {
+            let reason = SkipReason::OutsideSchedule;
+            primaries.insert(path.clone(), None);
+            emit(OrganizeEvent::FileSkipped {
+                path: path.clone(),
+                rule_name: Some(rule.name.clone()),
+                reason,
+            });
+            summary.skipped.push(SkippedFile {
+                path,
+                destination: target,
+                rule_name: rule.name.clone(),
+                reason,
+            });
+            continue;
+        }
+        let Some((rule, target)) = applied else {
+            emit(OrganizeEvent::FileSkipped {
+                path,
+                rule_name: None,
+                reason: SkipReason::NoMatchingRule,
+            });
+            continue;
+        };
 
-            let mut symlink_info = None;
-            if rule.create_symlink {
-                #[cfg(windows)]
-                let res = std::os::windows::fs::symlink_file(&target, &path);
-                #[cfg(unix)]
-                let res = std::os::unix::fs::symlink(&target, &path);
-
-                match res {
-                    Ok(_) => {
-                        symlink_info = Some("Symlink created".to_string());
-                        #[cfg(windows)]
-                        {
-                            let _ = std::process::Command::new("attrib")
-                                .arg("+h")
-                                .arg(&path)
-                                .arg("/L")
-                                .creation_flags(0x08000000) // CREATE_NO_WINDOW
-                                .status();
-                        }
-                    }
-                    Err(e) => symlink_info = Some(format!("Symlink failed: {}", e)),
-                }
-            }
+        let target_dir = target.parent().unwrap_or(&base);
+        let min_free = rule
+            .min_free_bytes
+            .or(cfg.min_free_space_bytes)
+            .unwrap_or(0);
+        let probe = existing_ancestor(fs, target_dir);
+        let cross_device = !fs.same_filesystem(&path, probe);
+        if let Some(reason) =
+            check_free_space(meta.len, min_free, cross_device, fs.available_space(probe))
+        {
+            primaries.insert(path.clone(), None);
+            emit(OrganizeEvent::FileSkipped {
+                path: path.clone(),
+                rule_name: Some(rule.name.clone()),
+                reason,
+            });
+            summary.skipped.push(SkippedFile {
+                path,
+                destination: target,
+                rule_name: rule.name.clone(),
+                reason,
+            });
+            continue;
+        }
+        if !follows_primary && !budget.admits(1, meta.len) {
+            primaries.insert(path.clone(), None);
+            summary.deferred += 1;
+            summary.deferred_bytes += meta.len;
+            emit(OrganizeEvent::FileSkipped {
+                path,
+                rule_name: Some(rule.name.clone()),
+                reason: SkipReason::PassLimitReached,
+            });
+            continue;
+        }
+        budget.record(1, meta.len);
 
+        if dry_run {
+            planned.insert(target.clone());
+            primaries.insert(path.clone(), Some((rule, target.clone())));
             summary.moved.push(OrganizeResult {
                 source: path,
-                destination: target.clone(),
+                destination: target,
                 rule_name: rule.name.clone(),
-                symlink_info,
+                symlink_info: None,
+            });
+            continue;
+        }
+
+        ensure_dir(fs, target_dir)?;
+        if let Err(e) = move_file(fs, &path, &target, cfg.max_copy_bytes_per_sec, cancelled) {
+            if cancelled() {
+                // The copy was abandoned, not failed; the source is untouched.
+                summary.cancelled = true;
+                break;
+            }
+            primaries.insert(path.clone(), None);
+            emit(OrganizeEvent::FileFailed {
+                path: path.clone(),
+                destination: Some(target.clone()),
+                rule_name: Some(rule.name.clone()),
+                error: e.to_string(),
             });
+            summary.errors.push(format!(
+                "Failed to move '{}' to '{}': {e}",
+                path.display(),
+                target.display()
+            ));
+            continue;
         }
+
+        primaries.insert(path.clone(), Some((rule, target.clone())));
+
+        let symlink_info = leave_breadcrumb(fs, rule, &path, &target);
+
+        let result = OrganizeResult {
+            source: path,
+            destination: target.clone(),
+            rule_name: rule.name.clone(),
+            symlink_info,
+        };
+        emit(OrganizeEvent::FileMoved(result.clone()));
+        summary.moved.push(result);
     }
     Ok(summary)
 }
@@ -521,6 +1443,9 @@ pub fn organize_once(cfg: &DownloadsConfig) -> Result<OrganizeSummary> {
 /// This runs `organize_once` in a loop, sleeping for `interval_secs` between iterations.
 /// When actions are taken, the `callback` is invoked with the list of actions.
 /// The function checks the `should_continue` flag on each iteration; when set to false, it exits.
+///
+/// Rule retention policies are enforced as well; see [`watch_polling_with_retention`]
+/// to observe the purged files.
 pub fn watch_polling<F>(
     cfg: &DownloadsConfig,
     interval_secs: u64,
@@ -530,212 +1455,635 @@ pub fn watch_polling<F>(
 where
     F: Fn(&[OrganizeResult]),
 {
-    use std::sync::atomic::Ordering;
+    watch_polling_with_retention(cfg, interval_secs, should_continue, callback, |_| {})
+}
+
+/// Same as [`watch_polling`], but also reports files removed by rule
+/// retention policies through `on_purged`.
+///
+/// Retention runs on the first iteration and then every
+/// `cfg.retention_interval_secs` (one hour by default), which is much slower
+/// than the organize cadence since target folders change rarely.
+pub fn watch_polling_with_retention<F, P>(
+    cfg: &DownloadsConfig,
+    interval_secs: u64,
+    should_continue: &std::sync::atomic::AtomicBool,
+    callback: F,
+    on_purged: P,
+) -> Result<()>
+where
+    F: Fn(&[OrganizeResult]),
+    P: Fn(&[PurgeResult]),
+{
+    let mut moved: Vec<OrganizeResult> = Vec::new();
+    watch_with(
+        &RealFs,
+        &SystemClock,
+        cfg,
+        None,
+        interval_secs,
+        &|| !should_continue.load(std::sync::atomic::Ordering::Relaxed),
+        &mut |event| match event {
+            OrganizeEvent::FileMoved(result) => moved.push(result),
+            OrganizeEvent::FileFailed {
+                path,
+                destination,
+                error,
+                ..
+            } => match destination {
+                Some(dest) => eprintln!(
+                    "[Harbor] Failed to move '{}' to '{}': {error}",
+                    path.display(),
+                    dest.display()
+                ),
+                None => eprintln!("[Harbor] Failed to move '{}': {error}", path.display()),
+            },
+            OrganizeEvent::FileSkipped { path, reason, .. } if reason.is_noteworthy() => {
+                eprintln!("[Harbor] Skipped '{}': {}", path.display(), reason);
+            }
+            OrganizeEvent::FilePurged(result) => on_purged(std::slice::from_ref(&result)),
+            OrganizeEvent::ScanFinished { .. } if !moved.is_empty() => {
+                callback(&moved);
+                moved.clear();
+            }
+            _ => {}
+        },
+    )
+}
+
+/// Runs the polling watcher and sends every [`OrganizeEvent`] to `events`,
+/// including `FilePurged` for retention policies.
+///
+/// Stops when `should_continue` is cleared; a disconnected receiver is ignored.
+/// With `lock` set, each pass holds that [`PassLock`], and a poll that finds
+/// it held leaves the pass to its holder.
+pub fn watch_with_events(
+    cfg: &DownloadsConfig,
+    lock: Option<&Path>,
+    interval_secs: u64,
+    should_continue: &std::sync::atomic::AtomicBool,
+    events: &EventSender,
+) -> Result<()> {
+    watch_with(
+        &RealFs,
+        &SystemClock,
+        cfg,
+        lock,
+        interval_secs,
+        &|| !should_continue.load(std::sync::atomic::Ordering::Relaxed),
+        &mut |event| {
+            let _ = events.send(event);
+        },
+    )
+}
+
+/// The polling loop behind the watchers. Runs a pass every `interval_secs`
+/// while the global `schedule` allows it, and retention when due, until
+/// `stop_requested` returns `true`. Nothing runs during quiet hours; a
+/// schedule that came due meanwhile runs once they are over. A poll that
+/// finds `lock` held by another pass leaves that pass to it.
+pub(crate) fn watch_with(
+    fs: &dyn FileSystem,
+    clock: &dyn Clock,
+    cfg: &DownloadsConfig,
+    lock: Option<&Path>,
+    interval_secs: u64,
+    stop_requested: &dyn Fn() -> bool,
+    emit: &mut dyn FnMut(OrganizeEvent),
+) -> Result<()> {
+    let retention_interval = Duration::from_secs(
+        cfg.retention_interval_secs
+            .unwrap_or(crate::retention::DEFAULT_RETENTION_INTERVAL_SECS),
+    );
+    let mut last_retention: Option<Instant> = None;
+    // An invalid schedule stops organizing rather than running around the clock.
+    let schedule = cfg
+        .schedule
+        .as_deref()
+        .map(|expr| match Schedule::parse(expr) {
+            Ok(schedule) => Some(schedule),
+            Err(e) => {
+                eprintln!("[Harbor] Not organizing: {e:#}");
+                None
+            }
+        });
+    let quiet_windows = cfg.quiet_windows();
+    let mut last_check: Option<SystemTime> = None;
+    let mut last_pass: Option<SystemTime> = None;
     loop {
-        if !should_continue.load(Ordering::Relaxed) {
+        if stop_requested() {
             return Ok(());
         }
-        match organize_once(cfg) {
-            Ok(summary) => {
-                for err in &summary.errors {
-                    eprintln!("[Harbor] {err}");
+        let now = clock.now();
+        if schedule::quiet_until(&quiet_windows, DateTime::from(now)).is_some() {
+            if sleep_unless_stopped(interval_secs, stop_requested) {
+                return Ok(());
+            }
+            continue;
+        }
+        let due = match &schedule {
+            None => true,
+            Some(schedule) => schedule.as_ref().is_some_and(|s| s.is_due(last_check, now)),
+        };
+        let previous_check = last_check.replace(now);
+        if due {
+            match organize_since(fs, clock, cfg, last_pass, lock, stop_requested, emit) {
+                Ok(_) => last_pass = Some(now),
+                // Try again next poll, with schedules still due.
+                Err(e) if e.is::<OrganizerBusy>() => last_check = previous_check,
+                Err(e) => {
+                    eprintln!("organize error: {}", e);
+                    last_pass = Some(now);
                 }
-                if !summary.moved.is_empty() {
-                    callback(&summary.moved);
+            }
+        }
+        let retention_due = last_retention.is_none_or(|t| t.elapsed() >= retention_interval);
+        if retention_due {
+            last_retention = Some(Instant::now());
+            if has_retention(cfg) {
+                match crate::retention::enforce_retention_at(fs, cfg, clock.now()) {
+                    Ok(summary) => {
+                        for err in &summary.errors {
+                            eprintln!("[Harbor] {err}");
+                        }
+                        for purged in summary.purged {
+                            emit(OrganizeEvent::FilePurged(purged));
+                        }
+                    }
+                    Err(e) => eprintln!("retention error: {}", e),
                 }
             }
-            Err(e) => eprintln!("organize error: {}", e),
-        }
-        // Sleep in small chunks so we can respond to stop signals quickly
-        // instead of being stuck in one long sleep that blocks thread join.
-        let chunk = Duration::from_millis(500);
-        let mut remaining = Duration::from_secs(interval_secs);
-        while remaining > Duration::ZERO {
-            if !should_continue.load(Ordering::Relaxed) {
-                return Ok(());
+            // After retention, so breadcrumbs to purged files go right away.
+            match cleanup_breadcrumbs_with(fs, clock, cfg, cfg.breadcrumb_max_age()) {
+                Ok(report) => {
+                    for err in &report.errors {
+                        eprintln!("[Harbor] {err}");
+                    }
+                    for removed in report.removed {
+                        emit(OrganizeEvent::BreadcrumbRemoved(removed));
+                    }
+                }
+                Err(e) => eprintln!("breadcrumb cleanup error: {}", e),
             }
-            let sleep_time = chunk.min(remaining);
-            thread::sleep(sleep_time);
-            remaining = remaining.saturating_sub(sleep_time);
         }
+        if sleep_unless_stopped(interval_secs, stop_requested) {
+            return Ok(());
+        }
+    }
+}
+
+/// Sleeps `secs`, returning `true` as soon as `stop_requested` does.
+fn sleep_unless_stopped(secs: u64, stop_requested: &dyn Fn() -> bool) -> bool {
+    // Sleep in small chunks so we can respond to stop signals quickly
+    // instead of being stuck in one long sleep that blocks thread join.
+    let chunk = Duration::from_millis(500);
+    let mut remaining = Duration::from_secs(secs);
+    while remaining > Duration::ZERO {
+        if stop_requested() {
+            return true;
+        }
+        let sleep_time = chunk.min(remaining);
+        thread::sleep(sleep_time);
+        remaining = remaining.saturating_sub(sleep_time);
     }
+    false
 }
 
-/// Expands environment variables in a string.
+/// Expands environment variables in a string; undefined ones become empty.
 ///
 /// Supports:
 /// - Windows style: `%VAR%`
-/// - POSIX style: `$VAR`, `${VAR}`
+/// - POSIX style: `$VAR`, `${VAR}`, and `${VAR:-default}` for a fallback when
+///   `VAR` is unset or empty
 /// - Tilde expansion: `~/` → `$HOME/`
-fn expand_env(input: &str) -> String {
-    let mut result = String::with_capacity(input.len());
-    let chars: Vec<char> = input.chars().collect();
-    let len = chars.len();
-    let mut i = 0;
+pub fn expand_env(input: &str) -> String {
+    expand_vars(input, &|name| std::env::var(name).ok()).0
+}
+
+/// Same as [`expand_env`], but fails listing the undefined variables instead
+/// of dropping them, so a typo cannot turn `$HOME/Archive` into `/Archive`.
+/// Variables with a `${VAR:-default}` fallback are never undefined.
+pub fn expand_env_strict(input: &str) -> Result<String> {
+    let (expanded, undefined) = expand_vars(input, &|name| std::env::var(name).ok());
+    if !undefined.is_empty() {
+        bail!(
+            "undefined environment variable{} {}",
+            if undefined.len() == 1 { "" } else { "s" },
+            undefined.join(", ")
+        );
+    }
+    Ok(expanded)
+}
 
-    while i < len {
-        let ch = chars[i];
+/// Expands `input` with `lookup`, returning the result and the names of the
+/// variables `lookup` did not know, in order of first use.
+fn expand_vars(input: &str, lookup: &dyn Fn(&str) -> Option<String>) -> (String, Vec<String>) {
+    let mut result = String::with_capacity(input.len());
+    let mut undefined: Vec<String> = Vec::new();
+    let mut substitute =
+        |name: &str, default: Option<&str>, out: &mut String| match (lookup(name), default) {
+            (Some(value), Some(default)) if value.is_empty() => out.push_str(default),
+            (Some(value), _) => out.push_str(&value),
+            (None, Some(default)) => out.push_str(default),
+            (None, None) => {
+                if !undefined.iter().any(|u| u == name) {
+                    undefined.push(name.to_string());
+                }
+            }
+        };
 
+    let mut rest = input;
+    while let Some(ch) = rest.chars().next() {
         // Windows-style %VAR%
         if ch == '%' {
-            if let Some(end) = input[i + 1..].find('%') {
-                let var_name = &input[i + 1..i + 1 + end];
-                if !var_name.is_empty() {
-                    let value = std::env::var(var_name).unwrap_or_default();
-                    result.push_str(&value);
-                    i = i + 1 + end + 1; // skip past closing '%'
-                    continue;
-                }
+            if let Some(end) = rest[1..].find('%').filter(|&end| end > 0) {
+                substitute(&rest[1..1 + end], None, &mut result);
+                rest = &rest[end + 2..];
+                continue;
             }
-            result.push(ch);
-            i += 1;
-            continue;
         }
 
         // Tilde expansion: ~/ or ~ at end
-        if ch == '~' {
-            let next_is_slash = i + 1 < len && chars[i + 1] == '/';
-            let is_end = i + 1 == len;
-            if next_is_slash || is_end {
-                let home = std::env::var("HOME").unwrap_or_default();
-                result.push_str(&home);
-                i += 1; // skip the '~'
-                continue;
-            }
+        if ch == '~' && (rest.len() == 1 || rest[1..].starts_with('/')) {
+            substitute("HOME", None, &mut result);
+            rest = &rest[1..];
+            continue;
         }
 
-        // POSIX-style $VAR or ${VAR}
-        if ch == '$' && i + 1 < len {
-            let next = chars[i + 1];
-            // ${VAR}
-            if next == '{' {
-                if let Some(close) = input[i + 2..].find('}') {
-                    let var_name = &input[i + 2..i + 2 + close];
-                    if !var_name.is_empty() {
-                        let value = std::env::var(var_name).unwrap_or_default();
-                        result.push_str(&value);
-                        i = i + 2 + close + 1; // skip past closing '}'
-                        continue;
-                    }
-                }
-                result.push(ch);
-                i += 1;
+        // POSIX-style ${VAR}, ${VAR:-default} or $VAR
+        if let Some(braced) = rest.strip_prefix("${") {
+            if let Some(close) = braced.find('}').filter(|&close| close > 0) {
+                let (name, default) = match braced[..close].split_once(":-") {
+                    Some((name, default)) => (name, Some(default)),
+                    None => (&braced[..close], None),
+                };
+                substitute(name, default, &mut result);
+                rest = &braced[close + 1..];
                 continue;
             }
-            // $VAR — consume while next char is alphanumeric or underscore
-            if next.is_ascii_alphabetic() || next == '_' {
-                let start = i + 1;
-                let mut end = start;
-                while end < len && (chars[end].is_ascii_alphanumeric() || chars[end] == '_') {
-                    end += 1;
-                }
-                let var_name: String = chars[start..end].iter().collect();
-                if !var_name.is_empty() {
-                    let value = std::env::var(&var_name).unwrap_or_default();
-                    result.push_str(&value);
-                    i = end;
-                    continue;
-                }
+        } else if let Some(after) = rest.strip_prefix('$') {
+            let end = after
+                .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+                .unwrap_or(after.len());
+            let name = &after[..end];
+            if name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+                substitute(name, None, &mut result);
+                rest = &after[end..];
+                continue;
             }
         }
 
         result.push(ch);
-        i += 1;
+        rest = &rest[ch.len_utf8()..];
     }
 
-    result
+    (result, undefined)
 }
 
-/// Scans the download directory for old symlinks created by Harbor and removes them.
+/// Removes breadcrumbs left in the download folder at startup.
 ///
-/// A symlink is considered "old" (and safe to remove) if:
-/// 1. It is a valid symbolic link.
-/// 2. It points to a file inside one of the configured `target_dirs`.
-///
-/// Returns the number of symlinks removed.
+/// An entry is a breadcrumb if it is one of the
+/// [`BreadcrumbKind`](crate::types::BreadcrumbKind)s (an absolute or relative
+/// symbolic link, a hard link, or a shortcut file) pointing into one of the
+/// rules' `target_dir`s. Dangling breadcrumbs are always removed; the rest
+/// only once older than `breadcrumb_max_age_days`, or all of them when no
+/// lifetime is configured.
+pub fn cleanup_breadcrumbs(cfg: &DownloadsConfig) -> Result<BreadcrumbCleanup> {
+    cleanup_breadcrumbs_with(
+        &RealFs,
+        &SystemClock,
+        cfg,
+        Some(cfg.breadcrumb_max_age().unwrap_or(Duration::ZERO)),
+    )
+}
+
+/// Same as [`cleanup_breadcrumbs`], returning only the number removed.
 pub fn cleanup_old_symlinks(cfg: &DownloadsConfig) -> Result<usize> {
-    let base = PathBuf::from(&cfg.download_dir);
-    if !base.exists() {
-        return Ok(0);
-    }
+    Ok(cleanup_breadcrumbs(cfg)?.removed.len())
+}
 
-    let mut count = 0;
-    // Collect target dirs to check against
-    let target_dirs: Vec<PathBuf> = cfg
-        .rules
-        .iter()
-        .map(|r| PathBuf::from(&r.target_dir))
-        .collect();
+/// Same as [`cleanup_old_symlinks`], but performs all file operations through `fs`.
+pub fn cleanup_old_symlinks_with_fs(cfg: &DownloadsConfig, fs: &dyn FileSystem) -> Result<usize> {
+    let max_age = cfg.breadcrumb_max_age().unwrap_or(Duration::ZERO);
+    Ok(
+        cleanup_breadcrumbs_with(fs, &SystemClock, cfg, Some(max_age))?
+            .removed
+            .len(),
+    )
+}
 
-    for entry in fs::read_dir(&base).with_context(|| format!("list {}", base.display()))? {
-        let entry = entry?;
-        let path = entry.path();
+/// Removes dangling breadcrumbs, and those older than `max_age` when given.
+///
+/// Age is the breadcrumb's own modification time. A hard link shares it with
+/// the moved file, so it ages from the file's last change instead.
+pub(crate) fn cleanup_breadcrumbs_with(
+    fs: &dyn FileSystem,
+    clock: &dyn Clock,
+    cfg: &DownloadsConfig,
+    max_age: Option<Duration>,
+) -> Result<BreadcrumbCleanup> {
+    let mut report = BreadcrumbCleanup::default();
+    let base = PathBuf::from(&cfg.download_dir);
+    if !fs.exists(&base) {
+        return Ok(report);
+    }
 
-        let meta = match fs::symlink_metadata(&path) {
+    let target_dirs = rule_target_dirs(cfg);
+    let now = clock.now();
+    let entries = fs
+        .read_dir(&base)
+        .with_context(|| format!("list {}", base.display()))?;
+    for path in entries {
+        let meta = match fs.metadata(&path) {
             Ok(m) => m,
             Err(_) => continue,
         };
+        let Some(found) = breadcrumb::identify(fs, &path, &meta, &target_dirs) else {
+            continue;
+        };
 
-        if meta.file_type().is_symlink() {
-            // Check if it points to one of our folders
-            if let Ok(target) = fs::read_link(&path) {
-                // If relative symlink, resolve it relative to base
-                let abs_target = if target.is_relative() {
-                    base.join(&target)
-                } else {
-                    target
-                };
-
-                let points_to_our_dir = target_dirs.iter().any(|d| abs_target.starts_with(d));
+        let reason = if !fs.exists(&found.target) {
+            CleanupReason::Dangling
+        } else if max_age.is_some_and(|max| {
+            meta.modified
+                .is_none_or(|m| now.duration_since(m).unwrap_or_default() >= max)
+        }) {
+            CleanupReason::Expired
+        } else {
+            continue;
+        };
 
-                if points_to_our_dir {
-                    // It's one of ours, delete it
-                    if fs::remove_file(&path).is_ok() {
-                        count += 1;
-                    }
-                }
-            }
+        match fs.remove_file(&path) {
+            Ok(()) => report.removed.push(RemovedBreadcrumb {
+                rule_name: rule_for_target(cfg, &found.target).map(|r| r.name.clone()),
+                path,
+                kind: found.kind,
+                target: found.target,
+                reason,
+            }),
+            Err(e) => report.errors.push(format!(
+                "Failed to remove breadcrumb '{}': {e}",
+                path.display()
+            )),
         }
     }
-    Ok(count)
+    Ok(report)
+}
+
+/// The rule with the most specific `target_dir` containing `target`.
+fn rule_for_target<'a>(cfg: &'a DownloadsConfig, target: &Path) -> Option<&'a Rule> {
+    cfg.rules
+        .iter()
+        .map(|r| (r, template::static_prefix(&r.target_dir)))
+        .filter(|(_, dir)| target.starts_with(dir))
+        .max_by_key(|(_, dir)| dir.components().count())
+        .map(|(r, _)| r)
 }
 
-/// Maximum number of lines kept in the activity log before it is trimmed.
+/// Target folders of all rules, enabled or not, for recognising breadcrumbs.
+/// A templated folder contributes the part before its first placeholder.
+fn rule_target_dirs(cfg: &DownloadsConfig) -> Vec<PathBuf> {
+    cfg.rules
+        .iter()
+        .map(|r| template::static_prefix(&r.target_dir))
+        .collect()
+}
+
+/// Maximum number of lines kept in the activity log before older entries are archived.
 pub const LOG_MAX_LINES: usize = 10_000;
 
-/// Trim the log when it exceeds this file-size threshold to avoid reading the
+/// Archive the log when it exceeds this file-size threshold to avoid reading the
 /// file on every single append.
 pub const LOG_ROTATION_THRESHOLD_BYTES: u64 = 1_024 * 1_024; // 1 MiB
 
-/// Appends a batch of [`OrganizeResult`] entries to the activity log file.
-///
-/// Each entry follows the format:
-/// `[timestamp] <source> -> <dest> (<rule>) <symlink_info>`
-///
-/// The log file is automatically rotated when it exceeds
-/// [`LOG_ROTATION_THRESHOLD_BYTES`] bytes — old entries beyond
-/// [`LOG_MAX_LINES`] are discarded.
-pub fn append_organize_results_to_log(log_path: &Path, actions: &[OrganizeResult]) {
-    if actions.is_empty() {
-        return;
+/// Default number of compressed log archives kept next to the live log.
+pub const LOG_MAX_ARCHIVES: usize = 10;
+
+/// Timestamp layout embedded in archive file names. Fixed-width so that
+/// lexicographic order matches chronological order.
+const LOG_ARCHIVE_STAMP_FORMAT: &str = "%Y%m%d-%H%M%S-%3f";
+
+/// Retention rules for the gzip-compressed segments produced by log rotation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LogArchivePolicy {
+    /// Maximum number of archives to keep; the oldest are deleted first.
+    pub max_archives: usize,
+    /// Archives older than this many days are deleted regardless of count.
+    pub max_age_days: Option<u64>,
+}
+
+impl Default for LogArchivePolicy {
+    fn default() -> Self {
+        Self {
+            max_archives: LOG_MAX_ARCHIVES,
+            max_age_days: None,
+        }
     }
+}
 
-    if let Some(parent) = log_path.parent() {
-        let _ = fs::create_dir_all(parent);
+impl LogArchivePolicy {
+    /// Builds the policy from the `log_archive_*` settings in `cfg`.
+    pub fn from_config(cfg: &DownloadsConfig) -> Self {
+        Self {
+            max_archives: cfg.log_archive_max_files.unwrap_or(LOG_MAX_ARCHIVES),
+            max_age_days: cfg.log_archive_max_age_days,
+        }
     }
+}
 
-    let mut buf = String::new();
-    for result in actions {
-        let symlink_msg = result.symlink_info.as_deref().unwrap_or("");
-        let timestamp = chrono::Local::now().format("%Y-%m-%d %H:%M:%S");
-        buf.push_str(&format!(
-            "[{}] {} -> {} ({}) {}\n",
-            timestamp,
-            result.source.display(),
-            result.destination.display(),
-            result.rule_name,
-            symlink_msg
-        ));
+/// Appends a batch of [`OrganizeResult`] entries to the activity log file.
+///
+/// Each entry follows the format:
+/// `[timestamp] <source> -> <dest> (<rule>) <symlink_info>`
+///
+/// The log file is automatically rotated when it exceeds
+/// [`LOG_ROTATION_THRESHOLD_BYTES`] bytes — entries beyond the newest
+/// [`LOG_MAX_LINES`] are moved into a gzip archive using the default
+/// [`LogArchivePolicy`].
+pub fn append_organize_results_to_log(log_path: &Path, actions: &[OrganizeResult]) {
+    append_organize_results_to_log_with_policy(log_path, actions, &LogArchivePolicy::default());
+}
+
+/// Same as [`append_organize_results_to_log`], but prunes archives according
+/// to `policy` when rotation happens.
+pub fn append_organize_results_to_log_with_policy(
+    log_path: &Path,
+    actions: &[OrganizeResult],
+    policy: &LogArchivePolicy,
+) {
+    append_organize_results_to_log_with_clock(log_path, actions, policy, &SystemClock);
+}
+
+/// Same as [`append_organize_results_to_log_with_policy`], but timestamps
+/// entries and archives with `clock`.
+pub fn append_organize_results_to_log_with_clock(
+    log_path: &Path,
+    actions: &[OrganizeResult],
+    policy: &LogArchivePolicy,
+    clock: &dyn Clock,
+) {
+    if actions.is_empty() {
+        return;
+    }
+
+    let ts = log_timestamp(clock);
+    let buf: String = actions.iter().map(|r| format_move_entry(&ts, r)).collect();
+    append_log_entries(log_path, &buf, policy, clock);
+}
+
+/// Records files removed by retention policies in the activity log.
+///
+/// Each entry follows the format:
+/// `[timestamp] Purged: <path> (<rule>) <deleted|trashed>: <reason>`
+pub fn append_purge_results_to_log(
+    log_path: &Path,
+    purged: &[PurgeResult],
+    policy: &LogArchivePolicy,
+) {
+    append_purge_results_to_log_with_clock(log_path, purged, policy, &SystemClock);
+}
+
+/// Same as [`append_purge_results_to_log`], but timestamps entries and
+/// archives with `clock`.
+pub fn append_purge_results_to_log_with_clock(
+    log_path: &Path,
+    purged: &[PurgeResult],
+    policy: &LogArchivePolicy,
+    clock: &dyn Clock,
+) {
+    if purged.is_empty() {
+        return;
+    }
+
+    let ts = log_timestamp(clock);
+    let buf: String = purged.iter().map(|r| format_purge_entry(&ts, r)).collect();
+    append_log_entries(log_path, &buf, policy, clock);
+}
+
+/// Records everything worth keeping from a stream of [`OrganizeEvent`]s:
+/// moves and purges in the formats above, plus noteworthy skips and failures
+/// as `[timestamp] <Skipped|Failed>: <path> (<rule>) -- <reason or error>`.
+///
+/// Routine skips (partial downloads, recent files, unmatched files) and scan
+/// start/finish markers are not logged.
+pub fn append_events_to_log(log_path: &Path, events: &[OrganizeEvent], policy: &LogArchivePolicy) {
+    append_events_to_log_with_clock(log_path, events, policy, &SystemClock);
+}
+
+/// Same as [`append_events_to_log`], but timestamps entries and archives with
+/// `clock`.
+pub fn append_events_to_log_with_clock(
+    log_path: &Path,
+    events: &[OrganizeEvent],
+    policy: &LogArchivePolicy,
+    clock: &dyn Clock,
+) {
+    let ts = log_timestamp(clock);
+    let buf: String = events
+        .iter()
+        .filter_map(|event| match event {
+            OrganizeEvent::FileMoved(result) => Some(format_move_entry(&ts, result)),
+            OrganizeEvent::FilePurged(result) => Some(format_purge_entry(&ts, result)),
+            OrganizeEvent::FileSkipped {
+                path,
+                rule_name,
+                reason,
+            } if reason.is_noteworthy() => Some(format_status_entry(
+                &ts,
+                "Skipped",
+                path,
+                rule_name.as_deref(),
+                &reason.to_string(),
+            )),
+            OrganizeEvent::FileFailed {
+                path,
+                rule_name,
+                error,
+                ..
+            } => Some(format_status_entry(
+                &ts,
+                "Failed",
+                path,
+                rule_name.as_deref(),
+                error,
+            )),
+            OrganizeEvent::FileRestored(result) => Some(format_status_entry(
+                &ts,
+                "Restored",
+                &result.source,
+                Some(&result.rule_name),
+                &format!("from {}", result.destination.display()),
+            )),
+            OrganizeEvent::HookFinished(outcome) => Some(format_status_entry(
+                &ts,
+                "Hook",
+                &outcome.destination,
+                Some(&outcome.rule_name),
+                &outcome.describe(),
+            )),
+            OrganizeEvent::BreadcrumbRemoved(removed) => Some(format_status_entry(
+                &ts,
+                "Removed",
+                &removed.path,
+                removed.rule_name.as_deref(),
+                &removed.describe(),
+            )),
+            _ => None,
+        })
+        .collect();
+    if buf.is_empty() {
+        return;
+    }
+    append_log_entries(log_path, &buf, policy, clock);
+}
+
+fn log_timestamp(clock: &dyn Clock) -> String {
+    clock.local_now().format("%Y-%m-%d %H:%M:%S").to_string()
+}
+
+fn format_move_entry(ts: &str, result: &OrganizeResult) -> String {
+    format!(
+        "[{ts}] {} -> {} ({}) {}\n",
+        result.source.display(),
+        result.destination.display(),
+        result.rule_name,
+        result.symlink_info.as_deref().unwrap_or("")
+    )
+}
+
+fn format_purge_entry(ts: &str, result: &PurgeResult) -> String {
+    format!(
+        "[{ts}] Purged: {} ({}) {}\n",
+        result.path.display(),
+        result.rule_name,
+        result.describe()
+    )
+}
+
+fn format_status_entry(
+    ts: &str,
+    status: &str,
+    path: &Path,
+    rule_name: Option<&str>,
+    note: &str,
+) -> String {
+    // Errors can span lines (e.g. with context chains); keep one entry per line.
+    let note = note.replace(['\r', '\n'], " ");
+    format!(
+        "[{ts}] {status}: {} ({}) -- {note}\n",
+        path.display(),
+        rule_name.unwrap_or("")
+    )
+}
+
+fn append_log_entries(
+    log_path: &Path,
+    entries: &str,
+    policy: &LogArchivePolicy,
+    clock: &dyn Clock,
+) {
+    if let Some(parent) = log_path.parent() {
+        let _ = fs::create_dir_all(parent);
     }
 
     if let Ok(mut file) = std::fs::OpenOptions::new()
@@ -744,15 +2092,19 @@ pub fn append_organize_results_to_log(log_path: &Path, actions: &[OrganizeResult
         .open(log_path)
     {
         use std::io::Write;
-        let _ = file.write_all(buf.as_bytes());
+        let _ = file.write_all(entries.as_bytes());
     }
 
-    rotate_log_if_needed(log_path);
+    rotate_log_if_needed(log_path, policy, clock.local_now().naive_local());
 }
 
-/// Trims the log file to the last [`LOG_MAX_LINES`] lines when it grows beyond
-/// [`LOG_ROTATION_THRESHOLD_BYTES`].
-fn rotate_log_if_needed(log_path: &Path) {
+/// Moves everything but the last [`LOG_MAX_LINES`] lines into a gzip archive
+/// once the log grows beyond [`LOG_ROTATION_THRESHOLD_BYTES`], then prunes
+/// archives that fall outside `policy`.
+///
+/// The live log is only trimmed after the archive has been written
+/// successfully, so a failed rotation never loses history.
+fn rotate_log_if_needed(log_path: &Path, policy: &LogArchivePolicy, now: chrono::NaiveDateTime) {
     let size = fs::metadata(log_path).map(|m| m.len()).unwrap_or(0);
     if size <= LOG_ROTATION_THRESHOLD_BYTES {
         return;
@@ -767,8 +2119,133 @@ fn rotate_log_if_needed(log_path: &Path) {
         return;
     }
 
-    let trimmed = lines[lines.len() - LOG_MAX_LINES..].join("\n") + "\n";
+    let split = lines.len() - LOG_MAX_LINES;
+    let archived = lines[..split].join("\n") + "\n";
+    if write_log_archive(log_path, archived.as_bytes(), now).is_err() {
+        return;
+    }
+
+    let trimmed = lines[split..].join("\n") + "\n";
     let _ = fs::write(log_path, trimmed);
+
+    prune_log_archives(log_path, policy, now);
+}
+
+/// Writes `data` as a new gzip archive next to `log_path`, stamped with `now`.
+fn write_log_archive(log_path: &Path, data: &[u8], now: chrono::NaiveDateTime) -> Result<PathBuf> {
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::io::Write;
+
+    let mut stamp = now;
+    let mut archive = log_archive_path(log_path, stamp);
+    // Two rotations within the same millisecond would collide; nudge forward.
+    while archive.exists() {
+        stamp += chrono::Duration::milliseconds(1);
+        archive = log_archive_path(log_path, stamp);
+    }
+
+    let file = fs::File::create(&archive)
+        .with_context(|| format!("create archive {}", archive.display()))?;
+    let mut encoder = GzEncoder::new(file, Compression::default());
+    let written = encoder
+        .write_all(data)
+        .and_then(|_| encoder.finish().map(|_| ()));
+    if let Err(e) = written {
+        let _ = fs::remove_file(&archive);
+        return Err(e).with_context(|| format!("write archive {}", archive.display()));
+    }
+    Ok(archive)
+}
+
+fn log_archive_path(log_path: &Path, stamp: chrono::NaiveDateTime) -> PathBuf {
+    let name = log_path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_else(|| "recent_moves.log".to_string());
+    log_path.with_file_name(format!(
+        "{name}.{}.gz",
+        stamp.format(LOG_ARCHIVE_STAMP_FORMAT)
+    ))
+}
+
+/// Parses the rotation timestamp out of an archive file name produced for `log_path`.
+fn log_archive_stamp(log_path: &Path, archive: &Path) -> Option<chrono::NaiveDateTime> {
+    let log_name = log_path.file_name()?.to_str()?;
+    let stamp = archive
+        .file_name()?
+        .to_str()?
+        .strip_prefix(log_name)?
+        .strip_prefix('.')?
+        .strip_suffix(".gz")?;
+    chrono::NaiveDateTime::parse_from_str(stamp, LOG_ARCHIVE_STAMP_FORMAT).ok()
+}
+
+/// Lists the compressed archives belonging to `log_path`, oldest first.
+pub fn list_log_archives(log_path: &Path) -> Vec<PathBuf> {
+    let Some(dir) = log_path.parent() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+
+    let mut archives: Vec<(chrono::NaiveDateTime, PathBuf)> = entries
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .filter_map(|path| log_archive_stamp(log_path, &path).map(|stamp| (stamp, path)))
+        .collect();
+    archives.sort();
+    archives.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Deletes archives beyond `policy.max_archives` (oldest first) and any archive
+/// whose rotation timestamp is older than `policy.max_age_days`.
+fn prune_log_archives(log_path: &Path, policy: &LogArchivePolicy, now: chrono::NaiveDateTime) {
+    let archives = list_log_archives(log_path);
+    let excess = archives.len().saturating_sub(policy.max_archives);
+
+    for (index, archive) in archives.iter().enumerate() {
+        let expired = match (policy.max_age_days, log_archive_stamp(log_path, archive)) {
+            (Some(days), Some(stamp)) => now - stamp > chrono::Duration::days(days as i64),
+            _ => false,
+        };
+        if index < excess || expired {
+            let _ = fs::remove_file(archive);
+        }
+    }
+}
+
+/// Returns the decompressed contents of every archive for `log_path`,
+/// concatenated oldest first. Unreadable archives are skipped.
+///
+/// Prepend the result to the live log to search the full history.
+pub fn read_log_archives(log_path: &Path) -> String {
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    let mut history = String::new();
+    for archive in list_log_archives(log_path) {
+        let Ok(file) = fs::File::open(&archive) else {
+            continue;
+        };
+        let mut segment = String::new();
+        if GzDecoder::new(file).read_to_string(&mut segment).is_err() {
+            continue;
+        }
+        history.push_str(&segment);
+        if !history.is_empty() && !history.ends_with('\n') {
+            history.push('\n');
+        }
+    }
+    history
+}
+
+/// Deletes every compressed archive belonging to `log_path`.
+pub fn remove_log_archives(log_path: &Path) -> Result<()> {
+    for archive in list_log_archives(log_path) {
+        fs::remove_file(&archive).with_context(|| format!("remove {}", archive.display()))?;
+    }
+    Ok(())
 }
 
 /// Loads the configuration from `config_path`, falling back to a default
@@ -846,6 +2323,31 @@ mod tests {
         assert_eq!(result, input);
     }
 
+    #[test]
+    fn test_expand_env_default_fallback() {
+        std::env::set_var("HARBOR_SET_VAR", "/data");
+        std::env::set_var("HARBOR_EMPTY_VAR", "");
+        assert_eq!(expand_env("${HARBOR_SET_VAR:-/tmp}/x"), "/data/x");
+        assert_eq!(expand_env("${HARBOR_EMPTY_VAR:-/tmp}/x"), "/tmp/x");
+        assert_eq!(expand_env("${HARBOR_UNSET_VAR:-}/x"), "/x");
+        // A lone `%` after non-ASCII text stays as it is.
+        assert_eq!(expand_env("Édouard à 100%"), "Édouard à 100%");
+    }
+
+    #[test]
+    fn test_expand_env_strict_lists_undefined() {
+        std::env::set_var("MY_PATH", "/usr/local/bin");
+        assert_eq!(
+            expand_env_strict("$MY_PATH/${HARBOR_UNSET_VAR:-x}").unwrap(),
+            "/usr/local/bin/x"
+        );
+        let err = expand_env_strict("$HARBOR_TYPO/%HARBOR_OTHER%/$HARBOR_TYPO").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "undefined environment variables HARBOR_TYPO, HARBOR_OTHER"
+        );
+    }
+
     #[test]
     fn test_is_partial() {
         assert!(is_partial("file.crdownload"));
@@ -865,7 +2367,7 @@ mod tests {
             let mut f = fs::File::create(&file_path).unwrap();
             f.write_all(b"123").unwrap(); // 3 bytes
         }
-        let meta = fs::metadata(&file_path).unwrap();
+        let meta = FileMeta::from(fs::metadata(&file_path).unwrap());
 
         let rule_ext = Rule {
             id: "ext-rule".to_string(),
@@ -877,6 +2379,7 @@ mod tests {
             target_dir: "target".into(),
             create_symlink: false,
             enabled: true,
+            ..Default::default()
         };
         assert!(matches_rule(
             &file_path,
@@ -884,135 +2387,1335 @@ mod tests {
             &CompiledRule::new(&rule_ext)
         ));
 
-        let rule_pat = Rule {
-            id: "pat-rule".to_string(),
-            name: "Pat".into(),
-            extensions: None,
-            pattern: Some(".*st\\.png".into()),
-            min_size_bytes: None,
-            max_size_bytes: None,
-            target_dir: "target".into(),
-            create_symlink: false,
-            enabled: true,
-        };
-        assert!(matches_rule(
-            &file_path,
-            &meta,
-            &CompiledRule::new(&rule_pat)
-        ));
+        let rule_pat = Rule {
+            id: "pat-rule".to_string(),
+            name: "Pat".into(),
+            extensions: None,
+            pattern: Some(".*st\\.png".into()),
+            min_size_bytes: None,
+            max_size_bytes: None,
+            target_dir: "target".into(),
+            create_symlink: false,
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(matches_rule(
+            &file_path,
+            &meta,
+            &CompiledRule::new(&rule_pat)
+        ));
+
+        let rule_size = Rule {
+            id: "size-rule".to_string(),
+            name: "Size".into(),
+            extensions: None,
+            pattern: None,
+            min_size_bytes: Some(2),
+            max_size_bytes: Some(10),
+            target_dir: "target".into(),
+            create_symlink: false,
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(matches_rule(
+            &file_path,
+            &meta,
+            &CompiledRule::new(&rule_size)
+        ));
+
+        let rule_fail = Rule {
+            id: "fail-rule".to_string(),
+            name: "Fail".into(),
+            extensions: Some(vec!["jpg".into()]),
+            pattern: None,
+            min_size_bytes: None,
+            max_size_bytes: None,
+            target_dir: "target".into(),
+            create_symlink: false,
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(!matches_rule(
+            &file_path,
+            &meta,
+            &CompiledRule::new(&rule_fail)
+        ));
+
+        // Invalid regex must NOT silently match — it should be skipped (false).
+        let rule_bad_re = Rule {
+            id: "bad-re-rule".to_string(),
+            name: "BadRe".into(),
+            extensions: None,
+            pattern: Some("[invalid regex".into()),
+            min_size_bytes: None,
+            max_size_bytes: None,
+            target_dir: "target".into(),
+            create_symlink: false,
+            enabled: true,
+            ..Default::default()
+        };
+        assert!(!matches_rule(
+            &file_path,
+            &meta,
+            &CompiledRule::new(&rule_bad_re)
+        ));
+    }
+
+    #[test]
+    fn test_unique_target() {
+        let temp = TempDir::new().unwrap();
+        let target = temp.path().join("file.txt");
+
+        // 1. Doesn't exist
+        assert_eq!(unique_target(&RealFs, &target, &HashSet::new()), target);
+
+        // 2. Exists
+        fs::File::create(&target).unwrap();
+        let expected = temp.path().join("file (1).txt");
+        assert_eq!(unique_target(&RealFs, &target, &HashSet::new()), expected);
+
+        // 3. (1) Exists
+        fs::File::create(&expected).unwrap();
+        let expected_2 = temp.path().join("file (2).txt");
+        assert_eq!(unique_target(&RealFs, &target, &HashSet::new()), expected_2);
+    }
+
+    #[test]
+    fn test_organize_basic() {
+        let root = TempDir::new().unwrap();
+        let dl = root.path().join("Downloads");
+        let target = root.path().join("Images");
+        fs::create_dir(&dl).unwrap();
+
+        // Create file
+        let file_path = dl.join("test.png");
+        {
+            let mut f = fs::File::create(&file_path).unwrap();
+            f.write_all(b"data").unwrap();
+        }
+
+        // Create config
+        let cfg = DownloadsConfig {
+            download_dir: dl.to_str().unwrap().into(),
+            min_age_secs: Some(0), // Immediate move
+            tutorial_completed: None,
+            service_enabled: None,
+            check_updates: None,
+            last_notified_version: None,
+            rules: vec![Rule {
+                id: "images-rule".to_string(),
+                name: "Images".into(),
+                extensions: Some(vec!["png".into()]),
+                pattern: None,
+                min_size_bytes: None,
+                max_size_bytes: None,
+                target_dir: target.to_str().unwrap().into(),
+                create_symlink: false,
+                enabled: true,
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Run
+        let summary = organize_once(&cfg).unwrap();
+        assert_eq!(summary.moved.len(), 1);
+        assert!(!file_path.exists());
+        assert!(target.join("test.png").exists());
+    }
+
+    #[test]
+    fn test_organize_with_events_reports_each_file() {
+        let root = TempDir::new().unwrap();
+        let dl = root.path().join("Downloads");
+        let target = root.path().join("Images");
+        fs::create_dir(&dl).unwrap();
+        fs::write(dl.join("photo.png"), b"data").unwrap();
+        fs::write(dl.join("notes.txt"), b"text").unwrap();
+        fs::write(dl.join("movie.mkv.part"), b"partial").unwrap();
+
+        let cfg = DownloadsConfig {
+            download_dir: dl.to_str().unwrap().into(),
+            min_age_secs: Some(0),
+            rules: vec![Rule {
+                name: "Images".into(),
+                extensions: Some(vec!["png".into()]),
+                target_dir: target.to_str().unwrap().into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let summary = organize_with_events(&cfg, None, &tx, &CancellationToken::new()).unwrap();
+        drop(tx);
+        let events: Vec<OrganizeEvent> = rx.iter().collect();
+
+        assert_eq!(summary.moved.len(), 1);
+        assert!(matches!(
+            events.first(),
+            Some(OrganizeEvent::ScanStarted { download_dir }) if *download_dir == dl
+        ));
+        let Some(OrganizeEvent::ScanFinished { counts, .. }) = events.last() else {
+            panic!("last event should be ScanFinished: {events:?}");
+        };
+        assert_eq!(
+            *counts,
+            ScanCounts {
+                moved: 1,
+                skipped: 2,
+                failed: 0,
+                deferred: 0,
+            }
+        );
+
+        let mut skips: Vec<(String, SkipReason)> = events
+            .iter()
+            .filter_map(|e| match e {
+                OrganizeEvent::FileSkipped { path, reason, .. } => Some((
+                    path.file_name().unwrap().to_string_lossy().into_owned(),
+                    *reason,
+                )),
+                _ => None,
+            })
+            .collect();
+        skips.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            skips,
+            vec![
+                ("movie.mkv.part".to_string(), SkipReason::PartialDownload),
+                ("notes.txt".to_string(), SkipReason::NoMatchingRule),
+            ]
+        );
+        assert!(events
+            .iter()
+            .any(|e| matches!(e, OrganizeEvent::FileMoved(r) if r.rule_name == "Images")));
+    }
+
+    #[test]
+    fn test_organize_with_events_finishes_on_error() {
+        let root = TempDir::new().unwrap();
+        let cfg = DownloadsConfig {
+            download_dir: root.path().join("missing").to_str().unwrap().into(),
+            ..Default::default()
+        };
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        assert!(organize_with_events(&cfg, None, &tx, &CancellationToken::new()).is_err());
+        drop(tx);
+        let events: Vec<OrganizeEvent> = rx.iter().collect();
+        assert_eq!(events.len(), 2);
+        assert!(matches!(events[1], OrganizeEvent::ScanFinished { .. }));
+    }
+
+    #[test]
+    fn test_organize_cancelled_mid_pass_returns_partial_summary() {
+        use std::cell::Cell;
+
+        let root = TempDir::new().unwrap();
+        let dl = root.path().join("Downloads");
+        let target = root.path().join("Images");
+        fs::create_dir(&dl).unwrap();
+        for name in ["a.png", "b.png", "c.png"] {
+            fs::write(dl.join(name), b"data").unwrap();
+        }
+        let cfg = DownloadsConfig {
+            download_dir: dl.to_str().unwrap().into(),
+            min_age_secs: Some(0),
+            rules: vec![Rule {
+                name: "Images".into(),
+                extensions: Some(vec!["png".into()]),
+                target_dir: target.to_str().unwrap().into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        // Cancel as soon as the first file has been moved.
+        let stop = Cell::new(false);
+        let mut finished_cancelled = None;
+        let summary =
+            organize_with(
+                &RealFs,
+                &SystemClock,
+                &cfg,
+                &|| stop.get(),
+                &mut |event| match event {
+                    OrganizeEvent::FileMoved(_) => stop.set(true),
+                    OrganizeEvent::ScanFinished { cancelled, .. } => {
+                        finished_cancelled = Some(cancelled)
+                    }
+                    _ => {}
+                },
+            )
+            .unwrap();
+
+        assert!(summary.cancelled);
+        assert_eq!(summary.moved.len(), 1);
+        assert_eq!(fs::read_dir(&dl).unwrap().count(), 2);
+        assert_eq!(finished_cancelled, Some(true));
+    }
+
+    #[test]
+    fn test_organize_cancellable_before_start_moves_nothing() {
+        let root = TempDir::new().unwrap();
+        let dl = root.path().join("Downloads");
+        fs::create_dir(&dl).unwrap();
+        fs::write(dl.join("a.png"), b"data").unwrap();
+        let cfg = DownloadsConfig {
+            download_dir: dl.to_str().unwrap().into(),
+            min_age_secs: Some(0),
+            rules: vec![Rule {
+                name: "Images".into(),
+                extensions: Some(vec!["png".into()]),
+                target_dir: root.path().join("Images").to_str().unwrap().into(),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let token = CancellationToken::new();
+        token.cancel();
+        let summary = organize_cancellable(&cfg, &token).unwrap();
+        assert!(summary.cancelled);
+        assert!(summary.moved.is_empty());
+        assert!(dl.join("a.png").exists());
+    }
+
+    #[test]
+    fn test_watch_polling_with_retention_reports_purges() {
+        use crate::types::RetentionPolicy;
+        use std::sync::atomic::{AtomicBool, Ordering};
+        use std::sync::Mutex;
+
+        let root = TempDir::new().unwrap();
+        let dl = root.path().join("Downloads");
+        let target = root.path().join("Installers");
+        fs::create_dir(&dl).unwrap();
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("a.exe"), b"a").unwrap();
+        fs::write(target.join("b.exe"), b"b").unwrap();
+
+        let cfg = DownloadsConfig {
+            download_dir: dl.to_str().unwrap().into(),
+            rules: vec![Rule {
+                name: "Installers".into(),
+                extensions: Some(vec!["exe".into()]),
+                target_dir: target.to_str().unwrap().into(),
+                retention: Some(RetentionPolicy {
+                    max_files: Some(1),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let running = AtomicBool::new(true);
+        let purged = Mutex::new(Vec::new());
+        watch_polling_with_retention(
+            &cfg,
+            1,
+            &running,
+            |_| {},
+            |results| {
+                purged
+                    .lock()
+                    .unwrap()
+                    .extend(results.iter().map(|r| r.rule_name.clone()));
+                running.store(false, Ordering::Relaxed);
+            },
+        )
+        .unwrap();
+
+        assert_eq!(*purged.lock().unwrap(), vec!["Installers".to_string()]);
+        assert_eq!(fs::read_dir(&target).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn test_check_free_space() {
+        let enough = || Ok(1_000);
+        // Same-filesystem renames never need space.
+        assert_eq!(check_free_space(5_000, 0, false, enough()), None);
+        assert_eq!(check_free_space(400, 500, true, enough()), None);
+        assert_eq!(
+            check_free_space(600, 500, true, enough()),
+            Some(SkipReason::InsufficientSpace {
+                required: 1_100,
+                available: 1_000
+            })
+        );
+        // Unknown free space does not block the move.
+        let unknown = Err(std::io::Error::other("statvfs failed"));
+        assert_eq!(check_free_space(600, 500, true, unknown), None);
+    }
+
+    #[test]
+    fn test_copy_then_remove_moves_file() {
+        let root = TempDir::new().unwrap();
+        let src = root.path().join("a.bin");
+        let dst = root.path().join("out").join("a.bin");
+        fs::create_dir(root.path().join("out")).unwrap();
+        fs::write(&src, b"payload").unwrap();
+
+        copy_then_remove(&RealFs, &src, &dst, &|| false).unwrap();
+        assert!(!src.exists());
+        assert_eq!(fs::read(&dst).unwrap(), b"payload");
+        assert!(!root
+            .path()
+            .join("out")
+            .join(".a.bin.harbor-partial")
+            .exists());
+    }
+
+    #[test]
+    fn test_copy_then_remove_stops_when_cancelled() {
+        let root = TempDir::new().unwrap();
+        let src = root.path().join("big.bin");
+        fs::write(&src, vec![7u8; crate::vfs::COPY_CHUNK_BYTES * 3]).unwrap();
+        let dst = root.path().join("out.bin");
+
+        let chunks = std::cell::Cell::new(0);
+        let err = copy_then_remove(&RealFs, &src, &dst, &|| {
+            chunks.set(chunks.get() + 1);
+            chunks.get() > 1
+        })
+        .unwrap_err();
+
+        assert_eq!(err.kind(), std::io::ErrorKind::Interrupted);
+        assert!(src.exists());
+        assert!(!dst.exists());
+        assert!(!root.path().join(".out.bin.harbor-partial").exists());
+    }
+
+    #[test]
+    fn test_copy_then_remove_cleans_up_partial_on_failure() {
+        let root = TempDir::new().unwrap();
+        let src = root.path().join("a.bin");
+        fs::write(&src, b"payload").unwrap();
+        // The destination "file" is an existing non-empty directory, so the
+        // final rename fails after the copy has been written.
+        let dst = root.path().join("taken");
+        fs::create_dir(&dst).unwrap();
+        fs::write(dst.join("keep"), b"x").unwrap();
+
+        assert!(copy_then_remove(&RealFs, &src, &dst, &|| false).is_err());
+        assert!(src.exists());
+        assert!(!root.path().join(".taken.harbor-partial").exists());
+    }
+
+    // ── organizer against MemoryFs ──────────────────────────────
+
+    fn memory_cfg(rules: Vec<Rule>) -> DownloadsConfig {
+        DownloadsConfig {
+            download_dir: "/home/u/Downloads".into(),
+            min_age_secs: Some(0),
+            rules,
+            ..Default::default()
+        }
+    }
+
+    fn memory_rule(name: &str, ext: &str, target_dir: &str) -> Rule {
+        Rule {
+            name: name.into(),
+            extensions: Some(vec![ext.into()]),
+            target_dir: target_dir.into(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_memory_fs_permission_denied_fails_only_that_file() {
+        use crate::vfs::{Fault, FsOp, MemoryFs};
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        fs.add_file("/home/u/Downloads/b.pdf", b"b".to_vec());
+        fs.inject(
+            FsOp::Rename,
+            "/home/u/Downloads/a.pdf",
+            Fault::Error(std::io::ErrorKind::PermissionDenied),
+        );
+        let cfg = memory_cfg(vec![memory_rule("Docs", "pdf", "/home/u/Docs")]);
+
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+
+        assert_eq!(summary.moved.len(), 1);
+        assert_eq!(summary.errors.len(), 1);
+        assert!(summary.errors[0].contains("a.pdf"));
+        assert_eq!(fs.read("/home/u/Downloads/a.pdf"), Some(b"a".to_vec()));
+        assert_eq!(fs.read("/home/u/Docs/b.pdf"), Some(b"b".to_vec()));
+    }
+
+    #[test]
+    fn test_memory_fs_cross_device_move_copies_and_removes_source() {
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/movie.mkv", vec![1u8; 64]);
+        fs.mount("/mnt/media", 1000);
+        let cfg = memory_cfg(vec![memory_rule("Video", "mkv", "/mnt/media/Video")]);
+
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+
+        assert_eq!(summary.moved.len(), 1);
+        assert!(!fs.exists(Path::new("/home/u/Downloads/movie.mkv")));
+        assert_eq!(fs.read("/mnt/media/Video/movie.mkv").unwrap().len(), 64);
+        assert_eq!(fs.available_space(Path::new("/mnt/media")).unwrap(), 936);
+        assert!(!fs
+            .paths()
+            .iter()
+            .any(|p| p.to_string_lossy().ends_with(".harbor-partial")));
+    }
+
+    #[test]
+    fn test_memory_fs_disk_full_during_copy_keeps_source() {
+        use crate::vfs::{Fault, FsOp, MemoryFs};
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/disk.iso", vec![1u8; 64]);
+        fs.mount("/mnt/media", 1000);
+        // Free space looked fine up front, but the disk filled during the copy.
+        fs.inject(
+            FsOp::Copy,
+            "/home/u/Downloads/disk.iso",
+            Fault::Error(std::io::ErrorKind::StorageFull),
+        );
+        let cfg = memory_cfg(vec![memory_rule("Images", "iso", "/mnt/media/Images")]);
+
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+
+        assert!(summary.moved.is_empty());
+        assert_eq!(summary.errors.len(), 1);
+        assert!(fs.exists(Path::new("/home/u/Downloads/disk.iso")));
+        assert_eq!(
+            fs.read_dir(Path::new("/mnt/media/Images")).unwrap(),
+            Vec::<PathBuf>::new()
+        );
+    }
+
+    #[test]
+    fn test_memory_fs_file_vanishing_mid_pass_is_reported() {
+        use crate::vfs::{Fault, FsOp, MemoryFs};
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/a.zip", b"zip".to_vec());
+        // The user deletes the file after the scan but before the move.
+        fs.inject_once(FsOp::Rename, "/home/u/Downloads/a.zip", Fault::Vanish);
+        let cfg = memory_cfg(vec![memory_rule("Archives", "zip", "/home/u/Archives")]);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        let summary = organize_with(&fs, &SystemClock, &cfg, &|| false, &mut |e| {
+            tx.send(e).unwrap()
+        })
+        .unwrap();
+        drop(tx);
+
+        assert!(summary.moved.is_empty());
+        assert!(rx.iter().any(|e| matches!(
+            e,
+            OrganizeEvent::FileFailed { ref path, .. } if path == Path::new("/home/u/Downloads/a.zip")
+        )));
+    }
+
+    #[test]
+    fn test_memory_fs_target_dir_unwritable_aborts_pass() {
+        use crate::vfs::{Fault, FsOp, MemoryFs};
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        fs.inject(
+            FsOp::CreateDir,
+            "/home/u/Docs",
+            Fault::Error(std::io::ErrorKind::PermissionDenied),
+        );
+        let cfg = memory_cfg(vec![memory_rule("Docs", "pdf", "/home/u/Docs")]);
+
+        let err = organize_once_with(&cfg, &fs, &SystemClock).unwrap_err();
+        assert!(format!("{err:#}").contains("create /home/u/Docs"));
+        assert!(fs.exists(Path::new("/home/u/Downloads/a.pdf")));
+    }
+
+    #[test]
+    fn test_min_age_is_judged_by_clock() {
+        use crate::clock::ManualClock;
+        use crate::vfs::MemoryFs;
+
+        let clock = ManualClock::at_local("2024-01-15 10:30:00");
+        let fs = MemoryFs::new();
+        fs.add_file_modified("/home/u/Downloads/a.pdf", b"a".to_vec(), clock.now());
+        let mut cfg = memory_cfg(vec![memory_rule("Docs", "pdf", "/home/u/Docs")]);
+        cfg.min_age_secs = Some(5);
+
+        clock.advance(Duration::from_secs(3));
+        let summary = organize_once_with(&cfg, &fs, &clock).unwrap();
+        assert!(summary.moved.is_empty());
+
+        clock.advance(Duration::from_secs(3));
+        let summary = organize_once_with(&cfg, &fs, &clock).unwrap();
+        assert_eq!(summary.moved.len(), 1);
+    }
+
+    #[test]
+    fn test_rule_schedule_holds_matching_files_until_due() {
+        use crate::clock::ManualClock;
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/setup.exe", b"exe".to_vec());
+        fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        let mut installers = memory_rule("Installers", "exe", "/home/u/Archive");
+        installers.schedule = Some("0 3 * * sun".into());
+        let mut everything = memory_rule("Everything", "exe", "/home/u/Misc");
+        everything.extensions = None;
+        let cfg = memory_cfg(vec![installers, everything]);
+
+        // Monday: the installer waits instead of falling through to "Everything".
+        let clock = ManualClock::at_local("2024-01-15 10:30:00");
+        let summary = organize_once_with(&cfg, &fs, &clock).unwrap();
+        assert_eq!(summary.moved.len(), 1);
+        assert_eq!(summary.skipped.len(), 1);
+        assert_eq!(summary.skipped[0].reason, SkipReason::OutsideSchedule);
+        assert_eq!(
+            summary.skipped[0].destination,
+            PathBuf::from("/home/u/Archive/setup.exe")
+        );
+
+        // The watcher's pass came a little after 03:00 on Sunday.
+        let clock = ManualClock::at_local("2024-01-21 03:01:04");
+        let since = ManualClock::at_local("2024-01-21 02:59:59").now();
+        let summary =
+            organize_since(&fs, &clock, &cfg, Some(since), None, &|| false, &mut |_| {}).unwrap();
+        assert_eq!(summary.moved.len(), 1);
+        assert!(fs.exists(Path::new("/home/u/Archive/setup.exe")));
+    }
+
+    #[test]
+    fn test_watch_organizes_only_while_the_schedule_allows() {
+        use crate::clock::ManualClock;
+        use crate::vfs::MemoryFs;
+        use std::cell::Cell;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        let mut cfg = memory_cfg(vec![memory_rule("Docs", "pdf", "/home/u/Docs")]);
+        cfg.schedule = Some("* 9-17 * * mon-fri".into());
+        let watch = |clock: &ManualClock| {
+            let checks = Cell::new(0);
+            let mut scans = 0;
+            watch_with(
+                &fs,
+                clock,
+                &cfg,
+                None,
+                0,
+                &|| {
+                    checks.set(checks.get() + 1);
+                    checks.get() > 3
+                },
+                &mut |event| {
+                    if matches!(event, OrganizeEvent::ScanStarted { .. }) {
+                        scans += 1;
+                    }
+                },
+            )
+            .unwrap();
+            scans
+        };
+
+        assert_eq!(watch(&ManualClock::at_local("2024-01-15 20:00:00")), 0);
+        assert!(fs.exists(Path::new("/home/u/Downloads/a.pdf")));
+        assert!(watch(&ManualClock::at_local("2024-01-16 09:00:00")) > 0);
+        assert!(fs.exists(Path::new("/home/u/Docs/a.pdf")));
+
+        let next = cfg.next_scheduled_run(ManualClock::at_local("2024-01-19 18:00:00").local_now());
+        assert_eq!(
+            next,
+            Some(ManualClock::at_local("2024-01-22 09:00:00").local_now())
+        );
+    }
+
+    #[test]
+    fn test_watch_does_nothing_during_quiet_hours() {
+        use crate::clock::ManualClock;
+        use crate::vfs::MemoryFs;
+        use std::cell::Cell;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        let mut cfg = memory_cfg(vec![memory_rule("Docs", "pdf", "/home/u/Docs")]);
+        cfg.quiet_hours = Some(vec![QuietHours {
+            start: "22:00".into(),
+            end: "07:00".into(),
+            days: None,
+        }]);
+        let watch = |clock: &ManualClock| {
+            let checks = Cell::new(0);
+            let mut events = 0;
+            watch_with(
+                &fs,
+                clock,
+                &cfg,
+                None,
+                0,
+                &|| {
+                    checks.set(checks.get() + 1);
+                    checks.get() > 3
+                },
+                &mut |_| events += 1,
+            )
+            .unwrap();
+            events
+        };
+
+        assert_eq!(watch(&ManualClock::at_local("2024-01-15 23:00:00")), 0);
+        assert!(fs.exists(Path::new("/home/u/Downloads/a.pdf")));
+        assert_eq!(
+            cfg.quiet_until(ManualClock::at_local("2024-01-16 06:00:00").local_now()),
+            Some(ManualClock::at_local("2024-01-16 07:00:00").local_now())
+        );
+        assert!(watch(&ManualClock::at_local("2024-01-16 07:00:00")) > 0);
+        assert!(fs.exists(Path::new("/home/u/Docs/a.pdf")));
+    }
+
+    #[test]
+    fn test_saved_pause_runs_until_its_end() {
+        use crate::clock::ManualClock;
+
+        let now = ManualClock::at_local("2024-01-15 10:00:00").local_now();
+        let mut cfg = DownloadsConfig::default();
+        assert_eq!(cfg.pause_running_at(now), None);
+
+        let until = now + chrono::TimeDelta::minutes(30);
+        cfg.paused_until = Some(until.to_rfc3339());
+        assert_eq!(cfg.pause_running_at(now), Some(until));
+        assert_eq!(cfg.pause_running_at(until), None);
+
+        cfg.paused_until = Some("soon".into());
+        assert_eq!(cfg.pause_running_at(now), None);
+    }
+
+    #[test]
+    fn test_pass_limits_leave_the_backlog_for_later_passes() {
+        use crate::clock::SystemClock;
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        for name in ["a", "b", "c", "d", "e"] {
+            fs.add_file(format!("/home/u/Downloads/{name}.pdf"), vec![0u8; 10]);
+        }
+        let mut cfg = memory_cfg(vec![memory_rule("Docs", "pdf", "/home/u/Docs")]);
+        cfg.max_moves_per_pass = Some(2);
+
+        let plan = plan_with(&fs, &SystemClock, &cfg, &mut |_| {}).unwrap();
+        assert_eq!((plan.moved.len(), plan.deferred), (2, 3));
+
+        let mut counts = ScanCounts::default();
+        let summary = organize_with(&fs, &SystemClock, &cfg, &|| false, &mut |event| {
+            if let OrganizeEvent::ScanFinished { counts: c, .. } = event {
+                counts = c;
+            }
+        })
+        .unwrap();
+        assert_eq!(summary.moved.len(), 2);
+        assert_eq!((summary.deferred, summary.deferred_bytes), (3, 30));
+        assert!(summary.skipped.is_empty());
+        assert_eq!(counts.deferred, 3);
+        assert!(fs.exists(Path::new("/home/u/Docs/b.pdf")));
+        assert!(fs.exists(Path::new("/home/u/Downloads/c.pdf")));
+
+        // 25 bytes fit two 10-byte files; a first file over the limit still moves.
+        cfg.max_moves_per_pass = None;
+        cfg.max_bytes_per_pass = Some(25);
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+        assert_eq!((summary.moved.len(), summary.deferred), (2, 1));
+        fs.add_file("/home/u/Downloads/big.pdf", vec![0u8; 100]);
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+        assert_eq!((summary.moved.len(), summary.deferred), (1, 1));
+        assert!(fs.exists(Path::new("/home/u/Docs/big.pdf")));
+    }
+
+    #[test]
+    fn test_copy_throughput_cap_paces_cross_device_moves() {
+        use crate::clock::SystemClock;
+        use crate::vfs::{MemoryFs, COPY_CHUNK_BYTES};
+
+        let fs = MemoryFs::new();
+        fs.add_file(
+            "/home/u/Downloads/disk.iso",
+            vec![1u8; COPY_CHUNK_BYTES * 3],
+        );
+        fs.mount("/mnt/nas", u64::MAX);
+        let mut cfg = memory_cfg(vec![memory_rule("Images", "iso", "/mnt/nas/Images")]);
+        // 20 chunks a second: the second and third chunk wait 50 ms each.
+        cfg.max_copy_bytes_per_sec = Some(COPY_CHUNK_BYTES as u64 * 20);
+
+        let started = Instant::now();
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+        assert_eq!(summary.moved.len(), 1);
+        assert!(started.elapsed() >= Duration::from_millis(100));
+        assert!(fs.exists(Path::new("/mnt/nas/Images/disk.iso")));
+    }
+
+    #[test]
+    fn test_load_downloads_config_rejects_invalid_schedules() {
+        let mut file = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        writeln!(
+            file,
+            r#"
+download_dir: /home/u/Downloads
+schedule: "* 9-17 * * mon-fri"
+quiet_hours:
+  - start: "22:00"
+    end: "7"
+rules:
+  - name: Installers
+    target_dir: /home/u/Archive
+    schedule: "0 3 * * sunday"
+"#
+        )
+        .unwrap();
+        let err = format!("{:#}", load_downloads_config(file.path()).unwrap_err());
+        assert!(
+            err.contains("rule \"Installers\" schedule: invalid cron expression"),
+            "{err}"
+        );
+        assert!(!err.contains("\n  schedule"), "{err}");
+        assert!(
+            err.contains("quiet_hours[0]: invalid quiet hours end"),
+            "{err}"
+        );
+    }
+
+    #[test]
+    fn test_memory_fs_cleanup_old_symlinks() {
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Docs/a.pdf", b"a".to_vec());
+        fs.add_symlink("/home/u/Downloads/a.pdf", "/home/u/Docs/a.pdf");
+        fs.add_symlink("/home/u/Downloads/other", "/etc/hosts");
+        let cfg = memory_cfg(vec![memory_rule("Docs", "pdf", "/home/u/Docs")]);
+
+        assert_eq!(cleanup_old_symlinks_with_fs(&cfg, &fs).unwrap(), 1);
+        assert!(!fs
+            .paths()
+            .contains(&PathBuf::from("/home/u/Downloads/a.pdf")));
+        assert!(fs
+            .paths()
+            .contains(&PathBuf::from("/home/u/Downloads/other")));
+    }
+
+    #[test]
+    fn test_memory_fs_watch_sweep_removes_only_dangling_breadcrumbs() {
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Docs/Work/a.pdf", b"a".to_vec());
+        fs.add_symlink("/home/u/Downloads/a.pdf", "/home/u/Docs/Work/a.pdf");
+        fs.add_symlink("/home/u/Downloads/b.pdf", "/home/u/Docs/Work/b.pdf");
+        let cfg = memory_cfg(vec![
+            memory_rule("Docs", "pdf", "/home/u/Docs"),
+            memory_rule("Work", "pdf", "/home/u/Docs/Work"),
+        ]);
+
+        // Without a lifetime the watcher only drops links to vanished files.
+        let report = cleanup_breadcrumbs_with(&fs, &SystemClock, &cfg, None).unwrap();
+        assert_eq!(report.removed.len(), 1);
+        let removed = &report.removed[0];
+        assert_eq!(removed.path, PathBuf::from("/home/u/Downloads/b.pdf"));
+        assert_eq!(removed.rule_name.as_deref(), Some("Work"));
+        assert_eq!(
+            removed.describe(),
+            "dangling symlink to /home/u/Docs/Work/b.pdf"
+        );
+
+        // Startup without a lifetime clears the rest.
+        assert_eq!(cleanup_old_symlinks_with_fs(&cfg, &fs).unwrap(), 1);
+        assert!(fs
+            .read_dir(Path::new("/home/u/Downloads"))
+            .unwrap()
+            .is_empty());
+    }
+
+    #[test]
+    fn test_memory_fs_capture_groups_fill_target_dir_and_rename_to() {
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/acme_invoice_2024_03.pdf", b"a".to_vec());
+        fs.add_file("/home/u/Downloads/other.pdf", b"b".to_vec());
+        let rule = Rule {
+            name: "Invoices".into(),
+            pattern: Some(r"^(?P<vendor>\w+?)_invoice_(?P<yyyy>\d{4})".into()),
+            target_dir: "/home/u/Invoices/{vendor}/{yyyy}".into(),
+            rename_to: Some("{vendor}-{yyyy}-{name}".into()),
+            ..Default::default()
+        };
+        let cfg = memory_cfg(vec![rule]);
+
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+
+        assert_eq!(summary.moved.len(), 1);
+        assert_eq!(
+            summary.moved[0].destination,
+            PathBuf::from("/home/u/Invoices/acme/2024/acme-2024-acme_invoice_2024_03.pdf")
+        );
+        assert_eq!(
+            rule_target_dirs(&cfg),
+            vec![PathBuf::from("/home/u/Invoices")]
+        );
+    }
+
+    #[test]
+    fn test_memory_fs_sidecars_follow_their_primary() {
+        use crate::clock::ManualClock;
+        use crate::types::SidecarConfig;
+        use crate::vfs::MemoryFs;
+
+        let clock = ManualClock::at_local("2024-01-15 10:30:00");
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/movie.mkv", b"video".to_vec());
+        fs.add_file("/home/u/Videos/movie.mkv", b"older".to_vec());
+        fs.add_file("/home/u/Downloads/other.srt", b"subs".to_vec());
+        // Still being written, so the whole group waits.
+        fs.add_file_modified(
+            "/home/u/Downloads/movie.en.srt",
+            b"subs".to_vec(),
+            clock.now(),
+        );
+        let mut cfg = memory_cfg(vec![
+            memory_rule("Videos", "mkv", "/home/u/Videos"),
+            memory_rule("Subtitles", "srt", "/home/u/Subtitles"),
+        ]);
+        cfg.min_age_secs = Some(5);
+        cfg.sidecars = Some(SidecarConfig::default());
+
+        let mut skipped = Vec::new();
+        organize_with(&fs, &clock, &cfg, &|| false, &mut |event| {
+            if let OrganizeEvent::FileSkipped { path, reason, .. } = event {
+                skipped.push((path.file_name().unwrap().to_os_string(), reason));
+            }
+        })
+        .unwrap();
+        skipped.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            skipped,
+            vec![
+                ("movie.en.srt".into(), SkipReason::WaitingForPrimary),
+                ("movie.mkv".into(), SkipReason::TooRecent),
+            ]
+        );
+        assert!(fs.read("/home/u/Subtitles/other.srt").is_some());
+
+        clock.advance(Duration::from_secs(10));
+        let summary = organize_once_with(&cfg, &fs, &clock).unwrap();
+
+        let moved: Vec<_> = summary
+            .moved
+            .iter()
+            .map(|r| (r.destination.clone(), r.rule_name.as_str()))
+            .collect();
+        assert_eq!(
+            moved,
+            vec![
+                (PathBuf::from("/home/u/Videos/movie (1).mkv"), "Videos"),
+                (PathBuf::from("/home/u/Videos/movie (1).en.srt"), "Videos"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_memory_fs_sidecars_wait_for_a_primary_that_could_not_move() {
+        use crate::types::SidecarConfig;
+        use crate::vfs::{Fault, FsOp, MemoryFs};
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/movie.mkv", vec![1u8; 64]);
+        fs.add_file("/home/u/Downloads/movie.srt", b"subs".to_vec());
+        fs.add_file("/home/u/Downloads/talk.mkv", b"video".to_vec());
+        fs.add_file("/home/u/Downloads/talk.srt", b"subs".to_vec());
+        fs.mount("/mnt/media", 32);
+        fs.inject(
+            FsOp::Rename,
+            "/home/u/Downloads/talk.mkv",
+            Fault::Error(std::io::ErrorKind::PermissionDenied),
+        );
+        let mut rules = vec![
+            memory_rule("Videos", "mkv", "/mnt/media/Videos"),
+            memory_rule("Subtitles", "srt", "/home/u/Subtitles"),
+        ];
+        rules[0].pattern = Some("^movie".into());
+        rules.push(memory_rule("Talks", "mkv", "/home/u/Talks"));
+        let mut cfg = memory_cfg(rules);
+        cfg.sidecars = Some(SidecarConfig::default());
+
+        let mut skipped = Vec::new();
+        organize_with(&fs, &SystemClock, &cfg, &|| false, &mut |event| {
+            if let OrganizeEvent::FileSkipped { path, reason, .. } = event {
+                skipped.push((path.file_name().unwrap().to_os_string(), reason));
+            }
+        })
+        .unwrap();
+        skipped.sort_by(|a, b| a.0.cmp(&b.0));
+
+        assert_eq!(
+            skipped,
+            vec![
+                (
+                    "movie.mkv".into(),
+                    SkipReason::InsufficientSpace {
+                        required: 64,
+                        available: 32
+                    }
+                ),
+                ("movie.srt".into(), SkipReason::WaitingForPrimary),
+                ("talk.srt".into(), SkipReason::WaitingForPrimary),
+            ]
+        );
+        assert!(fs.exists(Path::new("/home/u/Downloads/movie.srt")));
+        assert!(fs.exists(Path::new("/home/u/Downloads/talk.srt")));
+        assert!(!fs.exists(Path::new("/home/u/Subtitles")));
+    }
+
+    #[test]
+    fn test_memory_fs_split_archive_waits_for_the_whole_set() {
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/movie.part1.rar", vec![0u8; 10]);
+        fs.add_file("/home/u/Downloads/movie.part3.rar", vec![0u8; 4]);
+        fs.add_file("/home/u/Downloads/movie.part2.rar.crdownload", vec![0u8; 6]);
+        fs.add_file("/home/u/Downloads/backup.7z.001", vec![0u8; 10]);
+        fs.add_file("/home/u/Downloads/backup.7z.002", vec![0u8; 3]);
+        fs.add_file("/home/u/Archives/backup.7z.002", b"older".to_vec());
+        let cfg = memory_cfg(vec![
+            memory_rule("Archives", "rar", "/home/u/Archives"),
+            memory_rule("7-Zip", "7z", "/home/u/Archives"),
+        ]);
+
+        let mut skipped = Vec::new();
+        organize_with(&fs, &SystemClock, &cfg, &|| false, &mut |event| {
+            if let OrganizeEvent::FileSkipped { path, reason, .. } = event {
+                skipped.push((path.file_name().unwrap().to_os_string(), reason));
+            }
+        })
+        .unwrap();
+        skipped.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            skipped,
+            vec![
+                ("movie.part1.rar".into(), SkipReason::IncompleteSet),
+                (
+                    "movie.part2.rar.crdownload".into(),
+                    SkipReason::PartialDownload
+                ),
+                ("movie.part3.rar".into(), SkipReason::IncompleteSet),
+            ]
+        );
+        // The set moved as a whole, renamed alike to stay openable.
+        assert!(fs.read("/home/u/Archives/backup (1).7z.001").is_some());
+        assert!(fs.read("/home/u/Archives/backup (1).7z.002").is_some());
+
+        fs.remove_file(Path::new("/home/u/Downloads/movie.part2.rar.crdownload"))
+            .unwrap();
+        fs.add_file("/home/u/Downloads/movie.part2.rar", vec![0u8; 10]);
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+
+        let moved: Vec<_> = summary
+            .moved
+            .iter()
+            .map(|r| (r.destination.clone(), r.rule_name.as_str()))
+            .collect();
+        assert_eq!(
+            moved,
+            vec![
+                (
+                    PathBuf::from("/home/u/Archives/movie.part1.rar"),
+                    "Archives"
+                ),
+                (
+                    PathBuf::from("/home/u/Archives/movie.part2.rar"),
+                    "Archives"
+                ),
+                (
+                    PathBuf::from("/home/u/Archives/movie.part3.rar"),
+                    "Archives"
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_memory_fs_held_back_files_create_no_target_folders() {
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.mount("/mnt/nas", 5);
+        fs.add_dir("/mnt/nas");
+        fs.add_file("/home/u/Downloads/a.pdf", vec![0u8; 10]);
+        fs.add_file("/home/u/Downloads/b.txt", vec![0u8; 1]);
+        fs.add_file("/home/u/Downloads/c.png", vec![0u8; 1]);
+        fs.add_file("/home/u/Downloads/backup.7z.001", vec![0u8; 10]);
+        fs.add_file("/home/u/Downloads/backup.7z.002", vec![0u8; 3]);
+        let mut cfg = memory_cfg(vec![
+            memory_rule("Docs", "pdf", "/mnt/nas/Docs"),
+            memory_rule("7-Zip", "7z", "/mnt/nas/Archives"),
+            memory_rule("Text", "txt", "/home/u/Text"),
+            memory_rule("Images", "png", "/home/u/Images"),
+        ]);
+        cfg.max_moves_per_pass = Some(1);
+
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+
+        // Neither the NAS folders (no space) nor the deferred one get created.
+        assert_eq!(summary.moved.len(), 1);
+        assert_eq!(summary.skipped.len(), 3);
+        assert_eq!(summary.deferred, 1);
+        for dir in ["/mnt/nas/Docs", "/mnt/nas/Archives", "/home/u/Images"] {
+            assert!(!fs.exists(Path::new(dir)), "{dir} was created");
+        }
+        assert!(fs.exists(Path::new("/home/u/Text/b.txt")));
+    }
+
+    #[test]
+    fn test_memory_fs_split_archive_leaves_a_breadcrumb_per_volume() {
+        use crate::types::BreadcrumbKind;
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/backup.7z.001", vec![0u8; 10]);
+        fs.add_file("/home/u/Downloads/backup.7z.002", vec![0u8; 3]);
+        let mut rule = memory_rule("7-Zip", "7z", "/home/u/Archives");
+        rule.breadcrumb = Some(BreadcrumbKind::Symlink);
+        let cfg = memory_cfg(vec![rule]);
+
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+
+        assert_eq!(summary.moved.len(), 2);
+        for result in &summary.moved {
+            assert_eq!(result.symlink_info.as_deref(), Some("Symlink created"));
+            assert_eq!(fs.read_link(&result.source).unwrap(), result.destination);
+        }
+        assert!(organize_once_with(&cfg, &fs, &SystemClock)
+            .unwrap()
+            .moved
+            .is_empty());
+    }
+
+    #[test]
+    fn test_memory_fs_split_archive_failure_puts_moved_parts_back() {
+        use crate::vfs::{Fault, FsOp, MemoryFs};
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/data.z01", vec![0u8; 10]);
+        fs.add_file("/home/u/Downloads/data.z02", vec![0u8; 10]);
+        fs.add_file("/home/u/Downloads/data.zip", vec![0u8; 2]);
+        fs.inject(
+            FsOp::Rename,
+            "/home/u/Downloads/data.zip",
+            Fault::Error(std::io::ErrorKind::PermissionDenied),
+        );
+        let cfg = memory_cfg(vec![memory_rule("Zips", "zip", "/home/u/Zips")]);
+
+        let mut failed = 0;
+        let summary = organize_with(&fs, &SystemClock, &cfg, &|| false, &mut |event| {
+            if let OrganizeEvent::FileFailed { .. } = event {
+                failed += 1;
+            }
+        })
+        .unwrap();
 
-        let rule_size = Rule {
-            id: "size-rule".to_string(),
-            name: "Size".into(),
-            extensions: None,
-            pattern: None,
-            min_size_bytes: Some(2),
-            max_size_bytes: Some(10),
-            target_dir: "target".into(),
-            create_symlink: false,
-            enabled: true,
-        };
-        assert!(matches_rule(
-            &file_path,
-            &meta,
-            &CompiledRule::new(&rule_size)
-        ));
+        assert_eq!(failed, 3);
+        assert!(summary.moved.is_empty());
+        assert_eq!(summary.errors.len(), 1);
+        assert!(summary.errors[0].contains("data.zip"));
+        for name in ["data.z01", "data.z02", "data.zip"] {
+            assert!(fs.exists(&Path::new("/home/u/Downloads").join(name)));
+            assert!(!fs.exists(&Path::new("/home/u/Zips").join(name)));
+        }
+    }
 
-        let rule_fail = Rule {
-            id: "fail-rule".to_string(),
-            name: "Fail".into(),
-            extensions: Some(vec!["jpg".into()]),
-            pattern: None,
-            min_size_bytes: None,
-            max_size_bytes: None,
-            target_dir: "target".into(),
-            create_symlink: false,
-            enabled: true,
-        };
-        assert!(!matches_rule(
-            &file_path,
-            &meta,
-            &CompiledRule::new(&rule_fail)
-        ));
+    #[cfg(unix)]
+    #[test]
+    fn test_on_moved_hooks_run_after_each_move() {
+        use crate::vfs::MemoryFs;
 
-        // Invalid regex must NOT silently match — it should be skipped (false).
-        let rule_bad_re = Rule {
-            id: "bad-re-rule".to_string(),
-            name: "BadRe".into(),
-            extensions: None,
-            pattern: Some("[invalid regex".into()),
-            min_size_bytes: None,
-            max_size_bytes: None,
-            target_dir: "target".into(),
-            create_symlink: false,
-            enabled: true,
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        fs.add_file("/home/u/Downloads/b.txt", b"b".to_vec());
+        let hook = |script: &str| HookCommand {
+            command: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            timeout_secs: None,
         };
-        assert!(!matches_rule(
-            &file_path,
-            &meta,
-            &CompiledRule::new(&rule_bad_re)
-        ));
+        let mut rule = memory_rule("Docs", "pdf", "/home/u/Docs");
+        rule.on_moved = Some(vec![hook("echo {ext} >&2")]);
+        let mut cfg = memory_cfg(vec![rule, memory_rule("Text", "txt", "/home/u/Text")]);
+        cfg.on_moved = Some(vec![hook("exit 4")]);
+
+        let mut seen = Vec::new();
+        organize_with(
+            &fs,
+            &SystemClock,
+            &cfg,
+            &|| false,
+            &mut |event| match event {
+                OrganizeEvent::FileMoved(r) => {
+                    seen.push(format!("moved {}", r.destination.display()));
+                }
+                OrganizeEvent::HookFinished(outcome) => seen.push(outcome.describe()),
+                _ => {}
+            },
+        )
+        .unwrap();
+
+        assert_eq!(
+            seen,
+            vec![
+                "moved /home/u/Docs/a.pdf",
+                "`sh -c echo pdf >&2` exited with 0: pdf",
+                "`sh -c exit 4` exited with 4",
+                "moved /home/u/Text/b.txt",
+                "`sh -c exit 4` exited with 4",
+            ]
+        );
     }
 
     #[test]
-    fn test_unique_target() {
-        let temp = TempDir::new().unwrap();
-        let target = temp.path().join("file.txt");
+    fn test_memory_fs_normalized_name_still_gets_unique_target() {
+        use crate::types::NormalizeStep;
+        use crate::vfs::MemoryFs;
 
-        // 1. Doesn't exist
-        assert_eq!(unique_target(&target), target);
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/Report%20(1).PDF", b"new".to_vec());
+        fs.add_file("/home/u/Docs/Report.pdf", b"old".to_vec());
+        let mut rule = memory_rule("Docs", "pdf", "/home/u/Docs");
+        rule.normalize = Some(vec![
+            NormalizeStep::LowercaseExtension,
+            NormalizeStep::UrlDecode,
+            NormalizeStep::StripDuplicateSuffix,
+        ]);
+        let cfg = memory_cfg(vec![rule]);
 
-        // 2. Exists
-        fs::File::create(&target).unwrap();
-        let expected = temp.path().join("file (1).txt");
-        assert_eq!(unique_target(&target), expected);
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
 
-        // 3. (1) Exists
-        fs::File::create(&expected).unwrap();
-        let expected_2 = temp.path().join("file (2).txt");
-        assert_eq!(unique_target(&target), expected_2);
+        assert_eq!(
+            summary.moved[0].destination,
+            PathBuf::from("/home/u/Docs/Report (1).pdf")
+        );
+        assert_eq!(
+            fs.read("/home/u/Docs/Report (1).pdf"),
+            Some(b"new".to_vec())
+        );
+        assert_eq!(fs.read("/home/u/Docs/Report.pdf"), Some(b"old".to_vec()));
     }
 
     #[test]
-    fn test_organize_basic() {
-        let root = TempDir::new().unwrap();
-        let dl = root.path().join("Downloads");
-        let target = root.path().join("Images");
-        fs::create_dir(&dl).unwrap();
+    fn test_memory_fs_breadcrumbs_are_skipped_then_cleaned_up() {
+        use crate::types::BreadcrumbKind;
+        use crate::vfs::MemoryFs;
 
-        // Create file
-        let file_path = dl.join("test.png");
-        {
-            let mut f = fs::File::create(&file_path).unwrap();
-            f.write_all(b"data").unwrap();
+        for kind in [
+            BreadcrumbKind::Symlink,
+            BreadcrumbKind::RelativeSymlink,
+            BreadcrumbKind::Hardlink,
+            BreadcrumbKind::Shortcut,
+        ] {
+            let fs = MemoryFs::new();
+            fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+            // Matches everything, breadcrumbs included.
+            let rule = Rule {
+                name: "All".into(),
+                target_dir: "/home/u/Sorted".into(),
+                breadcrumb: Some(kind),
+                ..Default::default()
+            };
+            let cfg = memory_cfg(vec![rule]);
+
+            let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+            assert_eq!(
+                summary.moved[0].symlink_info.as_deref(),
+                Some(format!("{} created", breadcrumb::label(kind)).as_str())
+            );
+
+            let mut skipped = Vec::new();
+            organize_with(&fs, &SystemClock, &cfg, &|| false, &mut |event| {
+                if let OrganizeEvent::FileSkipped { reason, .. } = event {
+                    skipped.push(reason);
+                }
+            })
+            .unwrap();
+            // Symlinks are never organized; the other kinds look like files.
+            let expected = match kind {
+                BreadcrumbKind::Hardlink | BreadcrumbKind::Shortcut => vec![SkipReason::Breadcrumb],
+                _ => vec![],
+            };
+            assert_eq!(skipped, expected, "{kind:?}");
+
+            assert_eq!(cleanup_old_symlinks_with_fs(&cfg, &fs).unwrap(), 1);
+            assert_eq!(
+                fs.read_dir(Path::new("/home/u/Downloads")).unwrap(),
+                Vec::<PathBuf>::new(),
+                "{kind:?}"
+            );
+            assert_eq!(fs.read("/home/u/Sorted/a.pdf"), Some(b"a".to_vec()));
         }
+    }
 
-        // Create config
-        let cfg = DownloadsConfig {
-            download_dir: dl.to_str().unwrap().into(),
-            min_age_secs: Some(0), // Immediate move
-            tutorial_completed: None,
-            service_enabled: None,
-            check_updates: None,
-            last_notified_version: None,
-            rules: vec![Rule {
-                id: "images-rule".to_string(),
-                name: "Images".into(),
-                extensions: Some(vec!["png".into()]),
-                pattern: None,
-                min_size_bytes: None,
-                max_size_bytes: None,
-                target_dir: target.to_str().unwrap().into(),
-                create_symlink: false,
-                enabled: true,
-            }],
-        };
+    #[test]
+    fn test_memory_fs_hardlink_breadcrumb_across_devices_fails_softly() {
+        use crate::types::BreadcrumbKind;
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        fs.mount("/mnt/nas", u64::MAX);
+        let mut rule = memory_rule("Docs", "pdf", "/mnt/nas/Docs");
+        rule.breadcrumb = Some(BreadcrumbKind::Hardlink);
+        let cfg = memory_cfg(vec![rule]);
+
+        let summary = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
 
-        // Run
-        let summary = organize_once(&cfg).unwrap();
         assert_eq!(summary.moved.len(), 1);
-        assert!(!file_path.exists());
-        assert!(target.join("test.png").exists());
+        assert!(summary.moved[0]
+            .symlink_info
+            .as_deref()
+            .unwrap()
+            .starts_with("Hardlink failed"));
+    }
+
+    #[test]
+    fn test_memory_fs_hardlink_breadcrumb_under_templated_target_is_recognised() {
+        use crate::types::BreadcrumbKind;
+        use crate::vfs::MemoryFs;
+
+        let fs = MemoryFs::new();
+        fs.add_file("/home/u/Downloads/acme_invoice.pdf", b"a".to_vec());
+        let rule = Rule {
+            name: "Invoices".into(),
+            pattern: Some(r"^(?P<vendor>\w+?)_invoice".into()),
+            target_dir: "/home/u/Invoices/{vendor}".into(),
+            breadcrumb: Some(BreadcrumbKind::Hardlink),
+            ..Default::default()
+        };
+        let cfg = memory_cfg(vec![rule]);
+
+        let first = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+        assert_eq!(
+            first.moved[0].symlink_info.as_deref(),
+            Some("Hardlink created")
+        );
+
+        let second = organize_once_with(&cfg, &fs, &SystemClock).unwrap();
+        assert!(second.moved.is_empty());
+        assert!(fs
+            .read("/home/u/Invoices/acme/acme_invoice (1).pdf")
+            .is_none());
+        assert_eq!(
+            fs.read_dir(Path::new("/home/u/Invoices/acme")).unwrap(),
+            vec![PathBuf::from("/home/u/Invoices/acme/acme_invoice.pdf")]
+        );
     }
 
     #[test]
@@ -1048,12 +3751,14 @@ mod tests {
                 target_dir: target.to_str().unwrap().into(),
                 create_symlink: false,
                 enabled: true,
+                ..Default::default()
             }],
             min_age_secs: None,
             tutorial_completed: None,
             service_enabled: None,
             check_updates: None,
             last_notified_version: None,
+            ..Default::default()
         };
 
         // Clean up
@@ -1103,6 +3808,63 @@ rules:
         assert_eq!(cfg.rules[0].name, "test");
     }
 
+    #[test]
+    fn test_load_downloads_config_rejects_undefined_variables() {
+        let mut file = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        writeln!(
+            file,
+            r#"
+download_dir: "${{HARBOR_UNSET_DL:-/home/u/Downloads}}"
+rules:
+  - name: Archive
+    target_dir: "$HARBOR_UNSET_HOME/Archive"
+"#
+        )
+        .unwrap();
+
+        let err = load_downloads_config(file.path()).unwrap_err().to_string();
+        assert!(
+            err.ends_with(
+                "rule \"Archive\" target_dir: undefined environment variable HARBOR_UNSET_HOME"
+            ),
+            "{err}"
+        );
+        assert!(!err.contains("download_dir"), "{err}");
+    }
+
+    #[test]
+    fn test_load_downloads_config_expands_webhook_settings() {
+        let mut file = tempfile::Builder::new().suffix(".yaml").tempfile().unwrap();
+        writeln!(
+            file,
+            r#"
+download_dir: /home/u/Downloads
+rules: []
+webhook:
+  url: "http://${{HARBOR_UNSET_WEBHOOK_HOST:-dashboard.lan}}/harbor"
+  secret: "$HARBOR_UNSET_WEBHOOK_SECRET"
+"#
+        )
+        .unwrap();
+        let err = load_downloads_config(file.path()).unwrap_err().to_string();
+        assert!(
+            err.ends_with(
+                "webhook secret: undefined environment variable HARBOR_UNSET_WEBHOOK_SECRET"
+            ),
+            "{err}"
+        );
+
+        writeln!(file, "  max_retries: 2").unwrap();
+        let text = std::fs::read_to_string(file.path())
+            .unwrap()
+            .replace("$HARBOR_UNSET_WEBHOOK_SECRET", "s3cret");
+        std::fs::write(file.path(), text).unwrap();
+        let webhook = load_downloads_config(file.path()).unwrap().webhook.unwrap();
+        assert_eq!(webhook.url, "http://dashboard.lan/harbor");
+        assert_eq!(webhook.secret.as_deref(), Some("s3cret"));
+        assert_eq!(webhook.max_retries, Some(2));
+    }
+
     #[test]
     fn test_default_config() {
         let cfg = default_config();
@@ -1127,6 +3889,7 @@ rules:
             target_dir: "t".into(),
             create_symlink: false,
             enabled: true,
+            ..Default::default()
         };
         assert_eq!(rule_priority(0, total, &rule_no_mod), 5);
 
@@ -1144,6 +3907,7 @@ rules:
             target_dir: "t".into(),
             create_symlink: false,
             enabled: true,
+            ..Default::default()
         };
         assert_eq!(rule_priority(4, total, &rule_regex), 6);
 
@@ -1158,6 +3922,7 @@ rules:
             target_dir: "t".into(),
             create_symlink: false,
             enabled: true,
+            ..Default::default()
         };
         assert_eq!(rule_priority(4, total, &rule_both), 11);
     }
@@ -1203,6 +3968,7 @@ rules:
                     target_dir: images.to_str().unwrap().into(),
                     create_symlink: false,
                     enabled: true,
+                    ..Default::default()
                 },
                 // Rule 2 (bottom): Broll — regex pattern for "broll"
                 Rule {
@@ -1215,8 +3981,10 @@ rules:
                     target_dir: social.to_str().unwrap().into(),
                     create_symlink: false,
                     enabled: true,
+                    ..Default::default()
                 },
             ],
+            ..Default::default()
         };
 
         let summary = organize_once(&cfg).unwrap();
@@ -1268,6 +4036,116 @@ rules:
         }
     }
 
+    #[test]
+    fn test_append_purge_results_to_log() {
+        use crate::retention::PurgeReason;
+        use crate::types::RetentionAction;
+
+        let root = TempDir::new().unwrap();
+        let log_path = root.path().join("recent.log");
+        let purged = vec![PurgeResult {
+            path: PathBuf::from("Installers/setup (1).exe"),
+            rule_name: "Installers".to_string(),
+            action: RetentionAction::Trash,
+            reason: PurgeReason::Age { max_age_days: 30 },
+            size_bytes: 42,
+        }];
+
+        append_purge_results_to_log(&log_path, &purged, &LogArchivePolicy::default());
+
+        let content = fs::read_to_string(&log_path).unwrap();
+        let line = content.lines().next().unwrap();
+        assert!(line.starts_with('['));
+        assert!(line.ends_with(
+            "] Purged: Installers/setup (1).exe (Installers) trashed: older than 30 days"
+        ));
+    }
+
+    #[test]
+    fn test_append_events_to_log_writes_noteworthy_entries() {
+        let root = TempDir::new().unwrap();
+        let log_path = root.path().join("recent.log");
+        let events = vec![
+            OrganizeEvent::ScanStarted {
+                download_dir: PathBuf::from("Downloads"),
+            },
+            OrganizeEvent::FileSkipped {
+                path: PathBuf::from("Downloads/a.part"),
+                rule_name: None,
+                reason: SkipReason::PartialDownload,
+            },
+            OrganizeEvent::FileSkipped {
+                path: PathBuf::from("Downloads/big.iso"),
+                rule_name: Some("Disk Images".to_string()),
+                reason: SkipReason::InsufficientSpace {
+                    required: 10,
+                    available: 5,
+                },
+            },
+            OrganizeEvent::FileFailed {
+                path: PathBuf::from("Downloads/locked (1).pdf"),
+                destination: Some(PathBuf::from("Documents/locked (1).pdf")),
+                rule_name: Some("Documents".to_string()),
+                error: "Permission denied (os error 13)".to_string(),
+            },
+            OrganizeEvent::FileMoved(OrganizeResult {
+                source: PathBuf::from("Downloads/x.png"),
+                destination: PathBuf::from("Images/x.png"),
+                rule_name: "Images".to_string(),
+                symlink_info: None,
+            }),
+        ];
+
+        append_events_to_log(&log_path, &events, &LogArchivePolicy::default());
+
+        let content = fs::read_to_string(&log_path).unwrap();
+        let lines: Vec<&str> = content.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].ends_with(
+            "] Skipped: Downloads/big.iso (Disk Images) -- insufficient space: 10 bytes required, 5 available"
+        ));
+        assert!(lines[1].ends_with(
+            "] Failed: Downloads/locked (1).pdf (Documents) -- Permission denied (os error 13)"
+        ));
+        assert!(lines[2].contains("Downloads/x.png -> Images/x.png (Images)"));
+    }
+
+    #[test]
+    fn test_log_timestamps_come_from_clock() {
+        use crate::clock::ManualClock;
+
+        let root = TempDir::new().unwrap();
+        let log_path = root.path().join("recent.log");
+        let clock = ManualClock::at_local("2024-01-15 10:30:00");
+        let result = OrganizeResult {
+            source: PathBuf::from("a.png"),
+            destination: PathBuf::from("Images/a.png"),
+            rule_name: "Images".to_string(),
+            symlink_info: None,
+        };
+
+        append_organize_results_to_log_with_clock(
+            &log_path,
+            std::slice::from_ref(&result),
+            &LogArchivePolicy::default(),
+            &clock,
+        );
+        clock.advance(Duration::from_secs(60));
+        append_events_to_log_with_clock(
+            &log_path,
+            &[OrganizeEvent::FileMoved(result)],
+            &LogArchivePolicy::default(),
+            &clock,
+        );
+
+        let content = fs::read_to_string(&log_path).unwrap();
+        let stamps: Vec<&str> = content.lines().map(|l| &l[..21]).collect();
+        assert_eq!(
+            stamps,
+            vec!["[2024-01-15 10:30:00]", "[2024-01-15 10:31:00]"]
+        );
+    }
+
     #[test]
     fn test_append_organize_results_empty() {
         let root = TempDir::new().unwrap();
@@ -1327,6 +4205,95 @@ rules:
         assert!(content.contains("src/new.txt -> dst/new.txt (Rule)"));
     }
 
+    #[test]
+    fn test_rotate_log_archives_old_entries() {
+        let root = TempDir::new().unwrap();
+        let log_path = root.path().join("rotate.log");
+
+        let pad = "x".repeat(100);
+        let mut big = String::new();
+        for i in 0..LOG_MAX_LINES + 100 {
+            big.push_str(&format!(
+                "[2024-01-01 00:00:00] src/file_{i:05}_{pad}.txt -> dst/file_{i:05}.txt (Rule)\n"
+            ));
+        }
+        fs::write(&log_path, &big).unwrap();
+
+        let action = OrganizeResult {
+            source: PathBuf::from("src/new.txt"),
+            destination: PathBuf::from("dst/new.txt"),
+            rule_name: "Rule".into(),
+            symlink_info: None,
+        };
+        append_organize_results_to_log(&log_path, &[action]);
+
+        let archives = list_log_archives(&log_path);
+        assert_eq!(archives.len(), 1);
+        assert!(archives[0].to_string_lossy().ends_with(".gz"));
+
+        // Archived and live segments together hold every line, in order.
+        let archived = read_log_archives(&log_path);
+        assert_eq!(archived.lines().count(), 101);
+        assert!(archived.starts_with("[2024-01-01 00:00:00] src/file_00000_"));
+        let live = fs::read_to_string(&log_path).unwrap();
+        assert_eq!(live.lines().count(), LOG_MAX_LINES);
+        assert!(live.lines().next().unwrap().contains("src/file_00101_"));
+
+        remove_log_archives(&log_path).unwrap();
+        assert!(list_log_archives(&log_path).is_empty());
+    }
+
+    #[test]
+    fn test_prune_log_archives_by_count_and_age() {
+        let root = TempDir::new().unwrap();
+        let log_path = root.path().join("recent.log");
+        let now = chrono::NaiveDate::from_ymd_opt(2024, 6, 30)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+
+        for days_ago in [40, 20, 3, 2, 1] {
+            let stamp = now - chrono::Duration::days(days_ago);
+            fs::write(log_archive_path(&log_path, stamp), b"").unwrap();
+        }
+        // Unrelated files in the same directory are left alone.
+        fs::write(root.path().join("recent.log.notes.gz"), b"").unwrap();
+
+        let policy = LogArchivePolicy {
+            max_archives: 4,
+            max_age_days: Some(30),
+        };
+        prune_log_archives(&log_path, &policy, now);
+        assert_eq!(list_log_archives(&log_path).len(), 4);
+
+        let policy = LogArchivePolicy {
+            max_archives: 10,
+            max_age_days: Some(10),
+        };
+        prune_log_archives(&log_path, &policy, now);
+        let remaining = list_log_archives(&log_path);
+        assert_eq!(remaining.len(), 3);
+        assert_eq!(
+            log_archive_stamp(&log_path, &remaining[0]),
+            Some(now - chrono::Duration::days(3))
+        );
+        assert!(root.path().join("recent.log.notes.gz").exists());
+    }
+
+    #[test]
+    fn test_log_archive_policy_from_config() {
+        let cfg: DownloadsConfig = serde_yaml::from_str(
+            "download_dir: /tmp\nrules: []\nlog_archive_max_files: 3\nlog_archive_max_age_days: 14\n",
+        )
+        .unwrap();
+        let policy = LogArchivePolicy::from_config(&cfg);
+        assert_eq!(policy.max_archives, 3);
+        assert_eq!(policy.max_age_days, Some(14));
+
+        let defaults = LogArchivePolicy::from_config(&DownloadsConfig::default());
+        assert_eq!(defaults, LogArchivePolicy::default());
+    }
+
     // ── load_or_initialize_config ───────────────────────────────
 
     #[test]
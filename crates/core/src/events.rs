@@ -0,0 +1,257 @@
+//! Live progress events emitted while organizing.
+//!
+//! [`organize_with_events`](crate::downloads::organize_with_events) and
+//! [`watch_with_events`](crate::downloads::watch_with_events) send an
+//! [`OrganizeEvent`] for every decision they make, so front-ends can show
+//! progress as it happens and log skips and failures the same way.
+
+use crate::breadcrumb::RemovedBreadcrumb;
+use crate::downloads::{OrganizeResult, SkipReason};
+use crate::hooks::HookOutcome;
+use crate::retention::PurgeResult;
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Sending half of an organizer event channel.
+pub type EventSender = std::sync::mpsc::Sender<OrganizeEvent>;
+
+#[derive(Debug, Clone)]
+pub enum OrganizeEvent {
+    /// A pass over the download folder began.
+    ScanStarted { download_dir: PathBuf },
+    /// A file was left in place. `rule_name` is set when a rule matched.
+    FileSkipped {
+        path: PathBuf,
+        rule_name: Option<String>,
+        reason: SkipReason,
+    },
+    /// A file was moved into a rule's target folder.
+    FileMoved(OrganizeResult),
+    /// Moving a file failed; it stays where it was.
+    FileFailed {
+        path: PathBuf,
+        destination: Option<PathBuf>,
+        rule_name: Option<String>,
+        error: String,
+    },
+    /// A retention policy removed a file from a target folder.
+    FilePurged(PurgeResult),
+    /// Undo moved a file back from `destination` to `source` of the
+    /// original move.
+    FileRestored(OrganizeResult),
+    /// An `on_moved` hook finished for a file that was just moved.
+    HookFinished(HookOutcome),
+    /// A breadcrumb was removed from the download folder because its target
+    /// is gone or it outlived `breadcrumb_max_age_days`.
+    BreadcrumbRemoved(RemovedBreadcrumb),
+    /// The pass ended, successfully or not. `cancelled` is set when it was
+    /// stopped before considering every file.
+    ScanFinished {
+        duration: Duration,
+        counts: ScanCounts,
+        cancelled: bool,
+    },
+}
+
+/// Per-pass tallies reported by [`OrganizeEvent::ScanFinished`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ScanCounts {
+    pub moved: usize,
+    pub skipped: usize,
+    pub failed: usize,
+    /// Skips that left a ready file for a later pass because of the per-pass
+    /// limits; also counted in `skipped`. This is the remaining backlog.
+    pub deferred: usize,
+}
+
+impl ScanCounts {
+    pub(crate) fn record(&mut self, event: &OrganizeEvent) {
+        match event {
+            OrganizeEvent::FileMoved(_) => self.moved += 1,
+            OrganizeEvent::FileSkipped { reason, .. } => {
+                self.skipped += 1;
+                if *reason == SkipReason::PassLimitReached {
+                    self.deferred += 1;
+                }
+            }
+            OrganizeEvent::FileFailed { .. } => self.failed += 1,
+            _ => {}
+        }
+    }
+}
+
+/// Serializable view of [`OrganizeEvent`], tagged by `kind`, as the desktop
+/// app's webview and the [control socket](crate::control) receive it.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum OrganizeEventDto {
+    ScanStarted {
+        download_dir: String,
+    },
+    FileSkipped {
+        source_path: String,
+        rule_name: Option<String>,
+        code: String,
+        message: String,
+    },
+    FileMoved {
+        source_path: String,
+        dest_path: String,
+        rule_name: String,
+        symlink_info: Option<String>,
+    },
+    FileFailed {
+        source_path: String,
+        dest_path: Option<String>,
+        rule_name: Option<String>,
+        message: String,
+    },
+    FilePurged {
+        path: String,
+        rule_name: String,
+        detail: String,
+    },
+    FileRestored {
+        source_path: String,
+        dest_path: String,
+        rule_name: String,
+    },
+    HookFinished {
+        dest_path: String,
+        rule_name: String,
+        command: String,
+        success: bool,
+        detail: String,
+        duration_ms: u64,
+    },
+    BreadcrumbRemoved {
+        path: String,
+        target_path: String,
+        rule_name: Option<String>,
+        reason: String,
+        detail: String,
+    },
+    ScanFinished {
+        duration_ms: u64,
+        moved: usize,
+        skipped: usize,
+        failed: usize,
+        /// Ready files left for later passes: the remaining backlog.
+        deferred: usize,
+        cancelled: bool,
+    },
+}
+
+impl From<&OrganizeEvent> for OrganizeEventDto {
+    fn from(event: &OrganizeEvent) -> Self {
+        match event {
+            OrganizeEvent::ScanStarted { download_dir } => Self::ScanStarted {
+                download_dir: download_dir.to_string_lossy().into_owned(),
+            },
+            OrganizeEvent::FileSkipped {
+                path,
+                rule_name,
+                reason,
+            } => Self::FileSkipped {
+                source_path: path.to_string_lossy().into_owned(),
+                rule_name: rule_name.clone(),
+                code: reason.code().to_string(),
+                message: reason.to_string(),
+            },
+            OrganizeEvent::FileMoved(result) => Self::FileMoved {
+                source_path: result.source.to_string_lossy().into_owned(),
+                dest_path: result.destination.to_string_lossy().into_owned(),
+                rule_name: result.rule_name.clone(),
+                symlink_info: result.symlink_info.clone(),
+            },
+            OrganizeEvent::FileFailed {
+                path,
+                destination,
+                rule_name,
+                error,
+            } => Self::FileFailed {
+                source_path: path.to_string_lossy().into_owned(),
+                dest_path: destination
+                    .as_ref()
+                    .map(|d| d.to_string_lossy().into_owned()),
+                rule_name: rule_name.clone(),
+                message: error.clone(),
+            },
+            OrganizeEvent::FilePurged(result) => Self::FilePurged {
+                path: result.path.to_string_lossy().into_owned(),
+                rule_name: result.rule_name.clone(),
+                detail: result.describe(),
+            },
+            OrganizeEvent::FileRestored(result) => Self::FileRestored {
+                source_path: result.source.to_string_lossy().into_owned(),
+                dest_path: result.destination.to_string_lossy().into_owned(),
+                rule_name: result.rule_name.clone(),
+            },
+            OrganizeEvent::HookFinished(outcome) => Self::HookFinished {
+                dest_path: outcome.destination.to_string_lossy().into_owned(),
+                rule_name: outcome.rule_name.clone(),
+                command: outcome.command.clone(),
+                success: outcome.status.is_success(),
+                detail: outcome.describe(),
+                duration_ms: outcome.duration.as_millis() as u64,
+            },
+            OrganizeEvent::BreadcrumbRemoved(removed) => Self::BreadcrumbRemoved {
+                path: removed.path.to_string_lossy().into_owned(),
+                target_path: removed.target.to_string_lossy().into_owned(),
+                rule_name: removed.rule_name.clone(),
+                reason: removed.reason.to_string(),
+                detail: removed.describe(),
+            },
+            OrganizeEvent::ScanFinished {
+                duration,
+                counts,
+                cancelled,
+            } => Self::ScanFinished {
+                duration_ms: duration.as_millis() as u64,
+                moved: counts.moved,
+                skipped: counts.skipped,
+                failed: counts.failed,
+                deferred: counts.deferred,
+                cancelled: *cancelled,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dto_serializes_with_kind_tag() {
+        let dto = OrganizeEventDto::from(&OrganizeEvent::FileSkipped {
+            path: PathBuf::from("big.iso"),
+            rule_name: Some("Disk Images".to_string()),
+            reason: SkipReason::InsufficientSpace {
+                required: 10,
+                available: 5,
+            },
+        });
+        let json = serde_json::to_value(&dto).unwrap();
+        assert_eq!(json["kind"], "file_skipped");
+        assert_eq!(json["code"], "insufficient_space");
+        assert_eq!(json["rule_name"], "Disk Images");
+
+        let dto = OrganizeEventDto::from(&OrganizeEvent::ScanFinished {
+            duration: Duration::from_millis(1500),
+            counts: ScanCounts {
+                moved: 2,
+                skipped: 1,
+                failed: 0,
+                deferred: 1240,
+            },
+            cancelled: false,
+        });
+        let json = serde_json::to_value(&dto).unwrap();
+        assert_eq!(json["kind"], "scan_finished");
+        assert_eq!(json["duration_ms"], 1500);
+        assert_eq!(json["moved"], 2);
+        assert_eq!(json["deferred"], 1240);
+    }
+}
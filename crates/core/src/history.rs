@@ -0,0 +1,232 @@
+//! Record of completed passes, so the most recent one can be undone.
+//!
+//! Each pass that moved at least one file becomes a [`HistoryEntry`].
+//! [`Organizer::undo`](crate::organizer::Organizer::undo) takes the latest
+//! entry off the store and moves its files back.
+
+use crate::downloads::OrganizeResult;
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Passes kept by [`JsonlHistory`] before the oldest are dropped.
+pub const HISTORY_MAX_ENTRIES: usize = 100;
+
+/// One file moved by a pass.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MoveRecord {
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub rule_name: String,
+}
+
+impl From<&OrganizeResult> for MoveRecord {
+    fn from(result: &OrganizeResult) -> Self {
+        Self {
+            source: result.source.clone(),
+            destination: result.destination.clone(),
+            rule_name: result.rule_name.clone(),
+        }
+    }
+}
+
+/// The moves made by one pass, in the order they happened.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HistoryEntry {
+    /// Local time the pass finished, formatted `%Y-%m-%d %H:%M:%S`.
+    pub recorded_at: String,
+    pub moves: Vec<MoveRecord>,
+}
+
+pub trait HistoryStore: Send + Sync {
+    fn record(&self, entry: HistoryEntry) -> Result<()>;
+
+    /// Removes and returns the most recent entry.
+    fn pop_last(&self) -> Result<Option<HistoryEntry>>;
+
+    /// All stored entries, oldest first.
+    fn entries(&self) -> Result<Vec<HistoryEntry>>;
+}
+
+/// A store that keeps nothing; undo always finds an empty history.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoHistory;
+
+impl HistoryStore for NoHistory {
+    fn record(&self, _entry: HistoryEntry) -> Result<()> {
+        Ok(())
+    }
+
+    fn pop_last(&self) -> Result<Option<HistoryEntry>> {
+        Ok(None)
+    }
+
+    fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        Ok(Vec::new())
+    }
+}
+
+/// History held in memory for the lifetime of the store.
+#[derive(Debug, Default)]
+pub struct MemoryHistory(Mutex<Vec<HistoryEntry>>);
+
+impl MemoryHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Vec<HistoryEntry>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl HistoryStore for MemoryHistory {
+    fn record(&self, entry: HistoryEntry) -> Result<()> {
+        self.lock().push(entry);
+        Ok(())
+    }
+
+    fn pop_last(&self) -> Result<Option<HistoryEntry>> {
+        Ok(self.lock().pop())
+    }
+
+    fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        Ok(self.lock().clone())
+    }
+}
+
+/// History stored as one JSON object per line, capped at `max_entries`.
+///
+/// The file is rewritten through a temporary file on every change, so a
+/// crash never leaves it half written. Lines that fail to parse are ignored.
+#[derive(Debug)]
+pub struct JsonlHistory {
+    path: PathBuf,
+    max_entries: usize,
+    // Serializes read-modify-write cycles within this process.
+    lock: Mutex<()>,
+}
+
+impl JsonlHistory {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            path: path.into(),
+            max_entries: HISTORY_MAX_ENTRIES,
+            lock: Mutex::new(()),
+        }
+    }
+
+    pub fn with_max_entries(mut self, max_entries: usize) -> Self {
+        self.max_entries = max_entries.max(1);
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn load(&self) -> Result<Vec<HistoryEntry>> {
+        let data = match fs::read_to_string(&self.path) {
+            Ok(data) => data,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+            Err(e) => {
+                return Err(e).with_context(|| format!("read {}", self.path.display()));
+            }
+        };
+        Ok(data
+            .lines()
+            .filter(|line| !line.trim().is_empty())
+            .filter_map(|line| serde_json::from_str(line).ok())
+            .collect())
+    }
+
+    fn store(&self, entries: &[HistoryEntry]) -> Result<()> {
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent).with_context(|| format!("create {}", parent.display()))?;
+        }
+        let mut buf = Vec::new();
+        for entry in entries {
+            serde_json::to_writer(&mut buf, entry)?;
+            buf.push(b'\n');
+        }
+        let tmp = self.path.with_extension("jsonl.tmp");
+        fs::File::create(&tmp)
+            .and_then(|mut f| f.write_all(&buf).and_then(|_| f.sync_all()))
+            .with_context(|| format!("write {}", tmp.display()))?;
+        fs::rename(&tmp, &self.path).with_context(|| format!("replace {}", self.path.display()))?;
+        Ok(())
+    }
+}
+
+impl HistoryStore for JsonlHistory {
+    fn record(&self, entry: HistoryEntry) -> Result<()> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.load()?;
+        entries.push(entry);
+        let excess = entries.len().saturating_sub(self.max_entries);
+        entries.drain(..excess);
+        self.store(&entries)
+    }
+
+    fn pop_last(&self) -> Result<Option<HistoryEntry>> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let mut entries = self.load()?;
+        let last = entries.pop();
+        if last.is_some() {
+            self.store(&entries)?;
+        }
+        Ok(last)
+    }
+
+    fn entries(&self) -> Result<Vec<HistoryEntry>> {
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        self.load()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    fn entry(n: usize) -> HistoryEntry {
+        HistoryEntry {
+            recorded_at: format!("2024-01-15 10:30:{n:02}"),
+            moves: vec![MoveRecord {
+                source: PathBuf::from(format!("/dl/{n}.pdf")),
+                destination: PathBuf::from(format!("/docs/{n}.pdf")),
+                rule_name: "Documents".to_string(),
+            }],
+        }
+    }
+
+    #[test]
+    fn test_jsonl_history_round_trip_and_cap() {
+        let tmp = TempDir::new().unwrap();
+        let store = JsonlHistory::new(tmp.path().join("history.jsonl")).with_max_entries(2);
+        assert_eq!(store.pop_last().unwrap(), None);
+
+        for n in 0..3 {
+            store.record(entry(n)).unwrap();
+        }
+        assert_eq!(store.entries().unwrap(), vec![entry(1), entry(2)]);
+
+        // A fresh store over the same file sees the same history.
+        let reopened = JsonlHistory::new(store.path());
+        assert_eq!(reopened.pop_last().unwrap(), Some(entry(2)));
+        assert_eq!(store.entries().unwrap(), vec![entry(1)]);
+    }
+
+    #[test]
+    fn test_jsonl_history_skips_corrupt_lines() {
+        let tmp = TempDir::new().unwrap();
+        let path = tmp.path().join("history.jsonl");
+        let good = serde_json::to_string(&entry(7)).unwrap();
+        fs::write(&path, format!("not json\n{good}\n")).unwrap();
+
+        assert_eq!(JsonlHistory::new(&path).entries().unwrap(), vec![entry(7)]);
+    }
+}
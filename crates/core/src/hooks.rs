@@ -0,0 +1,338 @@
+//! `on_moved` hooks: external commands run after Harbor files something, e.g.
+//! a virus scan, an indexer, `chmod` or `notify-send`.
+//!
+//! A rule's hooks run first, then the global ones, one after the other on
+//! the organizing thread. Each command gets the move's details as arguments
+//! (through the `{src}`, `{dest}`, `{rule}` and `{ext}` placeholders) and as
+//! environment variables:
+//!
+//! - `HARBOR_SRC` — where the file was downloaded
+//! - `HARBOR_DEST` — where Harbor moved it
+//! - `HARBOR_RULE` — the rule that matched
+//! - `HARBOR_EXT` — the file extension, without the dot
+//!
+//! The placeholders are for arguments handed straight to a program, such as
+//! `chmod 644 {dest}`. File names are chosen by whoever serves the download,
+//! so a shell script must read the variables instead:
+//! `sh -c 'clamscan "$HARBOR_DEST"'`. With `{dest}` inside the script, a
+//! file named `a";rm -rf ~;".pdf` would run as shell code.
+//!
+//! Standard output is discarded. The exit status and the start of standard
+//! error are reported as a [`HookOutcome`] and end up in the activity log.
+//! On Unix a hook runs in its own process group, and a timeout kills the
+//! whole group, including anything the command started. On Windows only the
+//! command itself is killed.
+
+use crate::downloads::{DownloadsConfig, OrganizeResult};
+use crate::types::HookCommand;
+use std::io::Read;
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::mpsc;
+use std::time::{Duration, Instant};
+
+/// Seconds a hook may run when [`HookCommand::timeout_secs`] is not set.
+pub const DEFAULT_HOOK_TIMEOUT_SECS: u64 = 30;
+
+/// Standard error beyond this many bytes is dropped.
+const MAX_STDERR_BYTES: usize = 4096;
+
+/// How a hook command ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HookStatus {
+    /// The command exited with this code.
+    Exited(i32),
+    /// The command was ended by a signal.
+    Killed,
+    /// The command ran longer than its timeout and was killed.
+    TimedOut(Duration),
+    /// The command could not be started, e.g. because it does not exist.
+    FailedToStart(String),
+}
+
+impl HookStatus {
+    /// Returns `true` for a zero exit code.
+    pub fn is_success(&self) -> bool {
+        *self == Self::Exited(0)
+    }
+}
+
+impl std::fmt::Display for HookStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Exited(code) => write!(f, "exited with {code}"),
+            Self::Killed => write!(f, "was killed by a signal"),
+            Self::TimedOut(limit) => write!(f, "timed out after {}s", limit.as_secs()),
+            Self::FailedToStart(error) => write!(f, "could not start: {error}"),
+        }
+    }
+}
+
+/// One hook run for one moved file.
+#[derive(Debug, Clone)]
+pub struct HookOutcome {
+    /// The command line as run, with placeholders filled in.
+    pub command: String,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    pub rule_name: String,
+    pub status: HookStatus,
+    /// Standard error, trimmed and cut to a few kilobytes.
+    pub stderr: String,
+    pub duration: Duration,
+}
+
+impl HookOutcome {
+    /// Summary for logs: ``"`chmod 644 /x.pdf` exited with 1: Operation not permitted"``.
+    pub fn describe(&self) -> String {
+        let mut text = format!("`{}` {}", self.command, self.status);
+        if !self.stderr.is_empty() {
+            text.push_str(": ");
+            text.push_str(&self.stderr);
+        }
+        text
+    }
+}
+
+/// The hooks to run after `rule_name` moved a file: the rule's own, then
+/// the global ones.
+pub fn hooks_for<'a>(
+    cfg: &'a DownloadsConfig,
+    rule_name: &str,
+) -> impl Iterator<Item = &'a HookCommand> {
+    let rule_hooks = cfg
+        .rules
+        .iter()
+        .find(|rule| rule.name == rule_name)
+        .and_then(|rule| rule.on_moved.as_deref())
+        .unwrap_or_default();
+    rule_hooks
+        .iter()
+        .chain(cfg.on_moved.as_deref().unwrap_or_default())
+}
+
+/// Runs `hook` for the move described by `result` and waits for it, up to
+/// its timeout.
+pub fn run(hook: &HookCommand, result: &OrganizeResult) -> HookOutcome {
+    let ext = result
+        .destination
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or_default();
+    let values = [
+        ("{src}", result.source.to_string_lossy()),
+        ("{dest}", result.destination.to_string_lossy()),
+        ("{rule}", result.rule_name.as_str().into()),
+        ("{ext}", ext.into()),
+    ];
+    let args: Vec<String> = hook
+        .args
+        .iter()
+        .map(|arg| {
+            values
+                .iter()
+                .fold(arg.clone(), |arg, (key, value)| arg.replace(key, value))
+        })
+        .collect();
+    let timeout = Duration::from_secs(hook.timeout_secs.unwrap_or(DEFAULT_HOOK_TIMEOUT_SECS));
+    let started = Instant::now();
+    let (status, stderr) = execute(
+        Command::new(&hook.command)
+            .args(&args)
+            .env("HARBOR_SRC", &result.source)
+            .env("HARBOR_DEST", &result.destination)
+            .env("HARBOR_RULE", &result.rule_name)
+            .env("HARBOR_EXT", ext),
+        timeout,
+    );
+    HookOutcome {
+        command: std::iter::once(hook.command.as_str())
+            .chain(args.iter().map(String::as_str))
+            .collect::<Vec<_>>()
+            .join(" "),
+        source: result.source.clone(),
+        destination: result.destination.clone(),
+        rule_name: result.rule_name.clone(),
+        status,
+        stderr,
+        duration: started.elapsed(),
+    }
+}
+
+fn execute(command: &mut Command, timeout: Duration) -> (HookStatus, String) {
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(command, 0);
+    let child = command
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .spawn();
+    let mut child = match child {
+        Ok(child) => child,
+        Err(e) => return (HookStatus::FailedToStart(e.to_string()), String::new()),
+    };
+
+    // Read stderr on its own thread so a chatty command cannot fill the pipe
+    // and stall while we wait for it.
+    let (tx, rx) = mpsc::channel();
+    if let Some(mut pipe) = child.stderr.take() {
+        std::thread::spawn(move || {
+            let mut kept = Vec::new();
+            let _ = (&mut pipe)
+                .take(MAX_STDERR_BYTES as u64)
+                .read_to_end(&mut kept);
+            let _ = std::io::copy(&mut pipe, &mut std::io::sink());
+            let _ = tx.send(kept);
+        });
+    }
+
+    let deadline = Instant::now() + timeout;
+    let status = loop {
+        match child.try_wait() {
+            Ok(Some(status)) => break status.code().map_or(HookStatus::Killed, HookStatus::Exited),
+            Ok(None) if Instant::now() >= deadline => {
+                // The group id is the child's pid; see `process_group` above.
+                #[cfg(unix)]
+                // SAFETY: kill only sends a signal.
+                unsafe {
+                    libc::kill(-(child.id() as libc::pid_t), libc::SIGKILL);
+                }
+                let _ = child.kill();
+                let _ = child.wait();
+                break HookStatus::TimedOut(timeout);
+            }
+            Ok(None) => std::thread::sleep(Duration::from_millis(20)),
+            Err(e) => break HookStatus::FailedToStart(e.to_string()),
+        }
+    };
+    // A process the command left behind may still hold the pipe open.
+    let stderr = rx
+        .recv_timeout(Duration::from_millis(200))
+        .map(|bytes| String::from_utf8_lossy(&bytes).trim().to_string())
+        .unwrap_or_default();
+    (status, stderr)
+}
+
+/// Returns `true` if a move by `rule_name` has hooks to run.
+pub(crate) fn has_hooks(cfg: &DownloadsConfig, rule_name: &str) -> bool {
+    hooks_for(cfg, rule_name).next().is_some()
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::types::Rule;
+
+    fn moved() -> OrganizeResult {
+        OrganizeResult {
+            source: PathBuf::from("/home/u/Downloads/report.pdf"),
+            destination: PathBuf::from("/home/u/Docs/report.pdf"),
+            rule_name: "Docs".into(),
+            symlink_info: None,
+        }
+    }
+
+    fn shell(script: &str, timeout_secs: Option<u64>) -> HookCommand {
+        HookCommand {
+            command: "sh".into(),
+            args: vec!["-c".into(), script.into()],
+            timeout_secs,
+        }
+    }
+
+    #[test]
+    fn test_run_fills_placeholders_and_environment() {
+        // Placeholders as arguments of their own, variables inside the script.
+        let hook = HookCommand {
+            command: "sh".into(),
+            args: vec![
+                "-c".into(),
+                r#"test "$1" = "$HARBOR_DEST" && test "$HARBOR_EXT" = pdf && echo "$2 $HARBOR_SRC" >&2"#
+                    .into(),
+                "hook".into(),
+                "{dest}".into(),
+                "{rule}".into(),
+            ],
+            timeout_secs: None,
+        };
+        let outcome = run(&hook, &moved());
+        assert_eq!(outcome.status, HookStatus::Exited(0));
+        assert!(outcome.status.is_success());
+        assert_eq!(outcome.stderr, "Docs /home/u/Downloads/report.pdf");
+        assert!(outcome
+            .command
+            .ends_with(" hook /home/u/Docs/report.pdf Docs"));
+    }
+
+    #[test]
+    fn test_environment_keeps_hostile_file_names_out_of_the_script() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut result = moved();
+        result.destination = dir.path().join(r#"a";touch pwned;".pdf"#);
+
+        let hook = shell(
+            r#"cd "$(dirname "$HARBOR_DEST")" && echo "$HARBOR_DEST" >&2"#,
+            None,
+        );
+        let outcome = run(&hook, &result);
+
+        assert_eq!(outcome.status, HookStatus::Exited(0));
+        assert!(outcome.stderr.ends_with(r#"a";touch pwned;".pdf"#));
+        assert!(!dir.path().join("pwned").exists());
+    }
+
+    #[test]
+    fn test_timeout_kills_what_the_hook_started() {
+        let outcome = run(&shell("sleep 30 & echo $! >&2; wait", Some(1)), &moved());
+        assert_eq!(outcome.status, HookStatus::TimedOut(Duration::from_secs(1)));
+
+        let pid: libc::pid_t = outcome.stderr.parse().unwrap();
+        let deadline = Instant::now() + Duration::from_secs(5);
+        // SAFETY: signal 0 only checks that the process exists.
+        while unsafe { libc::kill(pid, 0) } == 0 {
+            assert!(Instant::now() < deadline, "sleep {pid} outlived its hook");
+            std::thread::sleep(Duration::from_millis(20));
+        }
+    }
+
+    #[test]
+    fn test_run_reports_failures_and_timeouts() {
+        let outcome = run(&shell("echo 'scan failed' >&2; exit 3", None), &moved());
+        assert_eq!(outcome.status, HookStatus::Exited(3));
+        assert_eq!(
+            outcome.describe(),
+            "`sh -c echo 'scan failed' >&2; exit 3` exited with 3: scan failed"
+        );
+
+        let outcome = run(&shell("sleep 5", Some(0)), &moved());
+        assert_eq!(outcome.status, HookStatus::TimedOut(Duration::ZERO));
+        assert!(outcome.duration < Duration::from_secs(5));
+
+        let missing = HookCommand {
+            command: "/nonexistent/harbor-hook".into(),
+            ..Default::default()
+        };
+        assert!(matches!(
+            run(&missing, &moved()).status,
+            HookStatus::FailedToStart(_)
+        ));
+    }
+
+    #[test]
+    fn test_hooks_for_runs_rule_hooks_before_global_ones() {
+        let cfg = DownloadsConfig {
+            rules: vec![Rule {
+                name: "Docs".into(),
+                on_moved: Some(vec![shell("rule", None)]),
+                ..Default::default()
+            }],
+            on_moved: Some(vec![shell("global", None)]),
+            ..Default::default()
+        };
+        let scripts: Vec<&str> = hooks_for(&cfg, "Docs")
+            .map(|h| h.args[1].as_str())
+            .collect();
+        assert_eq!(scripts, vec!["rule", "global"]);
+        assert_eq!(hooks_for(&cfg, "Other").count(), 1);
+    }
+}
@@ -1,3 +1,20 @@
+pub mod breadcrumb;
+pub mod cancel;
+pub mod clock;
+pub mod control;
 pub mod downloads;
+pub mod events;
+pub mod history;
+pub mod hooks;
+pub mod lock;
+pub mod multipart;
+pub mod normalize;
+pub mod organizer;
 pub mod platform;
+pub mod retention;
+pub mod schedule;
+pub mod sidecar;
+pub mod template;
 pub mod types;
+pub mod vfs;
+pub mod webhook;
@@ -0,0 +1,238 @@
+//! Advisory lock that lets one pass at a time organize the download folder.
+//!
+//! The CLI watcher, the tray, the desktop app and "Organize now" all build
+//! an [`Organizer`](crate::organizer::Organizer) with the same lock file
+//! ([`harbor_lock_path`](crate::downloads::harbor_lock_path)). Each pass and
+//! each undo takes the lock first, so two of them never race on the same
+//! target names. The lock is an OS file lock (`flock` on Unix, `LockFileEx`
+//! on Windows), which the OS drops when its holder exits or crashes.
+//!
+//! While holding the lock, a pass writes who it is into the file and clears
+//! that record when it is done. A record found on a free lock therefore
+//! belongs to a pass that died; it is reported and taken over. On
+//! filesystems without file locks the record is all there is: it counts as
+//! held while its process is alive and younger than [`STALE_AFTER`].
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Age after which a record on a filesystem without file locks is ignored.
+pub const STALE_AFTER: Duration = Duration::from_secs(60 * 60);
+
+/// The process holding, or last holding, the lock.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LockHolder {
+    pub pid: u32,
+    /// File name of the holder's executable, e.g. `harbor-cli`.
+    pub program: String,
+    /// RFC 3339.
+    pub acquired_at: String,
+}
+
+impl LockHolder {
+    fn current() -> Self {
+        let program = std::env::current_exe()
+            .ok()
+            .and_then(|exe| exe.file_stem().map(|s| s.to_string_lossy().into_owned()))
+            .unwrap_or_else(|| "harbor".to_string());
+        Self {
+            pid: std::process::id(),
+            program,
+            acquired_at: Local::now().to_rfc3339(),
+        }
+    }
+
+    fn is_stale(&self) -> bool {
+        let too_old = DateTime::parse_from_rfc3339(&self.acquired_at)
+            .map(|at| {
+                Local::now()
+                    .signed_duration_since(at)
+                    .to_std()
+                    .unwrap_or_default()
+                    > STALE_AFTER
+            })
+            .unwrap_or(true);
+        too_old || !process_alive(self.pid)
+    }
+}
+
+impl std::fmt::Display for LockHolder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} (pid {}) since {}",
+            self.program, self.pid, self.acquired_at
+        )
+    }
+}
+
+/// Another pass holds the lock. Returned inside the [`anyhow::Error`] of a
+/// pass or undo that could not start; find it with `downcast_ref`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OrganizerBusy {
+    /// Who holds it, when that can be read. Windows keeps the locked file
+    /// from being read, so it is `None` there.
+    pub holder: Option<LockHolder>,
+}
+
+impl std::fmt::Display for OrganizerBusy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "another Harbor instance is organizing")?;
+        match &self.holder {
+            Some(holder) => write!(f, ": {holder}"),
+            None => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for OrganizerBusy {}
+
+/// Holds the lock until dropped.
+#[derive(Debug)]
+pub struct PassLock {
+    file: File,
+    path: PathBuf,
+    /// The record of an earlier holder that died while holding the lock.
+    pub recovered: Option<LockHolder>,
+}
+
+impl PassLock {
+    /// Takes the lock at `path`, creating the file and its folder if
+    /// needed. Fails with [`OrganizerBusy`] when another pass holds it.
+    pub fn try_acquire(path: &Path) -> Result<Self> {
+        if let Some(dir) = path.parent() {
+            std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("open lock file {}", path.display()))?;
+        let locked = match file.try_lock() {
+            Ok(()) => true,
+            Err(TryLockError::WouldBlock) => {
+                return Err(OrganizerBusy {
+                    holder: read_holder(&mut file),
+                }
+                .into())
+            }
+            Err(TryLockError::Error(e)) if e.kind() == std::io::ErrorKind::Unsupported => false,
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("lock {}", path.display()))
+            }
+        };
+
+        let recovered = read_holder(&mut file);
+        if let Some(holder) = recovered.as_ref().filter(|h| !locked && !h.is_stale()) {
+            return Err(OrganizerBusy {
+                holder: Some(holder.clone()),
+            }
+            .into());
+        }
+        if let Some(holder) = &recovered {
+            eprintln!(
+                "[Harbor] Taking over the organize lock from {holder}, which did not release it"
+            );
+        }
+
+        let record = serde_json::to_vec(&LockHolder::current())?;
+        file.set_len(0)
+            .and_then(|_| file.seek(SeekFrom::Start(0)))
+            .and_then(|_| file.write_all(&record))
+            .with_context(|| format!("write {}", path.display()))?;
+        Ok(Self {
+            file,
+            path: path.to_path_buf(),
+            recovered,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PassLock {
+    fn drop(&mut self) {
+        // Clear the record first: a record on a free lock means a crash.
+        let _ = self.file.set_len(0);
+        let _ = self.file.unlock();
+    }
+}
+
+fn read_holder(file: &mut File) -> Option<LockHolder> {
+    let mut text = String::new();
+    file.seek(SeekFrom::Start(0)).ok()?;
+    file.read_to_string(&mut text).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // Signal 0 only checks whether the process exists. EPERM means it does
+    // but belongs to someone else.
+    let exists = unsafe { libc::kill(pid, 0) } == 0;
+    exists || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    // Only the age of the record counts here.
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_second_lock_is_busy_until_the_first_is_dropped() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("state").join("organize.lock");
+
+        let first = PassLock::try_acquire(&path).unwrap();
+        assert!(first.recovered.is_none());
+        let err = PassLock::try_acquire(&path).unwrap_err();
+        let busy = err.downcast_ref::<OrganizerBusy>().unwrap();
+        if cfg!(unix) {
+            assert_eq!(busy.holder.as_ref().unwrap().pid, std::process::id());
+        }
+        assert!(err
+            .to_string()
+            .starts_with("another Harbor instance is organizing"));
+
+        drop(first);
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), 0);
+        let second = PassLock::try_acquire(&path).unwrap();
+        assert!(second.recovered.is_none());
+    }
+
+    #[test]
+    fn test_record_left_by_a_crashed_pass_is_recovered() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("organize.lock");
+        let crashed = LockHolder {
+            pid: u32::MAX,
+            program: "harbor-cli".into(),
+            acquired_at: "2024-01-15T10:30:00+00:00".into(),
+        };
+        std::fs::write(&path, serde_json::to_vec(&crashed).unwrap()).unwrap();
+        assert!(crashed.is_stale());
+
+        let lock = PassLock::try_acquire(&path).unwrap();
+        assert_eq!(lock.recovered, Some(crashed));
+        let record: LockHolder = serde_json::from_slice(&std::fs::read(&path).unwrap()).unwrap();
+        assert_eq!(record.pid, std::process::id());
+        assert!(!record.is_stale());
+    }
+}
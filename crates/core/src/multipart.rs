@@ -0,0 +1,332 @@
+//! Multi-part archives and split files that only make sense together.
+//!
+//! These naming schemes are recognised:
+//!
+//! - `movie.part1.rar`, `movie.part2.rar`, ... (RAR volumes)
+//! - `movie.rar`, `movie.r00`, `movie.r01`, ... (old-style RAR volumes)
+//! - `backup.7z.001`, `backup.7z.002`, ... (7-Zip, HJSplit and `split`)
+//! - `data.z01`, `data.z02`, ..., `data.zip` (split ZIP, `.zip` is the last part)
+//!
+//! A set is moved as a whole once it looks complete: its numbering has no
+//! gap, the closing `.zip` or first `.rar` is there, no browser temp file for
+//! one of its parts remains, and every part but the last has the same size.
+
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::LazyLock;
+
+/// `movie.part01.rar`
+static RAR_PART_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(.+)(\.part(\d{1,4})\.rar)$").expect("RAR_PART_RE is a valid pattern")
+});
+
+/// `movie.r00`
+static RAR_OLD_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(.+)(\.r(\d{2,3}))$").expect("RAR_OLD_RE is a valid pattern")
+});
+
+/// `backup.7z.001`
+static NUMBERED_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(.+)(\.[a-z0-9]+\.(\d{3}))$").expect("NUMBERED_RE is a valid pattern")
+});
+
+/// `data.z01`
+static ZIP_SPLIT_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)^(.+)(\.z(\d{2,3}))$").expect("ZIP_SPLIT_RE is a valid pattern")
+});
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Scheme {
+    RarParts,
+    RarOld,
+    Numbered,
+    ZipSplit,
+}
+
+/// One file of a [`SplitSet`].
+#[derive(Debug, Clone)]
+pub(crate) struct Volume {
+    pub(crate) path: PathBuf,
+    index: u32,
+    /// The name is always `base` followed by `suffix`.
+    base: String,
+    suffix: String,
+}
+
+/// The parts of one multi-part archive found in the download folder, in
+/// volume order.
+#[derive(Debug, Clone)]
+pub(crate) struct SplitSet {
+    /// What the whole set is called for rule matching: `movie.rar`,
+    /// `backup.7z`, `data.zip`.
+    pub(crate) name: String,
+    pub(crate) volumes: Vec<Volume>,
+    /// A part is missing from the numbering, or the first `.rar` or the
+    /// closing `.zip` is not there yet.
+    missing_parts: bool,
+    /// A browser temp file for one of the parts is still being written.
+    downloading: bool,
+}
+
+/// Splits a part's name into scheme, set base name, volume number and the
+/// suffix after the base.
+fn parse(name: &str) -> Option<(Scheme, &str, u32, &str)> {
+    let schemes = [
+        (Scheme::RarParts, &*RAR_PART_RE),
+        (Scheme::RarOld, &*RAR_OLD_RE),
+        (Scheme::ZipSplit, &*ZIP_SPLIT_RE),
+        (Scheme::Numbered, &*NUMBERED_RE),
+    ];
+    schemes.into_iter().find_map(|(scheme, re)| {
+        let caps = re.captures(name)?;
+        let base = caps.get(1)?.as_str();
+        let suffix = caps.get(2)?.as_str();
+        let index = caps.get(3)?.as_str().parse().ok()?;
+        Some((scheme, base, index, suffix))
+    })
+}
+
+/// The extension the set is matched under: `rar` for `.part1.rar` and
+/// `.r00`, `7z` for `.7z.001`, `zip` for `.z01`.
+fn set_extension(scheme: Scheme, suffix: &str) -> String {
+    match scheme {
+        Scheme::RarParts | Scheme::RarOld => "rar".to_string(),
+        Scheme::ZipSplit => "zip".to_string(),
+        Scheme::Numbered => suffix
+            .trim_start_matches('.')
+            .split('.')
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+    }
+}
+
+/// Finds the multi-part sets among `files`. Names for which `is_partial`
+/// holds are browser temp files: they are not parts themselves, but mark the
+/// part they will become as still downloading.
+pub(crate) fn find_sets(files: &[PathBuf], is_partial: impl Fn(&str) -> bool) -> Vec<SplitSet> {
+    let mut sets: HashMap<(Scheme, String), SplitSet> = HashMap::new();
+    let mut temp_names = Vec::new();
+    for path in files {
+        let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if is_partial(name) {
+            temp_names.push(name);
+            continue;
+        }
+        let Some((scheme, base, index, suffix)) = parse(name) else {
+            continue;
+        };
+        let name = format!("{base}.{}", set_extension(scheme, suffix));
+        let set = sets
+            .entry((scheme, name.to_ascii_lowercase()))
+            .or_insert_with(|| SplitSet {
+                name,
+                volumes: Vec::new(),
+                missing_parts: false,
+                downloading: false,
+            });
+        set.volumes.push(Volume {
+            path: path.clone(),
+            // `.r00` follows `movie.rar`, which is volume 0 below.
+            index: if scheme == Scheme::RarOld {
+                index + 1
+            } else {
+                index
+            },
+            base: base.to_string(),
+            suffix: suffix.to_string(),
+        });
+    }
+
+    // `movie.rar` opens an old-style RAR set and `data.zip` closes a split ZIP.
+    let by_name: HashMap<String, &PathBuf> = files
+        .iter()
+        .filter_map(|p| Some((p.file_name()?.to_str()?.to_ascii_lowercase(), p)))
+        .collect();
+    for ((scheme, key), set) in sets.iter_mut() {
+        if !matches!(scheme, Scheme::RarOld | Scheme::ZipSplit) {
+            continue;
+        }
+        let last = set.volumes.iter().map(|v| v.index).max().unwrap_or(0);
+        let Some(path) = by_name.get(key) else {
+            set.missing_parts = true;
+            continue;
+        };
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or(key);
+        let (base, suffix) = name.split_at(name.len() - 4);
+        set.volumes.push(Volume {
+            path: (*path).clone(),
+            index: if *scheme == Scheme::RarOld {
+                0
+            } else {
+                last + 1
+            },
+            base: base.to_string(),
+            suffix: suffix.to_string(),
+        });
+    }
+
+    for name in temp_names {
+        let Some(part) = name.rfind('.').map(|i| &name[..i]) else {
+            continue;
+        };
+        if let Some((scheme, base, _, suffix)) = parse(part) {
+            let key = format!("{base}.{}", set_extension(scheme, suffix)).to_ascii_lowercase();
+            if let Some(set) = sets.get_mut(&(scheme, key)) {
+                set.downloading = true;
+            }
+        }
+    }
+
+    let mut sets: Vec<SplitSet> = sets.into_values().collect();
+    for set in &mut sets {
+        set.volumes.sort_by_key(|v| v.index);
+        let first = set.volumes.first().map_or(0, |v| v.index);
+        let contiguous = set
+            .volumes
+            .iter()
+            .enumerate()
+            .all(|(i, v)| v.index == first + i as u32);
+        // Volumes count from 1, except `.000` splits and old-style RAR sets.
+        if !contiguous || first > 1 {
+            set.missing_parts = true;
+        }
+    }
+    sets.sort_by(|a, b| a.name.cmp(&b.name));
+    sets
+}
+
+impl SplitSet {
+    /// Whether the set can be moved, given the size of each volume in order.
+    /// A volume after the last one may still be on its way if every volume
+    /// is full-sized, which this cannot tell; waiting for `min_age_secs`
+    /// covers that gap.
+    pub(crate) fn is_complete(&self, sizes: &[u64]) -> bool {
+        if self.missing_parts || self.downloading {
+            return false;
+        }
+        let Some((last, rest)) = sizes.split_last() else {
+            return false;
+        };
+        match rest.first() {
+            Some(full) => rest.iter().all(|s| s == full) && last <= full,
+            None => true,
+        }
+    }
+
+    /// Destinations for every volume in `dir`, with the set renamed to
+    /// `base`. If any name is taken, all volumes get the same ` (n)` after
+    /// the base name so the archive still finds its parts.
+    pub(crate) fn targets(
+        &self,
+        dir: &Path,
+        base: Option<&str>,
+        taken: impl Fn(&Path) -> bool,
+    ) -> Vec<PathBuf> {
+        let mut n = 0u32;
+        loop {
+            let targets: Vec<PathBuf> = self
+                .volumes
+                .iter()
+                .map(|v| {
+                    let base = base.unwrap_or(&v.base);
+                    match n {
+                        0 => dir.join(format!("{base}{}", v.suffix)),
+                        n => dir.join(format!("{base} ({n}){}", v.suffix)),
+                    }
+                })
+                .collect();
+            if !targets.iter().any(|t| taken(t)) {
+                return targets;
+            }
+            n += 1;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|n| Path::new("/dl").join(n)).collect()
+    }
+
+    fn sets(names: &[&str]) -> Vec<SplitSet> {
+        find_sets(&paths(names), |n| {
+            n.ends_with(".part") || n.ends_with(".crdownload")
+        })
+    }
+
+    fn volume_names(set: &SplitSet) -> Vec<&str> {
+        set.volumes
+            .iter()
+            .map(|v| v.path.file_name().unwrap().to_str().unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_find_sets_recognises_each_scheme() {
+        let found = sets(&[
+            "movie.part2.rar",
+            "movie.part1.rar",
+            "backup.7z.001",
+            "backup.7z.002",
+            "data.zip",
+            "data.z01",
+            "old.r00",
+            "old.rar",
+            "single.zip",
+            "notes.txt",
+        ]);
+        let summary: Vec<(&str, Vec<&str>)> = found
+            .iter()
+            .map(|s| (s.name.as_str(), volume_names(s)))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("backup.7z", vec!["backup.7z.001", "backup.7z.002"]),
+                ("data.zip", vec!["data.z01", "data.zip"]),
+                ("movie.rar", vec!["movie.part1.rar", "movie.part2.rar"]),
+                ("old.rar", vec!["old.rar", "old.r00"]),
+            ]
+        );
+        assert!(found.iter().all(|s| s.is_complete(&[10, 4])));
+    }
+
+    #[test]
+    fn test_incomplete_sets() {
+        // A gap in the numbering.
+        let gap = sets(&["movie.part1.rar", "movie.part3.rar"]);
+        assert!(!gap[0].is_complete(&[10, 10]));
+        // The closing `.zip` has not arrived.
+        let open = sets(&["data.z01", "data.z02"]);
+        assert!(!open[0].is_complete(&[10, 10]));
+        // The third part is still downloading.
+        let busy = sets(&["backup.7z.001", "backup.7z.002", "backup.7z.003.part"]);
+        assert!(!busy[0].is_complete(&[10, 10]));
+        // A short volume before the last one means the set was cut.
+        let short = sets(&["backup.7z.001", "backup.7z.002", "backup.7z.003"]);
+        assert!(!short[0].is_complete(&[10, 4, 10]));
+        assert!(short[0].is_complete(&[10, 10, 10]));
+    }
+
+    #[test]
+    fn test_targets_rename_every_volume_alike() {
+        let set = &sets(&["movie.part1.rar", "movie.part2.rar"])[0];
+        let taken: HashSet<PathBuf> = paths(&["movie.part2.rar"]).into_iter().collect();
+        assert_eq!(
+            set.targets(Path::new("/dl"), None, |p| taken.contains(p)),
+            paths(&["movie (1).part1.rar", "movie (1).part2.rar"])
+        );
+        assert_eq!(
+            set.targets(Path::new("/dl"), Some("Film"), |p| taken.contains(p)),
+            paths(&["Film.part1.rar", "Film.part2.rar"])
+        );
+    }
+}
@@ -0,0 +1,240 @@
+//! File name clean-ups applied before a file is moved.
+//!
+//! Each [`NormalizeStep`] looks at the name alone. The organize pass then
+//! finds a free name in the target folder as usual, so a cleaned-up name that
+//! is already taken still gets a ` (n)` suffix.
+
+use crate::types::NormalizeStep;
+use regex::Regex;
+use std::sync::LazyLock;
+
+/// Longest name, in bytes, that [`NormalizeStep::Truncate`] produces. 255
+/// bytes fits ext4 and APFS, and never exceeds the 255 UTF-16 units of NTFS
+/// and FAT.
+pub const MAX_NAME_BYTES: usize = 255;
+
+/// Room truncation leaves for the ` (n)` suffix a name collision adds.
+const COLLISION_SUFFIX_BYTES: usize = 8;
+
+/// Longer extensions are cut along with the rest of the name when truncating.
+const MAX_KEPT_EXTENSION_BYTES: usize = 16;
+
+/// Characters rejected by FAT, NTFS or SMB shares, besides control characters.
+const INVALID_CHARS: &[char] = &['<', '>', ':', '"', '/', '\\', '|', '?', '*'];
+
+/// Device names Windows refuses as a file name, with or without an extension.
+const RESERVED_NAMES: &[&str] = &[
+    "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8",
+    "COM9", "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+];
+
+/// One duplicate marker at the end of a stem: ` (1)`, `(12)` or ` - Copy`.
+static DUPLICATE_SUFFIX_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(?i)(?:\s*\(\d{1,4}\)|\s+-\s+copy)$")
+        .expect("DUPLICATE_SUFFIX_RE is a valid pattern")
+});
+
+/// Applies `steps` to `name` in [`NormalizeStep`] declaration order.
+///
+/// A step that would leave nothing of the name (e.g. stripping ` (1)` from a
+/// file called `(1).pdf`) is skipped.
+pub fn normalize_file_name(name: &str, steps: &[NormalizeStep]) -> String {
+    let mut steps = steps.to_vec();
+    steps.sort();
+    steps.dedup();
+
+    let mut name = name.to_string();
+    for step in steps {
+        let next = match step {
+            NormalizeStep::UrlDecode => url_decode(&name),
+            NormalizeStep::StripDuplicateSuffix => strip_duplicate_suffix(&name),
+            NormalizeStep::ReplaceInvalidChars => replace_invalid_chars(&name),
+            NormalizeStep::CollapseWhitespace => collapse_whitespace(&name),
+            NormalizeStep::LowercaseExtension => lowercase_extension(&name),
+            NormalizeStep::Truncate => truncate(&name, MAX_NAME_BYTES - COLLISION_SUFFIX_BYTES),
+        };
+        if !next.is_empty() && next != "." && next != ".." {
+            name = next;
+        }
+    }
+    name
+}
+
+/// Splits `report.final.pdf` into `("report.final", Some("pdf"))`. Dot files
+/// such as `.bashrc` and names ending in a dot have no extension.
+fn split_extension(name: &str) -> (&str, Option<&str>) {
+    match name.rfind('.') {
+        Some(i) if i > 0 && i + 1 < name.len() => (&name[..i], Some(&name[i + 1..])),
+        _ => (name, None),
+    }
+}
+
+fn join_extension(stem: &str, ext: Option<&str>) -> String {
+    match ext {
+        Some(ext) => format!("{stem}.{ext}"),
+        None => stem.to_string(),
+    }
+}
+
+/// Decodes `%XX` escapes. Names that would not decode to valid UTF-8 are left
+/// alone, and decoded path separators and control characters become `_`.
+fn url_decode(name: &str) -> String {
+    let bytes = name.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|h| std::str::from_utf8(h).ok())
+            .and_then(|h| u8::from_str_radix(h, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(match byte {
+                    b'/' | b'\\' | 0..=0x1f | 0x7f => b'_',
+                    _ => byte,
+                });
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).unwrap_or_else(|_| name.to_string())
+}
+
+fn strip_duplicate_suffix(name: &str) -> String {
+    let (stem, ext) = split_extension(name);
+    let mut stem = stem;
+    while let Some(m) = DUPLICATE_SUFFIX_RE.find(stem) {
+        if m.start() == 0 {
+            break;
+        }
+        stem = &stem[..m.start()];
+    }
+    join_extension(stem, ext)
+}
+
+/// Replaces rejected characters with `_`, drops the trailing dots and spaces
+/// Windows would silently remove, and suffixes reserved device names.
+fn replace_invalid_chars(name: &str) -> String {
+    let replaced: String = name
+        .chars()
+        .map(|c| {
+            if c.is_control() || INVALID_CHARS.contains(&c) {
+                '_'
+            } else {
+                c
+            }
+        })
+        .collect();
+    let mut name = replaced.trim_end_matches(['.', ' ']).to_string();
+    // Windows checks the part before the first dot: `CON.tar.gz` is reserved too.
+    let base_len = name.find('.').unwrap_or(name.len());
+    if RESERVED_NAMES
+        .iter()
+        .any(|r| r.eq_ignore_ascii_case(&name[..base_len]))
+    {
+        name.insert(base_len, '_');
+    }
+    name
+}
+
+fn collapse_whitespace(name: &str) -> String {
+    let (stem, ext) = split_extension(name);
+    let stem = stem.split_whitespace().collect::<Vec<_>>().join(" ");
+    let ext = ext.map(str::trim).filter(|e| !e.is_empty());
+    join_extension(&stem, ext)
+}
+
+fn lowercase_extension(name: &str) -> String {
+    let (stem, ext) = split_extension(name);
+    join_extension(stem, ext.map(str::to_lowercase).as_deref())
+}
+
+/// Shortens `name` to at most `max_bytes`, cutting the stem on a character
+/// boundary and keeping a short extension.
+fn truncate(name: &str, max_bytes: usize) -> String {
+    if name.len() <= max_bytes {
+        return name.to_string();
+    }
+    let (stem, ext) = match split_extension(name) {
+        (stem, Some(ext)) if ext.len() <= MAX_KEPT_EXTENSION_BYTES => (stem, Some(ext)),
+        _ => (name, None),
+    };
+    let budget = max_bytes - ext.map_or(0, |e| e.len() + 1);
+    let mut end = budget.min(stem.len());
+    while !stem.is_char_boundary(end) {
+        end -= 1;
+    }
+    let stem = stem[..end].trim_end_matches(['.', ' ']);
+    join_extension(stem, ext)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use NormalizeStep::*;
+
+    const ALL: &[NormalizeStep] = &[
+        UrlDecode,
+        StripDuplicateSuffix,
+        ReplaceInvalidChars,
+        CollapseWhitespace,
+        LowercaseExtension,
+        Truncate,
+    ];
+
+    #[test]
+    fn test_each_step() {
+        let cases = [
+            (UrlDecode, "My%20Report%3A%20Q1.pdf", "My Report: Q1.pdf"),
+            (UrlDecode, "a%2Fb%zz.txt", "a_b%zz.txt"),
+            (UrlDecode, "bad%FF.txt", "bad%FF.txt"),
+            (StripDuplicateSuffix, "Report (3) - Copy.PDF", "Report.PDF"),
+            (StripDuplicateSuffix, "setup(12).exe", "setup.exe"),
+            (StripDuplicateSuffix, "(1).pdf", "(1).pdf"),
+            (ReplaceInvalidChars, "Q1: plan?.pdf", "Q1_ plan_.pdf"),
+            (ReplaceInvalidChars, "notes. ", "notes"),
+            (ReplaceInvalidChars, "con.tar.gz", "con_.tar.gz"),
+            (ReplaceInvalidChars, "console.log", "console.log"),
+            (CollapseWhitespace, "  My \t  File .pdf", "My File.pdf"),
+            (LowercaseExtension, "IMG_1234.JPEG", "IMG_1234.jpeg"),
+            (LowercaseExtension, ".BASHRC", ".BASHRC"),
+        ];
+        for (step, input, expected) in cases {
+            assert_eq!(normalize_file_name(input, &[step]), expected, "{step:?}");
+        }
+    }
+
+    #[test]
+    fn test_steps_run_in_declaration_order() {
+        // Decoding first exposes the duplicate marker and the invalid `:`.
+        let steps = [
+            LowercaseExtension,
+            StripDuplicateSuffix,
+            ReplaceInvalidChars,
+            UrlDecode,
+        ];
+        assert_eq!(
+            normalize_file_name("Q1%3A%20Report%20(2).PDF", &steps),
+            "Q1_ Report.pdf"
+        );
+        assert_eq!(
+            normalize_file_name("Report (3) - Copy.PDF", ALL),
+            "Report.pdf"
+        );
+    }
+
+    #[test]
+    fn test_truncate_keeps_extension_and_char_boundaries() {
+        let long = format!("{}.pdf", "é".repeat(200));
+        let name = normalize_file_name(&long, &[Truncate]);
+        assert!(name.len() <= MAX_NAME_BYTES - COLLISION_SUFFIX_BYTES);
+        assert!(name.ends_with("é.pdf"));
+
+        let short = "short.pdf";
+        assert_eq!(normalize_file_name(short, &[Truncate]), short);
+    }
+}
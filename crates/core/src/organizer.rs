@@ -0,0 +1,894 @@
+//! One entry point for organizing a download folder.
+//!
+//! [`Organizer`] bundles the configuration with everything a pass needs
+//! around it — filesystem, clock, activity log, move history and an event
+//! subscriber — so front-ends and embedders share the same orchestration
+//! instead of wiring the free functions in [`crate::downloads`] themselves.
+//!
+//! ```no_run
+//! use harbor_core::downloads::{
+//!     default_config, harbor_history_path, harbor_lock_path, harbor_log_path,
+//! };
+//! use harbor_core::history::JsonlHistory;
+//! use harbor_core::organizer::{FileLog, Organizer};
+//! use std::sync::Arc;
+//!
+//! let cfg = default_config();
+//! let organizer = Organizer::builder(cfg.clone())
+//!     .log_sink(Arc::new(FileLog::from_config(harbor_log_path(), &cfg)))
+//!     .history(Arc::new(JsonlHistory::new(harbor_history_path())))
+//!     .pass_lock(harbor_lock_path())
+//!     .on_event(|event| println!("{event:?}"))
+//!     .build();
+//! let summary = organizer.run_once()?;
+//! println!("moved {} file(s)", summary.moved.len());
+//! # Ok::<(), anyhow::Error>(())
+//! ```
+
+use crate::breadcrumb::{self, BreadcrumbCleanup};
+use crate::cancel::CancellationToken;
+use crate::clock::{Clock, SystemClock};
+use crate::downloads::{
+    append_events_to_log_with_clock, cleanup_breadcrumbs_with, move_file, organize_since,
+    plan_with, watch_with, DownloadsConfig, LogArchivePolicy, OrganizeResult, OrganizeSummary,
+    SkipReason,
+};
+use crate::events::OrganizeEvent;
+use crate::history::{HistoryEntry, HistoryStore, MoveRecord, NoHistory};
+use crate::lock::PassLock;
+use crate::vfs::{FileSystem, RealFs};
+use crate::webhook::Webhook;
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Destination for the activity log.
+pub trait LogSink: Send + Sync {
+    /// Records a batch of events; timestamps come from `clock`.
+    fn write(&self, events: &[OrganizeEvent], clock: &dyn Clock);
+}
+
+/// A sink that drops everything.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct NoLog;
+
+impl LogSink for NoLog {
+    fn write(&self, _events: &[OrganizeEvent], _clock: &dyn Clock) {}
+}
+
+/// The plain-text activity log read by the desktop app, with rotation.
+#[derive(Debug, Clone)]
+pub struct FileLog {
+    path: PathBuf,
+    policy: LogArchivePolicy,
+}
+
+impl FileLog {
+    pub fn new(path: impl Into<PathBuf>, policy: LogArchivePolicy) -> Self {
+        Self {
+            path: path.into(),
+            policy,
+        }
+    }
+
+    /// Uses the archive limits from `cfg`.
+    pub fn from_config(path: impl Into<PathBuf>, cfg: &DownloadsConfig) -> Self {
+        Self::new(path, LogArchivePolicy::from_config(cfg))
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl LogSink for FileLog {
+    fn write(&self, events: &[OrganizeEvent], clock: &dyn Clock) {
+        append_events_to_log_with_clock(&self.path, events, &self.policy, clock);
+    }
+}
+
+/// Callback receiving every [`OrganizeEvent`] as it happens.
+pub type EventSubscriber = Arc<dyn Fn(&OrganizeEvent) + Send + Sync>;
+
+/// What a pass would do right now; see [`Organizer::plan`].
+#[derive(Debug, Default)]
+pub struct Plan {
+    /// Moves the pass would make, with the destination it would pick.
+    pub moves: Vec<OrganizeResult>,
+    /// Every file the pass would leave in place, in scan order.
+    pub skipped: Vec<PlannedSkip>,
+    /// Files ready to move that the per-pass limits would leave for later
+    /// passes. They are also in `skipped`, as [`SkipReason::PassLimitReached`].
+    pub deferred: usize,
+}
+
+/// A file [`Organizer::plan`] would leave in place.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlannedSkip {
+    pub path: PathBuf,
+    /// The rule that matched the file, if any.
+    pub rule_name: Option<String>,
+    /// Where the file would go, when the pass got as far as picking it.
+    pub destination: Option<PathBuf>,
+    pub reason: SkipReason,
+}
+
+/// Result of [`Organizer::undo`].
+#[derive(Debug, Default)]
+pub struct UndoSummary {
+    /// Moves that were reverted; each file is back at its `source`.
+    pub restored: Vec<OrganizeResult>,
+    /// Moves that could not be reverted. They stay in the history so a
+    /// later undo can retry them.
+    pub errors: Vec<String>,
+}
+
+/// Builds an [`Organizer`]. Everything except the configuration is optional:
+/// by default the real disk and wall clock are used, nothing is logged,
+/// recorded or published, and passes do not lock.
+pub struct OrganizerBuilder {
+    config: DownloadsConfig,
+    fs: Arc<dyn FileSystem>,
+    clock: Arc<dyn Clock>,
+    log: Arc<dyn LogSink>,
+    history: Arc<dyn HistoryStore>,
+    subscriber: Option<EventSubscriber>,
+    webhook: Option<Arc<Webhook>>,
+    lock: Option<PathBuf>,
+}
+
+impl OrganizerBuilder {
+    pub fn new(config: DownloadsConfig) -> Self {
+        Self {
+            config,
+            fs: Arc::new(RealFs),
+            clock: Arc::new(SystemClock),
+            log: Arc::new(NoLog),
+            history: Arc::new(NoHistory),
+            subscriber: None,
+            webhook: None,
+            lock: None,
+        }
+    }
+
+    pub fn filesystem(mut self, fs: Arc<dyn FileSystem>) -> Self {
+        self.fs = fs;
+        self
+    }
+
+    pub fn clock(mut self, clock: Arc<dyn Clock>) -> Self {
+        self.clock = clock;
+        self
+    }
+
+    pub fn log_sink(mut self, log: Arc<dyn LogSink>) -> Self {
+        self.log = log;
+        self
+    }
+
+    pub fn history(mut self, history: Arc<dyn HistoryStore>) -> Self {
+        self.history = history;
+        self
+    }
+
+    /// Calls `subscriber` for every event, on the thread doing the work.
+    pub fn on_event(mut self, subscriber: impl Fn(&OrganizeEvent) + Send + Sync + 'static) -> Self {
+        self.subscriber = Some(Arc::new(subscriber));
+        self
+    }
+
+    /// Publishes every finished pass to `webhook`.
+    pub fn webhook(mut self, webhook: Arc<Webhook>) -> Self {
+        self.webhook = Some(webhook);
+        self
+    }
+
+    /// Holds the [`PassLock`] at `path` during every pass and undo, so
+    /// organizers sharing that path, in this process or another, take
+    /// turns. A pass that finds it taken fails with
+    /// [`OrganizerBusy`](crate::lock::OrganizerBusy).
+    pub fn pass_lock(mut self, path: impl Into<PathBuf>) -> Self {
+        self.lock = Some(path.into());
+        self
+    }
+
+    pub fn build(self) -> Organizer {
+        Organizer {
+            config: Arc::new(self.config),
+            fs: self.fs,
+            clock: self.clock,
+            log: self.log,
+            history: self.history,
+            subscriber: self.subscriber,
+            webhook: self.webhook,
+            lock: self.lock,
+        }
+    }
+}
+
+/// Organizes one download folder; cheap to clone and share between threads.
+///
+/// Every pass publishes its events to the subscriber as they happen, writes
+/// them to the log sink once the pass finishes (retention purges are written
+/// as they occur), records its moves in the history store and, if one is
+/// set, sends the outcome to the webhook.
+#[derive(Clone)]
+pub struct Organizer {
+    config: Arc<DownloadsConfig>,
+    fs: Arc<dyn FileSystem>,
+    clock: Arc<dyn Clock>,
+    log: Arc<dyn LogSink>,
+    history: Arc<dyn HistoryStore>,
+    subscriber: Option<EventSubscriber>,
+    webhook: Option<Arc<Webhook>>,
+    lock: Option<PathBuf>,
+}
+
+impl Organizer {
+    pub fn builder(config: DownloadsConfig) -> OrganizerBuilder {
+        OrganizerBuilder::new(config)
+    }
+
+    pub fn config(&self) -> &DownloadsConfig {
+        &self.config
+    }
+
+    /// Runs a single pass.
+    pub fn run_once(&self) -> Result<OrganizeSummary> {
+        self.run_once_cancellable(&CancellationToken::new())
+    }
+
+    /// Runs a single pass that stops early once `cancel` is triggered; see
+    /// [`organize_cancellable`](crate::downloads::organize_cancellable).
+    pub fn run_once_cancellable(&self, cancel: &CancellationToken) -> Result<OrganizeSummary> {
+        let mut batch = Vec::new();
+        organize_since(
+            &*self.fs,
+            &*self.clock,
+            &self.config,
+            None,
+            self.lock.as_deref(),
+            &|| cancel.is_cancelled(),
+            &mut |event| self.observe(event, &mut batch),
+        )
+    }
+
+    /// Polls the download folder every `interval_secs` and applies retention
+    /// policies until `should_continue` is cleared.
+    pub fn watch(&self, interval_secs: u64, should_continue: &AtomicBool) -> Result<()> {
+        let mut batch = Vec::new();
+        watch_with(
+            &*self.fs,
+            &*self.clock,
+            &self.config,
+            self.lock.as_deref(),
+            interval_secs,
+            &|| !should_continue.load(Ordering::Relaxed),
+            &mut |event| self.observe(event, &mut batch),
+        )
+    }
+
+    /// Works out what [`Organizer::run_once`] would do without moving,
+    /// creating, logging or recording anything.
+    pub fn plan(&self) -> Result<Plan> {
+        let mut skipped = Vec::new();
+        let summary = plan_with(&*self.fs, &*self.clock, &self.config, &mut |event| {
+            if let OrganizeEvent::FileSkipped {
+                path,
+                rule_name,
+                reason,
+            } = event
+            {
+                skipped.push(PlannedSkip {
+                    path,
+                    rule_name,
+                    destination: None,
+                    reason,
+                });
+            }
+        })?;
+        for skip in &mut skipped {
+            skip.destination = summary
+                .skipped
+                .iter()
+                .find(|held| held.path == skip.path)
+                .map(|held| held.destination.clone());
+        }
+        Ok(Plan {
+            moves: summary.moved,
+            skipped,
+            deferred: summary.deferred,
+        })
+    }
+
+    /// Reverts the most recent recorded pass, newest move first.
+    ///
+    /// Breadcrumbs left at the original location are removed first. A move
+    /// is not reverted when its file is gone from the destination or
+    /// something else now occupies the original location.
+    pub fn undo(&self) -> Result<UndoSummary> {
+        let _lock = self
+            .lock
+            .as_deref()
+            .map(PassLock::try_acquire)
+            .transpose()?;
+        let mut summary = UndoSummary::default();
+        let Some(entry) = self.history.pop_last()? else {
+            return Ok(summary);
+        };
+
+        let mut events = Vec::new();
+        let mut remaining = Vec::new();
+        for record in entry.moves.iter().rev() {
+            match self.restore(record) {
+                Ok(()) => {
+                    let result = OrganizeResult {
+                        source: record.source.clone(),
+                        destination: record.destination.clone(),
+                        rule_name: record.rule_name.clone(),
+                        symlink_info: None,
+                    };
+                    let event = OrganizeEvent::FileRestored(result.clone());
+                    self.notify(&event);
+                    events.push(event);
+                    summary.restored.push(result);
+                }
+                Err(e) => {
+                    summary.errors.push(format!(
+                        "Failed to restore '{}' from '{}': {e}",
+                        record.source.display(),
+                        record.destination.display()
+                    ));
+                    remaining.push(record.clone());
+                }
+            }
+        }
+        self.log.write(&events, &*self.clock);
+
+        if !remaining.is_empty() {
+            remaining.reverse();
+            self.history.record(HistoryEntry {
+                recorded_at: entry.recorded_at,
+                moves: remaining,
+            })?;
+        }
+        Ok(summary)
+    }
+
+    /// Removes dangling and expired breadcrumbs from the download folder,
+    /// publishing and logging each removal; see
+    /// [`cleanup_breadcrumbs`](crate::downloads::cleanup_breadcrumbs).
+    pub fn cleanup_breadcrumbs(&self) -> Result<BreadcrumbCleanup> {
+        let max_age = self.config.breadcrumb_max_age().unwrap_or(Duration::ZERO);
+        let report =
+            cleanup_breadcrumbs_with(&*self.fs, &*self.clock, &self.config, Some(max_age))?;
+        let events: Vec<OrganizeEvent> = report
+            .removed
+            .iter()
+            .map(|removed| OrganizeEvent::BreadcrumbRemoved(removed.clone()))
+            .collect();
+        for event in &events {
+            self.notify(event);
+        }
+        self.log.write(&events, &*self.clock);
+        Ok(report)
+    }
+
+    /// Moves the file back next to its original name first and swaps it in
+    /// only then, so a move that fails keeps the breadcrumbs.
+    fn restore(&self, record: &MoveRecord) -> std::io::Result<()> {
+        let fs = &*self.fs;
+        let rate = self.config.max_copy_bytes_per_sec;
+        let breadcrumbs = breadcrumb::find_for(fs, &record.source, &record.destination);
+        if fs.metadata(&record.source).is_ok() && !breadcrumbs.contains(&record.source) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::AlreadyExists,
+                "original location is occupied",
+            ));
+        }
+        if !fs.metadata(&record.destination).is_ok_and(|m| m.is_file()) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                "file is no longer at its destination",
+            ));
+        }
+        let name = record
+            .source
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let staged = record
+            .source
+            .with_file_name(format!(".{name}.harbor-restore"));
+        move_file(fs, &record.destination, &staged, rate, &|| false)?;
+        let swapped = breadcrumbs
+            .iter()
+            .try_for_each(|path| fs.remove_file(path))
+            .and_then(|()| fs.rename(&staged, &record.source));
+        if swapped.is_err() {
+            let _ = move_file(fs, &staged, &record.destination, rate, &|| false);
+        }
+        swapped
+    }
+
+    fn notify(&self, event: &OrganizeEvent) {
+        if let Some(subscriber) = &self.subscriber {
+            subscriber(event);
+        }
+    }
+
+    /// Publishes `event` and flushes the batch to the log and history at the
+    /// end of a pass or when housekeeping removes a file.
+    fn observe(&self, event: OrganizeEvent, batch: &mut Vec<OrganizeEvent>) {
+        self.notify(&event);
+        let flush = matches!(
+            event,
+            OrganizeEvent::ScanFinished { .. }
+                | OrganizeEvent::FilePurged(_)
+                | OrganizeEvent::BreadcrumbRemoved(_)
+        );
+        batch.push(event);
+        if flush {
+            self.flush(batch);
+        }
+    }
+
+    fn flush(&self, batch: &mut Vec<OrganizeEvent>) {
+        self.log.write(batch, &*self.clock);
+        if let Some(webhook) = &self.webhook {
+            webhook.publish(batch, &*self.clock);
+        }
+        let moves: Vec<MoveRecord> = batch
+            .iter()
+            .filter_map(|event| match event {
+                OrganizeEvent::FileMoved(result) => Some(MoveRecord::from(result)),
+                _ => None,
+            })
+            .collect();
+        batch.clear();
+        if moves.is_empty() {
+            return;
+        }
+        let entry = HistoryEntry {
+            recorded_at: self
+                .clock
+                .local_now()
+                .format("%Y-%m-%d %H:%M:%S")
+                .to_string(),
+            moves,
+        };
+        if let Err(e) = self.history.record(entry) {
+            eprintln!("[Harbor] Failed to record move history: {e:#}");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::history::MemoryHistory;
+    use crate::types::Rule;
+    use crate::vfs::MemoryFs;
+    use std::sync::Mutex;
+
+    const DOWNLOADS: &str = "/home/u/Downloads";
+
+    #[derive(Default)]
+    struct RecordingLog(Mutex<Vec<String>>);
+
+    impl LogSink for RecordingLog {
+        fn write(&self, events: &[OrganizeEvent], _clock: &dyn Clock) {
+            let mut lines = self.0.lock().unwrap();
+            for event in events {
+                match event {
+                    OrganizeEvent::FileMoved(r) => {
+                        lines.push(format!("moved {}", r.source.display()))
+                    }
+                    OrganizeEvent::FileRestored(r) => {
+                        lines.push(format!("restored {}", r.source.display()))
+                    }
+                    OrganizeEvent::BreadcrumbRemoved(r) => {
+                        lines.push(format!("removed {}", r.path.display()))
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+
+    fn docs_config(create_symlink: bool) -> DownloadsConfig {
+        DownloadsConfig {
+            download_dir: DOWNLOADS.into(),
+            min_age_secs: Some(0),
+            rules: vec![Rule {
+                name: "Docs".into(),
+                extensions: Some(vec!["pdf".into()]),
+                target_dir: "/home/u/Docs".into(),
+                create_symlink,
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    struct Fixture {
+        fs: Arc<MemoryFs>,
+        log: Arc<RecordingLog>,
+        history: Arc<MemoryHistory>,
+        seen: Arc<Mutex<Vec<String>>>,
+        organizer: Organizer,
+    }
+
+    fn fixture(cfg: DownloadsConfig) -> Fixture {
+        let fs = Arc::new(MemoryFs::new());
+        fs.add_dir(DOWNLOADS);
+        let log = Arc::new(RecordingLog::default());
+        let history = Arc::new(MemoryHistory::new());
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        let organizer = Organizer::builder(cfg)
+            .filesystem(fs.clone())
+            .clock(Arc::new(ManualClock::at_local("2024-01-15 10:30:00")))
+            .log_sink(log.clone())
+            .history(history.clone())
+            .on_event(move |event| {
+                let kind = format!("{event:?}");
+                sink.lock().unwrap().push(
+                    kind.split([' ', '('])
+                        .next()
+                        .unwrap_or_default()
+                        .to_string(),
+                );
+            })
+            .build();
+        Fixture {
+            fs,
+            log,
+            history,
+            seen,
+            organizer,
+        }
+    }
+
+    // ── run_once ─────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_run_once_publishes_logs_and_records_history() {
+        let f = fixture(docs_config(false));
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+
+        let summary = f.organizer.run_once().unwrap();
+
+        assert_eq!(summary.moved.len(), 1);
+        assert_eq!(
+            *f.seen.lock().unwrap(),
+            vec!["ScanStarted", "FileMoved", "ScanFinished"]
+        );
+        assert_eq!(
+            *f.log.0.lock().unwrap(),
+            vec!["moved /home/u/Downloads/a.pdf"]
+        );
+        let entries = f.history.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].recorded_at, "2024-01-15 10:30:00");
+        assert_eq!(
+            entries[0].moves[0].destination,
+            PathBuf::from("/home/u/Docs/a.pdf")
+        );
+    }
+
+    #[test]
+    fn test_run_once_without_moves_records_no_history() {
+        let f = fixture(docs_config(false));
+        f.fs.add_file("/home/u/Downloads/a.txt", b"a".to_vec());
+
+        f.organizer.run_once().unwrap();
+
+        assert!(f.history.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_run_once_and_undo_are_busy_while_the_lock_is_held() {
+        let dir = tempfile::tempdir().unwrap();
+        let lock_path = dir.path().join("organize.lock");
+        let f = fixture(docs_config(false));
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        let organizer = Organizer::builder(docs_config(false))
+            .filesystem(f.fs.clone())
+            .clock(Arc::new(ManualClock::at_local("2024-01-15 10:30:00")))
+            .history(f.history.clone())
+            .pass_lock(&lock_path)
+            .build();
+
+        let held = PassLock::try_acquire(&lock_path).unwrap();
+        let err = organizer.run_once().unwrap_err();
+        assert!(err.is::<crate::lock::OrganizerBusy>());
+        assert!(organizer
+            .undo()
+            .unwrap_err()
+            .is::<crate::lock::OrganizerBusy>());
+        assert_eq!(f.fs.read("/home/u/Downloads/a.pdf"), Some(b"a".to_vec()));
+
+        drop(held);
+        assert_eq!(organizer.run_once().unwrap().moved.len(), 1);
+    }
+
+    #[test]
+    fn test_run_once_leaves_files_of_a_rule_that_is_not_due() {
+        let mut cfg = docs_config(false);
+        cfg.schedule = Some("0 3 * * sun".into());
+        cfg.rules[0].schedule = Some("0 3 * * sun".into());
+        let f = fixture(cfg);
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+
+        // Monday 10:30: the global schedule is ignored, the rule's is not.
+        let summary = f.organizer.run_once().unwrap();
+
+        assert!(summary.moved.is_empty());
+        assert_eq!(summary.skipped.len(), 1);
+        assert_eq!(summary.skipped[0].reason, SkipReason::OutsideSchedule);
+        assert!(f.fs.exists(Path::new("/home/u/Downloads/a.pdf")));
+    }
+
+    // ── plan ─────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_plan_changes_nothing_and_keeps_targets_apart() {
+        let f = fixture(docs_config(false));
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        f.fs.add_file("/home/u/Docs/a.pdf", b"old".to_vec());
+        let before = f.fs.paths();
+
+        let plan = f.organizer.plan().unwrap();
+
+        assert_eq!(plan.moves.len(), 1);
+        assert_eq!(
+            plan.moves[0].destination,
+            PathBuf::from("/home/u/Docs/a (1).pdf")
+        );
+        assert_eq!(f.fs.paths(), before);
+        assert!(f.seen.lock().unwrap().is_empty());
+        assert!(f.history.entries().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_plan_lists_every_file_left_in_place() {
+        let mut cfg = docs_config(false);
+        cfg.min_age_secs = Some(60);
+        cfg.rules[0].schedule = Some("0 3 * * *".into());
+        let f = fixture(cfg);
+        let now = ManualClock::at_local("2024-01-15 10:30:00").now();
+        let old = now - Duration::from_secs(600);
+        f.fs.add_file_modified("/home/u/Downloads/a.pdf", b"a".to_vec(), old);
+        f.fs.add_file_modified("/home/u/Downloads/b.pdf", b"b".to_vec(), now);
+        f.fs.add_file_modified("/home/u/Downloads/c.zip", b"c".to_vec(), old);
+        f.fs.add_file_modified("/home/u/Downloads/d.iso.part", b"d".to_vec(), old);
+
+        let plan = f.organizer.plan().unwrap();
+
+        let mut skipped: Vec<_> = plan
+            .skipped
+            .iter()
+            .map(|skip| {
+                (
+                    skip.path.file_name().unwrap().to_str().unwrap(),
+                    skip.rule_name.as_deref(),
+                    skip.destination.clone(),
+                    skip.reason,
+                )
+            })
+            .collect();
+        skipped.sort_by_key(|skip| skip.0);
+        assert_eq!(
+            skipped,
+            vec![
+                (
+                    "a.pdf",
+                    Some("Docs"),
+                    Some(PathBuf::from("/home/u/Docs/a.pdf")),
+                    SkipReason::OutsideSchedule
+                ),
+                ("b.pdf", None, None, SkipReason::TooRecent),
+                ("c.zip", None, None, SkipReason::NoMatchingRule),
+                ("d.iso.part", None, None, SkipReason::PartialDownload),
+            ]
+        );
+        assert!(plan.moves.is_empty());
+    }
+
+    // ── cleanup_breadcrumbs ──────────────────────────────────────────────────
+
+    #[test]
+    fn test_cleanup_breadcrumbs_reports_expired_and_dangling() {
+        use crate::breadcrumb::CleanupReason;
+        use std::time::Duration;
+
+        let mut cfg = docs_config(true);
+        cfg.breadcrumb_max_age_days = Some(7);
+        let f = fixture(cfg);
+        let now = ManualClock::at_local("2024-01-15 10:30:00").now();
+        let day = Duration::from_secs(24 * 60 * 60);
+        for name in ["fresh.pdf", "old.pdf"] {
+            f.fs.add_file(format!("/home/u/Docs/{name}"), b"x".to_vec());
+        }
+        let link = |name: &str, age: Duration| {
+            f.fs.add_symlink_modified(
+                format!("{DOWNLOADS}/{name}"),
+                format!("/home/u/Docs/{name}"),
+                now - age,
+            )
+        };
+        link("fresh.pdf", day);
+        link("old.pdf", 10 * day);
+        link("gone.pdf", day);
+
+        let report = f.organizer.cleanup_breadcrumbs().unwrap();
+
+        let mut removed: Vec<_> = report
+            .removed
+            .iter()
+            .map(|r| (r.path.clone(), r.reason, r.rule_name.as_deref()))
+            .collect();
+        removed.sort_by(|a, b| a.0.cmp(&b.0));
+        assert_eq!(
+            removed,
+            vec![
+                (
+                    PathBuf::from("/home/u/Downloads/gone.pdf"),
+                    CleanupReason::Dangling,
+                    Some("Docs")
+                ),
+                (
+                    PathBuf::from("/home/u/Downloads/old.pdf"),
+                    CleanupReason::Expired,
+                    Some("Docs")
+                ),
+            ]
+        );
+        assert!(f
+            .fs
+            .paths()
+            .contains(&PathBuf::from("/home/u/Downloads/fresh.pdf")));
+        assert_eq!(
+            *f.seen.lock().unwrap(),
+            vec!["BreadcrumbRemoved", "BreadcrumbRemoved"]
+        );
+        assert_eq!(f.log.0.lock().unwrap().len(), 2);
+    }
+
+    // ── undo ─────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_undo_restores_last_pass_and_removes_symlink() {
+        let f = fixture(docs_config(true));
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        f.organizer.run_once().unwrap();
+        assert!(f
+            .fs
+            .metadata(Path::new("/home/u/Downloads/a.pdf"))
+            .unwrap()
+            .is_symlink());
+
+        let summary = f.organizer.undo().unwrap();
+
+        assert_eq!(summary.restored.len(), 1);
+        assert!(summary.errors.is_empty());
+        assert_eq!(f.fs.read("/home/u/Downloads/a.pdf"), Some(b"a".to_vec()));
+        assert_eq!(f.fs.read("/home/u/Docs/a.pdf"), None);
+        assert!(f.history.entries().unwrap().is_empty());
+        assert_eq!(
+            f.log.0.lock().unwrap().last().map(String::as_str),
+            Some("restored /home/u/Downloads/a.pdf")
+        );
+        assert_eq!(
+            f.seen.lock().unwrap().last().map(String::as_str),
+            Some("FileRestored")
+        );
+
+        // Nothing left to undo.
+        assert!(f.organizer.undo().unwrap().restored.is_empty());
+    }
+
+    #[test]
+    fn test_undo_keeps_moves_it_cannot_revert() {
+        let f = fixture(docs_config(false));
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        f.fs.add_file("/home/u/Downloads/b.pdf", b"b".to_vec());
+        f.organizer.run_once().unwrap();
+        // A new download took a.pdf's old name.
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"new".to_vec());
+
+        let summary = f.organizer.undo().unwrap();
+
+        assert_eq!(summary.restored.len(), 1);
+        assert_eq!(summary.errors.len(), 1);
+        assert!(summary.errors[0].contains("occupied"));
+        assert_eq!(f.fs.read("/home/u/Downloads/a.pdf"), Some(b"new".to_vec()));
+        assert_eq!(f.fs.read("/home/u/Downloads/b.pdf"), Some(b"b".to_vec()));
+        let entries = f.history.entries().unwrap();
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            entries[0].moves,
+            vec![MoveRecord {
+                source: PathBuf::from("/home/u/Downloads/a.pdf"),
+                destination: PathBuf::from("/home/u/Docs/a.pdf"),
+                rule_name: "Docs".into(),
+            }]
+        );
+    }
+
+    #[test]
+    fn test_undo_that_fails_keeps_the_breadcrumb() {
+        use crate::vfs::{Fault, FsOp};
+
+        let f = fixture(docs_config(true));
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        f.fs.add_file("/home/u/Downloads/b.pdf", b"b".to_vec());
+        f.organizer.run_once().unwrap();
+        f.fs.remove_file(Path::new("/home/u/Docs/a.pdf")).unwrap();
+        f.fs.inject(
+            FsOp::Rename,
+            "/home/u/Docs/b.pdf",
+            Fault::Error(std::io::ErrorKind::PermissionDenied),
+        );
+
+        let summary = f.organizer.undo().unwrap();
+
+        assert!(summary.restored.is_empty());
+        assert_eq!(summary.errors.len(), 2);
+        for name in ["a.pdf", "b.pdf"] {
+            let original = PathBuf::from(format!("/home/u/Downloads/{name}"));
+            assert_eq!(
+                f.fs.read_link(&original).unwrap(),
+                PathBuf::from(format!("/home/u/Docs/{name}"))
+            );
+        }
+        assert_eq!(f.fs.read("/home/u/Docs/b.pdf"), Some(b"b".to_vec()));
+        assert_eq!(f.history.entries().unwrap()[0].moves.len(), 2);
+    }
+
+    #[test]
+    fn test_undo_restores_over_a_hard_link_breadcrumb() {
+        let mut cfg = docs_config(false);
+        cfg.rules[0].breadcrumb = Some(crate::types::BreadcrumbKind::Hardlink);
+        let f = fixture(cfg);
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        f.organizer.run_once().unwrap();
+
+        let summary = f.organizer.undo().unwrap();
+
+        assert_eq!(summary.restored.len(), 1, "{:?}", summary.errors);
+        assert_eq!(f.fs.read("/home/u/Downloads/a.pdf"), Some(b"a".to_vec()));
+        assert_eq!(
+            f.fs.paths()
+                .into_iter()
+                .filter(|p| p.starts_with("/home/u/Docs") || p.to_string_lossy().contains("harbor"))
+                .collect::<Vec<_>>(),
+            vec![PathBuf::from("/home/u/Docs")]
+        );
+    }
+
+    // ── watch ────────────────────────────────────────────────────────────────
+
+    #[test]
+    fn test_watch_records_each_pass() {
+        let f = fixture(docs_config(false));
+        f.fs.add_file("/home/u/Downloads/a.pdf", b"a".to_vec());
+        let running = Arc::new(AtomicBool::new(true));
+        let stop = running.clone();
+        let seen = f.seen.clone();
+
+        std::thread::scope(|scope| {
+            scope.spawn(|| f.organizer.watch(1, &running).unwrap());
+            while !seen.lock().unwrap().iter().any(|k| k == "ScanFinished") {
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            stop.store(false, Ordering::Relaxed);
+        });
+
+        assert_eq!(f.history.entries().unwrap().len(), 1);
+    }
+}
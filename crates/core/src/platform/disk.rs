@@ -0,0 +1,176 @@
+//! Filesystem capacity and file identity queries used for free-space
+//! pre-flight checks and hard link breadcrumbs.
+
+use std::io;
+use std::path::Path;
+
+/// Returns the number of bytes available to the current user on the
+/// filesystem containing `path` (`statvfs` `f_bavail * f_frsize`).
+#[cfg(unix)]
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    use std::ffi::CString;
+    use std::os::unix::ffi::OsStrExt;
+
+    let c_path = CString::new(path.as_os_str().as_bytes())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "path contains a NUL byte"))?;
+    // SAFETY: `statvfs` is plain old data; an all-zero value is valid.
+    let mut stat: libc::statvfs = unsafe { std::mem::zeroed() };
+    // SAFETY: `c_path` is NUL-terminated and `stat` is a valid, writable out-pointer.
+    let rc = unsafe { libc::statvfs(c_path.as_ptr(), &mut stat) };
+    if rc != 0 {
+        return Err(io::Error::last_os_error());
+    }
+    // Field widths differ between platforms (u32 on macOS, u64 on Linux).
+    #[allow(clippy::unnecessary_cast)]
+    let available = (stat.f_bavail as u64).saturating_mul(stat.f_frsize as u64);
+    Ok(available)
+}
+
+/// Returns the number of bytes available to the current user on the volume
+/// containing `path` (`GetDiskFreeSpaceExW`).
+#[cfg(windows)]
+pub fn available_space(path: &Path) -> io::Result<u64> {
+    use std::os::windows::ffi::OsStrExt;
+    use windows_sys::Win32::Storage::FileSystem::GetDiskFreeSpaceExW;
+
+    let wide: Vec<u16> = path
+        .as_os_str()
+        .encode_wide()
+        .chain(std::iter::once(0))
+        .collect();
+    let mut available: u64 = 0;
+    // SAFETY: `wide` is NUL-terminated; the optional out-pointers may be null.
+    let ok = unsafe {
+        GetDiskFreeSpaceExW(
+            wide.as_ptr(),
+            &mut available,
+            std::ptr::null_mut(),
+            std::ptr::null_mut(),
+        )
+    };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(available)
+}
+
+/// Returns `true` when `a` and `b` are on the same filesystem, i.e. a rename
+/// between them is a metadata update rather than a copy. Unknown → `false`.
+#[cfg(unix)]
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    use std::os::unix::fs::MetadataExt;
+
+    match (std::fs::metadata(a), std::fs::metadata(b)) {
+        (Ok(a), Ok(b)) => a.dev() == b.dev(),
+        _ => false,
+    }
+}
+
+/// Returns `true` when `a` and `b` are on the same volume (same drive letter or
+/// UNC share). Unknown → `false`.
+#[cfg(windows)]
+pub fn same_filesystem(a: &Path, b: &Path) -> bool {
+    use std::path::Component;
+
+    let prefix = |p: &Path| {
+        let absolute = std::path::absolute(p).ok()?;
+        match absolute.components().next() {
+            Some(Component::Prefix(prefix)) => {
+                Some(prefix.as_os_str().to_string_lossy().to_ascii_lowercase())
+            }
+            _ => None,
+        }
+    };
+    match (prefix(a), prefix(b)) {
+        (Some(a), Some(b)) => a == b,
+        _ => false,
+    }
+}
+
+/// Identity of a file on disk: two paths are hard links to the same file
+/// when their `volume` and `index` match.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileId {
+    pub volume: u64,
+    pub index: u64,
+    /// Number of hard links (names) the file has.
+    pub links: u64,
+}
+
+/// Device, inode and link count of `path`, following symlinks.
+#[cfg(unix)]
+pub fn file_id(path: &Path) -> io::Result<FileId> {
+    use std::os::unix::fs::MetadataExt;
+
+    let meta = std::fs::metadata(path)?;
+    Ok(FileId {
+        volume: meta.dev(),
+        index: meta.ino(),
+        links: meta.nlink(),
+    })
+}
+
+/// Volume serial number, file index and link count of `path`
+/// (`GetFileInformationByHandle`), following symlinks.
+#[cfg(windows)]
+pub fn file_id(path: &Path) -> io::Result<FileId> {
+    use std::os::windows::io::AsRawHandle;
+    use windows_sys::Win32::Storage::FileSystem::{
+        GetFileInformationByHandle, BY_HANDLE_FILE_INFORMATION,
+    };
+
+    let file = std::fs::File::open(path)?;
+    // SAFETY: plain old data; an all-zero value is valid.
+    let mut info: BY_HANDLE_FILE_INFORMATION = unsafe { std::mem::zeroed() };
+    // SAFETY: `file` owns a valid handle for the duration of the call and
+    // `info` is a valid, writable out-pointer.
+    let ok = unsafe { GetFileInformationByHandle(file.as_raw_handle(), &mut info) };
+    if ok == 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(FileId {
+        volume: u64::from(info.dwVolumeSerialNumber),
+        index: (u64::from(info.nFileIndexHigh) << 32) | u64::from(info.nFileIndexLow),
+        links: u64::from(info.nNumberOfLinks),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn available_space_reports_existing_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert!(available_space(tmp.path()).unwrap() > 0);
+    }
+
+    #[test]
+    fn available_space_errors_for_missing_path() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        assert!(available_space(&tmp.path().join("missing")).is_err());
+    }
+
+    #[test]
+    fn file_id_matches_across_hard_links() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let a = tmp.path().join("a");
+        let b = tmp.path().join("b");
+        std::fs::write(&a, b"x").unwrap();
+        assert_eq!(file_id(&a).unwrap().links, 1);
+
+        std::fs::hard_link(&a, &b).unwrap();
+        let (id_a, id_b) = (file_id(&a).unwrap(), file_id(&b).unwrap());
+        assert_eq!(id_a, id_b);
+        assert_eq!(id_a.links, 2);
+    }
+
+    #[test]
+    fn same_filesystem_within_one_dir() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let nested = tmp.path().join("nested");
+        std::fs::create_dir(&nested).unwrap();
+        assert!(same_filesystem(tmp.path(), &nested));
+        assert!(!same_filesystem(tmp.path(), &tmp.path().join("missing")));
+    }
+}
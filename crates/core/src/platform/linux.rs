@@ -0,0 +1,274 @@
+//! Path resolution on Linux and other XDG desktops.
+//!
+//! Follows the XDG Base Directory spec for Harbor's own files and reads the
+//! Downloads folder from `user-dirs.dirs`, which `xdg-user-dirs-update`
+//! writes with the folder names of the user's language (`~/Téléchargements`,
+//! `~/Descargas`, ...).
+
+use std::ffi::OsString;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Returns the Harbor application data directory (logs, history):
+/// `$XDG_DATA_HOME/harbor`, by default `~/.local/share/harbor`.
+pub fn app_data_dir() -> PathBuf {
+    xdg_base("XDG_DATA_HOME", ".local/share").join("harbor")
+}
+
+/// Returns the Harbor configuration directory:
+/// `$XDG_CONFIG_HOME/harbor`, by default `~/.config/harbor`.
+pub fn config_dir() -> PathBuf {
+    xdg_base("XDG_CONFIG_HOME", ".config").join("harbor")
+}
+
+/// Returns the user's Downloads directory: `XDG_DOWNLOAD_DIR` from
+/// `$XDG_CONFIG_HOME/user-dirs.dirs`, or `~/Downloads` if it is not set there.
+///
+/// Without a home directory, this and the folders above are relative to the
+/// working directory; [`migrate_legacy_files`] reports the missing `HOME`.
+pub fn downloads_dir() -> PathBuf {
+    let home = home_dir().unwrap_or_default();
+    let user_dirs = xdg_base("XDG_CONFIG_HOME", ".config").join("user-dirs.dirs");
+    std::fs::read_to_string(user_dirs)
+        .ok()
+        .and_then(|content| user_dir(&content, "XDG_DOWNLOAD_DIR", &home))
+        .unwrap_or_else(|| home.join("Downloads"))
+}
+
+/// Returns the user's home directory from the `HOME` environment variable.
+/// Fails when `HOME` is unset or not an absolute path.
+pub fn home_dir() -> io::Result<PathBuf> {
+    home_from(std::env::var_os("HOME"))
+}
+
+fn home_from(var: Option<OsString>) -> io::Result<PathBuf> {
+    var.map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "HOME is not set to an absolute path",
+            )
+        })
+}
+
+/// Moves the configuration, activity log, log archives and history that
+/// Harbor kept directly in `$HOME` before it followed the XDG spec. A file
+/// whose new location already exists is left alone.
+pub fn migrate_legacy_files() -> io::Result<Vec<PathBuf>> {
+    migrate_from_home(&home_dir()?, &config_dir(), &app_data_dir())
+}
+
+/// The log, its archives and the history have generic names, so they are
+/// only taken when the old `harbor.downloads.yaml` shows that Harbor wrote
+/// them. The config moves last, so an interrupted migration is retried.
+fn migrate_from_home(home: &Path, config_dir: &Path, data_dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let legacy_config = home.join("harbor.downloads.yaml");
+    if !legacy_config.is_file() {
+        return Ok(Vec::new());
+    }
+    let mut moves = vec![
+        (home.join("recent_moves.log"), data_dir),
+        (home.join("history.jsonl"), data_dir),
+    ];
+    for entry in std::fs::read_dir(home)? {
+        let path = entry?.path();
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name.starts_with("recent_moves.log.") && name.ends_with(".gz") {
+            moves.push((path, data_dir));
+        }
+    }
+    moves.push((legacy_config, config_dir));
+
+    let mut migrated = Vec::new();
+    for (from, dir) in moves {
+        let Some(name) = from.file_name() else {
+            continue;
+        };
+        let to = dir.join(name);
+        if !from.is_file() || to.exists() {
+            continue;
+        }
+        std::fs::create_dir_all(dir)?;
+        // `$XDG_DATA_HOME` may be on another filesystem than `$HOME`.
+        if std::fs::rename(&from, &to).is_err() {
+            std::fs::copy(&from, &to)?;
+            std::fs::remove_file(&from)?;
+        }
+        migrated.push(to);
+    }
+    Ok(migrated)
+}
+
+/// `$var` if it holds an absolute path, `~/<default>` otherwise. The spec
+/// says relative values are invalid and must be ignored.
+fn xdg_base(var: &str, default: &str) -> PathBuf {
+    std::env::var_os(var)
+        .map(PathBuf::from)
+        .filter(|p| p.is_absolute())
+        .unwrap_or_else(|| home_dir().unwrap_or_default().join(default))
+}
+
+/// Looks up `key` in the contents of a `user-dirs.dirs` file.
+///
+/// Values are shell-quoted and either absolute or relative to `$HOME`:
+/// `XDG_DOWNLOAD_DIR="$HOME/Téléchargements"`. A value of `"$HOME"` or
+/// `"$HOME/"` means the folder is disabled, which is treated as not set so
+/// Harbor never organizes the home directory itself.
+fn user_dir(content: &str, key: &str, home: &Path) -> Option<PathBuf> {
+    let value = content.lines().rev().find_map(|line| {
+        let (name, value) = line.trim().split_once('=')?;
+        (name.trim() == key).then(|| unquote(value.trim()))
+    })??;
+    let path = match value.strip_prefix("$HOME") {
+        Some(rest) if rest.trim_matches('/').is_empty() => return None,
+        Some(rest) if rest.starts_with('/') => home.join(rest.trim_start_matches('/')),
+        Some(_) => return None,
+        None if value.starts_with('/') => PathBuf::from(value),
+        None => return None,
+    };
+    Some(path)
+}
+
+/// Strips the double quotes around a value and resolves backslash escapes.
+fn unquote(value: &str) -> Option<String> {
+    let inner = value.strip_prefix('"')?.strip_suffix('"')?;
+    let mut out = String::with_capacity(inner.len());
+    let mut chars = inner.chars();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => out.push(chars.next()?),
+            _ => out.push(ch),
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const USER_DIRS: &str = r#"
+# This file is written by xdg-user-dirs-update
+# If you want to change or add directories, just edit the line you're
+# interested in. All local changes will be retained on the next run.
+XDG_DESKTOP_DIR="$HOME/Bureau"
+XDG_DOWNLOAD_DIR="$HOME/Téléchargements"
+XDG_MUSIC_DIR="/mnt/média/Musique"
+XDG_VIDEOS_DIR="$HOME/"
+XDG_PICTURES_DIR="$HOME/My \"Pictures\""
+"#;
+
+    #[test]
+    fn user_dir_reads_localized_and_absolute_folders() {
+        let home = Path::new("/home/édouard");
+        assert_eq!(
+            user_dir(USER_DIRS, "XDG_DOWNLOAD_DIR", home),
+            Some(PathBuf::from("/home/édouard/Téléchargements"))
+        );
+        assert_eq!(
+            user_dir(USER_DIRS, "XDG_MUSIC_DIR", home),
+            Some(PathBuf::from("/mnt/média/Musique"))
+        );
+        assert_eq!(
+            user_dir(USER_DIRS, "XDG_PICTURES_DIR", home),
+            Some(PathBuf::from("/home/édouard/My \"Pictures\""))
+        );
+    }
+
+    #[test]
+    fn user_dir_ignores_disabled_missing_and_relative_entries() {
+        let home = Path::new("/home/u");
+        assert_eq!(user_dir(USER_DIRS, "XDG_VIDEOS_DIR", home), None);
+        assert_eq!(user_dir(USER_DIRS, "XDG_TEMPLATES_DIR", home), None);
+        assert_eq!(
+            user_dir("XDG_DOWNLOAD_DIR=\"Downloads\"", "XDG_DOWNLOAD_DIR", home),
+            None
+        );
+        assert_eq!(
+            user_dir("XDG_DOWNLOAD_DIR=$HOME/dl", "XDG_DOWNLOAD_DIR", home),
+            None
+        );
+    }
+
+    #[test]
+    fn legacy_files_move_out_of_home_once() {
+        let root = tempfile::tempdir().unwrap();
+        let home = root.path().join("home");
+        let config = home.join(".config/harbor");
+        let data = home.join(".local/share/harbor");
+        std::fs::create_dir_all(&home).unwrap();
+        for name in [
+            "harbor.downloads.yaml",
+            "recent_moves.log",
+            "recent_moves.log.20240115-103000-000.gz",
+            "history.jsonl",
+            "notes.txt",
+        ] {
+            std::fs::write(home.join(name), name).unwrap();
+        }
+
+        let mut migrated = migrate_from_home(&home, &config, &data).unwrap();
+        migrated.sort();
+
+        assert_eq!(
+            migrated,
+            vec![
+                config.join("harbor.downloads.yaml"),
+                data.join("history.jsonl"),
+                data.join("recent_moves.log"),
+                data.join("recent_moves.log.20240115-103000-000.gz"),
+            ]
+        );
+        assert_eq!(
+            std::fs::read_to_string(config.join("harbor.downloads.yaml")).unwrap(),
+            "harbor.downloads.yaml"
+        );
+        assert!(!home.join("harbor.downloads.yaml").exists());
+        assert!(home.join("notes.txt").exists());
+
+        // A file already at its new location is never overwritten.
+        std::fs::write(home.join("harbor.downloads.yaml"), "stale").unwrap();
+        assert!(migrate_from_home(&home, &config, &data).unwrap().is_empty());
+        assert_eq!(
+            std::fs::read_to_string(config.join("harbor.downloads.yaml")).unwrap(),
+            "harbor.downloads.yaml"
+        );
+    }
+
+    #[test]
+    fn files_with_generic_names_stay_without_the_old_config() {
+        let root = tempfile::tempdir().unwrap();
+        let home = root.path().join("home");
+        let data = home.join(".local/share/harbor");
+        std::fs::create_dir_all(&home).unwrap();
+        for name in ["recent_moves.log", "recent_moves.log.1.gz", "history.jsonl"] {
+            std::fs::write(home.join(name), name).unwrap();
+        }
+
+        let migrated = migrate_from_home(&home, &home.join(".config/harbor"), &data).unwrap();
+
+        assert!(migrated.is_empty());
+        assert!(home.join("history.jsonl").exists());
+        assert!(!data.exists());
+    }
+
+    #[test]
+    fn missing_or_relative_home_is_an_error() {
+        assert_eq!(
+            home_from(Some("/home/u".into())).unwrap(),
+            PathBuf::from("/home/u")
+        );
+        for var in [None, Some("home/u".into()), Some("".into())] {
+            let err = home_from(var).unwrap_err();
+            assert_eq!(err.kind(), io::ErrorKind::NotFound);
+        }
+    }
+
+    #[test]
+    fn app_dirs_end_with_harbor() {
+        assert!(app_data_dir().ends_with("harbor"));
+        assert!(config_dir().ends_with("harbor"));
+        assert_ne!(downloads_dir(), home_dir().unwrap());
+    }
+}
@@ -3,22 +3,36 @@ use std::path::PathBuf;
 /// Returns the Harbor application data directory on macOS:
 /// `$HOME/Library/Application Support/Harbor`
 pub fn app_data_dir() -> PathBuf {
-    home_dir().join("Library/Application Support/Harbor")
+    home().join("Library/Application Support/Harbor")
+}
+
+/// Returns the Harbor configuration directory, the same as [`app_data_dir`].
+pub fn config_dir() -> PathBuf {
+    app_data_dir()
 }
 
 /// Returns the user's Downloads directory on macOS:
 /// `$HOME/Downloads`
 pub fn downloads_dir() -> PathBuf {
-    home_dir().join("Downloads")
+    home().join("Downloads")
 }
 
 /// Returns the user's home directory from the `HOME` environment variable.
-pub fn home_dir() -> PathBuf {
+pub fn home_dir() -> std::io::Result<PathBuf> {
+    Ok(home())
+}
+
+fn home() -> PathBuf {
     std::env::var("HOME")
         .map(PathBuf::from)
         .unwrap_or_else(|_| PathBuf::from("/Users/Shared"))
 }
 
+/// Nothing to migrate: Harbor has always used [`app_data_dir`] here.
+pub fn migrate_legacy_files() -> std::io::Result<Vec<PathBuf>> {
+    Ok(Vec::new())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -2,6 +2,9 @@
 //!
 //! Dispatch to OS-specific implementations via `#[cfg(target_os)]`.
 
+mod disk;
+#[cfg(not(any(target_os = "macos", target_os = "windows")))]
+mod linux;
 #[cfg(target_os = "macos")]
 mod macos;
 #[cfg(target_os = "windows")]
@@ -9,21 +12,37 @@ mod windows;
 
 use std::path::PathBuf;
 
-/// Returns the Harbor application data directory (config, logs, state).
+pub use disk::{available_space, file_id, same_filesystem, FileId};
+
+/// Returns the Harbor application data directory (logs, state).
 pub fn app_data_dir() -> PathBuf {
     os::app_data_dir()
 }
 
+/// Returns the directory holding Harbor's configuration file. Same as
+/// [`app_data_dir`] except on XDG desktops.
+pub fn config_dir() -> PathBuf {
+    os::config_dir()
+}
+
 /// Returns the user's Downloads directory.
 pub fn downloads_dir() -> PathBuf {
     os::downloads_dir()
 }
 
-/// Returns the user's home directory.
-pub fn home_dir() -> PathBuf {
+/// Returns the user's home directory. Fails on XDG desktops when `HOME` is
+/// unset, rather than guessing a folder Harbor might then organize.
+pub fn home_dir() -> std::io::Result<PathBuf> {
     os::home_dir()
 }
 
+/// Moves files an older Harbor kept elsewhere into [`config_dir`] and
+/// [`app_data_dir`], unless they already exist there, and returns their new
+/// paths. Front-ends call it once at startup, before reading the config.
+pub fn migrate_legacy_files() -> std::io::Result<Vec<PathBuf>> {
+    os::migrate_legacy_files()
+}
+
 // Platform-specific module selected at compile time.
 #[cfg(target_os = "macos")]
 mod os {
@@ -35,26 +54,7 @@ mod os {
 }
 #[cfg(not(any(target_os = "macos", target_os = "windows")))]
 mod os {
-    use std::path::PathBuf;
-    pub fn app_data_dir() -> PathBuf {
-        dirs_next().unwrap_or_else(|| PathBuf::from("."))
-    }
-    pub fn downloads_dir() -> PathBuf {
-        dirs_next().unwrap_or_else(|| PathBuf::from("."))
-    }
-    pub fn home_dir() -> PathBuf {
-        dirs_next().unwrap_or_else(|| PathBuf::from("."))
-    }
-    fn dirs_next() -> Option<PathBuf> {
-        #[cfg(unix)]
-        {
-            std::env::var("HOME").ok().map(PathBuf::from)
-        }
-        #[cfg(not(unix))]
-        {
-            None
-        }
-    }
+    pub use super::linux::*;
 }
 
 #[cfg(test)]
@@ -70,6 +70,12 @@ mod tests {
         );
     }
 
+    #[test]
+    fn config_dir_is_absolute() {
+        let dir = config_dir();
+        assert!(dir.is_absolute(), "config_dir should be absolute: {dir:?}");
+    }
+
     #[test]
     fn downloads_dir_is_absolute() {
         let dir = downloads_dir();
@@ -81,7 +87,7 @@ mod tests {
 
     #[test]
     fn home_dir_is_absolute() {
-        let dir = home_dir();
+        let dir = home_dir().unwrap();
         assert!(dir.is_absolute(), "home_dir should be absolute: {dir:?}");
     }
 }
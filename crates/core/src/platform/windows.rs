@@ -8,19 +8,33 @@ pub fn app_data_dir() -> PathBuf {
         .unwrap_or_else(|_| PathBuf::from("C:\\Harbor"))
 }
 
+/// Returns the Harbor configuration directory, the same as [`app_data_dir`].
+pub fn config_dir() -> PathBuf {
+    app_data_dir()
+}
+
 /// Returns the user's Downloads directory on Windows:
 /// `%USERPROFILE%\Downloads`
 pub fn downloads_dir() -> PathBuf {
-    home_dir().join("Downloads")
+    home().join("Downloads")
 }
 
 /// Returns the user's home directory from the `USERPROFILE` environment variable.
-pub fn home_dir() -> PathBuf {
+pub fn home_dir() -> std::io::Result<PathBuf> {
+    Ok(home())
+}
+
+fn home() -> PathBuf {
     std::env::var("USERPROFILE")
         .map(PathBuf::from)
         .unwrap_or_else(|_| PathBuf::from("C:\\Users\\Public"))
 }
 
+/// Nothing to migrate: Harbor has always used [`app_data_dir`] here.
+pub fn migrate_legacy_files() -> std::io::Result<Vec<PathBuf>> {
+    Ok(Vec::new())
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -0,0 +1,568 @@
+//! Retention policies for rule target folders.
+//!
+//! Files that Harbor moves into a rule's `target_dir` are otherwise never
+//! revisited. [`enforce_retention`] walks every target folder whose rule has a
+//! [`RetentionPolicy`] and deletes (or trashes) the rule's files that are too
+//! old, or that push it over its file-count or total-size budget.
+
+use crate::downloads::{rule_matcher, DownloadsConfig};
+use crate::template;
+use crate::types::{RetentionAction, RetentionPolicy};
+use crate::vfs::{normalize_lexically, FileMeta, FileSystem, RealFs};
+use anyhow::{Context, Result};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// How often the watcher enforces retention when `retention_interval_secs` is unset.
+pub const DEFAULT_RETENTION_INTERVAL_SECS: u64 = 60 * 60;
+
+/// Why a file was purged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PurgeReason {
+    /// Last modified more than `max_age_days` ago.
+    Age { max_age_days: u64 },
+    /// The folder already holds `max_files` newer files.
+    Count { max_files: usize },
+    /// Keeping the file would exceed `max_total_bytes`.
+    Size { max_total_bytes: u64 },
+}
+
+impl fmt::Display for PurgeReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Age { max_age_days } => write!(f, "older than {max_age_days} days"),
+            Self::Count { max_files } => write!(f, "exceeds {max_files} files"),
+            Self::Size { max_total_bytes } => write!(f, "exceeds {max_total_bytes} bytes"),
+        }
+    }
+}
+
+/// A single file removed by a retention pass.
+#[derive(Debug, Clone)]
+pub struct PurgeResult {
+    pub path: PathBuf,
+    pub rule_name: String,
+    pub action: RetentionAction,
+    pub reason: PurgeReason,
+    pub size_bytes: u64,
+}
+
+impl PurgeResult {
+    /// Short human-readable description, e.g. `trashed: older than 30 days`.
+    pub fn describe(&self) -> String {
+        let verb = match self.action {
+            RetentionAction::Delete => "deleted",
+            RetentionAction::Trash => "trashed",
+        };
+        format!("{verb}: {}", self.reason)
+    }
+}
+
+/// Summary returned by [`enforce_retention`].
+#[derive(Debug, Default)]
+pub struct RetentionSummary {
+    pub purged: Vec<PurgeResult>,
+    pub errors: Vec<String>,
+}
+
+/// Returns `true` if any enabled rule carries a retention policy.
+pub fn has_retention(cfg: &DownloadsConfig) -> bool {
+    cfg.rules
+        .iter()
+        .any(|r| r.enabled && r.retention.as_ref().is_some_and(|p| !p.is_unbounded()))
+}
+
+/// Applies every enabled rule's [`RetentionPolicy`] to its `target_dir`.
+///
+/// Only regular files directly inside the target folder that the rule
+/// matches (by extension, pattern and size) are considered and counted
+/// against the limits. Files the user put there by hand, files of other
+/// rules sharing the folder, sub-folders and symlinks are left alone, and so
+/// is a file whose name `rename_to` changed so the pattern no longer matches. A rule whose target folder is the
+/// download folder itself is skipped so a misconfigured policy can never purge
+/// unsorted downloads. Rules with placeholders in `target_dir` are skipped
+/// and reported: their files are spread over folders that may hold files of
+/// other rules or the user's own. Hard-link breadcrumbs of a purged file are removed
+/// from the download folder with it.
+pub fn enforce_retention(cfg: &DownloadsConfig) -> Result<RetentionSummary> {
+    enforce_retention_at(&RealFs, cfg, SystemTime::now())
+}
+
+pub(crate) fn enforce_retention_at(
+    fs: &dyn FileSystem,
+    cfg: &DownloadsConfig,
+    now: SystemTime,
+) -> Result<RetentionSummary> {
+    let mut summary = RetentionSummary::default();
+    let download_dir = Path::new(&cfg.download_dir);
+
+    for rule in cfg.rules.iter().filter(|r| r.enabled) {
+        let Some(policy) = rule.retention.as_ref().filter(|p| !p.is_unbounded()) else {
+            continue;
+        };
+        if rule.target_dir.trim().is_empty() {
+            continue;
+        }
+        if template::has_placeholders(&rule.target_dir) {
+            summary.errors.push(format!(
+                "Retention skipped for rule '{}': target folder has placeholders",
+                rule.name
+            ));
+            continue;
+        }
+        let target_dir = PathBuf::from(&rule.target_dir);
+        if !fs.metadata(&target_dir).is_ok_and(|meta| meta.is_dir()) {
+            continue;
+        }
+        if normalize_lexically(&target_dir) == normalize_lexically(download_dir)
+            || fs.same_file(&target_dir, download_dir)
+        {
+            summary.errors.push(format!(
+                "Retention skipped for rule '{}': target folder is the download folder",
+                rule.name
+            ));
+            continue;
+        }
+
+        let matches = rule_matcher(rule);
+        let files = list_files_newest_first(fs, &target_dir, &matches)?;
+        for (path, size, reason) in select_purges(files, policy, now) {
+            // Looked up first, while the link count still gives them away.
+            let breadcrumbs = hard_link_breadcrumbs(fs, &path, download_dir);
+            match fs.purge(&path, policy.action) {
+                Ok(()) => {
+                    for breadcrumb in breadcrumbs {
+                        if let Err(e) = fs.remove_file(&breadcrumb) {
+                            summary.errors.push(format!(
+                                "Failed to remove breadcrumb '{}': {e}",
+                                breadcrumb.display()
+                            ));
+                        }
+                    }
+                    summary.purged.push(PurgeResult {
+                        path,
+                        rule_name: rule.name.clone(),
+                        action: policy.action,
+                        reason,
+                        size_bytes: size,
+                    });
+                }
+                Err(e) => summary
+                    .errors
+                    .push(format!("Failed to purge '{}': {e}", path.display())),
+            }
+        }
+    }
+    Ok(summary)
+}
+
+/// Lists the regular files in `dir` that `matches` accepts as
+/// `(path, size, modified)`, newest first.
+fn list_files_newest_first(
+    fs: &dyn FileSystem,
+    dir: &Path,
+    matches: &dyn Fn(&Path, &FileMeta) -> bool,
+) -> Result<Vec<(PathBuf, u64, SystemTime)>> {
+    let mut files = Vec::new();
+    for path in fs
+        .read_dir(dir)
+        .with_context(|| format!("list {}", dir.display()))?
+    {
+        let Ok(meta) = fs.metadata(&path) else {
+            continue;
+        };
+        if !meta.is_file() || !matches(&path, &meta) {
+            continue;
+        }
+        let modified = meta.modified.unwrap_or(SystemTime::UNIX_EPOCH);
+        files.push((path, meta.len, modified));
+    }
+    files.sort_by(|a, b| b.2.cmp(&a.2).then_with(|| a.0.cmp(&b.0)));
+    Ok(files)
+}
+
+/// Walks `files` (newest first) and returns the ones that violate `policy`.
+///
+/// Count and size budgets are filled by the newest files; once a file does not
+/// fit, every older file is purged as well so the budget never keeps old files
+/// at the expense of newer ones.
+fn select_purges(
+    files: Vec<(PathBuf, u64, SystemTime)>,
+    policy: &RetentionPolicy,
+    now: SystemTime,
+) -> Vec<(PathBuf, u64, PurgeReason)> {
+    let max_age = policy
+        .max_age_days
+        .map(|days| Duration::from_secs(days.saturating_mul(24 * 60 * 60)));
+    let mut kept_files = 0usize;
+    let mut kept_bytes = 0u64;
+    let mut overflow: Option<PurgeReason> = None;
+    let mut purges = Vec::new();
+
+    for (path, size, modified) in files {
+        let age = now.duration_since(modified).unwrap_or(Duration::ZERO);
+        if let (Some(limit), Some(days)) = (max_age, policy.max_age_days) {
+            if age > limit {
+                purges.push((path, size, PurgeReason::Age { max_age_days: days }));
+                continue;
+            }
+        }
+        if overflow.is_none() {
+            if let Some(max_files) = policy.max_files.filter(|max| kept_files >= *max) {
+                overflow = Some(PurgeReason::Count { max_files });
+            } else if let Some(max_total_bytes) = policy
+                .max_total_bytes
+                .filter(|max| kept_bytes.saturating_add(size) > *max)
+            {
+                overflow = Some(PurgeReason::Size { max_total_bytes });
+            }
+        }
+        match overflow {
+            Some(reason) => purges.push((path, size, reason)),
+            None => {
+                kept_files += 1;
+                kept_bytes = kept_bytes.saturating_add(size);
+            }
+        }
+    }
+    purges
+}
+
+/// Hard links to `path` directly inside the download folder. Once `path` is
+/// purged they no longer look like breadcrumbs, and the next pass would file
+/// them again.
+fn hard_link_breadcrumbs(fs: &dyn FileSystem, path: &Path, download_dir: &Path) -> Vec<PathBuf> {
+    if !fs.metadata(path).is_ok_and(|meta| meta.links > 1) {
+        return Vec::new();
+    }
+    fs.read_dir(download_dir)
+        .unwrap_or_default()
+        .into_iter()
+        .filter(|candidate| {
+            fs.metadata(candidate).is_ok_and(|meta| meta.is_file()) && fs.same_file(path, candidate)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Rule;
+    use crate::vfs::MemoryFs;
+    use std::fs;
+    use tempfile::TempDir;
+
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+
+    fn write_aged(dir: &Path, name: &str, size: usize, age: Duration, now: SystemTime) -> PathBuf {
+        let path = dir.join(name);
+        fs::write(&path, vec![b'x'; size]).unwrap();
+        let file = fs::File::options().write(true).open(&path).unwrap();
+        file.set_modified(now - age).unwrap();
+        path
+    }
+
+    fn config(root: &TempDir, policy: RetentionPolicy) -> DownloadsConfig {
+        let dl = root.path().join("Downloads");
+        let target = root.path().join("Installers");
+        fs::create_dir_all(&dl).unwrap();
+        fs::create_dir_all(&target).unwrap();
+        DownloadsConfig {
+            download_dir: dl.to_string_lossy().to_string(),
+            rules: vec![Rule {
+                name: "Installers".into(),
+                extensions: Some(vec!["exe".into()]),
+                target_dir: target.to_string_lossy().to_string(),
+                retention: Some(policy),
+                ..Default::default()
+            }],
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_retention_purges_by_age() {
+        let root = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let cfg = config(
+            &root,
+            RetentionPolicy {
+                max_age_days: Some(30),
+                action: RetentionAction::Delete,
+                ..Default::default()
+            },
+        );
+        let target = root.path().join("Installers");
+        let old = write_aged(&target, "old.exe", 10, DAY * 40, now);
+        let fresh = write_aged(&target, "fresh.exe", 10, DAY * 2, now);
+
+        let summary = enforce_retention_at(&RealFs, &cfg, now).unwrap();
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+        assert_eq!(summary.purged.len(), 1);
+        assert_eq!(summary.purged[0].path, old);
+        assert_eq!(summary.purged[0].rule_name, "Installers");
+        assert_eq!(summary.purged[0].describe(), "deleted: older than 30 days");
+        assert!(!old.exists());
+        assert!(fresh.exists());
+    }
+
+    #[test]
+    fn test_retention_purges_through_the_file_system() {
+        use crate::vfs::{Fault, FsOp};
+
+        let now = SystemTime::now();
+        let fs = MemoryFs::new();
+        fs.add_dir("/home/u/Downloads");
+        for name in ["a.exe", "b.exe"] {
+            fs.add_file_modified(
+                format!("/home/u/Installers/{name}"),
+                b"x".to_vec(),
+                now - DAY * 40,
+            );
+        }
+        fs.add_file_modified("/home/u/Installers/new.exe", b"x".to_vec(), now);
+        fs.inject(
+            FsOp::Purge,
+            "/home/u/Installers/b.exe",
+            Fault::Error(std::io::ErrorKind::PermissionDenied),
+        );
+        let cfg = DownloadsConfig {
+            download_dir: "/home/u/Downloads".into(),
+            rules: vec![Rule {
+                name: "Installers".into(),
+                extensions: Some(vec!["exe".into()]),
+                target_dir: "/home/u/Installers".into(),
+                retention: Some(RetentionPolicy {
+                    max_age_days: Some(30),
+                    action: RetentionAction::Trash,
+                    ..Default::default()
+                }),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+
+        let summary = enforce_retention_at(&fs, &cfg, now).unwrap();
+
+        assert_eq!(summary.purged.len(), 1);
+        assert_eq!(summary.purged[0].describe(), "trashed: older than 30 days");
+        assert_eq!(
+            fs.trashed(),
+            vec![PathBuf::from("/home/u/Installers/a.exe")]
+        );
+        assert_eq!(summary.errors.len(), 1);
+        assert!(summary.errors[0].starts_with("Failed to purge '/home/u/Installers/b.exe'"));
+        assert!(fs.exists(Path::new("/home/u/Installers/b.exe")));
+        assert!(fs.exists(Path::new("/home/u/Installers/new.exe")));
+    }
+
+    #[test]
+    fn test_retention_leaves_files_the_rule_does_not_match() {
+        let root = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let mut cfg = config(
+            &root,
+            RetentionPolicy {
+                max_files: Some(1),
+                action: RetentionAction::Delete,
+                ..Default::default()
+            },
+        );
+        // A second rule files its installers into the same folder.
+        cfg.rules.push(Rule {
+            name: "Packages".into(),
+            extensions: Some(vec!["msi".into()]),
+            target_dir: cfg.rules[0].target_dir.clone(),
+            ..Default::default()
+        });
+        let target = root.path().join("Installers");
+        let old = write_aged(&target, "old.exe", 1, DAY * 3, now);
+        let new = write_aged(&target, "new.exe", 1, DAY, now);
+        let notes = write_aged(&target, "notes.txt", 1, DAY * 90, now);
+        let package = write_aged(&target, "tool.msi", 1, DAY * 90, now);
+
+        let summary = enforce_retention_at(&RealFs, &cfg, now).unwrap();
+
+        let purged: Vec<_> = summary.purged.iter().map(|p| p.path.clone()).collect();
+        assert_eq!(purged, vec![old]);
+        assert!(new.exists() && notes.exists() && package.exists());
+    }
+
+    #[test]
+    fn test_retention_skips_templated_target_folders() {
+        let root = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let mut cfg = config(
+            &root,
+            RetentionPolicy {
+                max_age_days: Some(30),
+                action: RetentionAction::Delete,
+                ..Default::default()
+            },
+        );
+        let vendor = root.path().join("Installers").join("acme");
+        fs::create_dir_all(&vendor).unwrap();
+        let old = write_aged(&vendor, "old.exe", 10, DAY * 40, now);
+        cfg.rules[0].target_dir = format!("{}/{{vendor}}", cfg.rules[0].target_dir);
+
+        let summary = enforce_retention_at(&RealFs, &cfg, now).unwrap();
+
+        assert!(summary.purged.is_empty());
+        assert_eq!(
+            summary.errors,
+            vec!["Retention skipped for rule 'Installers': target folder has placeholders"]
+        );
+        assert!(old.exists());
+    }
+
+    #[test]
+    fn test_retention_removes_hard_link_breadcrumbs_of_purged_files() {
+        let root = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let cfg = config(
+            &root,
+            RetentionPolicy {
+                max_age_days: Some(30),
+                action: RetentionAction::Delete,
+                ..Default::default()
+            },
+        );
+        let dl = root.path().join("Downloads");
+        let target = root.path().join("Installers");
+        let old = write_aged(&target, "old.exe", 10, DAY * 40, now);
+        let fresh = write_aged(&target, "fresh.exe", 10, DAY * 2, now);
+        fs::hard_link(&old, dl.join("old.exe")).unwrap();
+        fs::hard_link(&fresh, dl.join("fresh.exe")).unwrap();
+        fs::write(dl.join("other.exe"), vec![b'x'; 10]).unwrap();
+
+        let summary = enforce_retention_at(&RealFs, &cfg, now).unwrap();
+
+        assert!(summary.errors.is_empty(), "{:?}", summary.errors);
+        assert_eq!(summary.purged.len(), 1);
+        assert!(!old.exists());
+        assert!(!dl.join("old.exe").exists());
+        assert!(dl.join("fresh.exe").exists());
+        assert!(dl.join("other.exe").exists());
+    }
+
+    #[test]
+    fn test_retention_keeps_newest_files_within_count() {
+        let root = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let cfg = config(
+            &root,
+            RetentionPolicy {
+                max_files: Some(2),
+                action: RetentionAction::Delete,
+                ..Default::default()
+            },
+        );
+        let target = root.path().join("Installers");
+        let a = write_aged(&target, "a.exe", 1, DAY * 3, now);
+        let b = write_aged(&target, "b.exe", 1, DAY * 2, now);
+        let c = write_aged(&target, "c.exe", 1, DAY, now);
+
+        let summary = enforce_retention_at(&RealFs, &cfg, now).unwrap();
+        assert_eq!(summary.purged.len(), 1);
+        assert_eq!(
+            summary.purged[0].reason,
+            PurgeReason::Count { max_files: 2 }
+        );
+        assert!(!a.exists());
+        assert!(b.exists() && c.exists());
+    }
+
+    #[test]
+    fn test_retention_caps_total_size() {
+        let root = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let cfg = config(
+            &root,
+            RetentionPolicy {
+                max_total_bytes: Some(150),
+                action: RetentionAction::Delete,
+                ..Default::default()
+            },
+        );
+        let target = root.path().join("Installers");
+        let oldest = write_aged(&target, "oldest.exe", 10, DAY * 3, now);
+        let older = write_aged(&target, "older.exe", 100, DAY * 2, now);
+        let newest = write_aged(&target, "newest.exe", 100, DAY, now);
+
+        let summary = enforce_retention_at(&RealFs, &cfg, now).unwrap();
+        // Once a file does not fit, older files go too even if they are small.
+        let purged: Vec<_> = summary.purged.iter().map(|p| p.path.clone()).collect();
+        assert_eq!(purged, vec![older.clone(), oldest.clone()]);
+        assert!(newest.exists());
+        assert_eq!(summary.purged[0].size_bytes, 100);
+    }
+
+    #[test]
+    fn test_retention_ignores_subdirectories_and_symlinks() {
+        let root = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let cfg = config(
+            &root,
+            RetentionPolicy {
+                max_files: Some(0),
+                action: RetentionAction::Delete,
+                ..Default::default()
+            },
+        );
+        let target = root.path().join("Installers");
+        fs::create_dir(target.join("nested")).unwrap();
+        let file = write_aged(&target, "a.exe", 1, DAY, now);
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(&file, target.join("link.exe")).unwrap();
+
+        let summary = enforce_retention_at(&RealFs, &cfg, now).unwrap();
+        assert_eq!(summary.purged.len(), 1);
+        assert!(!file.exists());
+        assert!(target.join("nested").is_dir());
+        #[cfg(unix)]
+        assert!(fs::symlink_metadata(target.join("link.exe")).is_ok());
+    }
+
+    #[test]
+    fn test_retention_never_touches_download_dir() {
+        let root = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let mut cfg = config(
+            &root,
+            RetentionPolicy {
+                max_files: Some(0),
+                action: RetentionAction::Delete,
+                ..Default::default()
+            },
+        );
+        cfg.rules[0].target_dir = cfg.download_dir.clone();
+        let file = write_aged(Path::new(&cfg.download_dir), "a.exe", 1, DAY, now);
+
+        let summary = enforce_retention_at(&RealFs, &cfg, now).unwrap();
+        assert!(summary.purged.is_empty());
+        assert_eq!(summary.errors.len(), 1);
+        assert!(file.exists());
+    }
+
+    #[test]
+    fn test_retention_skips_disabled_and_unbounded_rules() {
+        let root = TempDir::new().unwrap();
+        let now = SystemTime::now();
+        let mut cfg = config(&root, RetentionPolicy::default());
+        let file = write_aged(&root.path().join("Installers"), "a.exe", 1, DAY * 90, now);
+        assert!(!has_retention(&cfg));
+
+        cfg.rules[0].retention = Some(RetentionPolicy {
+            max_age_days: Some(1),
+            ..Default::default()
+        });
+        cfg.rules[0].enabled = false;
+        assert!(!has_retention(&cfg));
+        assert!(enforce_retention_at(&RealFs, &cfg, now)
+            .unwrap()
+            .purged
+            .is_empty());
+        assert!(file.exists());
+    }
+}
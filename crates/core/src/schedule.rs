@@ -0,0 +1,421 @@
+//! Cron-style schedules for the watcher and for single rules.
+//!
+//! An expression has the five classic fields, separated by spaces:
+//!
+//! ```text
+//! minute (0-59)  hour (0-23)  day of month (1-31)  month (1-12)  day of week (0-7)
+//! ```
+//!
+//! Each field is `*`, a value, a range `a-b`, a step `*/n` or `a-b/n`, or a
+//! comma-separated list of those. Months and weekdays can be written as
+//! `jan`..`dec` and `sun`..`sat`; both `0` and `7` are Sunday. As in cron,
+//! when both the day of month and the day of week are restricted, a day
+//! matching either one counts. `@hourly`, `@daily`, `@weekly`, `@monthly` and
+//! `@yearly` are accepted as shorthands.
+//!
+//! A schedule is *due* during every minute it matches, and once right after
+//! a matching minute that passed while no pass ran. `* 9-17 * * mon-fri`
+//! therefore describes a window (work hours), while `0 3 * * sun` lets a
+//! rule act in the pass at Sunday 03:00 or the first one after it.
+//!
+//! Quiet hours are the opposite: daily `start`–`end` windows, optionally
+//! limited to some weekdays, during which the watcher does nothing at all.
+
+use crate::types::QuietHours;
+use anyhow::{bail, Context, Result};
+use chrono::{DateTime, Datelike, Local, NaiveDate, TimeDelta, TimeZone, Timelike};
+use std::time::SystemTime;
+
+/// How far ahead [`Schedule::next_after`] looks: long enough for
+/// `0 0 29 2 mon` (February 29th on a Monday) to come around.
+const SEARCH_DAYS: u32 = 366 * 28;
+
+const MONTHS: [&str; 12] = [
+    "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+];
+const WEEKDAYS: [&str; 7] = ["sun", "mon", "tue", "wed", "thu", "fri", "sat"];
+
+/// A parsed cron expression.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Schedule {
+    expression: String,
+    minutes: u64,
+    hours: u32,
+    days: u32,
+    months: u16,
+    weekdays: u8,
+    /// The day-of-month field was `*`.
+    any_day: bool,
+    /// The day-of-week field was `*`.
+    any_weekday: bool,
+}
+
+impl Schedule {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let trimmed = expression.trim();
+        let expanded = match trimmed.to_ascii_lowercase().as_str() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            _ => trimmed,
+        };
+        let fields: Vec<&str> = expanded.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields[..] else {
+            bail!(
+                "cron expression \"{trimmed}\" needs 5 fields (minute hour day month weekday), found {}",
+                fields.len()
+            );
+        };
+        let context = || format!("invalid cron expression \"{trimmed}\"");
+        // Sunday is both 0 and 7; fold 7 onto 0.
+        let weekdays = parse_field(weekday, 0, 7, &WEEKDAYS).with_context(context)?;
+        let weekdays = (weekdays | (weekdays >> 7)) & 0x7f;
+        Ok(Self {
+            expression: trimmed.to_string(),
+            minutes: parse_field(minute, 0, 59, &[]).with_context(context)?,
+            hours: parse_field(hour, 0, 23, &[]).with_context(context)? as u32,
+            days: parse_field(day, 1, 31, &[]).with_context(context)? as u32,
+            months: parse_field(month, 1, 12, &MONTHS).with_context(context)? as u16,
+            weekdays: weekdays as u8,
+            any_day: day == "*",
+            any_weekday: weekday == "*",
+        })
+    }
+
+    /// The expression as written.
+    pub fn expression(&self) -> &str {
+        &self.expression
+    }
+
+    /// Whether the minute containing `at` matches.
+    pub fn matches(&self, at: DateTime<Local>) -> bool {
+        self.day_matches(at.date_naive())
+            && self.hours & (1 << at.hour()) != 0
+            && self.minutes & (1 << at.minute()) != 0
+    }
+
+    /// The start of the first matching minute after `after`, if there is one
+    /// within the next 28 years.
+    pub fn next_after(&self, after: DateTime<Local>) -> Option<DateTime<Local>> {
+        let start = after
+            .naive_local()
+            .with_second(0)?
+            .with_nanosecond(0)?
+            .checked_add_signed(TimeDelta::minutes(1))?;
+        let mut date = start.date();
+        for _ in 0..SEARCH_DAYS {
+            if self.day_matches(date) {
+                for hour in (0..24).filter(|h| self.hours & (1 << h) != 0) {
+                    for minute in (0..60).filter(|m| self.minutes & (1 << m) != 0) {
+                        let Some(naive) = date.and_hms_opt(hour, minute, 0) else {
+                            continue;
+                        };
+                        if naive < start {
+                            continue;
+                        }
+                        // Minutes skipped by a DST change do not exist.
+                        if let Some(at) = Local.from_local_datetime(&naive).earliest() {
+                            return Some(at);
+                        }
+                    }
+                }
+            }
+            date = date.succ_opt()?;
+        }
+        None
+    }
+
+    /// Whether a pass at `now` should act: the current minute matches, or a
+    /// matching minute passed since the previous pass at `since`.
+    pub fn is_due(&self, since: Option<SystemTime>, now: SystemTime) -> bool {
+        let now = DateTime::<Local>::from(now);
+        self.matches(now)
+            || since.is_some_and(|since| {
+                self.next_after(DateTime::from(since))
+                    .is_some_and(|next| next <= now)
+            })
+    }
+
+    fn day_matches(&self, date: NaiveDate) -> bool {
+        if self.months & (1 << date.month()) == 0 {
+            return false;
+        }
+        let day = self.days & (1 << date.day()) != 0;
+        let weekday = self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        match (self.any_day, self.any_weekday) {
+            (false, false) => day || weekday,
+            _ => day && weekday,
+        }
+    }
+}
+
+/// A parsed [`QuietHours`] entry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct QuietWindow {
+    /// Minutes after midnight.
+    start: u32,
+    end: u32,
+    weekdays: u8,
+}
+
+impl QuietWindow {
+    pub fn parse(hours: &QuietHours) -> Result<Self> {
+        let start = parse_time(&hours.start).context("invalid quiet hours start")?;
+        let end = parse_time(&hours.end).context("invalid quiet hours end")?;
+        if start == end {
+            bail!("quiet hours {}-{} are empty", hours.start, hours.end);
+        }
+        let weekdays = match hours.days.as_deref() {
+            None => 0x7f,
+            Some(days) => {
+                let bits = parse_field(days, 0, 7, &WEEKDAYS)
+                    .with_context(|| format!("invalid quiet hours days \"{days}\""))?;
+                ((bits | (bits >> 7)) & 0x7f) as u8
+            }
+        };
+        Ok(Self {
+            start,
+            end,
+            weekdays,
+        })
+    }
+
+    /// When the window ends, if `at` falls inside it.
+    pub fn end_if_inside(&self, at: DateTime<Local>) -> Option<DateTime<Local>> {
+        let today = at.date_naive();
+        let minute = at.hour() * 60 + at.minute();
+        let opens_on =
+            |date: NaiveDate| self.weekdays & (1 << date.weekday().num_days_from_sunday()) != 0;
+        if self.start < self.end {
+            (opens_on(today) && (self.start..self.end).contains(&minute))
+                .then(|| local_at(today, self.end))
+        } else if minute >= self.start && opens_on(today) {
+            Some(local_at(today.succ_opt()?, self.end))
+        } else if minute < self.end && opens_on(today.pred_opt()?) {
+            Some(local_at(today, self.end))
+        } else {
+            None
+        }
+    }
+}
+
+/// When the quiet hours covering `at` are over, following windows that
+/// overlap or adjoin; `None` outside quiet hours.
+pub fn quiet_until(windows: &[QuietWindow], at: DateTime<Local>) -> Option<DateTime<Local>> {
+    let mut until = None;
+    let mut probe = at;
+    // Each step moves past one window; more steps than windows means they
+    // cover the whole week, which is reported as the last end found.
+    for _ in 0..=windows.len() * 7 {
+        match windows.iter().filter_map(|w| w.end_if_inside(probe)).max() {
+            Some(end) => {
+                until = Some(end);
+                probe = end;
+            }
+            None => break,
+        }
+    }
+    until
+}
+
+/// `HH:MM` as minutes after midnight.
+fn parse_time(text: &str) -> Result<u32> {
+    let parsed = text.trim().split_once(':').and_then(|(hour, minute)| {
+        let (h, m): (u32, u32) = (hour.parse().ok()?, minute.parse().ok()?);
+        (h < 24 && m < 60 && minute.len() == 2).then_some(h * 60 + m)
+    });
+    parsed.with_context(|| format!("\"{text}\" is not a HH:MM time"))
+}
+
+/// `minutes` after midnight on `date`, pushed past a DST gap if needed.
+fn local_at(date: NaiveDate, minutes: u32) -> DateTime<Local> {
+    let naive = date.and_time(chrono::NaiveTime::MIN) + TimeDelta::minutes(minutes.into());
+    (0..=2)
+        .find_map(|hours| {
+            Local
+                .from_local_datetime(&(naive + TimeDelta::hours(hours)))
+                .earliest()
+        })
+        .unwrap_or_else(|| Local.from_utc_datetime(&naive))
+}
+
+/// Parses one field into a bit set with bit `n` set for each allowed `n`.
+fn parse_field(field: &str, min: u32, max: u32, names: &[&str]) -> Result<u64> {
+    let value = |text: &str| -> Result<u32> {
+        let lower = text.to_ascii_lowercase();
+        if let Some(i) = names.iter().position(|name| *name == lower) {
+            return Ok(i as u32 + min);
+        }
+        let n: u32 = text
+            .parse()
+            .ok()
+            .with_context(|| format!("\"{text}\" is not a number"))?;
+        if n < min || n > max {
+            bail!("{n} is outside {min}-{max}");
+        }
+        Ok(n)
+    };
+    let mut bits = 0u64;
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => {
+                let step: u32 = step
+                    .parse()
+                    .ok()
+                    .filter(|s| *s > 0)
+                    .with_context(|| format!("\"{step}\" is not a valid step"))?;
+                (range, step)
+            }
+            None => (part, 1),
+        };
+        let (from, to) = match range {
+            "*" => (min, max),
+            _ => match range.split_once('-') {
+                Some((from, to)) => (value(from)?, value(to)?),
+                // `5/15` means every 15 from 5 on.
+                None if step > 1 => (value(range)?, max),
+                None => {
+                    let n = value(range)?;
+                    (n, n)
+                }
+            },
+        };
+        if from > to {
+            bail!("range {range} runs backwards");
+        }
+        for n in (from..=to).step_by(step as usize) {
+            bits |= 1 << n;
+        }
+    }
+    Ok(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::{Clock, ManualClock};
+
+    fn local(text: &str) -> DateTime<Local> {
+        ManualClock::at_local(text).local_now()
+    }
+
+    #[test]
+    fn test_parse_fields_names_and_shorthands() {
+        let work_hours = Schedule::parse("*/15 9-17 * * mon-fri").unwrap();
+        // 2024-01-15 is a Monday.
+        assert!(work_hours.matches(local("2024-01-15 09:45:30")));
+        assert!(!work_hours.matches(local("2024-01-15 09:50:00")));
+        assert!(!work_hours.matches(local("2024-01-15 18:00:00")));
+        assert!(!work_hours.matches(local("2024-01-14 10:00:00")));
+
+        let sunday = Schedule::parse("0 3 * * 7").unwrap();
+        assert_eq!(
+            sunday,
+            Schedule {
+                expression: "0 3 * * 7".into(),
+                ..Schedule::parse("0 3 * * sun").unwrap()
+            }
+        );
+        assert!(sunday.matches(local("2024-01-14 03:00:59")));
+
+        assert_eq!(
+            Schedule::parse("@weekly")
+                .unwrap()
+                .next_after(local("2024-01-15 12:00:00")),
+            Some(local("2024-01-21 00:00:00"))
+        );
+
+        for bad in [
+            "",
+            "* * * *",
+            "60 * * * *",
+            "* * * * 8",
+            "5-1 * * * *",
+            "*/0 * * * *",
+            "0 3 * foo *",
+        ] {
+            assert!(Schedule::parse(bad).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn test_day_of_month_or_weekday() {
+        // The 1st of the month or any Friday.
+        let schedule = Schedule::parse("0 12 1 * fri").unwrap();
+        assert!(schedule.matches(local("2024-02-01 12:00:00")));
+        assert!(schedule.matches(local("2024-02-02 12:00:00")));
+        assert!(!schedule.matches(local("2024-02-03 12:00:00")));
+        assert_eq!(
+            schedule.next_after(local("2024-02-02 12:00:00")),
+            Some(local("2024-02-09 12:00:00"))
+        );
+
+        let leap = Schedule::parse("0 0 29 2 *").unwrap();
+        assert_eq!(
+            leap.next_after(local("2024-03-01 00:00:00")),
+            Some(local("2028-02-29 00:00:00"))
+        );
+        assert_eq!(
+            Schedule::parse("0 0 31 2 *")
+                .unwrap()
+                .next_after(local("2024-01-01 00:00:00")),
+            None
+        );
+    }
+
+    #[test]
+    fn test_quiet_windows_cross_midnight_and_chain() {
+        let window = |start: &str, end: &str, days: Option<&str>| {
+            QuietWindow::parse(&QuietHours {
+                start: start.into(),
+                end: end.into(),
+                days: days.map(Into::into),
+            })
+        };
+        // Weeknights from 22:00, opening Monday (2024-01-15) to Friday.
+        let nights = [window("22:00", "07:00", Some("mon-fri")).unwrap()];
+        assert_eq!(
+            quiet_until(&nights, local("2024-01-15 23:30:00")),
+            Some(local("2024-01-16 07:00:00"))
+        );
+        assert_eq!(
+            quiet_until(&nights, local("2024-01-16 06:59:59")),
+            Some(local("2024-01-16 07:00:00"))
+        );
+        assert_eq!(quiet_until(&nights, local("2024-01-16 07:00:00")), None);
+        // Sunday night is not covered, nor is early Monday.
+        assert_eq!(quiet_until(&nights, local("2024-01-14 23:00:00")), None);
+        assert_eq!(quiet_until(&nights, local("2024-01-15 05:00:00")), None);
+
+        let chained = [
+            window("09:00", "12:00", None).unwrap(),
+            window("12:00", "13:30", None).unwrap(),
+        ];
+        assert_eq!(
+            quiet_until(&chained, local("2024-01-15 10:00:00")),
+            Some(local("2024-01-15 13:30:00"))
+        );
+
+        for (start, end, days) in [
+            ("22:00", "22:00", None),
+            ("24:00", "07:00", None),
+            ("22", "07:00", None),
+            ("22:00", "07:00", Some("weekdays")),
+        ] {
+            assert!(window(start, end, days).is_err(), "{start}-{end} {days:?}");
+        }
+    }
+
+    #[test]
+    fn test_is_due_catches_up_on_missed_minutes() {
+        let schedule = Schedule::parse("0 3 * * sun").unwrap();
+        let at = |text: &str| ManualClock::at_local(text).now();
+        assert!(schedule.is_due(None, at("2024-01-14 03:00:20")));
+        assert!(!schedule.is_due(None, at("2024-01-14 03:01:00")));
+        // The previous pass was just before 03:00; this one is a bit late.
+        assert!(schedule.is_due(Some(at("2024-01-14 02:59:58")), at("2024-01-14 03:01:05")));
+        assert!(!schedule.is_due(Some(at("2024-01-14 03:01:05")), at("2024-01-14 03:01:10")));
+    }
+}
@@ -0,0 +1,340 @@
+//! Sidecar files: subtitles next to a video, signatures and checksums next
+//! to an installer.
+//!
+//! A sidecar follows its primary file into the primary's destination folder
+//! in the same pass instead of being matched against the rules on its own.
+//! A file is a sidecar when
+//!
+//! - it has one of the sidecar extensions and, without that extension, is
+//!   named like another file (`setup.exe.sig` → `setup.exe`) or starts with
+//!   its stem (`movie.en.srt` → `movie.mkv`), or
+//! - it matches a companion pattern such as `{stem}-poster.jpg`.
+
+use crate::types::SidecarConfig;
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+/// Sidecar extensions used when [`SidecarConfig::extensions`] is not set.
+pub const DEFAULT_SIDECAR_EXTENSIONS: &[&str] = &[
+    "srt", "vtt", "ass", "ssa", "sub", "idx", "sig", "asc", "sha1", "sha256", "sha512", "md5",
+    "nfo",
+];
+
+/// Which part of the primary's name a companion pattern refers to.
+#[derive(Debug, Clone, Copy)]
+enum Key {
+    Stem,
+    Name,
+}
+
+pub(crate) struct SidecarMatcher {
+    extensions: Vec<String>,
+    patterns: Vec<(Regex, Key)>,
+}
+
+impl SidecarMatcher {
+    /// Patterns that do not compile are reported and ignored.
+    pub(crate) fn new(cfg: &SidecarConfig) -> Self {
+        let extensions = match &cfg.extensions {
+            Some(exts) => exts
+                .iter()
+                .map(|e| e.trim_start_matches('.').to_ascii_lowercase())
+                .collect(),
+            None => DEFAULT_SIDECAR_EXTENSIONS
+                .iter()
+                .map(|e| e.to_string())
+                .collect(),
+        };
+        let patterns = cfg
+            .patterns
+            .iter()
+            .filter_map(|pattern| match compile_pattern(pattern) {
+                Ok(compiled) => Some(compiled),
+                Err(e) => {
+                    eprintln!("[Harbor] ignoring sidecar pattern \"{pattern}\": {e}");
+                    None
+                }
+            })
+            .collect();
+        Self {
+            extensions,
+            patterns,
+        }
+    }
+
+    fn has_sidecar_extension(&self, name: &str) -> bool {
+        Path::new(name)
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| self.extensions.contains(&e.to_ascii_lowercase()))
+    }
+
+    /// Maps each sidecar among `files` to its primary file.
+    ///
+    /// Files with a sidecar extension are never primaries. A stem shared by
+    /// several primaries (`movie.mkv` and `movie.mp4`) is ambiguous and
+    /// matches neither.
+    pub(crate) fn group(&self, files: &[PathBuf]) -> HashMap<PathBuf, PathBuf> {
+        let mut by_name: HashMap<&str, &PathBuf> = HashMap::new();
+        let mut by_stem: HashMap<&str, Option<&PathBuf>> = HashMap::new();
+        for path in files {
+            let Some(name) = file_name(path) else {
+                continue;
+            };
+            if self.has_sidecar_extension(name) {
+                continue;
+            }
+            by_name.insert(name, path);
+            if let Some(stem) = path.file_stem().and_then(|s| s.to_str()) {
+                by_stem
+                    .entry(stem)
+                    .and_modify(|p| *p = None)
+                    .or_insert(Some(path));
+            }
+        }
+        let stem = |s: &str| by_stem.get(s).copied().flatten();
+
+        let mut groups = HashMap::new();
+        for path in files {
+            let Some(name) = file_name(path) else {
+                continue;
+            };
+            let mut primary = None;
+            if self.has_sidecar_extension(name) {
+                let base = &name[..name.rfind('.').unwrap_or(name.len())];
+                primary = by_name.get(base).copied();
+                // `movie.en.forced` → `movie.en` → `movie`
+                let mut prefix = base;
+                while primary.is_none() {
+                    primary = stem(prefix);
+                    match prefix.rfind('.') {
+                        Some(i) if primary.is_none() => prefix = &prefix[..i],
+                        _ => break,
+                    }
+                }
+            }
+            for (re, key) in &self.patterns {
+                if primary.is_some() {
+                    break;
+                }
+                let Some(value) = re.captures(name).and_then(|c| c.get(1)) else {
+                    continue;
+                };
+                primary = match key {
+                    Key::Stem => stem(value.as_str()),
+                    Key::Name => by_name.get(value.as_str()).copied(),
+                };
+            }
+            if let Some(primary) = primary.filter(|p| *p != path) {
+                groups.insert(path.clone(), primary.clone());
+            }
+        }
+        // A pattern can make a primary look like a sidecar of another file;
+        // keep groups one level deep.
+        let nested: Vec<PathBuf> = groups
+            .iter()
+            .filter(|(_, primary)| groups.contains_key(*primary))
+            .map(|(sidecar, _)| sidecar.clone())
+            .collect();
+        for sidecar in nested {
+            groups.remove(&sidecar);
+        }
+        groups
+    }
+}
+
+/// Orders `entries` so each primary comes right before its sidecars.
+pub(crate) fn primaries_first(
+    entries: Vec<PathBuf>,
+    groups: &HashMap<PathBuf, PathBuf>,
+) -> Vec<PathBuf> {
+    if groups.is_empty() {
+        return entries;
+    }
+    let mut members: HashMap<&Path, Vec<&PathBuf>> = HashMap::new();
+    for path in &entries {
+        if let Some(primary) = groups.get(path) {
+            members.entry(primary.as_path()).or_default().push(path);
+        }
+    }
+    let mut ordered = Vec::with_capacity(entries.len());
+    for path in &entries {
+        if groups.contains_key(path) {
+            continue;
+        }
+        ordered.push(path.clone());
+        if let Some(sidecars) = members.get(path.as_path()) {
+            ordered.extend(sidecars.iter().map(|p| (*p).clone()));
+        }
+    }
+    ordered
+}
+
+/// Where a sidecar goes once its primary moved from `primary` to
+/// `primary_dest`: the same folder, renamed along with the primary, so
+/// `movie.en.srt` becomes `movie (1).en.srt` next to `movie (1).mkv`.
+pub(crate) fn sidecar_target(sidecar: &Path, primary: &Path, primary_dest: &Path) -> PathBuf {
+    let dir = primary_dest.parent().unwrap_or(Path::new(""));
+    let name = sidecar.file_name().unwrap_or_default();
+    let (Some(text), Some(old_name), Some(new_name)) =
+        (name.to_str(), file_name(primary), file_name(primary_dest))
+    else {
+        return dir.join(name);
+    };
+    let stem = |n: &'_ str| -> String {
+        Path::new(n)
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or(n)
+            .to_string()
+    };
+    let renamed = if let Some(rest) = text.strip_prefix(old_name) {
+        format!("{new_name}{rest}")
+    } else if let Some(rest) = text.strip_prefix(&stem(old_name)) {
+        format!("{}{rest}", stem(new_name))
+    } else {
+        text.to_string()
+    };
+    dir.join(renamed)
+}
+
+fn file_name(path: &Path) -> Option<&str> {
+    path.file_name().and_then(|n| n.to_str())
+}
+
+/// Turns `{stem}-poster.jpg` into `^(.+?)-poster\.jpg$` (case-insensitive).
+fn compile_pattern(pattern: &str) -> Result<(Regex, Key), String> {
+    let mut keys = Vec::new();
+    let mut regex = String::from("(?i)^");
+    let mut rest = pattern;
+    while !rest.is_empty() {
+        if let Some(after) = rest.strip_prefix("{stem}") {
+            keys.push(Key::Stem);
+            regex.push_str("(.+?)");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix("{name}") {
+            keys.push(Key::Name);
+            regex.push_str("(.+?)");
+            rest = after;
+        } else if let Some(after) = rest.strip_prefix('*') {
+            regex.push_str(".*?");
+            rest = after;
+        } else {
+            let ch = rest.chars().next().unwrap_or_default();
+            regex.push_str(&regex::escape(&ch.to_string()));
+            rest = &rest[ch.len_utf8()..];
+        }
+    }
+    regex.push('$');
+    let [key] = keys[..] else {
+        return Err("needs exactly one {stem} or {name}".to_string());
+    };
+    let re = Regex::new(&regex).map_err(|e| e.to_string())?;
+    Ok((re, key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn paths(names: &[&str]) -> Vec<PathBuf> {
+        names.iter().map(|n| Path::new("/dl").join(n)).collect()
+    }
+
+    fn grouped(cfg: &SidecarConfig, names: &[&str]) -> Vec<(String, String)> {
+        let mut pairs: Vec<(String, String)> = SidecarMatcher::new(cfg)
+            .group(&paths(names))
+            .into_iter()
+            .map(|(s, p)| {
+                (
+                    file_name(&s).unwrap().to_string(),
+                    file_name(&p).unwrap().to_string(),
+                )
+            })
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    fn pair(sidecar: &str, primary: &str) -> (String, String) {
+        (sidecar.to_string(), primary.to_string())
+    }
+
+    #[test]
+    fn test_group_by_name_and_stem() {
+        let cfg = SidecarConfig::default();
+        assert_eq!(
+            grouped(
+                &cfg,
+                &[
+                    "movie.mkv",
+                    "movie.en.srt",
+                    "movie.vtt",
+                    "setup.exe",
+                    "setup.exe.sha256",
+                    "lonely.srt",
+                    "clip.mp4",
+                    "clip.mov",
+                    "clip.srt",
+                ]
+            ),
+            vec![
+                pair("movie.en.srt", "movie.mkv"),
+                pair("movie.vtt", "movie.mkv"),
+                pair("setup.exe.sha256", "setup.exe"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_group_by_companion_pattern() {
+        let cfg = SidecarConfig {
+            extensions: Some(vec![]),
+            patterns: vec!["{stem}-poster.*".into(), "{name}.asc".into()],
+        };
+        assert_eq!(
+            grouped(
+                &cfg,
+                &[
+                    "film.mkv",
+                    "film-poster.JPG",
+                    "tool.tar.gz",
+                    "tool.tar.gz.asc"
+                ]
+            ),
+            vec![
+                pair("film-poster.JPG", "film.mkv"),
+                pair("tool.tar.gz.asc", "tool.tar.gz"),
+            ]
+        );
+        assert!(compile_pattern("poster.jpg").is_err());
+    }
+
+    #[test]
+    fn test_primaries_first_and_sidecar_target() {
+        let entries = paths(&["a.srt", "b.txt", "a.mkv"]);
+        let groups = SidecarMatcher::new(&SidecarConfig::default()).group(&entries);
+        assert_eq!(
+            primaries_first(entries, &groups),
+            paths(&["b.txt", "a.mkv", "a.srt"])
+        );
+
+        assert_eq!(
+            sidecar_target(
+                Path::new("/dl/movie.en.srt"),
+                Path::new("/dl/movie.mkv"),
+                Path::new("/videos/movie (1).mkv")
+            ),
+            PathBuf::from("/videos/movie (1).en.srt")
+        );
+        assert_eq!(
+            sidecar_target(
+                Path::new("/dl/setup.exe.sig"),
+                Path::new("/dl/setup.exe"),
+                Path::new("/apps/setup (2).exe")
+            ),
+            PathBuf::from("/apps/setup (2).exe.sig")
+        );
+    }
+}
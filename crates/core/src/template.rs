@@ -0,0 +1,189 @@
+//! `{group}` placeholders in a rule's `target_dir` and `rename_to`.
+//!
+//! A placeholder names a capture group of the rule's `pattern`, e.g.
+//! `Invoices/{vendor}/{yyyy}` with `^(?P<vendor>\w+)_invoice_(?P<yyyy>\d{4})`.
+//! `{name}` is the file's original name without its extension unless the
+//! pattern defines a group of that name. `{{` and `}}` stand for literal
+//! braces, and `${VAR}` is left for environment expansion.
+
+use anyhow::{bail, Result};
+use regex::Regex;
+use std::collections::HashMap;
+use std::path::{Component, Path, PathBuf};
+
+/// Placeholder filled with the original file stem when the pattern has no
+/// group of the same name.
+pub const NAME_PLACEHOLDER: &str = "name";
+
+enum Piece<'a> {
+    Text(&'a str),
+    Placeholder(&'a str),
+}
+
+/// Splits `template` into literal text and placeholders.
+fn parse(template: &str) -> Result<Vec<Piece<'_>>> {
+    let mut pieces = Vec::new();
+    let mut rest = template;
+    while let Some(i) = rest.find(['{', '}']) {
+        let (before, from) = rest.split_at(i);
+        pieces.push(Piece::Text(before));
+        if from.starts_with("{{") || from.starts_with("}}") {
+            pieces.push(Piece::Text(&from[..1]));
+            rest = &from[2..];
+        } else if from.starts_with('}') {
+            bail!("unmatched `}}` in \"{template}\"");
+        } else if before.ends_with('$') {
+            // `${VAR}` belongs to environment expansion.
+            let end = from.find('}').map_or(from.len(), |j| j + 1);
+            pieces.push(Piece::Text(&from[..end]));
+            rest = &from[end..];
+        } else {
+            let Some(end) = from.find('}') else {
+                bail!("unclosed `{{` in \"{template}\"");
+            };
+            let name = &from[1..end];
+            let valid = name
+                .chars()
+                .next()
+                .is_some_and(|c| c.is_alphabetic() || c == '_')
+                && name.chars().all(|c| c.is_alphanumeric() || c == '_');
+            if !valid {
+                bail!("`{{{name}}}` in \"{template}\" is not a valid group name");
+            }
+            pieces.push(Piece::Placeholder(name));
+            rest = &from[end + 1..];
+        }
+    }
+    pieces.push(Piece::Text(rest));
+    Ok(pieces)
+}
+
+/// Names of the placeholders in `template`, in order of appearance.
+pub fn placeholders(template: &str) -> Result<Vec<String>> {
+    Ok(parse(template)?
+        .into_iter()
+        .filter_map(|piece| match piece {
+            Piece::Placeholder(name) => Some(name.to_string()),
+            Piece::Text(_) => None,
+        })
+        .collect())
+}
+
+/// Returns `true` if `template` contains at least one placeholder.
+pub fn has_placeholders(template: &str) -> bool {
+    placeholders(template).is_ok_and(|names| !names.is_empty())
+}
+
+/// Placeholders in `template` that `pattern` does not define as a named
+/// group. `{name}` is always available.
+pub fn unknown_placeholders(template: &str, pattern: Option<&Regex>) -> Result<Vec<String>> {
+    let groups: Vec<&str> = pattern
+        .map(|re| re.capture_names().flatten().collect())
+        .unwrap_or_default();
+    Ok(placeholders(template)?
+        .into_iter()
+        .filter(|name| name != NAME_PLACEHOLDER && !groups.contains(&name.as_str()))
+        .collect())
+}
+
+/// Fills the placeholders in `template` from `values`; missing ones become
+/// empty. Values are made safe as a single path component, so a capture can
+/// never add folders or climb out of the target. A template that does not
+/// parse is returned unchanged.
+pub fn render(template: &str, values: &HashMap<String, String>) -> String {
+    let Ok(pieces) = parse(template) else {
+        return template.to_string();
+    };
+    pieces
+        .into_iter()
+        .map(|piece| match piece {
+            Piece::Text(text) => text.to_string(),
+            Piece::Placeholder(name) => values
+                .get(name)
+                .map(|value| sanitize(value))
+                .unwrap_or_default(),
+        })
+        .collect()
+}
+
+/// The leading folders of a `target_dir` template, before the first
+/// component with a placeholder: `/docs/Invoices` for `/docs/Invoices/{vendor}`.
+pub fn static_prefix(target_dir: &str) -> PathBuf {
+    let templated = |c: &Component| match c {
+        Component::Normal(part) => part.to_str().is_some_and(has_placeholders),
+        _ => false,
+    };
+    Path::new(target_dir)
+        .components()
+        .take_while(|c| !templated(c))
+        .collect()
+}
+
+fn sanitize(value: &str) -> String {
+    let value = value.trim().replace(['/', '\\'], "_");
+    match value.as_str() {
+        "." | ".." => "_".to_string(),
+        _ => value,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(pairs: &[(&str, &str)]) -> HashMap<String, String> {
+        pairs
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_placeholders_and_escapes() {
+        assert_eq!(
+            placeholders("Invoices/{vendor}/{yyyy}").unwrap(),
+            vec!["vendor", "yyyy"]
+        );
+        assert!(placeholders("${HOME}/{{literal}}").unwrap().is_empty());
+        assert!(placeholders("Invoices/{vendor").is_err());
+        assert!(placeholders("Invoices/vendor}").is_err());
+        assert!(placeholders("Invoices/{1st}").is_err());
+    }
+
+    #[test]
+    fn test_unknown_placeholders_checks_named_groups() {
+        let re = Regex::new(r"^(?P<vendor>\w+)_invoice_(?P<yyyy>\d{4})").unwrap();
+        assert!(
+            unknown_placeholders("Invoices/{vendor}/{yyyy}/{name}", Some(&re))
+                .unwrap()
+                .is_empty()
+        );
+        assert_eq!(
+            unknown_placeholders("{vendor}-{month}", Some(&re)).unwrap(),
+            vec!["month"]
+        );
+        assert_eq!(
+            unknown_placeholders("{vendor}", None).unwrap(),
+            vec!["vendor"]
+        );
+    }
+
+    #[test]
+    fn test_render_keeps_captures_inside_one_component() {
+        let v = values(&[("vendor", "../acme/x"), ("yyyy", "2024")]);
+        assert_eq!(
+            render("Invoices/{vendor}/{yyyy}/{{x}}/{missing}", &v),
+            "Invoices/.._acme_x/2024/{x}/"
+        );
+        assert_eq!(render("{vendor}", &values(&[("vendor", "..")])), "_");
+    }
+
+    #[test]
+    fn test_static_prefix_stops_at_first_placeholder() {
+        assert_eq!(
+            static_prefix("/docs/Invoices/{vendor}/{yyyy}"),
+            PathBuf::from("/docs/Invoices")
+        );
+        assert_eq!(static_prefix("/docs/Plain"), PathBuf::from("/docs/Plain"));
+    }
+}
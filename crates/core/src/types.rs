@@ -16,17 +16,223 @@ pub struct Rule {
     pub pattern: Option<String>,
     pub min_size_bytes: Option<u64>,
     pub max_size_bytes: Option<u64>,
+    /// Destination folder. May contain `{group}` placeholders filled from the
+    /// named capture groups of `pattern`; see [`crate::template`].
     pub target_dir: String,
+    /// New name for the file, without its extension, which is kept. Takes
+    /// the same placeholders as `target_dir`, e.g. `{vendor}-{yyyy}`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rename_to: Option<String>,
     #[serde(default)]
     pub create_symlink: bool,
+    /// What to leave at the original location after a move; overrides
+    /// `create_symlink`, which means an absolute symlink.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub breadcrumb: Option<BreadcrumbKind>,
     #[serde(default = "default_enabled")]
     pub enabled: bool,
+    /// Minimum free space to leave on the target filesystem after a
+    /// cross-device move; overrides the global `min_free_space_bytes`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_free_bytes: Option<u64>,
+    /// Housekeeping applied to `target_dir` by the watcher; `None` keeps files forever.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retention: Option<RetentionPolicy>,
+    /// Clean-ups applied to the file name before it is moved; always run in
+    /// [`NormalizeStep`] declaration order, whatever order they are listed in.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub normalize: Option<Vec<NormalizeStep>>,
+    /// Commands run after this rule moved a file, before the global
+    /// `on_moved` hooks.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub on_moved: Option<Vec<HookCommand>>,
+    /// Cron expression limiting when this rule moves files, e.g.
+    /// `0 3 * * sun`; see [`crate::schedule`]. Matching files wait in the
+    /// download folder in between, also through "Organize now", which
+    /// reports them as waiting for the schedule.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub schedule: Option<String>,
+}
+
+impl Default for Rule {
+    fn default() -> Self {
+        Self {
+            id: new_rule_id(),
+            name: String::new(),
+            extensions: None,
+            pattern: None,
+            min_size_bytes: None,
+            max_size_bytes: None,
+            target_dir: String::new(),
+            rename_to: None,
+            create_symlink: false,
+            breadcrumb: None,
+            enabled: default_enabled(),
+            min_free_bytes: None,
+            retention: None,
+            normalize: None,
+            on_moved: None,
+            schedule: None,
+        }
+    }
+}
+
+impl Rule {
+    /// The breadcrumb to leave behind, if any: `breadcrumb` when set,
+    /// otherwise an absolute symlink when `create_symlink` is on.
+    pub fn breadcrumb_kind(&self) -> Option<BreadcrumbKind> {
+        self.breadcrumb
+            .or(self.create_symlink.then_some(BreadcrumbKind::Symlink))
+    }
 }
 
 fn default_enabled() -> bool {
     true
 }
 
+/// A pointer left at a file's original location after it is moved.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BreadcrumbKind {
+    /// Symlink with an absolute target path.
+    Symlink,
+    /// Symlink with a target relative to the download folder, so it keeps
+    /// working when the home directory is moved or mounted elsewhere.
+    RelativeSymlink,
+    /// Hard link to the moved file. Only possible on the same filesystem.
+    Hardlink,
+    /// Shortcut file next to the original name: a freedesktop `.desktop`
+    /// link, or an Internet Shortcut (`.url`) on Windows.
+    Shortcut,
+}
+
+/// A file name clean-up applied by [`normalize_file_name`](crate::normalize::normalize_file_name).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NormalizeStep {
+    /// Decode `%20`-style escapes left by browsers, e.g. `My%20File.pdf`.
+    UrlDecode,
+    /// Drop duplicate markers such as ` (1)` and ` - Copy` from the end of the name.
+    StripDuplicateSuffix,
+    /// Replace characters that FAT, NTFS or SMB shares reject, and names
+    /// Windows reserves for devices (`CON`, `NUL`, ...).
+    ReplaceInvalidChars,
+    /// Turn runs of whitespace into one space and trim the name.
+    CollapseWhitespace,
+    /// `IMG_1234.JPEG` becomes `IMG_1234.jpeg`.
+    LowercaseExtension,
+    /// Shorten the name to fit the 255-byte limit of common filesystems,
+    /// keeping the extension.
+    Truncate,
+}
+
+/// Files that travel with a primary file; see [`crate::sidecar`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SidecarConfig {
+    /// Extensions of sidecar files, e.g. `srt` or `sig`; defaults to
+    /// [`DEFAULT_SIDECAR_EXTENSIONS`](crate::sidecar::DEFAULT_SIDECAR_EXTENSIONS).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub extensions: Option<Vec<String>>,
+    /// Companion names with one `{stem}` or `{name}` placeholder for the
+    /// primary file and `*` wildcards, e.g. `{stem}-poster.jpg`.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub patterns: Vec<String>,
+}
+
+/// An external command run after a file was moved; see [`crate::hooks`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct HookCommand {
+    /// Program to run, looked up on `PATH` unless it is a path. It is started
+    /// directly, not through a shell.
+    pub command: String,
+    /// Arguments, in which `{src}`, `{dest}`, `{rule}` and `{ext}` are
+    /// replaced with the move's details. Shell scripts should read
+    /// `$HARBOR_DEST` and friends instead, since file names can hold quotes.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// Seconds before the command is killed; defaults to
+    /// [`DEFAULT_HOOK_TIMEOUT_SECS`](crate::hooks::DEFAULT_HOOK_TIMEOUT_SECS).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+/// Where and how to send webhook notifications; see [`crate::webhook`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WebhookConfig {
+    /// Receiver on the local network, e.g. `http://192.168.1.20:8080/harbor`.
+    /// Only plain `http://` is supported.
+    pub url: String,
+    /// Key for the `X-Harbor-Signature` HMAC-SHA256 header. Unset sends
+    /// requests unsigned.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secret: Option<String>,
+    /// Retries of a failed request before its events are dropped; defaults to
+    /// [`DEFAULT_WEBHOOK_RETRIES`](crate::webhook::DEFAULT_WEBHOOK_RETRIES).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_retries: Option<u32>,
+    /// Most events sent in one request; defaults to
+    /// [`DEFAULT_WEBHOOK_BATCH`](crate::webhook::DEFAULT_WEBHOOK_BATCH).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_batch: Option<usize>,
+    /// Seconds to wait for the receiver to connect and answer; defaults to
+    /// [`DEFAULT_WEBHOOK_TIMEOUT_SECS`](crate::webhook::DEFAULT_WEBHOOK_TIMEOUT_SECS).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timeout_secs: Option<u64>,
+}
+
+/// A daily window during which the watcher leaves the download folder alone;
+/// see [`crate::schedule::QuietWindow`].
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuietHours {
+    /// Local time the window opens, `HH:MM`.
+    pub start: String,
+    /// Local time the window closes, `HH:MM`. An end before the start runs
+    /// past midnight, e.g. `22:00`–`07:00`.
+    pub end: String,
+    /// Weekdays the window opens on, in cron weekday syntax such as
+    /// `mon-fri` or `sat,sun`. Unset means every day.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub days: Option<String>,
+}
+
+/// What happens to files that fall outside a [`RetentionPolicy`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RetentionAction {
+    /// Permanently delete the file.
+    Delete,
+    /// Move the file to the operating system's trash / recycle bin. The
+    /// default, so permanent deletion has to be chosen explicitly.
+    #[default]
+    Trash,
+}
+
+/// Limits enforced on a rule's target folder. Every limit is optional; a file
+/// is purged as soon as it violates any of them, oldest files first. Only
+/// files the rule matches count and can be purged; other files in the
+/// folder are never touched.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RetentionPolicy {
+    /// Purge files last modified more than this many days ago.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_age_days: Option<u64>,
+    /// Keep at most this many files (the newest ones).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_files: Option<usize>,
+    /// Keep the newest files whose combined size fits within this many bytes.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_total_bytes: Option<u64>,
+    #[serde(default)]
+    pub action: RetentionAction,
+}
+
+impl RetentionPolicy {
+    /// Returns `true` when no limit is set, i.e. the policy would never purge anything.
+    pub fn is_unbounded(&self) -> bool {
+        self.max_age_days.is_none() && self.max_files.is_none() && self.max_total_bytes.is_none()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -43,12 +249,27 @@ mod tests {
             target_dir: "target".to_string(),
             create_symlink: false,
             enabled: true,
+            ..Default::default()
         };
         let json = serde_json::to_string(&r).unwrap();
         let r2: Rule = serde_json::from_str(&json).unwrap();
         assert_eq!(r.name, r2.name);
     }
 
+    #[test]
+    fn test_breadcrumb_kind_falls_back_to_create_symlink() {
+        let mut r = Rule::default();
+        assert_eq!(r.breadcrumb_kind(), None);
+        r.create_symlink = true;
+        assert_eq!(r.breadcrumb_kind(), Some(BreadcrumbKind::Symlink));
+
+        let r: Rule = serde_yaml::from_str(
+            "name: r\ntarget_dir: t\ncreate_symlink: true\nbreadcrumb: relative_symlink\n",
+        )
+        .unwrap();
+        assert_eq!(r.breadcrumb_kind(), Some(BreadcrumbKind::RelativeSymlink));
+    }
+
     #[test]
     fn test_rule_serde_missing_id_gets_default() {
         // Simulates deserializing an old config that has no `id` field.
@@ -57,5 +278,25 @@ mod tests {
         assert_eq!(r.name, "old");
         // A UUID should have been generated automatically.
         assert!(!r.id.is_empty());
+        assert!(r.retention.is_none());
+    }
+
+    #[test]
+    fn test_retention_policy_serde() {
+        let yaml = "name: installers\ntarget_dir: t\nextensions: [exe]\nretention:\n  max_age_days: 30\n  max_files: 20\n  action: trash\n";
+        let r: Rule = serde_yaml::from_str(yaml).unwrap();
+        let policy = r.retention.unwrap();
+        assert_eq!(policy.max_age_days, Some(30));
+        assert_eq!(policy.max_files, Some(20));
+        assert_eq!(policy.max_total_bytes, None);
+        assert_eq!(policy.action, RetentionAction::Trash);
+        assert!(!policy.is_unbounded());
+
+        // The action defaults to trash; deleting must be asked for.
+        let policy: RetentionPolicy = serde_yaml::from_str("max_total_bytes: 1024").unwrap();
+        assert_eq!(policy.action, RetentionAction::Trash);
+        let policy: RetentionPolicy = serde_yaml::from_str("max_files: 1\naction: delete").unwrap();
+        assert_eq!(policy.action, RetentionAction::Delete);
+        assert!(RetentionPolicy::default().is_unbounded());
     }
 }
@@ -0,0 +1,640 @@
+//! In-memory [`FileSystem`] with fault injection for tests.
+
+use super::{
+    cancelled_error, normalize_lexically, FileKind, FileMeta, FileSystem, COPY_CHUNK_BYTES,
+};
+use crate::types::RetentionAction;
+use std::collections::BTreeMap;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+
+/// Operations that faults can be attached to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FsOp {
+    ReadDir,
+    Metadata,
+    CreateDir,
+    Rename,
+    Copy,
+    Remove,
+    Purge,
+    Read,
+    Write,
+    Symlink,
+    ReadLink,
+    HardLink,
+}
+
+/// What happens when an injected fault triggers.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fault {
+    /// The operation fails with this error kind, e.g.
+    /// [`io::ErrorKind::PermissionDenied`] for `EACCES` or
+    /// [`io::ErrorKind::StorageFull`] for `ENOSPC`.
+    Error(io::ErrorKind),
+    /// Another process deletes the path just before the operation runs.
+    Vanish,
+    /// Another process creates an empty file at this path just before the
+    /// operation runs.
+    Appear(PathBuf),
+}
+
+/// Hard links share an `id`; their data is copied, which is fine as long as
+/// tests don't modify linked files in place.
+#[derive(Debug, Clone)]
+enum Node {
+    File {
+        id: u64,
+        data: Vec<u8>,
+        modified: SystemTime,
+    },
+    Dir,
+    Symlink {
+        target: PathBuf,
+        modified: SystemTime,
+    },
+}
+
+#[derive(Debug)]
+struct Injected {
+    op: FsOp,
+    path: PathBuf,
+    fault: Fault,
+    once: bool,
+}
+
+#[derive(Debug)]
+struct Device {
+    root: PathBuf,
+    free: u64,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    nodes: BTreeMap<PathBuf, Node>,
+    /// Extra filesystems; paths outside all of them share a default
+    /// filesystem with unlimited space.
+    devices: Vec<Device>,
+    faults: Vec<Injected>,
+    next_id: u64,
+    /// Files [`FileSystem::purge`] moved to the trash, oldest first.
+    trashed: Vec<PathBuf>,
+}
+
+/// [`FileSystem`] kept entirely in memory.
+///
+/// Files added with [`MemoryFs::add_file`] are dated at the Unix epoch so they
+/// pass any `min_age_secs`. Separate devices are declared with
+/// [`MemoryFs::mount`]; renames between devices fail with `EXDEV` and copies
+/// fail with `ENOSPC` once a device runs out of space, just like a real disk.
+/// Faults attach to an operation on a path (either side of a rename or copy).
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    state: Mutex<State>,
+}
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates `path` and any missing parents as directories.
+    pub fn add_dir(&self, path: impl AsRef<Path>) {
+        self.lock().create_dirs(path.as_ref());
+    }
+
+    /// Creates a file with `data`, dated at the Unix epoch.
+    pub fn add_file(&self, path: impl AsRef<Path>, data: impl Into<Vec<u8>>) {
+        self.add_file_modified(path, data, SystemTime::UNIX_EPOCH);
+    }
+
+    /// Creates a file with `data` and the given modification time.
+    pub fn add_file_modified(
+        &self,
+        path: impl AsRef<Path>,
+        data: impl Into<Vec<u8>>,
+        modified: SystemTime,
+    ) {
+        let path = path.as_ref();
+        let mut state = self.lock();
+        if let Some(parent) = path.parent() {
+            state.create_dirs(parent);
+        }
+        let node = state.new_file(data.into(), modified);
+        state.nodes.insert(path.to_path_buf(), node);
+    }
+
+    /// Creates a symlink at `link` pointing to `target`.
+    pub fn add_symlink(&self, link: impl AsRef<Path>, target: impl AsRef<Path>) {
+        self.add_symlink_modified(link, target, SystemTime::UNIX_EPOCH);
+    }
+
+    /// Creates a symlink at `link` pointing to `target`, created at `modified`.
+    pub fn add_symlink_modified(
+        &self,
+        link: impl AsRef<Path>,
+        target: impl AsRef<Path>,
+        modified: SystemTime,
+    ) {
+        let link = link.as_ref();
+        let mut state = self.lock();
+        if let Some(parent) = link.parent() {
+            state.create_dirs(parent);
+        }
+        state.nodes.insert(
+            link.to_path_buf(),
+            Node::Symlink {
+                target: target.as_ref().to_path_buf(),
+                modified,
+            },
+        );
+    }
+
+    /// Declares everything under `root` to be a separate filesystem with
+    /// `free_bytes` of space, and creates `root` as a directory.
+    pub fn mount(&self, root: impl AsRef<Path>, free_bytes: u64) {
+        let root = root.as_ref();
+        let mut state = self.lock();
+        state.create_dirs(root);
+        state.devices.push(Device {
+            root: root.to_path_buf(),
+            free: free_bytes,
+        });
+    }
+
+    /// Makes every `op` touching `path` trigger `fault`.
+    pub fn inject(&self, op: FsOp, path: impl AsRef<Path>, fault: Fault) {
+        self.push_fault(op, path.as_ref(), fault, false);
+    }
+
+    /// Makes the next `op` touching `path` trigger `fault`.
+    pub fn inject_once(&self, op: FsOp, path: impl AsRef<Path>, fault: Fault) {
+        self.push_fault(op, path.as_ref(), fault, true);
+    }
+
+    /// Contents of the file at `path`, if it is a regular file.
+    pub fn read(&self, path: impl AsRef<Path>) -> Option<Vec<u8>> {
+        match self.lock().nodes.get(path.as_ref()) {
+            Some(Node::File { data, .. }) => Some(data.clone()),
+            _ => None,
+        }
+    }
+
+    /// Every path currently present, in sorted order.
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.lock().nodes.keys().cloned().collect()
+    }
+
+    /// Files moved to the trash by [`FileSystem::purge`], oldest first.
+    pub fn trashed(&self) -> Vec<PathBuf> {
+        self.lock().trashed.clone()
+    }
+
+    fn push_fault(&self, op: FsOp, path: &Path, fault: Fault, once: bool) {
+        self.lock().faults.push(Injected {
+            op,
+            path: path.to_path_buf(),
+            fault,
+            once,
+        });
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Locks the state and applies any fault registered for `op` on `paths`.
+    fn begin(&self, op: FsOp, paths: &[&Path]) -> io::Result<MutexGuard<'_, State>> {
+        let mut state = self.lock();
+        let Some(index) = state
+            .faults
+            .iter()
+            .position(|f| f.op == op && paths.contains(&f.path.as_path()))
+        else {
+            return Ok(state);
+        };
+        let (path, fault) = if state.faults[index].once {
+            let injected = state.faults.remove(index);
+            (injected.path, injected.fault)
+        } else {
+            let injected = &state.faults[index];
+            (injected.path.clone(), injected.fault.clone())
+        };
+        match fault {
+            Fault::Error(kind) => Err(io::Error::from(kind)),
+            Fault::Vanish => {
+                state.nodes.remove(&path);
+                Ok(state)
+            }
+            Fault::Appear(other) => {
+                let node = state.new_file(Vec::new(), SystemTime::now());
+                state.nodes.insert(other, node);
+                Ok(state)
+            }
+        }
+    }
+}
+
+impl State {
+    fn new_file(&mut self, data: Vec<u8>, modified: SystemTime) -> Node {
+        self.next_id += 1;
+        Node::File {
+            id: self.next_id,
+            data,
+            modified,
+        }
+    }
+
+    fn file_id(&self, path: &Path) -> Option<u64> {
+        match self.nodes.get(path) {
+            Some(Node::File { id, .. }) => Some(*id),
+            _ => None,
+        }
+    }
+
+    fn link_count(&self, id: u64) -> u64 {
+        self.nodes
+            .values()
+            .filter(|n| matches!(n, Node::File { id: other, .. } if *other == id))
+            .count() as u64
+    }
+
+    fn create_dirs(&mut self, path: &Path) {
+        for ancestor in path.ancestors() {
+            if ancestor.as_os_str().is_empty() || ancestor.parent().is_none() {
+                continue;
+            }
+            self.nodes
+                .entry(ancestor.to_path_buf())
+                .or_insert(Node::Dir);
+        }
+    }
+
+    /// Roots and empty paths always exist as directories.
+    fn is_dir(&self, path: &Path) -> bool {
+        path.as_os_str().is_empty()
+            || path.parent().is_none()
+            || matches!(self.nodes.get(path), Some(Node::Dir))
+    }
+
+    fn require_parent(&self, path: &Path) -> io::Result<()> {
+        match path.parent() {
+            Some(parent) if !self.is_dir(parent) => Err(io::Error::from(io::ErrorKind::NotFound)),
+            _ => Ok(()),
+        }
+    }
+
+    fn device(&self, path: &Path) -> Option<usize> {
+        self.devices
+            .iter()
+            .enumerate()
+            .filter(|(_, d)| path.starts_with(&d.root))
+            .max_by_key(|(_, d)| d.root.components().count())
+            .map(|(i, _)| i)
+    }
+
+    /// Resolves a symlink target lexically, since there is no real disk to
+    /// canonicalize against.
+    fn resolve(&self, link: &Path, target: &Path) -> PathBuf {
+        normalize_lexically(&link.parent().unwrap_or(Path::new("")).join(target))
+    }
+}
+
+fn not_found() -> io::Error {
+    io::Error::from(io::ErrorKind::NotFound)
+}
+
+impl FileSystem for MemoryFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        let state = self.begin(FsOp::ReadDir, &[dir])?;
+        if !state.is_dir(dir) {
+            return Err(match state.nodes.get(dir) {
+                Some(_) => io::Error::from(io::ErrorKind::NotADirectory),
+                None => not_found(),
+            });
+        }
+        Ok(state
+            .nodes
+            .keys()
+            .filter(|p| p.parent() == Some(dir))
+            .cloned()
+            .collect())
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMeta> {
+        let state = self.begin(FsOp::Metadata, &[path])?;
+        let meta = match state.nodes.get(path).ok_or_else(not_found)? {
+            Node::File { id, data, modified } => FileMeta {
+                kind: FileKind::File,
+                len: data.len() as u64,
+                modified: Some(*modified),
+                links: state.link_count(*id),
+            },
+            Node::Dir => FileMeta {
+                kind: FileKind::Dir,
+                len: 0,
+                modified: None,
+                links: 1,
+            },
+            Node::Symlink { target, modified } => FileMeta {
+                kind: FileKind::Symlink,
+                len: target.as_os_str().len() as u64,
+                modified: Some(*modified),
+                links: 1,
+            },
+        };
+        Ok(meta)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        let state = self.lock();
+        let mut current = path.to_path_buf();
+        // Follow a bounded number of links, like the OS does.
+        for _ in 0..40 {
+            match state.nodes.get(&current) {
+                Some(Node::Symlink { target, .. }) => current = state.resolve(&current, target),
+                Some(_) => return true,
+                None => return state.is_dir(&current),
+            }
+        }
+        false
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.begin(FsOp::CreateDir, &[path])?;
+        let blocked = path.ancestors().any(|a| {
+            matches!(
+                state.nodes.get(a),
+                Some(Node::File { .. } | Node::Symlink { .. })
+            )
+        });
+        if blocked {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        state.create_dirs(path);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        let mut state = self.begin(FsOp::Rename, &[from, to])?;
+        if !state.nodes.contains_key(from) {
+            return Err(not_found());
+        }
+        state.require_parent(to)?;
+        if state.device(from) != state.device(to) {
+            return Err(io::Error::from(io::ErrorKind::CrossesDevices));
+        }
+        if matches!(state.nodes.get(to), Some(Node::Dir)) {
+            return Err(io::Error::from(io::ErrorKind::IsADirectory));
+        }
+        let node = state.nodes.remove(from).ok_or_else(not_found)?;
+        state.nodes.insert(to.to_path_buf(), node);
+        Ok(())
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path, cancelled: &dyn Fn() -> bool) -> io::Result<()> {
+        let data = {
+            let state = self.begin(FsOp::Copy, &[from, to])?;
+            match state.nodes.get(from) {
+                Some(Node::File { data, .. }) => data.clone(),
+                Some(_) => return Err(io::Error::from(io::ErrorKind::InvalidInput)),
+                None => return Err(not_found()),
+            }
+        };
+        // Check for cancellation per chunk without holding the lock, so the
+        // callback may inspect this filesystem.
+        for _ in 0..data.len().div_ceil(COPY_CHUNK_BYTES).max(1) {
+            if cancelled() {
+                return Err(cancelled_error());
+            }
+        }
+
+        let mut state = self.lock();
+        state.require_parent(to)?;
+        if matches!(state.nodes.get(to), Some(Node::Dir)) {
+            return Err(io::Error::from(io::ErrorKind::IsADirectory));
+        }
+        if let Some(device) = state.device(to) {
+            let free = &mut state.devices[device].free;
+            if *free < data.len() as u64 {
+                return Err(io::Error::from(io::ErrorKind::StorageFull));
+            }
+            *free -= data.len() as u64;
+        }
+        let node = state.new_file(data, SystemTime::now());
+        state.nodes.insert(to.to_path_buf(), node);
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        let mut state = self.begin(FsOp::Remove, &[path])?;
+        match state.nodes.get(path) {
+            Some(Node::Dir) => return Err(io::Error::from(io::ErrorKind::IsADirectory)),
+            None => return Err(not_found()),
+            _ => {}
+        }
+        if let Some(Node::File { id, data, .. }) = state.nodes.remove(path) {
+            if let Some(device) = state.device(path).filter(|_| state.link_count(id) == 0) {
+                state.devices[device].free += data.len() as u64;
+            }
+        }
+        Ok(())
+    }
+
+    fn purge(&self, path: &Path, action: RetentionAction) -> io::Result<()> {
+        drop(self.begin(FsOp::Purge, &[path])?);
+        self.remove_file(path)?;
+        if action == RetentionAction::Trash {
+            self.lock().trashed.push(path.to_path_buf());
+        }
+        Ok(())
+    }
+
+    fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+        let state = self.begin(FsOp::Read, &[path])?;
+        match state.nodes.get(path) {
+            Some(Node::File { data, .. }) => Ok(data.clone()),
+            Some(_) => Err(io::Error::from(io::ErrorKind::InvalidInput)),
+            None => Err(not_found()),
+        }
+    }
+
+    fn write_file(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        let mut state = self.begin(FsOp::Write, &[path])?;
+        state.require_parent(path)?;
+        if matches!(state.nodes.get(path), Some(Node::Dir)) {
+            return Err(io::Error::from(io::ErrorKind::IsADirectory));
+        }
+        let node = state.new_file(data.to_vec(), SystemTime::now());
+        state.nodes.insert(path.to_path_buf(), node);
+        Ok(())
+    }
+
+    fn symlink_file(&self, target: &Path, link: &Path) -> io::Result<()> {
+        let mut state = self.begin(FsOp::Symlink, &[link])?;
+        state.require_parent(link)?;
+        if state.nodes.contains_key(link) {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        state.nodes.insert(
+            link.to_path_buf(),
+            Node::Symlink {
+                target: target.to_path_buf(),
+                modified: SystemTime::now(),
+            },
+        );
+        Ok(())
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        let state = self.begin(FsOp::ReadLink, &[path])?;
+        match state.nodes.get(path) {
+            Some(Node::Symlink { target, .. }) => Ok(target.clone()),
+            Some(_) => Err(io::Error::from(io::ErrorKind::InvalidInput)),
+            None => Err(not_found()),
+        }
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+        let mut state = self.begin(FsOp::HardLink, &[original, link])?;
+        state.require_parent(link)?;
+        let node = match state.nodes.get(original) {
+            Some(node @ Node::File { .. }) => node.clone(),
+            Some(_) => return Err(io::Error::from(io::ErrorKind::InvalidInput)),
+            None => return Err(not_found()),
+        };
+        if state.nodes.contains_key(link) {
+            return Err(io::Error::from(io::ErrorKind::AlreadyExists));
+        }
+        if state.device(original) != state.device(link) {
+            return Err(io::Error::from(io::ErrorKind::CrossesDevices));
+        }
+        state.nodes.insert(link.to_path_buf(), node);
+        Ok(())
+    }
+
+    fn same_file(&self, a: &Path, b: &Path) -> bool {
+        let state = self.lock();
+        matches!((state.file_id(a), state.file_id(b)), (Some(a), Some(b)) if a == b)
+    }
+
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        let state = self.lock();
+        Ok(state
+            .device(path)
+            .map_or(u64::MAX, |device| state.devices[device].free))
+    }
+
+    fn same_filesystem(&self, a: &Path, b: &Path) -> bool {
+        let state = self.lock();
+        state.device(a) == state.device(b)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hard_links_share_identity_and_space() {
+        let fs = MemoryFs::new();
+        fs.mount("/mnt/usb", 10);
+        fs.add_file("/mnt/usb/a.bin", vec![0u8; 4]);
+        let (a, b) = (Path::new("/mnt/usb/a.bin"), Path::new("/mnt/usb/b.bin"));
+
+        fs.hard_link(a, b).unwrap();
+        assert!(fs.same_file(a, b));
+        assert_eq!(fs.metadata(a).unwrap().links, 2);
+
+        // Space only comes back once the last name is gone.
+        fs.remove_file(a).unwrap();
+        assert_eq!(fs.metadata(b).unwrap().links, 1);
+        assert_eq!(fs.available_space(b).unwrap(), 10);
+        fs.remove_file(b).unwrap();
+        assert_eq!(fs.available_space(b).unwrap(), 14);
+
+        let err = fs
+            .hard_link(Path::new("/mnt/usb/none"), Path::new("/dl/c"))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_rename_across_mounts_crosses_devices() {
+        let fs = MemoryFs::new();
+        fs.add_file("/dl/a.bin", b"abc".to_vec());
+        fs.mount("/mnt/usb", 10);
+
+        let err = fs
+            .rename(Path::new("/dl/a.bin"), Path::new("/mnt/usb/a.bin"))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::CrossesDevices);
+        assert!(!fs.same_filesystem(Path::new("/dl"), Path::new("/mnt/usb")));
+        assert!(fs.same_filesystem(Path::new("/mnt/usb"), Path::new("/mnt/usb/x")));
+    }
+
+    #[test]
+    fn test_copy_consumes_and_remove_frees_device_space() {
+        let fs = MemoryFs::new();
+        fs.add_file("/dl/a.bin", vec![0u8; 8]);
+        fs.mount("/mnt/usb", 10);
+
+        fs.copy_file(Path::new("/dl/a.bin"), Path::new("/mnt/usb/a.bin"), &|| {
+            false
+        })
+        .unwrap();
+        assert_eq!(fs.available_space(Path::new("/mnt/usb")).unwrap(), 2);
+
+        let err = fs
+            .copy_file(Path::new("/dl/a.bin"), Path::new("/mnt/usb/b.bin"), &|| {
+                false
+            })
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::StorageFull);
+
+        fs.remove_file(Path::new("/mnt/usb/a.bin")).unwrap();
+        assert_eq!(fs.available_space(Path::new("/mnt/usb")).unwrap(), 10);
+    }
+
+    #[test]
+    fn test_injected_faults() {
+        let fs = MemoryFs::new();
+        fs.add_file("/dl/a.bin", b"a".to_vec());
+        fs.add_dir("/out");
+        fs.inject_once(
+            FsOp::Rename,
+            "/dl/a.bin",
+            Fault::Error(io::ErrorKind::PermissionDenied),
+        );
+
+        let err = fs
+            .rename(Path::new("/dl/a.bin"), Path::new("/out/a.bin"))
+            .unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::PermissionDenied);
+        // One-shot faults clear after firing.
+        fs.rename(Path::new("/dl/a.bin"), Path::new("/out/a.bin"))
+            .unwrap();
+
+        fs.inject(FsOp::Remove, "/out/a.bin", Fault::Vanish);
+        let err = fs.remove_file(Path::new("/out/a.bin")).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::NotFound);
+    }
+
+    #[test]
+    fn test_exists_follows_symlinks() {
+        let fs = MemoryFs::new();
+        fs.add_file("/out/a.bin", b"a".to_vec());
+        fs.add_symlink("/dl/a.bin", "../out/a.bin");
+        fs.add_symlink("/dl/dangling", "/out/missing");
+
+        assert!(fs.exists(Path::new("/dl/a.bin")));
+        assert!(!fs.exists(Path::new("/dl/dangling")));
+        assert!(fs.metadata(Path::new("/dl/a.bin")).unwrap().is_symlink());
+        assert_eq!(
+            fs.read_dir(Path::new("/dl")).unwrap(),
+            vec![PathBuf::from("/dl/a.bin"), PathBuf::from("/dl/dangling")]
+        );
+    }
+}
@@ -0,0 +1,331 @@
+//! Filesystem access used by the organizer.
+//!
+//! The organizer talks to the disk only through [`FileSystem`], so the same
+//! pipeline runs against the real disk ([`RealFs`]) or against [`MemoryFs`],
+//! which can inject errors such as `EACCES`, `EXDEV` or `ENOSPC` and simulate
+//! files changing underneath a pass.
+
+mod memory;
+
+pub use memory::{Fault, FsOp, MemoryFs};
+
+use crate::types::RetentionAction;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+#[cfg(windows)]
+use std::os::windows::process::CommandExt;
+
+/// Chunk size for [`FileSystem::copy_file`]; cancellation is checked between chunks.
+pub const COPY_CHUNK_BYTES: usize = 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileKind {
+    File,
+    Dir,
+    Symlink,
+    Other,
+}
+
+/// The parts of file metadata the organizer looks at. Symlinks are reported
+/// as [`FileKind::Symlink`], not followed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FileMeta {
+    pub kind: FileKind,
+    pub len: u64,
+    pub modified: Option<SystemTime>,
+    /// Number of hard links to the file; more than one means another name
+    /// shares its contents.
+    pub links: u64,
+}
+
+impl FileMeta {
+    pub fn is_file(&self) -> bool {
+        self.kind == FileKind::File
+    }
+
+    pub fn is_dir(&self) -> bool {
+        self.kind == FileKind::Dir
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.kind == FileKind::Symlink
+    }
+}
+
+impl From<fs::Metadata> for FileMeta {
+    fn from(meta: fs::Metadata) -> Self {
+        let file_type = meta.file_type();
+        let kind = if file_type.is_symlink() {
+            FileKind::Symlink
+        } else if file_type.is_dir() {
+            FileKind::Dir
+        } else if file_type.is_file() {
+            FileKind::File
+        } else {
+            FileKind::Other
+        };
+        #[cfg(unix)]
+        let links = std::os::unix::fs::MetadataExt::nlink(&meta);
+        // Windows only reports link counts through a file handle; see
+        // `RealFs::metadata`.
+        #[cfg(not(unix))]
+        let links = 1;
+        Self {
+            kind,
+            len: meta.len(),
+            modified: meta.modified().ok(),
+            links,
+        }
+    }
+}
+
+/// Filesystem operations needed to organize a download folder.
+pub trait FileSystem: Send + Sync {
+    /// Lists the entries directly inside `dir`.
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>>;
+
+    /// Metadata for `path` without following symlinks.
+    fn metadata(&self, path: &Path) -> io::Result<FileMeta>;
+
+    /// Returns `true` if `path` exists, following symlinks.
+    fn exists(&self, path: &Path) -> bool;
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()>;
+
+    /// Renames `from` to `to`. Fails with [`io::ErrorKind::CrossesDevices`]
+    /// when they are on different filesystems.
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()>;
+
+    /// Copies file contents and permissions from `from` to `to` in
+    /// [`COPY_CHUNK_BYTES`] chunks, failing with [`io::ErrorKind::Interrupted`]
+    /// as soon as `cancelled` returns `true`.
+    fn copy_file(&self, from: &Path, to: &Path, cancelled: &dyn Fn() -> bool) -> io::Result<()>;
+
+    fn remove_file(&self, path: &Path) -> io::Result<()>;
+
+    /// Removes a file for a retention policy: deletes it, or moves it to the
+    /// operating system's trash.
+    fn purge(&self, path: &Path, action: RetentionAction) -> io::Result<()>;
+
+    fn read_file(&self, path: &Path) -> io::Result<Vec<u8>>;
+
+    /// Creates or replaces the file at `path` with `data`.
+    fn write_file(&self, path: &Path, data: &[u8]) -> io::Result<()>;
+
+    /// Creates a symlink at `link` pointing to the file `target`.
+    fn symlink_file(&self, target: &Path, link: &Path) -> io::Result<()>;
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf>;
+
+    /// Creates a hard link at `link` to the file `original`. Fails when they
+    /// are on different filesystems.
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()>;
+
+    /// Returns `true` if `a` and `b` name the same file, e.g. because one is
+    /// a hard link to the other.
+    fn same_file(&self, a: &Path, b: &Path) -> bool;
+
+    /// Marks `path` hidden where the platform has such a flag. The default
+    /// does nothing.
+    fn hide(&self, _path: &Path) -> io::Result<()> {
+        Ok(())
+    }
+
+    /// Bytes available to unprivileged users on the filesystem holding `path`.
+    fn available_space(&self, path: &Path) -> io::Result<u64>;
+
+    /// Returns `true` if `a` and `b` are on the same filesystem, i.e. a rename
+    /// between them does not copy data.
+    fn same_filesystem(&self, a: &Path, b: &Path) -> bool;
+}
+
+/// [`FileSystem`] backed by `std::fs` and the platform disk APIs.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RealFs;
+
+impl FileSystem for RealFs {
+    fn read_dir(&self, dir: &Path) -> io::Result<Vec<PathBuf>> {
+        fs::read_dir(dir)?
+            .map(|entry| entry.map(|e| e.path()))
+            .collect()
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FileMeta> {
+        let meta = FileMeta::from(fs::symlink_metadata(path)?);
+        #[cfg(windows)]
+        if meta.is_file() {
+            if let Ok(id) = crate::platform::file_id(path) {
+                return Ok(FileMeta {
+                    links: id.links,
+                    ..meta
+                });
+            }
+        }
+        Ok(meta)
+    }
+
+    fn exists(&self, path: &Path) -> bool {
+        path.exists()
+    }
+
+    fn create_dir_all(&self, path: &Path) -> io::Result<()> {
+        fs::create_dir_all(path)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> io::Result<()> {
+        fs::rename(from, to)
+    }
+
+    fn copy_file(&self, from: &Path, to: &Path, cancelled: &dyn Fn() -> bool) -> io::Result<()> {
+        use std::io::{Read, Write};
+
+        let mut reader = fs::File::open(from)?;
+        let permissions = reader.metadata()?.permissions();
+        let mut writer = fs::File::create(to)?;
+        let mut buf = vec![0u8; COPY_CHUNK_BYTES];
+        loop {
+            if cancelled() {
+                return Err(cancelled_error());
+            }
+            let n = match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e),
+            };
+            writer.write_all(&buf[..n])?;
+        }
+        writer.sync_all()?;
+        drop(writer);
+        fs::set_permissions(to, permissions)
+    }
+
+    fn remove_file(&self, path: &Path) -> io::Result<()> {
+        fs::remove_file(path)
+    }
+
+    fn purge(&self, path: &Path, action: RetentionAction) -> io::Result<()> {
+        match action {
+            RetentionAction::Delete => fs::remove_file(path),
+            RetentionAction::Trash => trash::delete(path).map_err(io::Error::other),
+        }
+    }
+
+    fn read_file(&self, path: &Path) -> io::Result<Vec<u8>> {
+        fs::read(path)
+    }
+
+    fn write_file(&self, path: &Path, data: &[u8]) -> io::Result<()> {
+        fs::write(path, data)
+    }
+
+    fn symlink_file(&self, target: &Path, link: &Path) -> io::Result<()> {
+        #[cfg(windows)]
+        let res = std::os::windows::fs::symlink_file(target, link);
+        #[cfg(unix)]
+        let res = std::os::unix::fs::symlink(target, link);
+        res
+    }
+
+    fn read_link(&self, path: &Path) -> io::Result<PathBuf> {
+        fs::read_link(path)
+    }
+
+    fn hard_link(&self, original: &Path, link: &Path) -> io::Result<()> {
+        fs::hard_link(original, link)
+    }
+
+    fn same_file(&self, a: &Path, b: &Path) -> bool {
+        match (crate::platform::file_id(a), crate::platform::file_id(b)) {
+            (Ok(a), Ok(b)) => a.volume == b.volume && a.index == b.index,
+            _ => false,
+        }
+    }
+
+    #[cfg(windows)]
+    fn hide(&self, path: &Path) -> io::Result<()> {
+        std::process::Command::new("attrib")
+            .arg("+h")
+            .arg(path)
+            .arg("/L")
+            .creation_flags(0x08000000) // CREATE_NO_WINDOW
+            .status()
+            .map(|_| ())
+    }
+
+    fn available_space(&self, path: &Path) -> io::Result<u64> {
+        crate::platform::available_space(path)
+    }
+
+    fn same_filesystem(&self, a: &Path, b: &Path) -> bool {
+        crate::platform::same_filesystem(a, b)
+    }
+}
+
+/// Resolves `.` and `..` in `path` without touching the disk. Symlink
+/// targets are compared this way so dangling or not-yet-created links work.
+pub fn normalize_lexically(path: &Path) -> PathBuf {
+    use std::path::Component;
+
+    let mut resolved = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Error returned by [`FileSystem::copy_file`] when the copy is cancelled.
+pub(crate) fn cancelled_error() -> io::Error {
+    io::Error::new(io::ErrorKind::Interrupted, "organize pass cancelled")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_real_fs_metadata_does_not_follow_symlinks() {
+        let root = TempDir::new().unwrap();
+        let file = root.path().join("a.txt");
+        fs::write(&file, b"abc").unwrap();
+
+        let meta = RealFs.metadata(&file).unwrap();
+        assert!(meta.is_file());
+        assert_eq!(meta.len, 3);
+        assert!(meta.modified.is_some());
+
+        #[cfg(unix)]
+        {
+            let link = root.path().join("link");
+            RealFs.symlink_file(&file, &link).unwrap();
+            assert!(RealFs.metadata(&link).unwrap().is_symlink());
+            assert_eq!(RealFs.read_link(&link).unwrap(), file);
+        }
+    }
+
+    #[test]
+    fn test_real_fs_read_dir_lists_children() {
+        let root = TempDir::new().unwrap();
+        fs::write(root.path().join("a"), b"").unwrap();
+        fs::create_dir(root.path().join("sub")).unwrap();
+
+        let mut names: Vec<_> = RealFs
+            .read_dir(root.path())
+            .unwrap()
+            .into_iter()
+            .map(|p| p.file_name().unwrap().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["a", "sub"]);
+    }
+}
@@ -1,9 +1,10 @@
 use crate::state::AppState;
 use std::fs;
-use std::io::{BufRead, BufReader};
+use std::io::{BufRead, BufReader, Read};
+use std::path::Path;
 use std::sync::LazyLock;
 
-use chrono::{Datelike, Local, NaiveDateTime};
+use chrono::{Datelike, NaiveDateTime};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use tauri::State;
@@ -21,6 +22,9 @@ pub struct ActivityLogDto {
     pub rule_name: String,
     pub status: String,
     pub symlink_info: Option<String>,
+    /// Extra context for non-move entries, e.g. why a file was purged.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub detail: Option<String>,
 }
 
 /// Activity statistics
@@ -56,7 +60,92 @@ static LOG_LINE_RE: LazyLock<Regex> = LazyLock::new(|| {
         .expect("LOG_LINE_RE is a valid pattern")
 });
 
+/// Matches entries written for files removed by a retention policy:
+/// `[2024-01-15 10:30:00] Purged: <path> (<rule>) <deleted|trashed>: <reason>`
+static PURGE_LINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"^\[(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2})\] Purged: (.+) \(([^)]+)\)\s*(.*)$")
+        .expect("PURGE_LINE_RE is a valid pattern")
+});
+
+/// Matches entries written for skipped, failed and restored (undone) files,
+/// for breadcrumbs removed from the download folder and for `on_moved` hooks:
+/// `[2024-01-15 10:30:00] <Skipped|Failed|Restored|Removed|Hook>: <path> (<rule>) -- <note>`
+static STATUS_LINE_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r"^\[(\d{4}-\d{2}-\d{2} \d{2}:\d{2}:\d{2})\] (Skipped|Failed|Restored|Removed|Hook): (.+?) \(([^)]*)\) -- (.*)$",
+    )
+    .expect("STATUS_LINE_RE is a valid pattern")
+});
+
+fn parse_status_line(line: &str, id: usize) -> Option<ActivityLogDto> {
+    if let Some(caps) = PURGE_LINE_RE.captures(line) {
+        return Some(status_entry(
+            id,
+            caps.get(1)?.as_str(),
+            "purged",
+            caps.get(2)?.as_str(),
+            caps.get(3)?.as_str(),
+            caps.get(4).map_or("", |m| m.as_str()),
+        ));
+    }
+    let caps = STATUS_LINE_RE.captures(line)?;
+    let status = match caps.get(2)?.as_str() {
+        "Skipped" => "skipped",
+        "Restored" => "restored",
+        "Removed" => "removed",
+        "Hook" => "hook",
+        _ => "failed",
+    };
+    Some(status_entry(
+        id,
+        caps.get(1)?.as_str(),
+        status,
+        caps.get(3)?.as_str(),
+        caps.get(4)?.as_str(),
+        caps.get(5).map_or("", |m| m.as_str()),
+    ))
+}
+
+fn status_entry(
+    id: usize,
+    timestamp: &str,
+    status: &str,
+    path: &str,
+    rule_name: &str,
+    detail: &str,
+) -> ActivityLogDto {
+    let detail = Some(detail.trim().to_string()).filter(|d| !d.is_empty());
+    let filename = std::path::Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+        .to_string();
+    let ext = std::path::Path::new(&filename)
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+    let (icon, icon_color) = super::ui_helpers::derive_file_icon_and_color(&ext);
+
+    ActivityLogDto {
+        id: id.to_string(),
+        timestamp: timestamp.to_string(),
+        filename,
+        icon,
+        icon_color,
+        source_path: path.to_string(),
+        dest_path: String::new(),
+        rule_name: rule_name.to_string(),
+        status: status.to_string(),
+        symlink_info: None,
+        detail,
+    }
+}
+
 fn parse_log_line(line: &str, id: usize) -> Option<ActivityLogDto> {
+    if let Some(entry) = parse_status_line(line, id) {
+        return Some(entry);
+    }
     let caps = LOG_LINE_RE.captures(line)?;
 
     let timestamp = caps
@@ -100,6 +189,7 @@ fn parse_log_line(line: &str, id: usize) -> Option<ActivityLogDto> {
         rule_name,
         status: "success".to_string(),
         symlink_info,
+        detail: None,
     })
 }
 
@@ -118,30 +208,73 @@ fn read_log_entries(reader: impl BufRead) -> Vec<ActivityLogDto> {
         .collect()
 }
 
+/// Opens the activity log for reading. When `include_archived` is set, the
+/// decompressed rotation archives are chained in front of the live log so
+/// entries stay in chronological order.
+///
+/// Returns `Ok(None)` when there is nothing to read.
+fn open_log_history(
+    log_path: &Path,
+    include_archived: bool,
+) -> Result<Option<Box<dyn BufRead>>, String> {
+    let archived = if include_archived {
+        harbor_core::downloads::read_log_archives(log_path)
+    } else {
+        String::new()
+    };
+
+    if !log_path.exists() {
+        if archived.is_empty() {
+            return Ok(None);
+        }
+        return Ok(Some(Box::new(std::io::Cursor::new(archived))));
+    }
+
+    let file = fs::File::open(log_path).map_err(|e| format!("Failed to open log file: {}", e))?;
+    let reader = std::io::Cursor::new(archived).chain(file);
+    Ok(Some(Box::new(BufReader::new(reader))))
+}
+
+/// Case-insensitive substring match over the user-visible fields of an entry.
+fn matches_search(log: &ActivityLogDto, query: &str) -> bool {
+    let query = query.to_lowercase();
+    [
+        &log.filename,
+        &log.source_path,
+        &log.dest_path,
+        &log.rule_name,
+    ]
+    .iter()
+    .any(|field| field.to_lowercase().contains(&query))
+}
+
 #[tauri::command]
 pub async fn get_activity_logs(
     state: State<'_, AppState>,
     limit: Option<usize>,
     offset: Option<usize>,
+    search: Option<String>,
+    include_archived: Option<bool>,
 ) -> Result<ActivityLogsResponse, String> {
     let log_path = state.recent_log_path();
     let limit = limit.unwrap_or(50);
     let offset = offset.unwrap_or(0);
 
-    if !log_path.exists() {
+    let Some(reader) = open_log_history(&log_path, include_archived.unwrap_or(false))? else {
         return Ok(ActivityLogsResponse {
             logs: vec![],
             total: 0,
             has_more: false,
         });
-    }
-
-    let file = fs::File::open(&log_path).map_err(|e| format!("Failed to open log file: {}", e))?;
-    let reader = BufReader::new(file);
+    };
 
     // Read all lines and parse them
     let mut all_logs = read_log_entries(reader);
 
+    if let Some(query) = search.as_deref().map(str::trim).filter(|q| !q.is_empty()) {
+        all_logs.retain(|log| matches_search(log, query));
+    }
+
     // Reverse to show most recent first
     all_logs.reverse();
 
@@ -172,15 +305,23 @@ pub async fn get_activity_stats(state: State<'_, AppState>) -> Result<ActivitySt
     }
 
     let file = fs::File::open(&log_path).map_err(|e| format!("Failed to open log file: {}", e))?;
-    let reader = BufReader::new(file);
+    let now = state.clock.local_now().naive_local();
+    Ok(compute_activity_stats(BufReader::new(file), now))
+}
 
-    let logs = read_log_entries(reader);
+/// Tallies successful moves overall, since midnight and since Monday
+/// relative to `now` (local time).
+fn compute_activity_stats<R: BufRead>(reader: R, now: NaiveDateTime) -> ActivityStats {
+    // Purges and other housekeeping entries are not moves.
+    let logs: Vec<ActivityLogDto> = read_log_entries(reader)
+        .into_iter()
+        .filter(|log| log.status == "success")
+        .collect();
     let total = logs.len();
     let mut rule_counts: std::collections::HashMap<String, usize> =
         std::collections::HashMap::new();
 
     // Compute time boundaries for today and this week.
-    let now = Local::now().naive_local();
     let today_start = now.date().and_hms_opt(0, 0, 0).unwrap_or(now);
     let days_since_monday = now.weekday().num_days_from_monday() as i64;
     let week_start = today_start - chrono::Duration::days(days_since_monday);
@@ -208,12 +349,12 @@ pub async fn get_activity_stats(state: State<'_, AppState>) -> Result<ActivitySt
         .max_by_key(|(_, count)| *count)
         .map(|(name, _)| name);
 
-    Ok(ActivityStats {
+    ActivityStats {
         total_files_moved: total,
         files_moved_today,
         files_moved_this_week,
         most_active_rule,
-    })
+    }
 }
 
 #[tauri::command]
@@ -225,6 +366,9 @@ pub async fn clear_activity_logs(state: State<'_, AppState>) -> Result<(), Strin
             .map_err(|e| format!("Failed to clear log file: {}", e))?;
     }
 
+    harbor_core::downloads::remove_log_archives(&log_path)
+        .map_err(|e| format!("Failed to clear log archives: {}", e))?;
+
     Ok(())
 }
 
@@ -288,6 +432,124 @@ mod tests {
         assert_eq!(dto3.rule_name, "Docs");
     }
 
+    #[test]
+    fn test_parse_purge_line() {
+        let sep = std::path::MAIN_SEPARATOR;
+        let line = format!(
+            "[2024-02-01 08:00:00] Purged: C:{sep}Installers{sep}setup (1).exe (Installers) trashed: older than 30 days"
+        );
+        let dto = parse_log_line(&line, 4).unwrap();
+        assert_eq!(dto.status, "purged");
+        assert_eq!(dto.timestamp, "2024-02-01 08:00:00");
+        assert_eq!(dto.filename, "setup (1).exe");
+        assert_eq!(dto.rule_name, "Installers");
+        assert_eq!(dto.dest_path, "");
+        assert_eq!(dto.detail.as_deref(), Some("trashed: older than 30 days"));
+    }
+
+    #[test]
+    fn test_parse_skipped_and_failed_lines() {
+        let sep = std::path::MAIN_SEPARATOR;
+        let line = format!(
+            "[2024-02-01 08:00:00] Failed: C:{sep}Downloads{sep}report (1).pdf (Documents) -- Access is denied. (os error 5)"
+        );
+        let dto = parse_log_line(&line, 5).unwrap();
+        assert_eq!(dto.status, "failed");
+        assert_eq!(dto.filename, "report (1).pdf");
+        assert_eq!(dto.rule_name, "Documents");
+        assert_eq!(
+            dto.detail.as_deref(),
+            Some("Access is denied. (os error 5)")
+        );
+
+        let line = format!(
+            "[2024-02-01 08:00:00] Skipped: C:{sep}Downloads{sep}big.iso (Disk Images) -- insufficient space: 10 bytes required, 5 available"
+        );
+        let dto = parse_log_line(&line, 6).unwrap();
+        assert_eq!(dto.status, "skipped");
+        assert_eq!(dto.rule_name, "Disk Images");
+        assert!(dto.detail.unwrap().starts_with("insufficient space"));
+
+        let line = format!(
+            "[2024-02-01 09:00:00] Restored: C:{sep}Downloads{sep}report.pdf (Documents) -- from C:{sep}Docs{sep}report.pdf"
+        );
+        let dto = parse_log_line(&line, 7).unwrap();
+        assert_eq!(dto.status, "restored");
+        assert_eq!(dto.filename, "report.pdf");
+        assert!(dto.detail.unwrap().starts_with("from "));
+
+        let line = format!(
+            "[2024-02-01 10:00:00] Removed: C:{sep}Downloads{sep}old.pdf () -- dangling symlink to C:{sep}Docs{sep}old.pdf"
+        );
+        let dto = parse_log_line(&line, 8).unwrap();
+        assert_eq!(dto.status, "removed");
+        assert_eq!(dto.rule_name, "");
+        assert!(dto.detail.unwrap().starts_with("dangling symlink"));
+
+        let line = format!(
+            "[2024-02-01 11:00:00] Hook: C:{sep}Docs{sep}report.pdf (Documents) -- `scan C:{sep}Docs{sep}report.pdf` exited with 2: infected"
+        );
+        let dto = parse_log_line(&line, 9).unwrap();
+        assert_eq!(dto.status, "hook");
+        assert_eq!(dto.filename, "report.pdf");
+        assert!(dto.detail.unwrap().ends_with("exited with 2: infected"));
+    }
+
+    #[test]
+    fn test_compute_activity_stats_uses_given_now() {
+        // Wednesday 2024-01-17; the week starts Monday 2024-01-15.
+        let now =
+            NaiveDateTime::parse_from_str("2024-01-17 12:00:00", "%Y-%m-%d %H:%M:%S").unwrap();
+        let log = "\
+[2024-01-12 09:00:00] a.pdf -> Docs/a.pdf (Docs)
+[2024-01-15 00:00:00] b.pdf -> Docs/b.pdf (Docs)
+[2024-01-17 08:00:00] c.png -> Images/c.png (Images)
+[2024-01-17 09:00:00] Purged: Docs/a.pdf (Docs) deleted: older than 1 days
+[2024-01-17 10:00:00] d.pdf -> Docs/d.pdf (Docs)
+";
+        let stats = compute_activity_stats(log.as_bytes(), now);
+        assert_eq!(stats.total_files_moved, 4);
+        assert_eq!(stats.files_moved_today, 2);
+        assert_eq!(stats.files_moved_this_week, 3);
+        assert_eq!(stats.most_active_rule.as_deref(), Some("Docs"));
+    }
+
+    #[test]
+    fn test_open_log_history_prepends_archives() {
+        use harbor_core::downloads::{
+            append_organize_results_to_log, OrganizeResult, LOG_MAX_LINES,
+        };
+
+        let tmp = tempfile::tempdir().unwrap();
+        let log_path = tmp.path().join("recent_moves.log");
+        let pad = "x".repeat(100);
+        let mut big = String::new();
+        for i in 0..LOG_MAX_LINES + 50 {
+            big.push_str(&format!(
+                "[2024-01-01 00:00:00] src/old_{i:05}_{pad}.txt -> dst/old_{i:05}.txt (Old)\n"
+            ));
+        }
+        fs::write(&log_path, big).unwrap();
+        append_organize_results_to_log(
+            &log_path,
+            &[OrganizeResult {
+                source: "src/new.txt".into(),
+                destination: "dst/new.txt".into(),
+                rule_name: "New".to_string(),
+                symlink_info: None,
+            }],
+        );
+
+        let live = read_log_entries(open_log_history(&log_path, false).unwrap().unwrap());
+        assert_eq!(live.len(), LOG_MAX_LINES);
+
+        let full = read_log_entries(open_log_history(&log_path, true).unwrap().unwrap());
+        assert_eq!(full.len(), LOG_MAX_LINES + 51);
+        assert_eq!(full[0].dest_path, "dst/old_00000.txt");
+        assert_eq!(full.last().unwrap().rule_name, "New");
+        assert!(full.iter().any(|log| matches_search(log, "OLD_00010")));
+    }
+
     #[test]
     fn test_read_log_entries() {
         let data = "Recent Moves Log\n----------------\n\nC:\\src\\a.txt -> C:\\dst\\a.txt (RuleA)\nC:\\src\\b.txt -> C:\\dst\\b.txt (RuleB)";
@@ -0,0 +1,168 @@
+//! Serves `harbor ctl` over the control socket; see [`harbor_core::control`].
+
+use crate::commands::settings::{
+    build_service_status, emit_lifecycle_status_for_app, impl_pause_service, impl_resume_service,
+    impl_trigger_organize_now, reload_config_impl, OrganizeNowResponse, PauseDuration,
+    ServiceStatus,
+};
+use crate::state::AppState;
+use anyhow::{anyhow, bail, Result};
+use harbor_core::control::{ControlHandler, OrganizeReport, ServiceReport};
+use harbor_core::downloads::load_downloads_config;
+use harbor_core::events::OrganizeEventDto;
+use std::sync::mpsc::Receiver;
+use tauri::{AppHandle, Manager};
+
+/// Runs control requests against the app's [`AppState`] and tells the UI
+/// and tray about the changes, like the matching Tauri commands do.
+pub struct AppControl {
+    app: AppHandle,
+}
+
+impl AppControl {
+    pub fn new(app: AppHandle) -> Self {
+        Self { app }
+    }
+
+    fn report(&self) -> Result<ServiceReport> {
+        let state = self.app.state::<AppState>();
+        let status = build_service_status(&state).map_err(|e| anyhow!(e))?;
+        let config = state.config.read().map_err(|e| anyhow!(e.to_string()))?;
+        Ok(service_report(
+            &status,
+            &config.download_dir,
+            &state.config_path.to_string_lossy(),
+        ))
+    }
+
+    /// Emits the service status after `result`, whether it failed or not.
+    fn notify<T>(&self, result: Result<T, String>) -> Result<ServiceReport> {
+        let state = self.app.state::<AppState>();
+        let _ = emit_lifecycle_status_for_app(&self.app, &state);
+        result.map_err(|e| anyhow!(e))?;
+        self.report()
+    }
+}
+
+impl ControlHandler for AppControl {
+    fn status(&self) -> Result<ServiceReport> {
+        self.report()
+    }
+
+    fn pause(&self, minutes: Option<u32>) -> Result<ServiceReport> {
+        let duration = minutes.map_or(PauseDuration::UntilTomorrow, PauseDuration::Minutes);
+        self.notify(impl_pause_service(&self.app.state::<AppState>(), duration))
+    }
+
+    fn resume(&self) -> Result<ServiceReport> {
+        self.notify(impl_resume_service(&self.app.state::<AppState>()))
+    }
+
+    fn organize_now(&self) -> Result<OrganizeReport> {
+        let state = self.app.state::<AppState>();
+        organize_report(&tauri::async_runtime::block_on(impl_trigger_organize_now(
+            &state,
+        )))
+    }
+
+    fn reload_config(&self) -> Result<ServiceReport> {
+        let state = self.app.state::<AppState>();
+        let result = load_downloads_config(&state.config_path)
+            .map_err(|e| format!("Failed to reload config: {e:#}"))
+            .and_then(|config| reload_config_impl(&state, config));
+        self.notify(result)
+    }
+
+    fn subscribe(&self) -> Receiver<OrganizeEventDto> {
+        self.app.state::<AppState>().control_events.subscribe()
+    }
+}
+
+fn service_report(status: &ServiceStatus, download_dir: &str, config_path: &str) -> ServiceReport {
+    ServiceReport {
+        state: status.lifecycle_state.clone(),
+        program: env!("CARGO_PKG_NAME").to_string(),
+        pid: status.pid,
+        download_dir: download_dir.to_string(),
+        config_path: config_path.to_string(),
+        uptime_secs: status.uptime_seconds,
+        paused_until: status.paused_until.clone(),
+        quiet_until: status.quiet_until.clone(),
+        next_scheduled_run: status.next_scheduled_run.clone(),
+    }
+}
+
+/// A pass that did not run, e.g. `busy`, is an error for the client.
+fn organize_report(response: &OrganizeNowResponse) -> Result<OrganizeReport> {
+    if response.status == "failed" || response.status == "busy" {
+        match response.errors.first() {
+            Some(error) => bail!("{} {error}", response.message),
+            None => bail!("{}", response.message),
+        }
+    }
+    Ok(OrganizeReport {
+        moved: response.moved_count,
+        skipped: response.skipped_count,
+        deferred: response.deferred_count,
+        errors: response.errors.clone(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_organize_report_fails_when_the_pass_did_not_run() {
+        let busy = OrganizeNowResponse {
+            status: "busy".to_string(),
+            message: "Another Harbor instance is organizing this folder right now.".to_string(),
+            errors: vec!["another Harbor instance is organizing".to_string()],
+            ..Default::default()
+        };
+        assert!(organize_report(&busy)
+            .unwrap_err()
+            .to_string()
+            .ends_with("right now. another Harbor instance is organizing"));
+
+        let partial = OrganizeNowResponse {
+            status: "partial_failure".to_string(),
+            moved_count: 3,
+            skipped_count: 1,
+            deferred_count: 40,
+            errors: vec!["a.pdf: permission denied".to_string()],
+            ..Default::default()
+        };
+        assert_eq!(
+            organize_report(&partial).unwrap(),
+            OrganizeReport {
+                moved: 3,
+                skipped: 1,
+                deferred: 40,
+                errors: vec!["a.pdf: permission denied".to_string()],
+            }
+        );
+    }
+
+    #[test]
+    fn test_service_report_carries_the_status() {
+        let status = ServiceStatus {
+            running: false,
+            lifecycle_state: "paused".to_string(),
+            pid: 42,
+            paused_until: Some("2024-01-15T11:00:00+00:00".to_string()),
+            ..Default::default()
+        };
+
+        let report = service_report(&status, "/home/u/Downloads", "/home/u/harbor.yaml");
+
+        assert_eq!(report.state, "paused");
+        assert_eq!(report.pid, 42);
+        assert_eq!(report.program, "harbor-tauri-app");
+        assert_eq!(report.download_dir, "/home/u/Downloads");
+        assert_eq!(
+            report.paused_until.as_deref(),
+            Some("2024-01-15T11:00:00+00:00")
+        );
+    }
+}
@@ -173,7 +173,7 @@ pub fn map_legacy_organize_error(message: &str) -> AppError {
     }
 }
 
-fn sanitize_path_for_ui(path: &str, download_dir: Option<&Path>) -> String {
+pub(crate) fn sanitize_path_for_ui(path: &str, download_dir: Option<&Path>) -> String {
     let path_buf = PathBuf::from(path);
     if let Some(base) = download_dir {
         if let Ok(relative) = path_buf.strip_prefix(base) {
@@ -0,0 +1,55 @@
+use harbor_core::events::OrganizeEvent;
+pub use harbor_core::events::OrganizeEventDto;
+use std::sync::{Arc, RwLock};
+
+/// Tauri event carrying live organizer progress to the frontend.
+pub const ORGANIZE_EVENT: &str = "harbor://organize-event";
+
+/// Callback that forwards organizer events out of the app state, e.g. to the
+/// webview. Shared between the watcher and "Organize now".
+pub type OrganizeEventListener = Arc<dyn Fn(&OrganizeEventDto) + Send + Sync>;
+
+/// Slot holding the current [`OrganizeEventListener`], if one is registered.
+pub type OrganizeEventSlot = Arc<RwLock<Option<OrganizeEventListener>>>;
+
+/// Sends `event` to the registered listener, if any.
+pub fn forward_organize_event(slot: &OrganizeEventSlot, event: &OrganizeEvent) {
+    let listener = match slot.read() {
+        Ok(guard) => guard.clone(),
+        Err(_) => return,
+    };
+    if let Some(listener) = listener {
+        listener(&OrganizeEventDto::from(event));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_forward_organize_event_reaches_listener() {
+        let slot: OrganizeEventSlot = Arc::new(RwLock::new(None));
+        let event = OrganizeEvent::ScanStarted {
+            download_dir: PathBuf::from("Downloads"),
+        };
+        // No listener registered: nothing happens.
+        forward_organize_event(&slot, &event);
+
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let sink = seen.clone();
+        *slot.write().unwrap() = Some(Arc::new(move |dto: &OrganizeEventDto| {
+            sink.lock().unwrap().push(dto.clone());
+        }));
+        forward_organize_event(&slot, &event);
+
+        assert_eq!(
+            *seen.lock().unwrap(),
+            vec![OrganizeEventDto::ScanStarted {
+                download_dir: "Downloads".to_string()
+            }]
+        );
+    }
+}
@@ -1,5 +1,7 @@
 pub mod activity;
+pub mod control;
 pub mod error_contract;
+pub mod events;
 pub mod rules;
 pub mod settings;
 pub mod ui_helpers;
@@ -1,8 +1,10 @@
 use crate::commands::settings::restart_service_if_running as restart_monitoring_if_running;
 use crate::state::AppState;
-use harbor_core::downloads::DownloadsConfig;
-use harbor_core::types::Rule;
+use harbor_core::downloads::{self, DownloadsConfig};
+use harbor_core::template;
+use harbor_core::types::{BreadcrumbKind, NormalizeStep, RetentionPolicy, Rule};
 
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::fs;
 use tauri::State;
@@ -38,20 +40,25 @@ where
 }
 
 /// Request struct for creating a new rule
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Default, serde::Deserialize)]
 pub struct CreateRuleRequest {
     pub name: String,
     pub extensions: Vec<String>,
     pub destination: String,
+    pub rename_to: Option<String>,
     pub pattern: Option<String>,
     pub min_size_bytes: Option<u64>,
     pub max_size_bytes: Option<u64>,
     pub create_symlink: Option<bool>,
+    pub breadcrumb: Option<BreadcrumbKind>,
     pub enabled: Option<bool>,
+    pub min_free_bytes: Option<u64>,
+    pub retention: Option<RetentionPolicy>,
+    pub normalize: Option<Vec<NormalizeStep>>,
 }
 
 /// Request struct for updating an existing rule
-#[derive(Debug, serde::Deserialize)]
+#[derive(Debug, Default, serde::Deserialize)]
 pub struct UpdateRuleRequest {
     pub id: String,
     pub name: Option<String>,
@@ -59,13 +66,23 @@ pub struct UpdateRuleRequest {
     pub extensions: NullableField<Vec<String>>,
     pub destination: Option<String>,
     #[serde(default)]
+    pub rename_to: NullableField<String>,
+    #[serde(default)]
     pub pattern: NullableField<String>,
     #[serde(default)]
     pub min_size_bytes: NullableField<u64>,
     #[serde(default)]
     pub max_size_bytes: NullableField<u64>,
     pub create_symlink: Option<bool>,
+    #[serde(default)]
+    pub breadcrumb: NullableField<BreadcrumbKind>,
     pub enabled: Option<bool>,
+    #[serde(default)]
+    pub min_free_bytes: NullableField<u64>,
+    #[serde(default)]
+    pub retention: NullableField<RetentionPolicy>,
+    #[serde(default)]
+    pub normalize: NullableField<Vec<NormalizeStep>>,
 }
 
 /// Frontend-facing rule representation
@@ -83,12 +100,24 @@ pub struct RuleDto {
     pub min_size_bytes: Option<u64>,
     /// Maximum file size in bytes
     pub max_size_bytes: Option<u64>,
-    /// Target directory for matched files
+    /// Target directory for matched files; may contain `{group}` placeholders
     pub destination: String,
+    /// `destination` with environment variables expanded
+    pub expanded_destination: String,
+    /// New file name (without extension) template
+    pub rename_to: Option<String>,
     /// Whether to create a symlink in the original location
     pub create_symlink: bool,
+    /// Breadcrumb kind overriding `create_symlink`
+    pub breadcrumb: Option<BreadcrumbKind>,
     /// Whether the rule is enabled
     pub enabled: bool,
+    /// Free space to keep on the destination drive after a cross-device move
+    pub min_free_bytes: Option<u64>,
+    /// Retention policy applied to the destination folder
+    pub retention: Option<RetentionPolicy>,
+    /// File name clean-ups applied before moving
+    pub normalize: Option<Vec<NormalizeStep>>,
     /// Whether this rule has a regex pattern (gives priority boost)
     pub has_pattern: bool,
     /// Whether this rule has size constraints (gives priority boost)
@@ -123,8 +152,14 @@ impl From<&Rule> for RuleDto {
             min_size_bytes: rule.min_size_bytes,
             max_size_bytes: rule.max_size_bytes,
             destination: rule.target_dir.clone(),
+            expanded_destination: downloads::expand_env(&rule.target_dir),
+            rename_to: rule.rename_to.clone(),
             create_symlink: rule.create_symlink,
+            breadcrumb: rule.breadcrumb,
             enabled: rule.enabled,
+            min_free_bytes: rule.min_free_bytes,
+            retention: rule.retention.clone(),
+            normalize: rule.normalize.clone(),
             has_pattern: rule.pattern.is_some(),
             has_size_constraint: rule.min_size_bytes.is_some() || rule.max_size_bytes.is_some(),
             icon,
@@ -158,6 +193,69 @@ fn validation_error(message: impl Into<String>, fields: Vec<&'static str>) -> St
     })
 }
 
+/// Rejects zero limits (which would empty the destination folder on the next
+/// pass) and drops policies that set no limit at all.
+fn normalize_retention(policy: Option<RetentionPolicy>) -> Result<Option<RetentionPolicy>, String> {
+    let Some(policy) = policy else {
+        return Ok(None);
+    };
+    if policy.max_age_days == Some(0)
+        || policy.max_files == Some(0)
+        || policy.max_total_bytes == Some(0)
+    {
+        return Err(validation_error(
+            "retention limits must be greater than zero",
+            vec!["retention"],
+        ));
+    }
+    Ok((!policy.is_unbounded()).then_some(policy))
+}
+
+/// Rejects retention on a destination with placeholders, which retention
+/// skips: the rule's files end up in folders it does not own.
+fn validate_retention_target(
+    retention: Option<&RetentionPolicy>,
+    destination: &str,
+) -> Result<(), String> {
+    if retention.is_some() && template::has_placeholders(destination) {
+        return Err(validation_error(
+            "retention is not available for a destination with placeholders",
+            vec!["retention", "destination"],
+        ));
+    }
+    Ok(())
+}
+
+/// Rejects a pattern that does not compile, undefined environment variables
+/// in the destination, and `{group}` placeholders in the destination or
+/// `rename_to` that are not named groups of the pattern.
+fn validate_templates(
+    pattern: Option<&str>,
+    destination: &str,
+    rename_to: Option<&str>,
+) -> Result<(), String> {
+    let pattern = pattern
+        .map(Regex::new)
+        .transpose()
+        .map_err(|e| validation_error(format!("invalid pattern: {e}"), vec!["pattern"]))?;
+    downloads::expand_env_strict(destination)
+        .map_err(|e| validation_error(format!("{e} in destination"), vec!["destination"]))?;
+    for (field, template) in [("destination", Some(destination)), ("rename_to", rename_to)] {
+        let Some(template) = template else {
+            continue;
+        };
+        let unknown = template::unknown_placeholders(template, pattern.as_ref())
+            .map_err(|e| validation_error(e.to_string(), vec![field]))?;
+        if let Some(name) = unknown.first() {
+            return Err(validation_error(
+                format!("{{{name}}} in {field} is not a named group in the pattern"),
+                vec![field, "pattern"],
+            ));
+        }
+    }
+    Ok(())
+}
+
 fn restart_service_if_running(state: &AppState) -> Result<(), String> {
     restart_monitoring_if_running(state)
 }
@@ -192,6 +290,15 @@ pub async fn impl_create_rule(
             return Err(format!("Rule with name '{}' already exists", rule.name));
         }
 
+        let retention = normalize_retention(rule.retention)?;
+        let rename_to = rule.rename_to.filter(|t| !t.trim().is_empty());
+        validate_templates(
+            rule.pattern.as_deref(),
+            &rule.destination,
+            rename_to.as_deref(),
+        )?;
+        validate_retention_target(retention.as_ref(), &rule.destination)?;
+
         // Convert extensions: remove leading dots if present
         let extensions: Vec<String> = rule
             .extensions
@@ -212,8 +319,15 @@ pub async fn impl_create_rule(
             min_size_bytes: rule.min_size_bytes,
             max_size_bytes: rule.max_size_bytes,
             target_dir: rule.destination,
+            rename_to,
             create_symlink: rule.create_symlink.unwrap_or(false),
+            breadcrumb: rule.breadcrumb,
             enabled: rule.enabled.unwrap_or(true),
+            min_free_bytes: rule.min_free_bytes,
+            retention,
+            normalize: rule.normalize.filter(|steps| !steps.is_empty()),
+            on_moved: None,
+            schedule: None,
         };
 
         config.rules.push(new.clone());
@@ -243,11 +357,16 @@ pub async fn impl_update_rule(
         name,
         extensions,
         destination,
+        rename_to,
         pattern,
         min_size_bytes,
         max_size_bytes,
         create_symlink,
+        breadcrumb,
         enabled,
+        min_free_bytes,
+        retention,
+        normalize,
     } = rule;
 
     let updated = {
@@ -279,6 +398,32 @@ pub async fn impl_update_rule(
             }
         }
 
+        let next_pattern = match pattern {
+            NullableField::Missing => r.pattern.clone(),
+            NullableField::Null => None,
+            NullableField::Value(next_pattern) => Some(next_pattern),
+        };
+        let next_rename_to = match rename_to {
+            NullableField::Missing => r.rename_to.clone(),
+            NullableField::Null => None,
+            NullableField::Value(template) => Some(template).filter(|t| !t.trim().is_empty()),
+        };
+        validate_templates(
+            next_pattern.as_deref(),
+            destination.as_deref().unwrap_or(&r.target_dir),
+            next_rename_to.as_deref(),
+        )?;
+
+        let next_retention = match retention {
+            NullableField::Missing => r.retention.clone(),
+            NullableField::Null => None,
+            NullableField::Value(policy) => normalize_retention(Some(policy))?,
+        };
+        validate_retention_target(
+            next_retention.as_ref(),
+            destination.as_deref().unwrap_or(&r.target_dir),
+        )?;
+
         if let Some(new_name) = name {
             r.name = new_name;
         }
@@ -303,13 +448,8 @@ pub async fn impl_update_rule(
         if let Some(dest) = destination {
             r.target_dir = dest;
         }
-        if pattern.is_provided() {
-            r.pattern = match pattern {
-                NullableField::Missing => r.pattern.clone(),
-                NullableField::Null => None,
-                NullableField::Value(next_pattern) => Some(next_pattern),
-            };
-        }
+        r.pattern = next_pattern;
+        r.rename_to = next_rename_to;
         if min_size_bytes.is_provided() {
             r.min_size_bytes = next_min_size;
         }
@@ -319,9 +459,31 @@ pub async fn impl_update_rule(
         if let Some(symlink) = create_symlink {
             r.create_symlink = symlink;
         }
+        if breadcrumb.is_provided() {
+            r.breadcrumb = match breadcrumb {
+                NullableField::Missing => r.breadcrumb,
+                NullableField::Null => None,
+                NullableField::Value(kind) => Some(kind),
+            };
+        }
         if let Some(en) = enabled {
             r.enabled = en;
         }
+        if min_free_bytes.is_provided() {
+            r.min_free_bytes = match min_free_bytes {
+                NullableField::Missing => r.min_free_bytes,
+                NullableField::Null => None,
+                NullableField::Value(value) => Some(value),
+            };
+        }
+        r.retention = next_retention;
+        if normalize.is_provided() {
+            r.normalize = match normalize {
+                NullableField::Missing => r.normalize.clone(),
+                NullableField::Null => None,
+                NullableField::Value(steps) => Some(steps).filter(|s| !s.is_empty()),
+            };
+        }
 
         let updated = RuleDto::from(&*r);
         save_config(state, &config)?;
@@ -493,6 +655,7 @@ mod tests {
             service_enabled: Some(true),
             check_updates: Some(true),
             last_notified_version: None,
+            ..Default::default()
         };
         let yaml = serde_yaml::to_string(&config).unwrap();
         std::fs::write(&cfg_path, yaml).unwrap();
@@ -515,6 +678,7 @@ mod tests {
                 max_size_bytes: None,
                 create_symlink: None,
                 enabled: None,
+                ..Default::default()
             },
         )
         .await;
@@ -540,6 +704,7 @@ mod tests {
                 max_size_bytes: None,
                 create_symlink: None,
                 enabled: None,
+                ..Default::default()
             },
         )
         .await;
@@ -561,6 +726,7 @@ mod tests {
                 max_size_bytes: None,
                 create_symlink: None,
                 enabled: None,
+                ..Default::default()
             },
         )
         .await
@@ -578,6 +744,7 @@ mod tests {
                 max_size_bytes: NullableField::Missing,
                 create_symlink: None,
                 enabled: None,
+                ..Default::default()
             },
         )
         .await;
@@ -609,16 +776,20 @@ mod tests {
                 max_size_bytes: Some(100),
                 create_symlink: None,
                 enabled: None,
+                min_free_bytes: Some(1_000),
+                ..Default::default()
             },
         )
         .await
         .unwrap();
+        assert_eq!(created.min_free_bytes, Some(1_000));
 
         let request: UpdateRuleRequest = serde_json::from_value(json!({
             "id": created.id,
             "pattern": null,
             "min_size_bytes": null,
-            "max_size_bytes": null
+            "max_size_bytes": null,
+            "min_free_bytes": null
         }))
         .unwrap();
 
@@ -626,6 +797,7 @@ mod tests {
         assert_eq!(updated.pattern, None);
         assert_eq!(updated.min_size_bytes, None);
         assert_eq!(updated.max_size_bytes, None);
+        assert_eq!(updated.min_free_bytes, None);
     }
 
     #[tokio::test]
@@ -643,6 +815,7 @@ mod tests {
                 max_size_bytes: Some(100),
                 create_symlink: None,
                 enabled: None,
+                ..Default::default()
             },
         )
         .await
@@ -660,6 +833,240 @@ mod tests {
         assert!(error.contains("max_size_bytes"));
     }
 
+    #[tokio::test]
+    async fn test_rule_retention_create_update_and_validate() {
+        let (state, _tmp) = create_test_state();
+
+        let created = impl_create_rule(
+            &state,
+            CreateRuleRequest {
+                name: "Installers".to_string(),
+                extensions: vec!["exe".to_string()],
+                destination: "Target".to_string(),
+                retention: Some(RetentionPolicy {
+                    max_age_days: Some(30),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            created.retention.as_ref().and_then(|p| p.max_age_days),
+            Some(30)
+        );
+
+        let request: UpdateRuleRequest = serde_json::from_value(json!({
+            "id": created.id,
+            "retention": { "max_files": 0 }
+        }))
+        .unwrap();
+        let error = impl_update_rule(&state, request).await.unwrap_err();
+        assert!(error.contains("retention"));
+
+        let request: UpdateRuleRequest = serde_json::from_value(json!({
+            "id": created.id,
+            "retention": { "max_files": 10, "action": "trash" }
+        }))
+        .unwrap();
+        let updated = impl_update_rule(&state, request).await.unwrap();
+        let policy = updated.retention.unwrap();
+        assert_eq!(policy.max_files, Some(10));
+        assert_eq!(policy.max_age_days, None);
+
+        // Other updates leave the policy alone; explicit null clears it.
+        let request: UpdateRuleRequest =
+            serde_json::from_value(json!({ "id": created.id, "name": "Setup files" })).unwrap();
+        assert!(impl_update_rule(&state, request)
+            .await
+            .unwrap()
+            .retention
+            .is_some());
+        let request: UpdateRuleRequest =
+            serde_json::from_value(json!({ "id": created.id, "retention": null })).unwrap();
+        assert!(impl_update_rule(&state, request)
+            .await
+            .unwrap()
+            .retention
+            .is_none());
+    }
+
+    #[tokio::test]
+    async fn test_rule_retention_is_rejected_for_templated_destinations() {
+        let (state, _tmp) = create_test_state();
+
+        let error = impl_create_rule(
+            &state,
+            CreateRuleRequest {
+                name: "Invoices".to_string(),
+                pattern: Some(r"^(?P<vendor>\w+)_invoice".to_string()),
+                destination: "Invoices/{vendor}".to_string(),
+                retention: Some(RetentionPolicy {
+                    max_files: Some(10),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+        assert!(error.contains("placeholders"));
+
+        let created = impl_create_rule(
+            &state,
+            CreateRuleRequest {
+                name: "Invoices".to_string(),
+                pattern: Some(r"^(?P<vendor>\w+)_invoice".to_string()),
+                destination: "Invoices".to_string(),
+                retention: Some(RetentionPolicy {
+                    max_files: Some(10),
+                    ..Default::default()
+                }),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        let request: UpdateRuleRequest = serde_json::from_value(json!({
+            "id": created.id,
+            "destination": "Invoices/{vendor}"
+        }))
+        .unwrap();
+        let error = impl_update_rule(&state, request).await.unwrap_err();
+        assert!(error.contains("retention"));
+    }
+
+    #[tokio::test]
+    async fn test_rule_breadcrumb_create_and_update() {
+        let (state, _tmp) = create_test_state();
+
+        let created = impl_create_rule(
+            &state,
+            CreateRuleRequest {
+                name: "Docs".to_string(),
+                extensions: vec!["pdf".to_string()],
+                destination: "Target".to_string(),
+                breadcrumb: Some(BreadcrumbKind::RelativeSymlink),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(created.breadcrumb, Some(BreadcrumbKind::RelativeSymlink));
+
+        let request: UpdateRuleRequest = serde_json::from_value(json!({
+            "id": created.id,
+            "breadcrumb": "shortcut"
+        }))
+        .unwrap();
+        let updated = impl_update_rule(&state, request).await.unwrap();
+        assert_eq!(updated.breadcrumb, Some(BreadcrumbKind::Shortcut));
+
+        let request: UpdateRuleRequest =
+            serde_json::from_value(json!({ "id": created.id, "breadcrumb": null })).unwrap();
+        assert_eq!(
+            impl_update_rule(&state, request).await.unwrap().breadcrumb,
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_rule_templates_must_reference_pattern_groups() {
+        let (state, _tmp) = create_test_state();
+        let request = |rename_to: &str| CreateRuleRequest {
+            name: "Invoices".to_string(),
+            extensions: vec!["pdf".to_string()],
+            destination: "Invoices/{vendor}/{yyyy}".to_string(),
+            rename_to: Some(rename_to.to_string()),
+            pattern: Some(r"^(?P<vendor>\w+)_invoice_(?P<yyyy>\d{4})".to_string()),
+            ..Default::default()
+        };
+
+        let error = impl_create_rule(&state, request("{vendor}-{month}"))
+            .await
+            .unwrap_err();
+        assert!(error.contains("{month} in rename_to"));
+
+        let created = impl_create_rule(&state, request("{vendor}-{yyyy}-{name}"))
+            .await
+            .unwrap();
+        assert_eq!(created.rename_to.as_deref(), Some("{vendor}-{yyyy}-{name}"));
+
+        // Dropping the pattern would orphan the placeholders.
+        let request: UpdateRuleRequest =
+            serde_json::from_value(json!({ "id": created.id, "pattern": null })).unwrap();
+        let error = impl_update_rule(&state, request).await.unwrap_err();
+        assert!(error.contains("{vendor} in destination"));
+    }
+
+    #[tokio::test]
+    async fn test_rule_destination_env_vars_are_checked_and_expanded() {
+        let (state, _tmp) = create_test_state();
+        std::env::set_var("HARBOR_TEST_ARCHIVE", "/srv/archive");
+        let request = |destination: &str| CreateRuleRequest {
+            name: "Archive".to_string(),
+            extensions: vec!["zip".to_string()],
+            destination: destination.to_string(),
+            ..Default::default()
+        };
+
+        let error = impl_create_rule(&state, request("$HARBOR_TEST_TYPO/zips"))
+            .await
+            .unwrap_err();
+        assert!(error.contains("undefined environment variable HARBOR_TEST_TYPO in destination"));
+
+        let created = impl_create_rule(&state, request("${HARBOR_TEST_ARCHIVE}/zips"))
+            .await
+            .unwrap();
+        assert_eq!(created.destination, "${HARBOR_TEST_ARCHIVE}/zips");
+        assert_eq!(created.expanded_destination, "/srv/archive/zips");
+    }
+
+    #[tokio::test]
+    async fn test_rule_normalize_create_and_update() {
+        let (state, _tmp) = create_test_state();
+
+        let created = impl_create_rule(
+            &state,
+            CreateRuleRequest {
+                name: "Images".to_string(),
+                extensions: vec!["jpeg".to_string()],
+                destination: "Target".to_string(),
+                normalize: Some(vec![NormalizeStep::LowercaseExtension]),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+        assert_eq!(
+            created.normalize,
+            Some(vec![NormalizeStep::LowercaseExtension])
+        );
+
+        let request: UpdateRuleRequest = serde_json::from_value(json!({
+            "id": created.id,
+            "normalize": ["url_decode", "strip_duplicate_suffix"]
+        }))
+        .unwrap();
+        let updated = impl_update_rule(&state, request).await.unwrap();
+        assert_eq!(
+            updated.normalize,
+            Some(vec![
+                NormalizeStep::UrlDecode,
+                NormalizeStep::StripDuplicateSuffix
+            ])
+        );
+
+        // An empty list turns normalization off, like `null`.
+        let request: UpdateRuleRequest =
+            serde_json::from_value(json!({ "id": created.id, "normalize": [] })).unwrap();
+        assert_eq!(
+            impl_update_rule(&state, request).await.unwrap().normalize,
+            None
+        );
+    }
+
     #[test]
     fn test_restart_service_if_running_debounces_rapid_requests() {
         let (state, _tmp) = create_test_state();
@@ -706,6 +1113,7 @@ mod tests {
                 max_size_bytes: None,
                 create_symlink: None,
                 enabled: None,
+                ..Default::default()
             },
         )
         .await
@@ -738,6 +1146,7 @@ mod tests {
                 max_size_bytes: None,
                 create_symlink: None,
                 enabled: Some(true),
+                ..Default::default()
             },
         )
         .await
@@ -766,6 +1175,7 @@ mod tests {
                 max_size_bytes: None,
                 create_symlink: None,
                 enabled: None,
+                ..Default::default()
             },
         )
         .await
@@ -781,6 +1191,7 @@ mod tests {
                 max_size_bytes: None,
                 create_symlink: None,
                 enabled: None,
+                ..Default::default()
             },
         )
         .await
@@ -796,6 +1207,7 @@ mod tests {
                 max_size_bytes: None,
                 create_symlink: None,
                 enabled: None,
+                ..Default::default()
             },
         )
         .await
@@ -1,8 +1,12 @@
-use crate::commands::error_contract::{map_legacy_organize_error, AppError, AppErrorDto};
-use crate::state::{AppState, ServiceLifecycleState};
-use harbor_core::downloads::{
-    append_organize_results_to_log, load_downloads_config, organize_once, watch_polling,
+use crate::commands::error_contract::{
+    map_legacy_organize_error, sanitize_path_for_ui, AppError, AppErrorDto,
 };
+use crate::state::{AppState, ServiceLifecycleState};
+use chrono::{DateTime, Local};
+use harbor_core::control::pause_end;
+use harbor_core::downloads::{load_downloads_config, SkipReason, SkippedFile};
+use harbor_core::lock::OrganizerBusy;
+use harbor_core::schedule::Schedule;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
 use std::sync::atomic::Ordering;
@@ -10,7 +14,7 @@ use std::sync::mpsc;
 use std::sync::Arc;
 use std::thread;
 use std::time::Duration;
-use tauri::{AppHandle, Emitter, State};
+use tauri::{AppHandle, Emitter, Manager, State};
 use tauri_plugin_autostart::ManagerExt;
 use tauri_plugin_notification::NotificationExt;
 
@@ -25,7 +29,7 @@ fn save_config_to_disk(state: &AppState) -> Result<(), String> {
 }
 
 /// Service status information
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ServiceStatus {
     pub running: bool,
     pub lifecycle_state: String,
@@ -34,6 +38,48 @@ pub struct ServiceStatus {
     pub stop_join_pending: bool,
     pub degraded: bool,
     pub degraded_reason: Option<String>,
+    /// The global `schedule` the watcher organizes on, if any.
+    pub schedule: Option<String>,
+    /// RFC 3339 time at which the global schedule next lets the watcher organize.
+    pub next_scheduled_run: Option<String>,
+    /// Rules that only move files on their own schedule.
+    #[serde(default)]
+    pub scheduled_rules: Vec<ScheduledRuleDto>,
+    /// RFC 3339 time at which a paused service resumes.
+    #[serde(default)]
+    pub paused_until: Option<String>,
+    /// RFC 3339 time at which the current quiet hours end; the watcher runs
+    /// but leaves the download folder alone until then.
+    #[serde(default)]
+    pub quiet_until: Option<String>,
+}
+
+/// How long [`pause_service`] pauses for.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum PauseDuration {
+    Minutes(u32),
+    /// Until local midnight.
+    UntilTomorrow,
+}
+
+impl PauseDuration {
+    /// When a pause started at `now` ends.
+    pub fn end(self, now: DateTime<Local>) -> DateTime<Local> {
+        match self {
+            Self::Minutes(minutes) => pause_end(Some(minutes), now),
+            Self::UntilTomorrow => pause_end(None, now),
+        }
+    }
+}
+
+/// A rule with a `schedule` and when it next comes due.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ScheduledRuleDto {
+    pub rule_name: String,
+    pub schedule: String,
+    /// RFC 3339; `None` for an invalid schedule or one that never matches.
+    pub next_run: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -56,6 +102,42 @@ pub struct OrganizeFailureGroupDto {
     pub legacy_errors: Vec<String>,
 }
 
+/// A file that matched a rule but was deliberately left in the download folder.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct OrganizeSkippedDto {
+    pub code: String,
+    pub message: String,
+    pub source_path: String,
+    pub rule_name: String,
+    pub required_bytes: u64,
+    pub available_bytes: u64,
+}
+
+impl OrganizeSkippedDto {
+    fn from_skipped(skipped: &SkippedFile, download_dir: &Path) -> Self {
+        let source_path = sanitize_path_for_ui(&skipped.path.to_string_lossy(), Some(download_dir));
+        let (message, required_bytes, available_bytes) = match skipped.reason {
+            SkipReason::InsufficientSpace {
+                required,
+                available,
+            } => (
+                "Not enough free space on the destination drive".to_string(),
+                required,
+                available,
+            ),
+            other => (other.to_string(), 0, 0),
+        };
+        Self {
+            code: skipped.reason.code().to_string(),
+            message,
+            source_path,
+            rule_name: skipped.rule_name.clone(),
+            required_bytes,
+            available_bytes,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub struct OrganizeNowResponse {
     pub status: String,
@@ -67,6 +149,16 @@ pub struct OrganizeNowResponse {
     /// Compatibility field preserving legacy string-based errors.
     pub errors: Vec<String>,
     pub failure_groups: Vec<OrganizeFailureGroupDto>,
+    #[serde(default)]
+    pub skipped_count: usize,
+    #[serde(default)]
+    pub skipped: Vec<OrganizeSkippedDto>,
+    /// Files left for later passes by `max_moves_per_pass` /
+    /// `max_bytes_per_pass`.
+    #[serde(default)]
+    pub deferred_count: usize,
+    #[serde(default)]
+    pub deferred_bytes: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, Default, PartialEq, Eq)]
@@ -101,14 +193,15 @@ pub fn map_tray_organize_outcome(response: &OrganizeNowResponse) -> TrayOrganize
     let status = response.status.clone();
     let severity = match status.as_str() {
         "success" => "info".to_string(),
-        "partial_failure" => "warning".to_string(),
+        "partial_failure" | "insufficient_space" | "busy" => "warning".to_string(),
         _ => "error".to_string(),
     };
 
     let primary_code = response
         .failure_groups
         .first()
-        .map(|group| group.code.clone());
+        .map(|group| group.code.clone())
+        .or_else(|| response.skipped.first().map(|skipped| skipped.code.clone()));
     let remediation_summary = remediation_summary(&response.failure_groups);
     let mut message = response.message.clone();
 
@@ -130,6 +223,36 @@ pub fn map_tray_organize_outcome(response: &OrganizeNowResponse) -> TrayOrganize
     }
 }
 
+/// What the tray's service items show for a [`ServiceStatus`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrayServiceItems {
+    /// "Service On" is checked; a paused service is still on.
+    pub service_on: bool,
+    pub can_pause: bool,
+    pub resume_label: String,
+    pub can_resume: bool,
+}
+
+pub fn map_tray_service_items(status: &ServiceStatus) -> TrayServiceItems {
+    let clock_time = |rfc3339: &str| {
+        DateTime::parse_from_rfc3339(rfc3339)
+            .map(|t| t.with_timezone(&Local).format("%H:%M").to_string())
+            .unwrap_or_else(|_| rfc3339.to_string())
+    };
+    let paused = status.lifecycle_state == "paused";
+    let resume_label = match (&status.paused_until, &status.quiet_until) {
+        (Some(until), _) if paused => format!("Resume (paused until {})", clock_time(until)),
+        (_, Some(until)) if status.running => format!("Quiet hours until {}", clock_time(until)),
+        _ => "Resume".to_string(),
+    };
+    TrayServiceItems {
+        service_on: status.running || paused,
+        can_pause: status.running || paused,
+        resume_label,
+        can_resume: paused,
+    }
+}
+
 #[tauri::command]
 pub async fn get_service_status(state: State<'_, AppState>) -> Result<ServiceStatus, String> {
     build_service_status(&state)
@@ -141,10 +264,11 @@ fn lifecycle_state_name(lifecycle: &ServiceLifecycleState) -> String {
         ServiceLifecycleState::Running => "running".to_string(),
         ServiceLifecycleState::Restarting => "restarting".to_string(),
         ServiceLifecycleState::Degraded => "degraded".to_string(),
+        ServiceLifecycleState::Paused => "paused".to_string(),
     }
 }
 
-fn build_service_status(state: &AppState) -> Result<ServiceStatus, String> {
+pub(crate) fn build_service_status(state: &AppState) -> Result<ServiceStatus, String> {
     let flag_guard = state.watcher_flag.lock().map_err(|e| e.to_string())?;
     let running = flag_guard.is_some();
     drop(flag_guard); // Release lock early
@@ -172,6 +296,27 @@ fn build_service_status(state: &AppState) -> Result<ServiceStatus, String> {
         .lock()
         .map_err(|e| e.to_string())?;
 
+    let paused_until = *state.paused_until.lock().map_err(|e| e.to_string())?;
+
+    let now = state.clock.local_now();
+    let config = state.config.read().map_err(|e| e.to_string())?;
+    let scheduled_rules = config
+        .rules
+        .iter()
+        .filter_map(|rule| {
+            let schedule = rule.schedule.clone()?;
+            let next_run = Schedule::parse(&schedule)
+                .ok()
+                .and_then(|s| s.next_after(now))
+                .map(|t| t.to_rfc3339());
+            Some(ScheduledRuleDto {
+                rule_name: rule.name.clone(),
+                schedule,
+                next_run,
+            })
+        })
+        .collect();
+
     Ok(ServiceStatus {
         running,
         lifecycle_state: lifecycle_state_name(&lifecycle_state),
@@ -180,6 +325,11 @@ fn build_service_status(state: &AppState) -> Result<ServiceStatus, String> {
         stop_join_pending,
         degraded: degraded_reason.is_some(),
         degraded_reason,
+        schedule: config.schedule.clone(),
+        next_scheduled_run: config.next_scheduled_run(now).map(|t| t.to_rfc3339()),
+        scheduled_rules,
+        paused_until: paused_until.map(|t| t.to_rfc3339()),
+        quiet_until: config.quiet_until(now).map(|t| t.to_rfc3339()),
     })
 }
 
@@ -195,6 +345,24 @@ fn set_degraded_reason(state: &AppState, reason: Option<String>) -> Result<(), S
     Ok(())
 }
 
+/// Sets when a pause ends, and saves it in the config so that the pause
+/// outlasts a restart of Harbor.
+fn set_paused_until(state: &AppState, until: Option<DateTime<Local>>) -> Result<(), String> {
+    let mut guard = state.paused_until.lock().map_err(|e| e.to_string())?;
+    *guard = until;
+    drop(guard);
+
+    let saved = until.map(|until| until.to_rfc3339());
+    {
+        let mut config = state.config.write().map_err(|e| e.to_string())?;
+        if config.paused_until == saved {
+            return Ok(());
+        }
+        config.paused_until = saved;
+    }
+    save_config_to_disk(state)
+}
+
 fn emit_service_status_event(app: &AppHandle, state: &AppState) -> Result<(), String> {
     let payload = ServiceStatusEvent {
         status: build_service_status(state)?,
@@ -257,14 +425,12 @@ pub fn internal_start_service(state: &AppState) -> Result<(), String> {
     *flag_guard = Some(new_flag.clone());
 
     let config = state.config.read().map_err(|e| e.to_string())?.clone();
-    let log_path = state.recent_log_path();
+    let organizer = state.organizer(config);
 
     // Use the *new* flag for the thread
     let thread_flag = new_flag.clone();
     let handle = thread::spawn(move || {
-        let _ = watch_polling(&config, 5, &thread_flag, |actions| {
-            append_organize_results_to_log(&log_path, actions);
-        });
+        let _ = organizer.watch(5, &thread_flag);
     });
 
     let mut guard = state.watcher_handle.lock().map_err(|e| e.to_string())?;
@@ -281,6 +447,7 @@ pub fn internal_start_service(state: &AppState) -> Result<(), String> {
     *pending = false;
 
     set_degraded_reason(state, None)?;
+    set_paused_until(state, None)?;
     set_lifecycle_state(state, ServiceLifecycleState::Running)?;
 
     Ok(())
@@ -308,9 +475,10 @@ pub fn internal_stop_service(state: &AppState) -> Result<(), String> {
         ) {
             Ok(()) => {}
             Err("join_timeout") => {
-                // The watcher thread will exit on its own when it next checks the
-                // flag (within 500 ms thanks to chunked polling sleep). We clear
-                // state and proceed — do not fail the restart or stop.
+                // The watcher checks the flag between files, between copy chunks
+                // and every 500 ms while sleeping, so it exits shortly on its own
+                // unless a filesystem call is hung. We clear state and proceed —
+                // do not fail the restart or stop.
                 eprintln!(
                     "[Harbor] Watcher thread join timed out after {} seconds. \
                      Proceeding — old thread will exit on next flag check.",
@@ -336,6 +504,7 @@ pub fn internal_stop_service(state: &AppState) -> Result<(), String> {
 
     let mut time_guard = state.service_start_time.lock().map_err(|e| e.to_string())?;
     *time_guard = None;
+    set_paused_until(state, None)?;
     set_lifecycle_state(state, ServiceLifecycleState::Stopped)?;
 
     Ok(())
@@ -417,7 +586,7 @@ pub fn restart_service_if_running(state: &AppState) -> Result<(), String> {
     Ok(())
 }
 
-fn reload_config_impl(
+pub(crate) fn reload_config_impl(
     state: &AppState,
     new_config: harbor_core::downloads::DownloadsConfig,
 ) -> Result<bool, String> {
@@ -530,6 +699,126 @@ pub async fn stop_service(state: State<'_, AppState>, app: AppHandle) -> Result<
     stop_result
 }
 
+/// Stops the watcher until `duration` is over, without turning the service
+/// off. Pausing a paused service moves its end. Returns when it resumes.
+pub fn impl_pause_service(
+    state: &AppState,
+    duration: PauseDuration,
+) -> Result<DateTime<Local>, String> {
+    if duration == PauseDuration::Minutes(0) {
+        return Err("Pause for at least one minute.".to_string());
+    }
+    let lifecycle = state
+        .service_lifecycle
+        .lock()
+        .map_err(|e| e.to_string())?
+        .clone();
+    match lifecycle {
+        ServiceLifecycleState::Running | ServiceLifecycleState::Paused => {}
+        ServiceLifecycleState::Restarting => {
+            return Err("Service is restarting. Pause it once it is running.".to_string())
+        }
+        ServiceLifecycleState::Stopped | ServiceLifecycleState::Degraded => {
+            return Err("Service is not running.".to_string())
+        }
+    }
+
+    let until = duration.end(state.clock.local_now());
+    if lifecycle == ServiceLifecycleState::Running {
+        internal_stop_service(state)?;
+    }
+    set_paused_until(state, Some(until))?;
+    set_lifecycle_state(state, ServiceLifecycleState::Paused)?;
+    Ok(until)
+}
+
+/// Starts the enabled service when Harbor launches, unless a pause saved
+/// before it quit is still running; that pause then ends as usual.
+pub fn start_service_at_launch(state: &AppState) -> Result<(), String> {
+    let saved_pause = state
+        .config
+        .read()
+        .map_err(|e| e.to_string())?
+        .pause_running_at(state.clock.local_now());
+    match saved_pause {
+        Some(until) => {
+            set_paused_until(state, Some(until))?;
+            set_lifecycle_state(state, ServiceLifecycleState::Paused)
+        }
+        None => internal_start_service(state),
+    }
+}
+
+/// Ends a pause early; does nothing unless the service is paused.
+pub fn impl_resume_service(state: &AppState) -> Result<(), String> {
+    let paused = *state.service_lifecycle.lock().map_err(|e| e.to_string())?
+        == ServiceLifecycleState::Paused;
+    if paused {
+        internal_start_service(state)?;
+    }
+    Ok(())
+}
+
+/// Resumes a pause that has run out. Returns whether it did.
+pub fn resume_if_pause_expired(state: &AppState) -> Result<bool, String> {
+    let expired = state
+        .paused_until
+        .lock()
+        .map_err(|e| e.to_string())?
+        .is_some_and(|until| until <= state.clock.local_now());
+    if expired {
+        impl_resume_service(state)?;
+    }
+    Ok(expired)
+}
+
+/// Watches for pauses running out and quiet hours starting or ending, and
+/// tells the UI and tray. Runs for the lifetime of the app.
+pub fn spawn_pause_monitor(app: AppHandle) {
+    const TICK: Duration = Duration::from_secs(5);
+
+    thread::spawn(move || {
+        let mut was_quiet = None;
+        loop {
+            let state = app.state::<AppState>();
+            let mut changed = match resume_if_pause_expired(&state) {
+                Ok(resumed) => resumed,
+                Err(e) => {
+                    eprintln!("[Harbor] Failed to resume paused service: {e}");
+                    false
+                }
+            };
+            if let Ok(config) = state.config.read() {
+                let quiet = config.quiet_until(state.clock.local_now()).is_some();
+                changed |= was_quiet.is_some_and(|was| was != quiet);
+                was_quiet = Some(quiet);
+            }
+            if changed {
+                let _ = emit_service_status_event(&app, &state);
+            }
+            thread::sleep(TICK);
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn pause_service(
+    state: State<'_, AppState>,
+    app: AppHandle,
+    duration: PauseDuration,
+) -> Result<(), String> {
+    let pause_result = impl_pause_service(&state, duration);
+    let _ = emit_service_status_event(&app, &state);
+    pause_result.map(|_| ())
+}
+
+#[tauri::command]
+pub async fn resume_service(state: State<'_, AppState>, app: AppHandle) -> Result<(), String> {
+    let resume_result = impl_resume_service(&state);
+    let _ = emit_service_status_event(&app, &state);
+    resume_result
+}
+
 #[tauri::command]
 pub async fn retry_service_restart(
     state: State<'_, AppState>,
@@ -577,10 +866,17 @@ pub async fn impl_trigger_organize_now(state: &AppState) -> OrganizeNowResponse
     }
 
     let download_dir = Path::new(&config.download_dir);
-    let log_path = state.recent_log_path();
 
-    let summary = match organize_once(&config) {
+    // Events reach the UI while the pass runs; the organizer logs them and
+    // records the pass for undo once it finishes.
+    let result = state.organizer(config.clone()).run_once();
+
+    let summary = match result {
         Ok(summary) => summary,
+        Err(e) if e.is::<OrganizerBusy>() => {
+            eprintln!("[Harbor] Organize skipped: {e}");
+            return organize_now_busy_response(e.to_string(), download_dir);
+        }
         Err(e) => {
             let legacy = format!("Organize failed: {}", e);
             eprintln!("[Harbor] {legacy}");
@@ -592,8 +888,6 @@ pub async fn impl_trigger_organize_now(state: &AppState) -> OrganizeNowResponse
         eprintln!("[Harbor] {err}");
     }
 
-    append_organize_results_to_log(&log_path, &summary.moved);
-
     map_organize_summary_to_response(summary, download_dir)
 }
 
@@ -621,9 +915,13 @@ fn group_errors_by_code(errors: &[AppErrorDto]) -> Vec<OrganizeFailureGroupDto>
     groups
 }
 
-fn derive_status(moved_count: usize, failure_count: usize) -> String {
-    if failure_count == 0 {
+/// `space_skipped` counts only files held back for lack of free space; files
+/// waiting for their rule's schedule do not make a pass fail.
+fn derive_status(moved_count: usize, failure_count: usize, space_skipped: usize) -> String {
+    if failure_count == 0 && space_skipped == 0 {
         "success".to_string()
+    } else if failure_count == 0 && moved_count == 0 {
+        "insufficient_space".to_string()
     } else if moved_count > 0 {
         "partial_failure".to_string()
     } else {
@@ -631,15 +929,33 @@ fn derive_status(moved_count: usize, failure_count: usize) -> String {
     }
 }
 
-fn response_message(moved_count: usize, failure_count: usize) -> String {
-    if failure_count == 0 {
+fn response_message(
+    moved_count: usize,
+    failure_count: usize,
+    space_skipped: usize,
+    scheduled: usize,
+) -> String {
+    let mut message = if failure_count == 0 {
         format!("Organize complete: moved {} file(s).", moved_count)
     } else {
         format!(
             "Organize finished with {} move(s) and {} failure(s).",
             moved_count, failure_count
         )
+    };
+    if space_skipped > 0 {
+        message.push_str(&format!(
+            " {} file(s) skipped: not enough free disk space.",
+            space_skipped
+        ));
+    }
+    if scheduled > 0 {
+        message.push_str(&format!(
+            " {} file(s) waiting for their rule's schedule.",
+            scheduled
+        ));
     }
+    message
 }
 
 fn map_organize_summary_to_response(
@@ -654,15 +970,41 @@ fn map_organize_summary_to_response(
     let failure_groups = group_errors_by_code(&structured_failures);
     let moved_count = summary.moved.len();
     let failure_count = structured_failures.len();
+    let skipped: Vec<OrganizeSkippedDto> = summary
+        .skipped
+        .iter()
+        .map(|skipped| OrganizeSkippedDto::from_skipped(skipped, download_dir))
+        .collect();
+    let skipped_count = skipped.len();
+    let count = |matches: fn(&SkipReason) -> bool| {
+        summary
+            .skipped
+            .iter()
+            .filter(|skipped| matches(&skipped.reason))
+            .count()
+    };
+    let space_skipped = count(|reason| matches!(reason, SkipReason::InsufficientSpace { .. }));
+    let scheduled = count(|reason| *reason == SkipReason::OutsideSchedule);
+    let mut message = response_message(moved_count, failure_count, space_skipped, scheduled);
+    if summary.deferred > 0 {
+        message.push_str(&format!(
+            " {} file(s) left to organize in later passes.",
+            summary.deferred
+        ));
+    }
 
     OrganizeNowResponse {
-        status: derive_status(moved_count, failure_count),
-        message: response_message(moved_count, failure_count),
+        status: derive_status(moved_count, failure_count, space_skipped),
+        message,
         moved_count,
         moved: moved_count,
         total_failures: failure_count,
         errors: summary.errors,
         failure_groups,
+        skipped_count,
+        skipped,
+        deferred_count: summary.deferred,
+        deferred_bytes: summary.deferred_bytes,
     }
 }
 
@@ -678,6 +1020,26 @@ fn organize_now_failure_response(error: AppError, download_dir: &Path) -> Organi
         total_failures: 1,
         errors: vec![failure.legacy_error.clone()],
         failure_groups,
+        ..Default::default()
+    }
+}
+
+/// Another Harbor instance holds the organize lock; nothing was attempted.
+fn organize_now_busy_response(legacy_error: String, download_dir: &Path) -> OrganizeNowResponse {
+    let failure = AppError::Conflict {
+        resource: "organizer".to_string(),
+        message: "Another Harbor instance is organizing".to_string(),
+        remediation_hint: "Wait for it to finish, then try again.".to_string(),
+        legacy_error,
+    }
+    .to_dto(Some(download_dir));
+
+    OrganizeNowResponse {
+        status: "busy".to_string(),
+        message: "Another Harbor instance is organizing this folder right now.".to_string(),
+        errors: vec![failure.legacy_error.clone()],
+        failure_groups: group_errors_by_code(std::slice::from_ref(&failure)),
+        ..Default::default()
     }
 }
 
@@ -954,7 +1316,10 @@ pub async fn notify_update_available(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use harbor_core::downloads::{DownloadsConfig, OrganizeResult, OrganizeSummary};
+    use harbor_core::downloads::{
+        append_organize_results_to_log, DownloadsConfig, OrganizeResult, OrganizeSummary,
+    };
+    use harbor_core::types::Rule;
     use std::path::PathBuf;
     use tempfile::tempdir;
 
@@ -967,6 +1332,7 @@ mod tests {
             service_enabled: Some(service_enabled),
             check_updates: Some(true),
             last_notified_version: None,
+            ..Default::default()
         }
     }
 
@@ -1018,6 +1384,85 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_service_status_reports_next_scheduled_runs() {
+        use harbor_core::clock::{Clock, ManualClock};
+        use harbor_core::types::{QuietHours, Rule};
+
+        let mut cfg = test_config(true, 5);
+        cfg.schedule = Some("* 9-17 * * mon-fri".into());
+        cfg.quiet_hours = Some(vec![QuietHours {
+            start: "17:30".into(),
+            end: "19:00".into(),
+            days: None,
+        }]);
+        cfg.rules = vec![
+            Rule {
+                name: "Installers".into(),
+                schedule: Some("0 3 * * sun".into()),
+                ..Default::default()
+            },
+            Rule {
+                name: "Docs".into(),
+                ..Default::default()
+            },
+        ];
+        let mut state = AppState::new(PathBuf::from("unused.yaml"), cfg);
+        // Friday evening.
+        let clock = ManualClock::at_local("2024-01-19 18:00:00");
+        let at = |text: &str| ManualClock::at_local(text).local_now().to_rfc3339();
+        state.clock = Arc::new(clock);
+
+        let status = build_service_status(&state).unwrap();
+        assert_eq!(status.schedule.as_deref(), Some("* 9-17 * * mon-fri"));
+        assert_eq!(status.next_scheduled_run, Some(at("2024-01-22 09:00:00")));
+        assert_eq!(
+            status.scheduled_rules,
+            vec![ScheduledRuleDto {
+                rule_name: "Installers".into(),
+                schedule: "0 3 * * sun".into(),
+                next_run: Some(at("2024-01-21 03:00:00")),
+            }]
+        );
+        assert_eq!(status.quiet_until, Some(at("2024-01-19 19:00:00")));
+        assert_eq!(status.paused_until, None);
+    }
+
+    #[test]
+    fn test_tray_service_items_reflect_pause_and_quiet_hours() {
+        use harbor_core::clock::{Clock, ManualClock};
+
+        let at = |text: &str| ManualClock::at_local(text).local_now().to_rfc3339();
+        let mut status = build_service_status(&AppState::new(
+            PathBuf::from("unused.yaml"),
+            test_config(true, 5),
+        ))
+        .unwrap();
+
+        let stopped = map_tray_service_items(&status);
+        assert!(!stopped.service_on && !stopped.can_pause && !stopped.can_resume);
+
+        status.lifecycle_state = "paused".into();
+        status.paused_until = Some(at("2024-01-15 14:30:00"));
+        assert_eq!(
+            map_tray_service_items(&status),
+            TrayServiceItems {
+                service_on: true,
+                can_pause: true,
+                resume_label: "Resume (paused until 14:30)".into(),
+                can_resume: true,
+            }
+        );
+
+        status.lifecycle_state = "running".into();
+        status.running = true;
+        status.paused_until = None;
+        status.quiet_until = Some(at("2024-01-16 07:00:00"));
+        let quiet = map_tray_service_items(&status);
+        assert_eq!(quiet.resume_label, "Quiet hours until 07:00");
+        assert!(quiet.service_on && quiet.can_pause && !quiet.can_resume);
+    }
+
     #[test]
     fn test_append_to_log_empty() {
         let tmp = tempdir().unwrap();
@@ -1042,6 +1487,7 @@ mod tests {
             service_enabled: Some(false),
             check_updates: None,
             last_notified_version: None,
+            ..Default::default()
         };
         let yaml = serde_yaml::to_string(&initial_cfg).unwrap();
         std::fs::write(&cfg_path, yaml).unwrap();
@@ -1076,6 +1522,7 @@ mod tests {
             service_enabled: None,
             check_updates: Some(true),
             last_notified_version: None,
+            ..Default::default()
         };
         let yaml = serde_yaml::to_string(&initial_cfg).unwrap();
         std::fs::write(&cfg_path, yaml).unwrap();
@@ -1149,11 +1596,17 @@ mod tests {
             errors: vec![format!(
                 "Failed to move '{base}{sep}locked.txt' to '{base}{sep}Docs{sep}locked.txt': Access denied"
             )],
+            deferred: 1240,
+            ..Default::default()
         };
 
         let response = map_organize_summary_to_response(summary, std::path::Path::new(&base));
 
         assert_eq!(response.status, "partial_failure");
+        assert_eq!(response.deferred_count, 1240);
+        assert!(response
+            .message
+            .ends_with("1240 file(s) left to organize in later passes."));
         assert_eq!(response.moved_count, 1);
         assert_eq!(response.moved, 1);
         assert_eq!(response.failure_groups.len(), 1);
@@ -1171,6 +1624,77 @@ mod tests {
             .is_some());
     }
 
+    #[test]
+    fn trigger_organize_now_reports_insufficient_space_separately() {
+        let sep = std::path::MAIN_SEPARATOR;
+        let base = format!("C:{0}Users{0}Alice{0}Downloads", sep);
+        let summary = OrganizeSummary {
+            skipped: vec![SkippedFile {
+                path: PathBuf::from(format!("{base}{sep}movie.mkv")),
+                destination: PathBuf::from(format!("D:{sep}Videos{sep}movie.mkv")),
+                rule_name: "Videos".to_string(),
+                reason: SkipReason::InsufficientSpace {
+                    required: 4_000,
+                    available: 1_000,
+                },
+            }],
+            ..Default::default()
+        };
+
+        let response = map_organize_summary_to_response(summary, std::path::Path::new(&base));
+
+        assert_eq!(response.status, "insufficient_space");
+        assert_eq!(response.total_failures, 0);
+        assert_eq!(response.skipped_count, 1);
+        assert_eq!(response.skipped[0].code, "insufficient_space");
+        assert_eq!(response.skipped[0].source_path, "movie.mkv");
+        assert_eq!(response.skipped[0].required_bytes, 4_000);
+        assert!(response.message.contains("not enough free disk space"));
+
+        let outcome = map_tray_organize_outcome(&response);
+        assert_eq!(outcome.severity, "warning");
+        assert_eq!(outcome.primary_code.as_deref(), Some("insufficient_space"));
+    }
+
+    #[test]
+    fn trigger_organize_now_reports_scheduled_rules_as_waiting() {
+        let sep = std::path::MAIN_SEPARATOR;
+        let base = format!("C:{0}Users{0}Alice{0}Downloads", sep);
+        let cfg = DownloadsConfig {
+            download_dir: base.clone(),
+            min_age_secs: Some(0),
+            rules: vec![Rule {
+                name: "Installers".into(),
+                extensions: Some(vec!["msi".into()]),
+                target_dir: format!("C:{sep}Archive"),
+                schedule: Some("0 3 * * sun".into()),
+                ..Default::default()
+            }],
+            ..Default::default()
+        };
+        let fs = harbor_core::vfs::MemoryFs::new();
+        fs.add_file(format!("{base}{sep}setup.msi"), b"x".to_vec());
+        // A Monday morning: the Sunday 03:00 rule is not due.
+        let clock = harbor_core::clock::ManualClock::at_local("2024-01-15 10:30:00");
+        let summary = harbor_core::organizer::Organizer::builder(cfg)
+            .filesystem(Arc::new(fs))
+            .clock(Arc::new(clock))
+            .build()
+            .run_once()
+            .unwrap();
+
+        let response = map_organize_summary_to_response(summary, std::path::Path::new(&base));
+
+        assert_eq!(response.status, "success");
+        assert_eq!(response.skipped_count, 1);
+        assert_eq!(response.skipped[0].code, "outside_schedule");
+        assert!(!response.message.contains("disk space"));
+        assert!(response
+            .message
+            .contains("1 file(s) waiting for their rule's schedule."));
+        assert_eq!(map_tray_organize_outcome(&response).severity, "info");
+    }
+
     #[test]
     fn trigger_organize_now_failure_response_keeps_legacy_compatibility_fields() {
         let response = organize_now_failure_response(
@@ -1241,6 +1765,7 @@ mod tests {
                 ],
                 legacy_errors: vec!["Access denied".to_string(), "In use".to_string()],
             }],
+            ..Default::default()
         };
 
         let partial_outcome = map_tray_organize_outcome(&partial_response);
@@ -1279,6 +1804,7 @@ mod tests {
                 }],
                 legacy_errors: vec!["Download directory is required".to_string()],
             }],
+            ..Default::default()
         };
 
         let failed_outcome = map_tray_organize_outcome(&failed_response);
@@ -1289,6 +1815,23 @@ mod tests {
             .contains("Set a valid download directory in Settings."));
     }
 
+    #[test]
+    fn trigger_organize_now_reports_busy_while_another_pass_holds_the_lock() {
+        let tmp = tempdir().unwrap();
+        let lock_path = tmp.path().join("organize.lock");
+        let _held = harbor_core::lock::PassLock::try_acquire(&lock_path).unwrap();
+        let err = harbor_core::lock::PassLock::try_acquire(&lock_path).unwrap_err();
+
+        let response = organize_now_busy_response(err.to_string(), tmp.path());
+
+        assert_eq!(response.status, "busy");
+        assert_eq!(response.moved_count, 0);
+        assert_eq!(response.failure_groups[0].code, "conflict_error");
+        let outcome = map_tray_organize_outcome(&response);
+        assert_eq!(outcome.severity, "warning");
+        assert!(outcome.message.contains("Wait for it to finish"));
+    }
+
     #[test]
     fn trigger_organize_now_tray_outcome_maps_success_to_non_error_message() {
         let response = OrganizeNowResponse {
@@ -1299,6 +1842,7 @@ mod tests {
             total_failures: 0,
             errors: vec!["legacy error should not drive success messaging".to_string()],
             failure_groups: vec![],
+            ..Default::default()
         };
 
         let outcome = map_tray_organize_outcome(&response);
@@ -1319,6 +1863,7 @@ mod tests {
             service_enabled: Some(true),
             check_updates: Some(true),
             last_notified_version: None,
+            ..Default::default()
         };
         std::fs::write(&cfg_path, serde_yaml::to_string(&config).unwrap()).unwrap();
         let state = AppState::new(cfg_path, config);
@@ -1363,6 +1908,7 @@ mod tests {
             service_enabled: Some(true),
             check_updates: Some(true),
             last_notified_version: None,
+            ..Default::default()
         };
         std::fs::write(&cfg_path, serde_yaml::to_string(&config).unwrap()).unwrap();
         let state = AppState::new(cfg_path, config);
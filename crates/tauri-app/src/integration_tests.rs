@@ -25,6 +25,7 @@ mod integration {
             service_enabled: Some(service_enabled),
             check_updates: Some(true),
             last_notified_version: None,
+            ..Default::default()
         }
     }
 
@@ -75,6 +76,106 @@ mod integration {
         assert!(state.watcher_flag.lock().unwrap().is_none());
     }
 
+    #[test]
+    fn pause_stops_the_watcher_and_resumes_when_it_runs_out() {
+        use harbor_core::clock::{Clock, ManualClock};
+        use settings::PauseDuration;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dir = tempdir().unwrap();
+        let mut state = state_with_config(&dir, test_config(true));
+        let clock = Arc::new(ManualClock::at_local("2024-01-15 23:50:00"));
+        state.clock = clock.clone();
+
+        // Only a running service can be paused.
+        assert!(settings::impl_pause_service(&state, PauseDuration::Minutes(15)).is_err());
+
+        settings::internal_start_service(&state).unwrap();
+        let until = settings::impl_pause_service(&state, PauseDuration::Minutes(15)).unwrap();
+        assert_eq!(until, clock.local_now() + chrono::TimeDelta::minutes(15));
+        assert!(state.watcher_flag.lock().unwrap().is_none());
+        assert_eq!(
+            *state.service_lifecycle.lock().unwrap(),
+            ServiceLifecycleState::Paused
+        );
+        // The service stays enabled across the pause.
+        assert_eq!(state.config.read().unwrap().service_enabled, Some(true));
+
+        // Pausing again moves the end; "until tomorrow" is local midnight.
+        let midnight = settings::impl_pause_service(&state, PauseDuration::UntilTomorrow).unwrap();
+        assert_eq!(
+            midnight,
+            ManualClock::at_local("2024-01-16 00:00:00").local_now()
+        );
+        assert!(!settings::resume_if_pause_expired(&state).unwrap());
+
+        clock.advance(Duration::from_secs(10 * 60));
+        assert!(settings::resume_if_pause_expired(&state).unwrap());
+        assert_eq!(
+            *state.service_lifecycle.lock().unwrap(),
+            ServiceLifecycleState::Running
+        );
+        assert!(state.paused_until.lock().unwrap().is_none());
+
+        // Stopping a paused service turns it off for good.
+        settings::impl_pause_service(&state, PauseDuration::Minutes(60)).unwrap();
+        settings::internal_stop_service(&state).unwrap();
+        assert!(state.paused_until.lock().unwrap().is_none());
+        settings::impl_resume_service(&state).unwrap();
+        assert_eq!(
+            *state.service_lifecycle.lock().unwrap(),
+            ServiceLifecycleState::Stopped
+        );
+    }
+
+    #[test]
+    fn a_pause_outlasts_a_restart_of_harbor() {
+        use harbor_core::clock::ManualClock;
+        use settings::PauseDuration;
+        use std::sync::Arc;
+        use std::time::Duration;
+
+        let dir = tempdir().unwrap();
+        let mut state = state_with_config(&dir, test_config(true));
+        let clock = Arc::new(ManualClock::at_local("2024-01-15 10:00:00"));
+        state.clock = clock.clone();
+        settings::internal_start_service(&state).unwrap();
+        let until = settings::impl_pause_service(&state, PauseDuration::Minutes(30)).unwrap();
+
+        // Harbor quits and starts again from the config on disk.
+        let launch = |clock: &Arc<ManualClock>| {
+            let config = harbor_core::downloads::load_downloads_config(&state.config_path).unwrap();
+            let mut state = AppState::new(state.config_path.clone(), config);
+            state.clock = clock.clone();
+            settings::start_service_at_launch(&state).unwrap();
+            state
+        };
+        let relaunched = launch(&clock);
+        assert_eq!(
+            *relaunched.service_lifecycle.lock().unwrap(),
+            ServiceLifecycleState::Paused
+        );
+        assert_eq!(*relaunched.paused_until.lock().unwrap(), Some(until));
+        assert!(relaunched.watcher_flag.lock().unwrap().is_none());
+
+        clock.advance(Duration::from_secs(30 * 60));
+        assert!(settings::resume_if_pause_expired(&relaunched).unwrap());
+        settings::internal_stop_service(&relaunched).unwrap();
+
+        // A pause that ran out while Harbor was closed is over at launch.
+        settings::impl_pause_service(&state, PauseDuration::Minutes(5)).unwrap();
+        clock.advance(Duration::from_secs(10 * 60));
+        let relaunched = launch(&clock);
+        assert_eq!(
+            *relaunched.service_lifecycle.lock().unwrap(),
+            ServiceLifecycleState::Running
+        );
+        assert_eq!(relaunched.config.read().unwrap().paused_until, None);
+        settings::internal_stop_service(&relaunched).unwrap();
+        settings::internal_stop_service(&state).unwrap();
+    }
+
     #[test]
     fn double_start_is_idempotent() {
         let dir = tempdir().unwrap();
@@ -164,6 +265,7 @@ mod integration {
             total_failures: 0,
             errors: vec![],
             failure_groups: vec![],
+            ..Default::default()
         };
 
         let outcome = settings::map_tray_organize_outcome(&response);
@@ -183,6 +285,7 @@ mod integration {
             total_failures: 2,
             errors: vec!["e1".to_string()],
             failure_groups: vec![],
+            ..Default::default()
         };
 
         let outcome = settings::map_tray_organize_outcome(&response);
@@ -199,6 +302,7 @@ mod integration {
             total_failures: 10,
             errors: vec!["critical".to_string()],
             failure_groups: vec![],
+            ..Default::default()
         };
 
         let outcome = settings::map_tray_organize_outcome(&response);
@@ -36,10 +36,22 @@ struct ServiceStatusEnvelope {
 }
 
 fn main() {
+    match harbor_core::platform::migrate_legacy_files() {
+        Ok(migrated) => {
+            for path in migrated {
+                eprintln!("[Harbor] Moved {} to its new location", path.display());
+            }
+        }
+        Err(e) => eprintln!("[Harbor] Warning: failed to move files from the old location: {e}"),
+    }
+
     let harbor_dir = harbor_core::downloads::harbor_app_dir();
     let _ = std::fs::create_dir_all(&harbor_dir);
 
-    let cfg_path = harbor_dir.join("harbor.downloads.yaml");
+    let cfg_path = harbor_core::downloads::harbor_config_path();
+    if let Some(cfg_dir) = cfg_path.parent() {
+        let _ = std::fs::create_dir_all(cfg_dir);
+    }
 
     let config = load_or_initialize_config(&cfg_path).unwrap_or_else(|e| {
         eprintln!("[Harbor] Warning: failed to load config: {e}");
@@ -61,7 +73,7 @@ fn main() {
     let app_state = AppState::new(cfg_path, config);
 
     if service_enabled {
-        let _ = commands::settings::internal_start_service(&app_state);
+        let _ = commands::settings::start_service_at_launch(&app_state);
     }
 
     tauri::Builder::default()
@@ -96,6 +108,8 @@ fn main() {
             commands::get_service_status,
             commands::start_service,
             commands::stop_service,
+            commands::pause_service,
+            commands::resume_service,
             commands::retry_service_restart,
             commands::trigger_organize_now,
             commands::get_startup_enabled,
@@ -128,14 +142,37 @@ fn main() {
         })
         .setup(move |app| {
             use tauri::image::Image;
-            use tauri::menu::{CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder};
+            use tauri::menu::{CheckMenuItemBuilder, MenuBuilder, MenuItemBuilder, SubmenuBuilder};
             #[cfg(target_os = "macos")]
-            use tauri::menu::{PredefinedMenuItem, SubmenuBuilder};
+            use tauri::menu::PredefinedMenuItem;
             use tauri::tray::{MouseButton, TrayIconBuilder, TrayIconEvent};
             if let Err(e) = commands::settings::reconcile_startup_authority(app.handle()) {
                 eprintln!("[Harbor] Warning: failed to reconcile startup authority: {e}");
             }
 
+            commands::settings::spawn_pause_monitor(app.handle().clone());
+
+            // Let `harbor ctl` reach this instance.
+            match harbor_core::control::ControlServer::start(
+                &harbor_core::downloads::harbor_control_path(),
+                std::sync::Arc::new(commands::control::AppControl::new(app.handle().clone())),
+            ) {
+                Ok(server) => {
+                    app.manage(server);
+                }
+                Err(e) => eprintln!("[Harbor] Control socket disabled: {e:#}"),
+            }
+
+            // Stream organizer progress to the frontend.
+            let event_handle = app.handle().clone();
+            if let Ok(mut listener) = app.state::<AppState>().organize_events.write() {
+                *listener = Some(std::sync::Arc::new(
+                    move |event: &commands::events::OrganizeEventDto| {
+                        let _ = event_handle.emit(commands::events::ORGANIZE_EVENT, event);
+                    },
+                ));
+            }
+
             // --- Override macOS app menu Quit item to intercept Cmd+Q ---
             // NOTE: Using a custom macOS menu to intercept Cmd+Q before the system handles it.
             // This is more reliable than ExitRequested::prevent_exit() on macOS.
@@ -255,6 +292,25 @@ fn main() {
                 .checked(!service_enabled)
                 .build(app)?;
 
+            let pause_15m = MenuItemBuilder::new("For 15 Minutes")
+                .id("pause_15m")
+                .build(app)?;
+            let pause_1h = MenuItemBuilder::new("For 1 Hour")
+                .id("pause_1h")
+                .build(app)?;
+            let pause_tomorrow = MenuItemBuilder::new("Until Tomorrow")
+                .id("pause_tomorrow")
+                .build(app)?;
+            let pause_menu = SubmenuBuilder::new(app, "Pause")
+                .items(&[&pause_15m, &pause_1h, &pause_tomorrow])
+                .enabled(service_enabled)
+                .build()?;
+            // Doubles as the quiet hours indicator while the service runs.
+            let resume = MenuItemBuilder::new("Resume")
+                .id("resume")
+                .enabled(false)
+                .build(app)?;
+
             let organize_now = MenuItemBuilder::new("Organize Now")
                 .id("organize")
                 .build(app)?;
@@ -278,6 +334,8 @@ fn main() {
                 .items(&[
                     &status_on,
                     &status_off,
+                    &pause_menu,
+                    &resume,
                     &tauri::menu::PredefinedMenuItem::separator(app)?,
                     &organize_now,
                     &tauri::menu::PredefinedMenuItem::separator(app)?,
@@ -296,9 +354,12 @@ fn main() {
                 let parsed = serde_json::from_str::<ServiceStatusEnvelope>(event.payload());
                 match parsed {
                     Ok(payload) => {
-                        let checked = payload.status.running;
-                        let _ = status_on_listener.set_checked(checked);
-                        let _ = status_off_listener.set_checked(!checked);
+                        let items = commands::settings::map_tray_service_items(&payload.status);
+                        let _ = status_on_listener.set_checked(items.service_on);
+                        let _ = status_off_listener.set_checked(!items.service_on);
+                        let _ = pause_menu.set_enabled(items.can_pause);
+                        let _ = resume.set_text(&items.resume_label);
+                        let _ = resume.set_enabled(items.can_resume);
                     }
                     Err(e) => {
                         eprintln!("[Harbor] Failed to parse service status event payload: {e}");
@@ -393,6 +454,27 @@ fn main() {
                         let _ = status_on.set_checked(false);
                         let _ = status_off.set_checked(true);
                     }
+                    "pause_15m" | "pause_1h" | "pause_tomorrow" => {
+                        let duration = match event.id.as_ref() {
+                            "pause_15m" => commands::settings::PauseDuration::Minutes(15),
+                            "pause_1h" => commands::settings::PauseDuration::Minutes(60),
+                            _ => commands::settings::PauseDuration::UntilTomorrow,
+                        };
+                        let state: tauri::State<AppState> = app.state();
+                        let pause_result = commands::settings::impl_pause_service(&state, duration);
+                        let _ = commands::settings::emit_lifecycle_status_for_app(app, &state);
+                        if let Err(error) = pause_result {
+                            eprintln!("[Harbor] Tray pause blocked: {error}");
+                        }
+                    }
+                    "resume" => {
+                        let state: tauri::State<AppState> = app.state();
+                        let resume_result = commands::settings::impl_resume_service(&state);
+                        let _ = commands::settings::emit_lifecycle_status_for_app(app, &state);
+                        if let Err(error) = resume_result {
+                            eprintln!("[Harbor] Tray resume failed: {error}");
+                        }
+                    }
                     "organize" => {
                         let app_handle = app.clone();
                         tauri::async_runtime::spawn(async move {
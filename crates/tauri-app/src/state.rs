@@ -1,4 +1,11 @@
+use crate::commands::events::{forward_organize_event, OrganizeEventSlot};
+use chrono::{DateTime, Local};
+use harbor_core::clock::{Clock, SystemClock};
+use harbor_core::control::EventHub;
 use harbor_core::downloads::DownloadsConfig;
+use harbor_core::history::JsonlHistory;
+use harbor_core::organizer::{FileLog, Organizer};
+use harbor_core::webhook::Webhook;
 use std::path::PathBuf;
 use std::sync::atomic::AtomicBool;
 use std::sync::{Arc, Mutex, RwLock};
@@ -11,6 +18,8 @@ pub enum ServiceLifecycleState {
     Running,
     Restarting,
     Degraded,
+    /// Stopped for a while by the user; resumes on its own at `paused_until`.
+    Paused,
 }
 
 /// Application state managed by Tauri
@@ -29,6 +38,9 @@ pub struct AppState {
     pub service_start_time: Arc<Mutex<Option<Instant>>>,
     /// Service lifecycle status for deterministic restart flows.
     pub service_lifecycle: Arc<Mutex<ServiceLifecycleState>>,
+    /// When a paused service resumes. Mirrored in the config's
+    /// `paused_until`, so a pause outlasts a restart of Harbor.
+    pub paused_until: Arc<Mutex<Option<DateTime<Local>>>>,
     /// Most recent degraded reason after a failed restart.
     pub degraded_reason: Arc<Mutex<Option<String>>>,
     /// Last successful restart request time used for deterministic debounce.
@@ -41,6 +53,12 @@ pub struct AppState {
     pub last_close_request: Arc<Mutex<Option<Instant>>>,
     /// Set to true when tray "Quit" is pressed, to bypass double-press logic.
     pub tray_quit_requested: Arc<AtomicBool>,
+    /// Receives live organizer events; registered once the app window exists.
+    pub organize_events: OrganizeEventSlot,
+    /// Organizer events for `harbor ctl tail-events` clients.
+    pub control_events: EventHub,
+    /// Time source for log timestamps and activity statistics.
+    pub clock: Arc<dyn Clock>,
 }
 
 impl AppState {
@@ -52,12 +70,16 @@ impl AppState {
             watcher_handle: Arc::new(Mutex::new(None)),
             service_start_time: Arc::new(Mutex::new(None)),
             service_lifecycle: Arc::new(Mutex::new(ServiceLifecycleState::Stopped)),
+            paused_until: Arc::new(Mutex::new(None)),
             degraded_reason: Arc::new(Mutex::new(None)),
             last_restart_request: Arc::new(Mutex::new(None)),
             restart_in_progress: Arc::new(Mutex::new(false)),
             watcher_join_pending: Arc::new(Mutex::new(false)),
             last_close_request: Arc::new(Mutex::new(None)),
             tray_quit_requested: Arc::new(AtomicBool::new(false)),
+            organize_events: Arc::new(RwLock::new(None)),
+            control_events: EventHub::new(),
+            clock: Arc::new(SystemClock),
         }
     }
 
@@ -65,4 +87,32 @@ impl AppState {
     pub fn recent_log_path(&self) -> PathBuf {
         harbor_core::downloads::harbor_log_path()
     }
+
+    /// Builds an organizer for `config` that writes the activity log, records
+    /// undo history, forwards live events to the UI and `harbor ctl` and
+    /// notifies the webhook.
+    pub fn organizer(&self, config: DownloadsConfig) -> Organizer {
+        let events = self.organize_events.clone();
+        let control_events = self.control_events.clone();
+        let log = FileLog::from_config(self.recent_log_path(), &config);
+        let webhook = config.webhook.clone();
+        let mut builder = Organizer::builder(config)
+            .clock(self.clock.clone())
+            .log_sink(Arc::new(log))
+            .history(Arc::new(JsonlHistory::new(
+                harbor_core::downloads::harbor_history_path(),
+            )))
+            .pass_lock(harbor_core::downloads::harbor_lock_path())
+            .on_event(move |event| {
+                forward_organize_event(&events, event);
+                control_events.publish(event);
+            });
+        if let Some(webhook) = webhook {
+            match Webhook::start(webhook) {
+                Ok(webhook) => builder = builder.webhook(Arc::new(webhook)),
+                Err(e) => eprintln!("[Harbor] Webhook disabled: {e:#}"),
+            }
+        }
+        builder.build()
+    }
 }
@@ -1,12 +1,17 @@
-use anyhow::Result;
+use anyhow::{Context, Result};
+use chrono::{DateTime, Local};
+use harbor_core::breadcrumb::BreadcrumbCleanup;
 use harbor_core::downloads::{
-    append_organize_results_to_log, cleanup_old_symlinks, organize_once, watch_polling,
-    DownloadsConfig, OrganizeResult,
+    append_organize_results_to_log_with_policy, DownloadsConfig, LogArchivePolicy, OrganizeResult,
 };
+use harbor_core::history::JsonlHistory;
+use harbor_core::organizer::{FileLog, Organizer};
+use harbor_core::webhook::Webhook;
 use std::path::PathBuf;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{Arc, Mutex};
 use std::thread;
+use std::time::Duration;
 
 pub mod windows {
     pub mod utils {
@@ -58,16 +63,34 @@ pub struct TrayLogic {
     pub config: Arc<DownloadsConfig>,
     watching: Arc<AtomicBool>,
     handle: Arc<Mutex<Option<thread::JoinHandle<()>>>>,
+    webhook: Option<Arc<Webhook>>,
+    /// When a pause ends; also saved as `paused_until` in `config_path`.
+    paused_until: Arc<Mutex<Option<DateTime<Local>>>>,
     pub log_path: PathBuf,
+    pub config_path: PathBuf,
 }
 
 impl TrayLogic {
     pub fn new(config: DownloadsConfig) -> Self {
+        // One delivery thread for every organizer the tray builds.
+        let webhook = config
+            .webhook
+            .clone()
+            .and_then(|webhook| match Webhook::start(webhook) {
+                Ok(webhook) => Some(Arc::new(webhook)),
+                Err(e) => {
+                    eprintln!("[Harbor] Webhook disabled: {e:#}");
+                    None
+                }
+            });
         Self {
             config: Arc::new(config),
             watching: Arc::new(AtomicBool::new(false)),
             handle: Arc::new(Mutex::new(None)),
+            webhook,
+            paused_until: Arc::new(Mutex::new(None)),
             log_path: harbor_core::downloads::harbor_log_path(),
+            config_path: harbor_core::downloads::harbor_config_path(),
         }
     }
 
@@ -77,18 +100,114 @@ impl TrayLogic {
         self
     }
 
+    #[allow(dead_code)]
+    pub fn with_config_path(mut self, path: PathBuf) -> Self {
+        self.config_path = path;
+        self
+    }
+
+    /// Starts watching at launch, unless a pause saved before the tray quit
+    /// is still running at `now`.
+    pub fn start(&self, now: DateTime<Local>) -> Result<()> {
+        match self.config.pause_running_at(now) {
+            Some(until) => {
+                if let Ok(mut guard) = self.paused_until.lock() {
+                    *guard = Some(until);
+                }
+                Ok(())
+            }
+            None if self.config.paused_until.is_some() => self.resume(),
+            None => {
+                self.start_watching();
+                Ok(())
+            }
+        }
+    }
+
+    /// Stops watching until `until` without turning the service off.
+    /// Pausing a paused tray moves the end.
+    pub fn pause(&self, until: DateTime<Local>) -> Result<()> {
+        self.stop_watching();
+        if let Ok(mut guard) = self.paused_until.lock() {
+            *guard = Some(until);
+        }
+        self.save_pause(Some(until))
+    }
+
+    /// Ends a pause, if there is one, and watches again.
+    pub fn resume(&self) -> Result<()> {
+        self.end_pause()?;
+        self.start_watching();
+        Ok(())
+    }
+
+    /// Stops watching for good, ending a pause.
+    pub fn stop(&self) -> Result<()> {
+        self.stop_watching();
+        self.end_pause()
+    }
+
+    pub fn paused_until(&self) -> Option<DateTime<Local>> {
+        *self.paused_until.lock().ok()?
+    }
+
+    /// Resumes a pause that has run out at `now`. Returns whether it did.
+    pub fn resume_if_pause_expired(&self, now: DateTime<Local>) -> Result<bool> {
+        let expired = self.paused_until().is_some_and(|until| until <= now);
+        if expired {
+            self.resume()?;
+        }
+        Ok(expired)
+    }
+
+    /// Resumes pauses as they run out, checking every 5 seconds for as long
+    /// as the tray runs.
+    pub fn spawn_pause_monitor(&self) {
+        let logic = self.clone();
+        thread::spawn(move || loop {
+            if let Err(e) = logic.resume_if_pause_expired(Local::now()) {
+                eprintln!("[Harbor] Failed to resume paused watcher: {e:#}");
+            }
+            thread::sleep(Duration::from_secs(5));
+        });
+    }
+
+    fn end_pause(&self) -> Result<()> {
+        let paused = self
+            .paused_until
+            .lock()
+            .ok()
+            .and_then(|mut guard| guard.take())
+            .is_some();
+        if paused || self.config.paused_until.is_some() {
+            self.save_pause(None)?;
+        }
+        Ok(())
+    }
+
+    /// Writes the end of the pause into the config file, leaving the rest of
+    /// it (including unexpanded `${VAR}`s) as the user wrote it.
+    fn save_pause(&self, until: Option<DateTime<Local>>) -> Result<()> {
+        let path = &self.config_path;
+        let content =
+            std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let mut config: DownloadsConfig =
+            serde_yaml::from_str(&content).context("parse downloads yaml")?;
+        config.paused_until = until.map(|until| until.to_rfc3339());
+        std::fs::write(path, serde_yaml::to_string(&config)?)
+            .with_context(|| format!("write {}", path.display()))
+    }
+
     pub fn start_watching(&self) {
         if self.watching.swap(true, Ordering::SeqCst) {
             return;
         }
 
-        let logic = self.clone();
-        let logic_cb = logic.clone();
+        let organizer = self.organizer();
+        let watching = self.watching.clone();
 
         let h = thread::spawn(move || {
-            let _ = watch_polling(&logic.config, 5, &logic.watching, move |actions| {
-                logic_cb.on_file_change(actions)
-            });
+            let _ = organizer.watch(5, &watching);
         });
 
         if let Ok(mut guard) = self.handle.lock() {
@@ -96,10 +215,26 @@ impl TrayLogic {
         }
     }
 
+    #[allow(dead_code)]
     pub fn on_file_change(&self, actions: &[OrganizeResult]) {
         self.append_recent(actions);
     }
 
+    /// Organizer logging to `log_path`, recording history and taking the
+    /// organize lock next to it, and notifying the configured webhook.
+    fn organizer(&self) -> Organizer {
+        let mut builder = Organizer::builder((*self.config).clone())
+            .log_sink(Arc::new(FileLog::from_config(&self.log_path, &self.config)))
+            .history(Arc::new(JsonlHistory::new(
+                self.log_path.with_file_name("history.jsonl"),
+            )))
+            .pass_lock(self.log_path.with_file_name("organize.lock"));
+        if let Some(webhook) = &self.webhook {
+            builder = builder.webhook(webhook.clone());
+        }
+        builder.build()
+    }
+
     pub fn stop_watching(&self) {
         self.watching.store(false, Ordering::SeqCst);
         if let Ok(mut guard) = self.handle.lock() {
@@ -108,16 +243,17 @@ impl TrayLogic {
     }
 
     pub fn organize_now(&self) -> Result<Vec<OrganizeResult>> {
-        let summary = organize_once(&self.config)?;
-        for err in &summary.errors {
-            eprintln!("[Harbor] {err}");
-        }
-        self.append_recent(&summary.moved);
-        Ok(summary.moved)
+        Ok(self.organizer().run_once()?.moved)
     }
 
-    pub fn cleanup_old_symlinks(&self) -> Result<usize> {
-        let count = cleanup_old_symlinks(&self.config)?;
+    /// Removes dangling and expired breadcrumbs; each removal is logged by
+    /// the organizer, followed by a one-line summary.
+    pub fn cleanup_breadcrumbs(&self) -> Result<BreadcrumbCleanup> {
+        let report = self.organizer().cleanup_breadcrumbs()?;
+        for err in &report.errors {
+            eprintln!("[Harbor] {err}");
+        }
+        let count = report.removed.len();
         if count > 0 {
             let _ = std::fs::OpenOptions::new()
                 .create(true)
@@ -125,14 +261,15 @@ impl TrayLogic {
                 .open(&self.log_path)
                 .and_then(|mut f| {
                     use std::io::Write;
-                    writeln!(f, "Startup: Cleaned up {} old symlink(s)", count)
+                    writeln!(f, "Startup: Cleaned up {} old breadcrumb(s)", count)
                 });
         }
-        Ok(count)
+        Ok(report)
     }
 
     fn append_recent(&self, actions: &[OrganizeResult]) {
-        append_organize_results_to_log(&self.log_path, actions);
+        let policy = LogArchivePolicy::from_config(&self.config);
+        append_organize_results_to_log_with_policy(&self.log_path, actions, &policy);
     }
 }
 
@@ -176,6 +313,7 @@ mod tests {
             service_enabled: Some(true),
             check_updates: Some(true),
             last_notified_version: None,
+            ..Default::default()
         };
         (config, tmp)
     }
@@ -193,6 +331,57 @@ mod tests {
         assert!(!logic.watching.load(Ordering::SeqCst));
     }
 
+    #[test]
+    fn test_webhook_is_started_once_and_shared() {
+        let (mut config, _tmp) = create_test_config();
+        config.webhook = Some(harbor_core::types::WebhookConfig {
+            url: "http://127.0.0.1:9/harbor".into(),
+            ..Default::default()
+        });
+        let logic = TrayLogic::new(config);
+
+        let webhook = logic.webhook.clone().expect("webhook started");
+        assert!(Arc::ptr_eq(
+            &webhook,
+            logic.clone().webhook.as_ref().unwrap()
+        ));
+        logic.organize_now().unwrap();
+        assert_eq!(Arc::strong_count(&webhook), 2);
+    }
+
+    #[test]
+    fn test_pause_stops_watching_and_outlasts_a_restart() {
+        let (config, tmp) = create_test_config();
+        let config_path = tmp.path().join("harbor.downloads.yaml");
+        std::fs::write(&config_path, serde_yaml::to_string(&config).unwrap()).unwrap();
+        let now = Local::now();
+        let until = now + chrono::TimeDelta::minutes(60);
+
+        let logic = TrayLogic::new(config).with_config_path(config_path.clone());
+        logic.start(now).unwrap();
+        logic.pause(until).unwrap();
+        assert!(!logic.watching.load(Ordering::SeqCst));
+        assert!(!logic.resume_if_pause_expired(now).unwrap());
+
+        // The tray quits and starts again from the config on disk.
+        let saved = harbor_core::downloads::load_downloads_config(&config_path).unwrap();
+        let relaunched = TrayLogic::new(saved).with_config_path(config_path.clone());
+        relaunched.start(now).unwrap();
+        assert!(!relaunched.watching.load(Ordering::SeqCst));
+        assert_eq!(
+            relaunched.paused_until().map(|t| t.timestamp()),
+            Some(until.timestamp())
+        );
+
+        assert!(relaunched.resume_if_pause_expired(until).unwrap());
+        assert!(relaunched.watching.load(Ordering::SeqCst));
+        let saved = harbor_core::downloads::load_downloads_config(&config_path).unwrap();
+        assert_eq!(saved.paused_until, None);
+
+        relaunched.stop().unwrap();
+        logic.stop().unwrap();
+    }
+
     #[test]
     fn test_organize_now_calls_organize() {
         let (config, _tmp) = create_test_config();
@@ -206,7 +395,7 @@ mod tests {
     fn test_cleanup_calls() {
         let (config, _tmp) = create_test_config();
         let logic = TrayLogic::new(config);
-        assert!(logic.cleanup_old_symlinks().is_ok());
+        assert!(logic.cleanup_breadcrumbs().is_ok());
     }
 
     #[cfg(windows)]
@@ -293,6 +482,7 @@ mod tests {
             target_dir: target_dir.to_string_lossy().to_string(),
             create_symlink: false,
             enabled: true,
+            ..Default::default()
         });
 
         // Create a file in target
@@ -317,7 +507,10 @@ mod tests {
         let logic = TrayLogic::new(config).with_log_path(tmp.path().join("cleanup.log"));
 
         // Act
-        let count = logic.cleanup_old_symlinks().unwrap_or(0);
+        let count = logic
+            .cleanup_breadcrumbs()
+            .map(|report| report.removed.len())
+            .unwrap_or(0);
 
         // Assert
         if count > 0 {
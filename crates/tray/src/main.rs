@@ -1,7 +1,11 @@
 #[cfg(windows)]
 use anyhow::Result;
 #[cfg(windows)]
-use harbor_core::downloads::{harbor_app_dir, harbor_log_path};
+use chrono::Local;
+#[cfg(windows)]
+use harbor_core::control::pause_end;
+#[cfg(windows)]
+use harbor_core::downloads::{harbor_config_path, harbor_log_path};
 #[cfg(windows)]
 use native_windows_gui as nwg;
 #[cfg(windows)]
@@ -30,6 +34,10 @@ struct TrayState {
     tray_menu: nwg::Menu,
     item_start: nwg::MenuItem,
     item_stop: nwg::MenuItem,
+    pause_menu: nwg::Menu,
+    item_pause_hour: nwg::MenuItem,
+    item_pause_tomorrow: nwg::MenuItem,
+    item_resume: nwg::MenuItem,
     item_organize: nwg::MenuItem,
     item_open_downloads: nwg::MenuItem,
     item_open_cfg: nwg::MenuItem,
@@ -73,7 +81,7 @@ fn tray_main() -> Result<()> {
 
     nwg::init()?;
 
-    let cfg_path = harbor_app_dir().join("harbor.downloads.yaml");
+    let cfg_path = harbor_config_path();
 
     // Load config using refactored function
     let cfg = load_or_initialize_config(&cfg_path)?;
@@ -123,6 +131,26 @@ fn tray_main() -> Result<()> {
         .parent(&ui.tray_menu)
         .build(&mut ui.item_stop)?;
 
+    nwg::Menu::builder()
+        .text("Pause")
+        .parent(&ui.tray_menu)
+        .build(&mut ui.pause_menu)?;
+
+    nwg::MenuItem::builder()
+        .text("For 1 Hour")
+        .parent(&ui.pause_menu)
+        .build(&mut ui.item_pause_hour)?;
+
+    nwg::MenuItem::builder()
+        .text("Until Tomorrow")
+        .parent(&ui.pause_menu)
+        .build(&mut ui.item_pause_tomorrow)?;
+
+    nwg::MenuItem::builder()
+        .text("Resume")
+        .parent(&ui.tray_menu)
+        .build(&mut ui.item_resume)?;
+
     nwg::MenuItem::builder()
         .text("Organize Now")
         .parent(&ui.tray_menu)
@@ -165,11 +193,21 @@ fn tray_main() -> Result<()> {
                     show_menu(&ui);
                 }
                 nwg::Event::OnMenuItemSelected => {
-                    if handle == ui.item_start {
-                        logic_c.start_watching();
+                    let result = if handle == ui.item_start || handle == ui.item_resume {
+                        logic_c.resume()
                     } else if handle == ui.item_stop {
-                        logic_c.stop_watching();
-                    } else if handle == ui.item_organize {
+                        logic_c.stop()
+                    } else if handle == ui.item_pause_hour {
+                        logic_c.pause(pause_end(Some(60), Local::now()))
+                    } else if handle == ui.item_pause_tomorrow {
+                        logic_c.pause(pause_end(None, Local::now()))
+                    } else {
+                        Ok(())
+                    };
+                    if let Err(e) = result {
+                        eprintln!("[Harbor] {e:#}");
+                    }
+                    if handle == ui.item_organize {
                         if let Ok(actions) = logic_c.organize_now() {
                             if !actions.is_empty() {
                                 ui.tray.show(
@@ -203,10 +241,13 @@ fn tray_main() -> Result<()> {
     };
     let _eh = nwg::full_bind_event_handler(&ui_ref.window.handle, handler);
 
-    // Cleanup old symlinks on startup
-    let _ = app_logic.cleanup_old_symlinks();
+    // Remove dangling and expired breadcrumbs on startup
+    let _ = app_logic.cleanup_breadcrumbs();
 
-    app_logic.start_watching();
+    if let Err(e) = app_logic.start(Local::now()) {
+        eprintln!("[Harbor] {e:#}");
+    }
+    app_logic.spawn_pause_monitor();
 
     nwg::dispatch_thread_events();
     Ok(())
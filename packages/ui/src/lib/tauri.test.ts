@@ -14,6 +14,8 @@ import {
     getServiceStatus,
     startService,
     stopService,
+    pauseService,
+    resumeService,
     triggerOrganizeNow,
     retryServiceRestart,
     subscribeServiceStatus,
@@ -115,6 +117,17 @@ describe('tauri API wrappers', () => {
         expect(result).toEqual(fakeResponse);
     });
 
+    it('getActivityLogs passes search and includeArchived', async () => {
+        mockInvoke.mockResolvedValue({ logs: [], total: 0, has_more: false });
+        await getActivityLogs(10, 0, 'invoice', true);
+        expect(mockInvoke).toHaveBeenCalledWith('get_activity_logs', {
+            limit: 10,
+            offset: 0,
+            search: 'invoice',
+            includeArchived: true,
+        });
+    });
+
     it('getActivityLogs works without arguments', async () => {
         const fakeResponse = { logs: [], total: 0, has_more: false };
         mockInvoke.mockResolvedValue(fakeResponse);
@@ -183,6 +196,20 @@ describe('tauri API wrappers', () => {
         expect(mockInvoke).toHaveBeenCalledWith('stop_service');
     });
 
+    it('pauseService passes the duration to invoke("pause_service")', async () => {
+        mockInvoke.mockResolvedValue(undefined);
+        await pauseService({ minutes: 15 });
+        expect(mockInvoke).toHaveBeenCalledWith('pause_service', { duration: { minutes: 15 } });
+        await pauseService('until_tomorrow');
+        expect(mockInvoke).toHaveBeenCalledWith('pause_service', { duration: 'until_tomorrow' });
+    });
+
+    it('resumeService calls invoke("resume_service")', async () => {
+        mockInvoke.mockResolvedValue(undefined);
+        await resumeService();
+        expect(mockInvoke).toHaveBeenCalledWith('resume_service');
+    });
+
     it('triggerOrganizeNow calls invoke("trigger_organize_now")', async () => {
         const organizeResult = {
             status: 'partial_failure',
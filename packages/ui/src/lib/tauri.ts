@@ -47,12 +47,25 @@ export interface ActivityLogsResponse {
 
 export interface ServiceStatus {
     running: boolean;
-    lifecycle_state?: 'stopped' | 'running' | 'restarting' | 'degraded' | string;
+    lifecycle_state?: 'stopped' | 'running' | 'restarting' | 'degraded' | 'paused' | string;
     uptime_seconds?: number;
     pid: number;
     stop_join_pending?: boolean;
     degraded?: boolean;
     degraded_reason?: string | null;
+    schedule?: string | null;
+    next_scheduled_run?: string | null;
+    scheduled_rules?: ScheduledRule[];
+    paused_until?: string | null;
+    quiet_until?: string | null;
+}
+
+export type PauseDuration = { minutes: number } | 'until_tomorrow';
+
+export interface ScheduledRule {
+    rule_name: string;
+    schedule: string;
+    next_run?: string | null;
 }
 
 export interface ServiceStatusEvent {
@@ -90,7 +103,7 @@ export interface OrganizeFailureGroup {
 }
 
 export interface OrganizeNowResponse {
-    status: 'success' | 'partial_failure' | 'failed' | string;
+    status: 'success' | 'partial_failure' | 'failed' | 'busy' | string;
     message: string;
     moved_count: number;
     // legacy compatibility field from backend
@@ -99,6 +112,9 @@ export interface OrganizeNowResponse {
     // legacy compatibility field from backend
     errors: string[];
     failure_groups: OrganizeFailureGroup[];
+    // files left for later passes by max_moves_per_pass / max_bytes_per_pass
+    deferred_count?: number;
+    deferred_bytes?: number;
 }
 
 export interface UpdateRuleRequest {
@@ -188,8 +204,13 @@ export async function resetToDefaults(): Promise<void> {
 }
 
 // Activity
-export const getActivityLogs = async (limit?: number, offset?: number): Promise<ActivityLogsResponse> => {
-    return await invoke('get_activity_logs', { limit, offset });
+export const getActivityLogs = async (
+    limit?: number,
+    offset?: number,
+    search?: string,
+    includeArchived?: boolean,
+): Promise<ActivityLogsResponse> => {
+    return await invoke('get_activity_logs', { limit, offset, search, includeArchived });
 };
 
 export const getActivityStats = async (): Promise<ActivityStats> => {
@@ -213,6 +234,14 @@ export const stopService = async (): Promise<void> => {
     return await invoke('stop_service');
 };
 
+export const pauseService = async (duration: PauseDuration): Promise<void> => {
+    return await invoke('pause_service', { duration });
+};
+
+export const resumeService = async (): Promise<void> => {
+    return await invoke('resume_service');
+};
+
 export const triggerOrganizeNow = async (): Promise<OrganizeNowResponse> => {
     return await invoke('trigger_organize_now');
 };